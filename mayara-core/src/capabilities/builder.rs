@@ -8,7 +8,7 @@ use crate::radar::RadarDiscovery;
 use super::controls::*;
 use super::{
     CapabilityManifest, Characteristics, ConstraintCondition, ConstraintEffect, ConstraintType,
-    ControlConstraint, ControlDefinition, SupportedFeature,
+    ControlConstraint, ControlDefinition, IntensityLegend, SupportedFeature,
 };
 
 /// Build a capability manifest for a discovered radar
@@ -55,6 +55,7 @@ pub fn build_capabilities(
         controls: build_controls(model_info, discovery.serial_number.is_some()),
         constraints: build_constraints(model_info),
         supported_features,
+        intensity_legend: Some(build_intensity_legend(model_info, discovery.pixel_values)),
     }
 }
 
@@ -94,6 +95,7 @@ pub fn build_capabilities_from_model(
         controls: build_controls(model_info, false), // No serial number available
         constraints: build_constraints(model_info),
         supported_features,
+        intensity_legend: Some(build_intensity_legend(model_info, model_info.pixel_values())),
     }
 }
 
@@ -154,6 +156,21 @@ pub fn build_capabilities_from_model_with_key(
         controls: build_controls(model_info, false),
         constraints: build_constraints(model_info),
         supported_features,
+        intensity_legend: Some(build_intensity_legend(model_info, model_info.pixel_values())),
+    }
+}
+
+/// Build the default intensity legend for a radar model
+///
+/// Doppler cells are placed right after the intensities the way the default
+/// display legend lays them out; providers that know their actual legend
+/// should replace it.
+fn build_intensity_legend(model: &ModelInfo, pixel_values: u8) -> IntensityLegend {
+    let legend = IntensityLegend::new(pixel_values);
+    if model.has_doppler {
+        legend.with_doppler(pixel_values.saturating_add(1), pixel_values.saturating_add(2))
+    } else {
+        legend
     }
 }
 
@@ -325,4 +342,23 @@ mod tests {
         assert!(caps.supported_features.contains(&SupportedFeature::Arpa));
         assert!(caps.supported_features.contains(&SupportedFeature::GuardZones));
     }
+
+    #[test]
+    fn test_capabilities_include_intensity_legend() {
+        let halo = models::get_model(Brand::Navico, "HALO24").unwrap();
+        let caps = build_capabilities_from_model(halo, "1", vec![]);
+
+        let json = serde_json::to_value(&caps).unwrap();
+        let legend = &json["intensityLegend"];
+        assert_eq!(legend["bitDepth"], 4);
+        assert_eq!(legend["minIntensity"], 0);
+        assert_eq!(legend["maxIntensity"], 15);
+        let stops = legend["stops"].as_array().unwrap();
+        let values: Vec<u64> = stops.iter().map(|s| s["value"].as_u64().unwrap()).collect();
+        assert_eq!(values, vec![0, 1, 5, 10, 15]);
+        assert_eq!(stops[0]["color"], "#00000000");
+        assert_eq!(stops[4]["color"], "#ff5500ff");
+        assert_eq!(legend["doppler"]["approaching"], 17);
+        assert_eq!(legend["doppler"]["receding"], 18);
+    }
 }
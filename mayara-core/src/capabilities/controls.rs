@@ -349,6 +349,75 @@ pub fn control_operating_hours() -> ControlDefinition {
     }
 }
 
+/// Warmup seconds: time left before the radar can transmit (read-only)
+///
+/// Navico: Report 01 bytes 6-7, on firmware that reports a countdown.
+#[inline(never)]
+pub fn control_warmup_seconds() -> ControlDefinition {
+    ControlDefinition {
+        id: "warmupSeconds".into(),
+        name: "Warmup Time Left".into(),
+        description: "Seconds of warmup left before the radar can transmit.".into(),
+        category: ControlCategory::Base,
+        control_type: ControlType::Number,
+        range: Some(RangeSpec {
+            min: 0.0,
+            max: 65535.0,
+            step: Some(1.0),
+            unit: Some("s".into()),
+        }),
+        values: None,
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: true,
+        default: None,
+        wire_hints: None,
+    }
+}
+
+/// Blanking active: a no transmit sector is blanking the picture (read-only)
+///
+/// Navico: Report 01 byte 3
+pub fn control_blanking_active() -> ControlDefinition {
+    ControlDefinition {
+        id: "blankingActive".into(),
+        name: "Blanking Active".into(),
+        description: "A no transmit sector is blanked, so part of the picture is missing on purpose.".into(),
+        category: ControlCategory::Base,
+        control_type: ControlType::Boolean,
+        range: None,
+        values: None,
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: true,
+        default: None,
+        wire_hints: None,
+    }
+}
+
+/// Antenna kind: dome or open array (read-only)
+///
+/// Navico: Report 03 byte 3 on HALO
+pub fn control_antenna_kind() -> ControlDefinition {
+    ControlDefinition {
+        id: "antennaKind".into(),
+        name: "Antenna Kind".into(),
+        description: "Whether the antenna is a dome or an open array.".into(),
+        category: ControlCategory::Base,
+        control_type: ControlType::String,
+        range: None,
+        values: None,
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: true,
+        default: None,
+        wire_hints: None,
+    }
+}
+
 /// Transmit hours: total hours the radar has been transmitting (read-only)
 pub fn control_transmit_hours() -> ControlDefinition {
     ControlDefinition {
@@ -892,6 +961,60 @@ pub fn control_antenna_height() -> ControlDefinition {
     }
 }
 
+/// EBL: electronic bearing line marker, kept in the server
+///
+/// Furuno: command 0x70 is unconfirmed, so the marker is not sent to the radar.
+#[inline(never)]
+pub fn control_ebl() -> ControlDefinition {
+    ControlDefinition {
+        id: "ebl".into(),
+        name: "EBL".into(),
+        description: "Bearing of the electronic bearing line marker.".into(),
+        category: ControlCategory::Extended,
+        control_type: ControlType::Number,
+        range: Some(RangeSpec {
+            min: 0.0,
+            max: 359.9,
+            step: Some(0.1),
+            unit: Some("degrees".into()),
+        }),
+        values: None,
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: false,
+        default: None,
+        wire_hints: None,
+    }
+}
+
+/// VRM: variable range marker, kept in the server
+///
+/// Furuno: command 0x71 is unconfirmed, so the marker is not sent to the radar.
+#[inline(never)]
+pub fn control_vrm() -> ControlDefinition {
+    ControlDefinition {
+        id: "vrm".into(),
+        name: "VRM".into(),
+        description: "Radius of the variable range marker ring.".into(),
+        category: ControlCategory::Extended,
+        control_type: ControlType::Number,
+        range: Some(RangeSpec {
+            min: 0.0,
+            max: 133344.0, // 72 NM
+            step: Some(1.0),
+            unit: Some("m".into()),
+        }),
+        values: None,
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: false,
+        default: None,
+        wire_hints: None,
+    }
+}
+
 /// No-transmit zones: sectors where radar won't transmit
 pub fn control_no_transmit_zones(zone_count: u8) -> ControlDefinition {
     ControlDefinition {
@@ -1031,6 +1154,7 @@ pub fn control_noise_reduction() -> ControlDefinition {
 /// Main bang suppression: reduces center artifact
 ///
 /// Furuno: Command 0x83
+/// Navico: Main bang suppression (0x32 C1, HALO only)
 /// Raymarine: Main bang suppression enabled
 pub fn control_main_bang_suppression() -> ControlDefinition {
     ControlDefinition {
@@ -1167,6 +1291,98 @@ pub fn control_sea_state() -> ControlDefinition {
     }
 }
 
+/// Auto response: how quickly auto gain and auto sea react to changes
+///
+/// Navico: Auto response (0x33 C1, HALO only)
+pub fn control_auto_response() -> ControlDefinition {
+    ControlDefinition {
+        id: "autoResponse".into(),
+        name: "Auto Response".into(),
+        description: "How quickly automatic gain and sea clutter follow changing conditions.".into(),
+        category: ControlCategory::Extended,
+        control_type: ControlType::Enum,
+        range: None,
+        values: Some(vec![
+            EnumValue {
+                value: 0.into(),
+                label: "Slow".into(),
+                description: Some("Steady picture, slow to follow changes".into()),
+            },
+            EnumValue {
+                value: 1.into(),
+                label: "Medium".into(),
+                description: None,
+            },
+            EnumValue {
+                value: 2.into(),
+                label: "Fast".into(),
+                description: Some("Follows quickly changing sea conditions".into()),
+            },
+        ]),
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: false,
+        default: Some(1.into()),
+        wire_hints: None,
+    }
+}
+
+/// LIRF: local interference source rejection, an on/off filter next to the
+/// interference rejection level
+///
+/// Navico: LIRF (0x34 C1, HALO only)
+pub fn control_lirf() -> ControlDefinition {
+    ControlDefinition {
+        id: "lirf".into(),
+        name: "Interference Source Rejection".into(),
+        description: "Suppresses interference from nearby radars, e.g. in a busy marina.".into(),
+        category: ControlCategory::Extended,
+        control_type: ControlType::Boolean,
+        range: None,
+        values: None,
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: false,
+        default: Some(false.into()),
+        wire_hints: None,
+    }
+}
+
+/// Rotation direction: order in which the antenna reports its azimuth (read-only)
+///
+/// Navico: Report 04 byte 14. Spokes are normalized to clockwise either way.
+#[inline(never)]
+pub fn control_rotation_direction() -> ControlDefinition {
+    ControlDefinition {
+        id: "rotationDirection".into(),
+        name: "Rotation Direction".into(),
+        description: "Direction in which the antenna reports its azimuth.".into(),
+        category: ControlCategory::Installation,
+        control_type: ControlType::Enum,
+        range: None,
+        values: Some(vec![
+            EnumValue {
+                value: 0.into(),
+                label: "Clockwise".into(),
+                description: None,
+            },
+            EnumValue {
+                value: 1.into(),
+                label: "Counter-clockwise".into(),
+                description: None,
+            },
+        ]),
+        properties: None,
+        modes: None,
+        default_mode: None,
+        read_only: true,
+        default: None,
+        wire_hints: None,
+    }
+}
+
 /// Sidelobe suppression: reduces sidelobe artifacts
 ///
 /// Navico: Sidelobe Suppression (0x06 C1 subtype 0x05)
@@ -1601,6 +1817,7 @@ pub fn get_extended_control(id: &str) -> Option<ControlDefinition> {
         // Interference
         "interferenceRejection" => Some(control_interference_rejection()),
         "localInterferenceRejection" => Some(control_local_interference_rejection()),
+        "lirf" => Some(control_lirf()),
         "crosstalkRejection" => Some(control_crosstalk_rejection()),
         "sidelobeSuppression" => Some(control_sidelobe_suppression()),
         // Target processing
@@ -1609,6 +1826,7 @@ pub fn get_extended_control(id: &str) -> Option<ControlDefinition> {
         "targetBoost" => Some(control_target_boost()),
         // Clutter
         "seaState" => Some(control_sea_state()),
+        "autoResponse" => Some(control_auto_response()),
         "ftc" => Some(control_ftc()),
         // Modes
         "presetMode" => Some(control_preset_mode()),
@@ -1620,10 +1838,17 @@ pub fn get_extended_control(id: &str) -> Option<ControlDefinition> {
         // Installation
         "bearingAlignment" => Some(control_bearing_alignment()),
         "antennaHeight" => Some(control_antenna_height()),
+        "rotationDirection" => Some(control_rotation_direction()),
         // Acquisition
         "autoAcquire" => Some(control_auto_acquire()),
+        // Markers
+        "ebl" => Some(control_ebl()),
+        "vrm" => Some(control_vrm()),
         // Hardware
         "accentLight" => Some(control_accent_light()),
+        "warmupSeconds" => Some(control_warmup_seconds()),
+        "blankingActive" => Some(control_blanking_active()),
+        "antennaKind" => Some(control_antenna_kind()),
         _ => None,
     }
 }
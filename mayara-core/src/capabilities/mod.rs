@@ -82,6 +82,118 @@ pub struct CapabilityManifest {
     /// - `dualRange`: Dual-range simultaneous display
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub supported_features: Vec<SupportedFeature>,
+
+    /// How spoke intensities map to meaning and color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intensity_legend: Option<IntensityLegend>,
+}
+
+/// Describes the intensity values in the spokes, so all clients render the
+/// same colors
+///
+/// Intensities run from `minIntensity` (no return) to `maxIntensity`
+/// (strongest return); `stops` is the recommended colormap, colors in
+/// between are interpolated linearly. Values above `maxIntensity` are not
+/// intensities but the special values listed in `doppler`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntensityLegend {
+    /// Bits of intensity the radar sends per cell
+    pub bit_depth: u8,
+    pub min_intensity: u8,
+    pub max_intensity: u8,
+    /// Recommended colormap, ordered by value
+    pub stops: Vec<ColorStop>,
+    /// Cell values marking Doppler returns, for radars that have Doppler
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doppler: Option<DopplerLegend>,
+}
+
+/// One point of the recommended colormap
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorStop {
+    pub value: u8,
+    /// `#rrggbbaa`
+    pub color: String,
+    pub label: String,
+}
+
+/// Cell values used for targets coming closer or moving away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DopplerLegend {
+    pub approaching: u8,
+    pub approaching_color: String,
+    pub receding: u8,
+    pub receding_color: String,
+}
+
+impl IntensityLegend {
+    /// The default colormap for `pixel_values` intensity levels: transparent
+    /// for no return, then blue through green to red at 1/3 and 2/3 of the
+    /// range, as drawn by the server's own display
+    pub fn new(pixel_values: u8) -> Self {
+        let max = pixel_values.saturating_sub(1);
+        let one_third = (max / 3).max(1);
+        let two_thirds = one_third * 2;
+        let color = |v: u8| {
+            const MIN: f64 = 85.0;
+            const RANGE: f64 = 255.0 - MIN;
+            let ramp = |n: i32| (MIN + RANGE * n as f64 / one_third as f64) as u8;
+            let (v, ot, tt) = (v as i32, one_third as i32, two_thirds as i32);
+            let r = if v >= tt { ramp(v - tt) } else { 0 };
+            let g = if v >= tt {
+                ramp(max as i32 - v)
+            } else if v >= ot {
+                ramp(v - ot)
+            } else {
+                0
+            };
+            let b = if v < ot {
+                ramp(v)
+            } else if v < tt {
+                ramp(tt - v)
+            } else {
+                0
+            };
+            format!("#{:02x}{:02x}{:02x}ff", r, g, b)
+        };
+
+        let mut stops = vec![ColorStop {
+            value: 0,
+            color: "#00000000".to_string(),
+            label: "none".to_string(),
+        }];
+        for (value, label) in [(1, "weak"), (one_third, "medium"), (two_thirds, "strong"), (max, "max")] {
+            if value <= max && stops.last().is_some_and(|s| s.value < value) {
+                stops.push(ColorStop {
+                    value,
+                    color: color(value),
+                    label: label.to_string(),
+                });
+            }
+        }
+
+        IntensityLegend {
+            bit_depth: (u8::BITS - max.leading_zeros()) as u8,
+            min_intensity: 0,
+            max_intensity: max,
+            stops,
+            doppler: None,
+        }
+    }
+
+    /// Add the cell values marking Doppler returns
+    pub fn with_doppler(mut self, approaching: u8, receding: u8) -> Self {
+        self.doppler = Some(DopplerLegend {
+            approaching,
+            approaching_color: "#ff00ffff".to_string(),
+            receding,
+            receding_color: "#00ff00ff".to_string(),
+        });
+        self
+    }
 }
 
 /// Hardware characteristics of the radar
@@ -409,6 +521,48 @@ pub struct RadarStateV5 {
     /// Controls currently disabled and why
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub disabled_controls: Vec<DisabledControl>,
+
+    /// Current range formatted in the preferred range units
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_label: Option<String>,
+
+    /// Rotations per minute, estimated from the time between rotations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rpm: Option<f64>,
+
+    /// Sectors the radar masks itself, e.g. behind a mast; not user blanking
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blind_sectors: Vec<BlindSector>,
+
+    /// "offline" when nothing has been heard from the radar for a while
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+
+    /// Offsets to apply when overlaying the image on a chart
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlay_alignment: Option<OverlayAlignment>,
+}
+
+/// A sector without picture that the radar learned, from the bow, in the
+/// server's bearing unit (degrees by default)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlindSector {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// How the radar image lines up with a chart. The bearing offset is the
+/// bearing alignment the radar reports (and applies to its spokes); the range
+/// offset is added to the range labels but not to the spoke ranges, so a
+/// client overlaying the image adds it itself.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayAlignment {
+    /// Positive clockwise, in the server's bearing unit (degrees by default)
+    pub bearing_offset: f64,
+    /// Meters
+    pub range_offset: f64,
 }
 
 /// Information about a disabled control
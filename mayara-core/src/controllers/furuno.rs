@@ -40,7 +40,7 @@ use crate::protocol::furuno::command::{
     format_request_modules, format_request_ontime, format_request_txtime, format_rezboost_command,
     format_scan_speed_command, format_sea_command, format_status_command,
     format_target_analyzer_command, format_tx_channel_command, parse_login_response,
-    LOGIN_MESSAGE,
+    strip_checksum, LOGIN_MESSAGE,
 };
 use crate::protocol::furuno::{BASE_PORT, BEACON_PORT};
 use crate::state::{generate_state_requests, RadarState};
@@ -708,7 +708,10 @@ impl FurunoController {
             let line = String::from_utf8_lossy(&buf[..len]);
             let line = line.trim();
             io.debug(&format!("[{}] Response: {}", self.radar_id, line));
-            self.parse_response(io, line);
+            match strip_checksum(line) {
+                Some(line) => self.parse_response(io, line),
+                None => io.debug(&format!("[{}] Ignoring response with bad checksum", self.radar_id)),
+            }
         }
 
         // Re-request state when radar transitions to transmit mode
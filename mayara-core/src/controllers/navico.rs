@@ -350,8 +350,7 @@ impl NavicoController {
 
     /// Set target expansion (0-2)
     pub fn set_target_expansion<I: IoProvider>(&mut self, io: &mut I, level: u8) {
-        let cmd_id = if self.model.is_halo() { 0x12 } else { 0x09 };
-        let cmd = [cmd_id, 0xC1, level];
+        let cmd = navico::create_target_expansion_command(level, self.model.is_halo());
         self.send_command(io, &cmd);
         io.debug(&format!("[{}] Set target expansion: {}", self.radar_id, level));
     }
@@ -439,9 +438,10 @@ impl NavicoController {
 
     /// Set noise rejection (0-3)
     pub fn set_noise_rejection<I: IoProvider>(&mut self, io: &mut I, level: u8) {
-        let cmd = [0x21, 0xC1, level];
-        self.send_command(io, &cmd);
-        io.debug(&format!("[{}] Set noise rejection: {}", self.radar_id, level));
+        if let Some(level) = navico::NoiseRejection::from_byte(level) {
+            self.send_command(io, &navico::create_noise_rejection_command(level));
+            io.debug(&format!("[{}] Set noise rejection: {}", self.radar_id, level.label()));
+        }
     }
 
     /// Set target separation (0-3)
@@ -460,6 +460,33 @@ impl NavicoController {
         }
     }
 
+    /// Set main bang suppression (HALO only, 0-255)
+    pub fn set_main_bang_suppression<I: IoProvider>(&mut self, io: &mut I, level: u8) {
+        if self.model.is_halo() {
+            self.send_command(io, &navico::create_main_bang_suppression_command(level));
+            io.debug(&format!("[{}] Set main bang suppression: {}", self.radar_id, level));
+        }
+    }
+
+    /// Set auto response (HALO only, 0=slow, 1=medium, 2=fast)
+    pub fn set_auto_response<I: IoProvider>(&mut self, io: &mut I, value: u8) {
+        if !self.model.is_halo() {
+            return;
+        }
+        if let Some(response) = navico::AutoResponse::from_byte(value) {
+            self.send_command(io, &navico::create_auto_response_command(response));
+            io.debug(&format!("[{}] Set auto response: {}", self.radar_id, response.label()));
+        }
+    }
+
+    /// Set local interference source rejection (HALO only)
+    pub fn set_lirf<I: IoProvider>(&mut self, io: &mut I, enabled: bool) {
+        if self.model.is_halo() {
+            self.send_command(io, &navico::create_lirf_command(enabled));
+            io.debug(&format!("[{}] Set LIRF: {}", self.radar_id, enabled));
+        }
+    }
+
     /// Set no-transmit zone (sector 0-3)
     /// Start and end angles are in deci-degrees (0-3599)
     pub fn set_no_transmit_zone<I: IoProvider>(
@@ -24,6 +24,37 @@
 //! ```
 
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Largest ratio between the two ranges the radar accepts. The secondary
+/// picture is built from the same sweep; beyond this the radar silently keeps
+/// the previous pair.
+pub const MAX_DUAL_RANGE_RATIO: u32 = 32;
+
+/// Reasons a dual-range pair is rejected
+#[derive(Error, Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "camelCase")]
+pub enum DualRangeError {
+    /// The radar (model) has no dual-range mode
+    #[error("Radar does not support dual-range")]
+    NotSupported,
+
+    /// The secondary range is above the hardware limit
+    #[error("Secondary range {secondary} exceeds maximum {maximum}")]
+    ExceedsMaximum { secondary: u32, maximum: u32 },
+
+    /// The range is not one of the radar's ranges
+    #[error("Range {range} is not in the radar's range table")]
+    UnknownRange { range: u32 },
+
+    /// Both ranges are valid on their own but not together
+    #[error("Ranges {primary} and {secondary} cannot be combined, ratio is limited to {max_ratio}")]
+    CombinationNotAllowed {
+        primary: u32,
+        secondary: u32,
+        max_ratio: u32,
+    },
+}
 
 /// Dual-range display configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -123,18 +154,49 @@ impl DualRangeController {
         true
     }
 
+    /// Check a primary/secondary pair against what the radar allows.
+    ///
+    /// A primary range of 0 means it is not known yet, and only the
+    /// secondary range is checked.
+    pub fn validate_pair(&self, primary: u32, secondary: u32) -> Result<(), DualRangeError> {
+        if secondary > self.state.max_secondary_range {
+            return Err(DualRangeError::ExceedsMaximum {
+                secondary,
+                maximum: self.state.max_secondary_range,
+            });
+        }
+        if !self.available_ranges.is_empty() && !self.available_ranges.contains(&secondary) {
+            return Err(DualRangeError::UnknownRange { range: secondary });
+        }
+        if primary > 0 && secondary > 0 {
+            let (short, long) = (primary.min(secondary), primary.max(secondary));
+            if long > short.saturating_mul(MAX_DUAL_RANGE_RATIO) {
+                return Err(DualRangeError::CombinationNotAllowed {
+                    primary,
+                    secondary,
+                    max_ratio: MAX_DUAL_RANGE_RATIO,
+                });
+            }
+        }
+        Ok(())
+    }
+
     /// Apply a configuration update
     ///
     /// Returns true if all values were accepted
     pub fn apply_config(&mut self, config: &DualRangeConfig) -> bool {
-        self.state.enabled = config.enabled;
+        self.try_apply_config(config).is_ok()
+    }
+
+    /// Apply a configuration update, validating the resulting pair first.
+    /// Nothing is changed when the pair is rejected.
+    pub fn try_apply_config(&mut self, config: &DualRangeConfig) -> Result<(), DualRangeError> {
         if config.secondary_range > 0 {
-            if config.secondary_range > self.state.max_secondary_range {
-                return false;
-            }
+            self.validate_pair(self.state.primary_range, config.secondary_range)?;
             self.state.secondary_range = config.secondary_range;
         }
-        true
+        self.state.enabled = config.enabled;
+        Ok(())
     }
 
     /// Find the closest valid secondary range to the requested value
@@ -186,6 +248,44 @@ mod tests {
         assert_eq!(controller.find_closest_range(5000), 3704);
     }
 
+    #[test]
+    fn test_disallowed_combination_rejected() {
+        let ranges = vec![231, 463, 926, 1852, 3704, 7408, 14816, 22224, 44448, 88896];
+        let mut controller = DualRangeController::new(22224, ranges);
+        controller.set_primary_range(88896);
+
+        // 88896 / 463 is more than the allowed ratio
+        let config = DualRangeConfig {
+            enabled: true,
+            secondary_range: 463,
+        };
+        assert_eq!(
+            controller.try_apply_config(&config),
+            Err(DualRangeError::CombinationNotAllowed {
+                primary: 88896,
+                secondary: 463,
+                max_ratio: MAX_DUAL_RANGE_RATIO,
+            })
+        );
+        assert!(!controller.state().enabled);
+        assert_eq!(controller.state().secondary_range, 926); // Unchanged
+
+        assert_eq!(
+            controller.validate_pair(1852, 1000),
+            Err(DualRangeError::UnknownRange { range: 1000 })
+        );
+
+        let config = DualRangeConfig {
+            enabled: true,
+            secondary_range: 3704,
+        };
+        assert!(controller.try_apply_config(&config).is_ok());
+        assert_eq!(controller.state().secondary_range, 3704);
+
+        let json = serde_json::to_string(&DualRangeError::UnknownRange { range: 1000 }).unwrap();
+        assert_eq!(json, r#"{"reason":"unknownRange","range":1000}"#);
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = DualRangeConfig {
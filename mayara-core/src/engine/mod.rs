@@ -35,7 +35,7 @@ use crate::controllers::{
     FurunoController, GarminController, NavicoController, NavicoModel, RaymarineController,
     RaymarineVariant,
 };
-use crate::dual_range::{DualRangeConfig, DualRangeController, DualRangeState};
+use crate::dual_range::{DualRangeConfig, DualRangeController, DualRangeError, DualRangeState};
 use crate::guard_zones::{GuardZone, GuardZoneProcessor, GuardZoneStatus};
 use crate::io::IoProvider;
 use crate::models::{self, ModelInfo};
@@ -481,6 +481,25 @@ impl RadarEngine {
             .unwrap_or(false)
     }
 
+    /// Apply dual-range configuration after checking the pair against the
+    /// radar's current primary range, if known
+    pub fn try_set_dual_range(
+        &mut self,
+        radar_id: &str,
+        primary_range: Option<u32>,
+        config: &DualRangeConfig,
+    ) -> Result<(), DualRangeError> {
+        let dr = self
+            .radars
+            .get_mut(radar_id)
+            .and_then(|r| r.dual_range.as_mut())
+            .ok_or(DualRangeError::NotSupported)?;
+        if let Some(primary) = primary_range {
+            dr.set_primary_range(primary);
+        }
+        dr.try_apply_config(config)
+    }
+
     /// Get available secondary ranges for dual-range
     pub fn get_dual_range_available_ranges(&self, radar_id: &str) -> Vec<u32> {
         self.radars
@@ -0,0 +1,163 @@
+//! Geographic positions of radar echoes
+//!
+//! The radar measures targets relative to the antenna: a bearing from the bow
+//! and a distance. Chartplotters want latitude and longitude instead. Given
+//! the own-ship position and heading the target position follows from the
+//! direct geodesic problem on the WGS84 ellipsoid, solved with Vincenty's
+//! formulae (sub-millimeter accurate at radar distances).
+//!
+//! Without a position or heading only the relative position is known, and
+//! [`TargetPosition`] carries just that.
+
+use serde::{Deserialize, Serialize};
+
+/// WGS84 semi-major axis in meters
+pub const WGS84_A: f64 = 6_378_137.0;
+/// WGS84 flattening
+pub const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Point reached from `lat`/`lon` (degrees) going `distance` meters on the
+/// initial true bearing `bearing` (degrees). Returns (lat, lon) in degrees,
+/// the longitude normalized to -180..180.
+pub fn destination(lat: f64, lon: f64, bearing: f64, distance: f64) -> (f64, f64) {
+    let (a, f) = (WGS84_A, WGS84_F);
+    let b = a * (1.0 - f);
+
+    let (sin_alpha1, cos_alpha1) = bearing.to_radians().sin_cos();
+    let tan_u1 = (1.0 - f) * lat.to_radians().tan();
+    let cos_u1 = 1.0 / (1.0 + tan_u1 * tan_u1).sqrt();
+    let sin_u1 = tan_u1 * cos_u1;
+
+    let sigma1 = tan_u1.atan2(cos_alpha1);
+    let sin_alpha = cos_u1 * sin_alpha1;
+    let cos_sq_alpha = 1.0 - sin_alpha * sin_alpha;
+    let u_sq = cos_sq_alpha * (a * a - b * b) / (b * b);
+    let big_a = 1.0 + u_sq / 16384.0 * (4096.0 + u_sq * (-768.0 + u_sq * (320.0 - 175.0 * u_sq)));
+    let big_b = u_sq / 1024.0 * (256.0 + u_sq * (-128.0 + u_sq * (74.0 - 47.0 * u_sq)));
+
+    let mut sigma = distance / (b * big_a);
+    let (mut sin_sigma, mut cos_sigma, mut cos_2sigma_m);
+    let mut iterations = 0;
+    loop {
+        cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+        (sin_sigma, cos_sigma) = sigma.sin_cos();
+        let delta_sigma = big_b
+            * sin_sigma
+            * (cos_2sigma_m
+                + big_b / 4.0
+                    * (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)
+                        - big_b / 6.0
+                            * cos_2sigma_m
+                            * (-3.0 + 4.0 * sin_sigma * sin_sigma)
+                            * (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+        let previous = sigma;
+        sigma = distance / (b * big_a) + delta_sigma;
+        iterations += 1;
+        if (sigma - previous).abs() < 1e-12 || iterations >= 100 {
+            break;
+        }
+    }
+
+    let x = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+    let lat2 = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1)
+        .atan2((1.0 - f) * (sin_alpha * sin_alpha + x * x).sqrt());
+    let lambda = (sin_sigma * sin_alpha1).atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+    let c = f / 16.0 * cos_sq_alpha * (4.0 + f * (4.0 - 3.0 * cos_sq_alpha));
+    let l = lambda
+        - (1.0 - c)
+            * f
+            * sin_alpha
+            * (sigma + c * sin_sigma * (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+
+    let lon2 = (lon + l.to_degrees() + 540.0).rem_euclid(360.0) - 180.0;
+    (lat2.to_degrees(), lon2)
+}
+
+/// Position of a target, relative to the radar and, when the own-ship
+/// position and heading are known, on the chart
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetPosition {
+    /// Bearing from the bow in degrees
+    pub bearing: f64,
+    /// Distance from the antenna in meters
+    pub distance: f64,
+    /// Bearing from true north in degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub true_bearing: Option<f64>,
+    /// WGS84 latitude in degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    /// WGS84 longitude in degrees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+}
+
+impl TargetPosition {
+    /// Locate a target seen at `bearing` (degrees from the bow) and `distance`
+    /// (meters) from a ship at `own_position` (lat, lon) steering `heading`
+    /// (degrees true). Missing either leaves only the relative position.
+    pub fn locate(
+        bearing: f64,
+        distance: f64,
+        own_position: Option<(f64, f64)>,
+        heading: Option<f64>,
+    ) -> Self {
+        let true_bearing = heading.map(|heading| (heading + bearing).rem_euclid(360.0));
+        let absolute = match (own_position, true_bearing) {
+            (Some((lat, lon)), Some(true_bearing)) => Some(destination(lat, lon, true_bearing, distance)),
+            _ => None,
+        };
+        TargetPosition {
+            bearing,
+            distance,
+            true_bearing,
+            latitude: absolute.map(|(lat, _)| lat),
+            longitude: absolute.map(|(_, lon)| lon),
+        }
+    }
+
+    /// Whether the position is only known relative to the radar
+    pub fn is_relative_only(&self) -> bool {
+        self.latitude.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_destination_along_equator_and_meridian() {
+        // One degree of longitude on the equator is a / 57.29... meters
+        let (lat, lon) = destination(0.0, 0.0, 90.0, 111_319.4908);
+        assert!(lat.abs() < 1e-9);
+        assert!((lon - 1.0).abs() < 1e-6);
+
+        // The WGS84 meridian arc from the equator to 1 degree north
+        let (lat, lon) = destination(0.0, 0.0, 0.0, 110_574.3886);
+        assert!((lat - 1.0).abs() < 1e-6);
+        assert!(lon.abs() < 1e-9);
+
+        // Crossing the antimeridian wraps the longitude
+        let (_, lon) = destination(0.0, 179.5, 90.0, 111_319.4908);
+        assert!((lon + 179.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_locate_target() {
+        // Own ship heading 045, a target 45 degrees to starboard lies due east
+        let own = (52.0, 4.0);
+        let target = TargetPosition::locate(45.0, 1852.0, Some(own), Some(45.0));
+        assert_eq!(target.true_bearing, Some(90.0));
+        let (lat, lon) = (target.latitude.unwrap(), target.longitude.unwrap());
+        assert!((lat - 52.0).abs() < 0.0002); // A great circle east bends south a little
+        let east_m = (lon - 4.0) * std::f64::consts::PI / 180.0 * WGS84_A * 52f64.to_radians().cos();
+        assert!((east_m - 1852.0).abs() < 10.0);
+
+        let relative = TargetPosition::locate(45.0, 1852.0, None, Some(45.0));
+        assert!(relative.is_relative_only());
+        assert_eq!(relative.true_bearing, Some(90.0));
+        assert!(TargetPosition::locate(45.0, 1852.0, Some(own), None).is_relative_only());
+    }
+}
@@ -5,6 +5,9 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
+use crate::geo::TargetPosition;
+use crate::spoke_scale::SpokeScale;
+
 /// Guard zone shape
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -133,12 +136,16 @@ impl GuardZone {
     }
 }
 
-/// Guard zone alert event
+/// Guard zone alert event, raised when a target enters a zone and when the
+/// zone is clear again
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ZoneAlert {
     /// Zone ID that triggered the alert
     pub zone_id: u32,
+    /// The state the zone went to: `Alarm` when a target entered it, `Clear`
+    /// when it left; a clear alert has where the target was last seen
+    pub state: ZoneAlertState,
     /// Timestamp of the alert (milliseconds)
     pub timestamp: u64,
     /// Bearing where intrusion was detected
@@ -147,6 +154,9 @@ pub struct ZoneAlert {
     pub distance: f64,
     /// Peak intensity of the detection
     pub intensity: u8,
+    /// Where the intrusion was detected, on the chart when the own-ship
+    /// position and heading were known, see `GuardZoneProcessor::set_own_ship`
+    pub position: TargetPosition,
 }
 
 /// Alert state for a zone
@@ -174,6 +184,10 @@ struct ZoneState {
     last_alert: Option<u64>,
     /// Consecutive clear scans (for hysteresis)
     clear_count: u32,
+    /// Whether an intrusion was seen in the current scan
+    seen: bool,
+    /// The last intrusion seen, while in alarm
+    last_seen: Option<ZoneAlert>,
 }
 
 /// Guard zone processor
@@ -185,8 +199,13 @@ pub struct GuardZoneProcessor {
     states: HashMap<u32, ZoneState>,
     /// Current range scale in meters
     range_scale: f64,
-    /// Number of clear scans required to clear alarm
+    /// Meters added to the range scale, as for the range labels
+    range_offset: f64,
+    /// Number of clear scans (revolutions) required to clear alarm
     hysteresis_count: u32,
+    /// Own-ship position (lat, lon) and heading (degrees true), to locate alerts
+    own_position: Option<(f64, f64)>,
+    heading: Option<f64>,
 }
 
 impl GuardZoneProcessor {
@@ -196,7 +215,10 @@ impl GuardZoneProcessor {
             zones: HashMap::new(),
             states: HashMap::new(),
             range_scale: 1852.0,
+            range_offset: 0.0,
             hysteresis_count: 3,
+            own_position: None,
+            heading: None,
         }
     }
 
@@ -205,6 +227,18 @@ impl GuardZoneProcessor {
         self.range_scale = range_meters;
     }
 
+    /// Set the range offset of the radar's preferences
+    pub fn set_range_offset(&mut self, range_offset: f64) {
+        self.range_offset = range_offset;
+    }
+
+    /// Set the own-ship position (lat, lon) and heading (degrees true) that
+    /// alerts are located from; either `None` leaves only relative positions
+    pub fn set_own_ship(&mut self, position: Option<(f64, f64)>, heading: Option<f64>) {
+        self.own_position = position;
+        self.heading = heading;
+    }
+
     /// Add or update a guard zone
     pub fn add_zone(&mut self, zone: GuardZone) {
         let id = zone.id;
@@ -235,9 +269,7 @@ impl GuardZoneProcessor {
             if !enabled {
                 // Reset state when disabled
                 if let Some(state) = self.states.get_mut(&zone_id) {
-                    state.alert_state = ZoneAlertState::Clear;
-                    state.last_alert = None;
-                    state.clear_count = 0;
+                    *state = ZoneState::default();
                 }
             }
             true
@@ -254,7 +286,8 @@ impl GuardZoneProcessor {
             .unwrap_or_default()
     }
 
-    /// Check a radar spoke for zone intrusions
+    /// Check a radar spoke for zone intrusions. A zone in alarm is only
+    /// cleared by `end_revolution`, when whole scans saw nothing in it.
     ///
     /// # Arguments
     ///
@@ -264,14 +297,12 @@ impl GuardZoneProcessor {
     ///
     /// # Returns
     ///
-    /// Vector of alert events for zones that detected intrusions
+    /// Vector of alert events for zones that a target entered
     pub fn check_spoke(&mut self, spoke_data: &[u8], bearing: f64, timestamp: u64) -> Vec<ZoneAlert> {
         let mut alerts = Vec::new();
-        let samples = spoke_data.len();
-
-        if samples == 0 {
+        let Some(scale) = SpokeScale::new(self.range_scale, spoke_data.len(), self.range_offset) else {
             return alerts;
-        }
+        };
 
         // Check each enabled zone
         for (&zone_id, zone) in &self.zones {
@@ -315,15 +346,16 @@ impl GuardZoneProcessor {
                 } => (*inner_radius, *outer_radius),
             };
 
-            // Convert distance to sample indices
-            let inner_idx = ((inner / self.range_scale) * samples as f64) as usize;
-            let outer_idx = ((outer / self.range_scale) * samples as f64).min(samples as f64) as usize;
+            // Convert distance to sample indices, including the cells the
+            // zone edges cross
+            let inner_idx = scale.cells_within(inner);
+            let outer_idx = scale.cells_before(outer);
 
             // Find peak intensity in the zone range
             let mut peak_intensity: u8 = 0;
             let mut peak_idx = 0;
 
-            for i in inner_idx..outer_idx.min(samples) {
+            for i in inner_idx..outer_idx {
                 if spoke_data[i] > peak_intensity {
                     peak_intensity = spoke_data[i];
                     peak_idx = i;
@@ -335,46 +367,65 @@ impl GuardZoneProcessor {
 
             if peak_intensity >= zone.sensitivity {
                 // Intrusion detected
-                let distance = (peak_idx as f64 / samples as f64) * self.range_scale;
+                let distance = scale.radius_to_meters(peak_idx as f64);
+                let alert = ZoneAlert {
+                    zone_id,
+                    state: ZoneAlertState::Alarm,
+                    timestamp,
+                    bearing,
+                    distance,
+                    intensity: peak_intensity,
+                    position: TargetPosition::locate(bearing, distance, self.own_position, self.heading),
+                };
 
                 // Only emit alert on state change to Alarm
                 if state.alert_state != ZoneAlertState::Alarm {
                     state.alert_state = ZoneAlertState::Alarm;
                     state.last_alert = Some(timestamp);
-                    alerts.push(ZoneAlert {
-                        zone_id,
-                        timestamp,
-                        bearing,
-                        distance,
-                        intensity: peak_intensity,
-                    });
+                    alerts.push(alert.clone());
                 }
+                state.seen = true;
+                state.last_seen = Some(alert);
                 state.clear_count = 0;
-            } else {
-                // No intrusion on this sweep
-                if state.alert_state == ZoneAlertState::Alarm {
-                    state.clear_count += 1;
-                    if state.clear_count >= self.hysteresis_count {
-                        state.alert_state = ZoneAlertState::Clear;
-                    }
-                }
             }
         }
 
         alerts
     }
 
-    /// Process end of revolution (for zones that track full scans)
-    pub fn end_revolution(&mut self, _timestamp: u64) {
-        // Could be used for zones that need full-scan analysis
-        // Currently a no-op placeholder
+    /// Process end of revolution: a zone in alarm that saw nothing during
+    /// `hysteresis_count` scans in a row is clear again
+    ///
+    /// # Returns
+    ///
+    /// Vector of alert events for zones that became clear
+    pub fn end_revolution(&mut self, timestamp: u64) -> Vec<ZoneAlert> {
+        let mut alerts = Vec::new();
+        for (&zone_id, state) in self.states.iter_mut() {
+            if state.alert_state == ZoneAlertState::Alarm && !state.seen {
+                state.clear_count += 1;
+                if state.clear_count >= self.hysteresis_count {
+                    state.alert_state = ZoneAlertState::Clear;
+                    state.clear_count = 0;
+                    if let Some(last_seen) = state.last_seen.take() {
+                        alerts.push(ZoneAlert {
+                            zone_id,
+                            state: ZoneAlertState::Clear,
+                            timestamp,
+                            ..last_seen
+                        });
+                    }
+                }
+            }
+            state.seen = false;
+        }
+        alerts
     }
 
     /// Clear all alert states
     pub fn clear_alerts(&mut self) {
         for state in self.states.values_mut() {
-            state.alert_state = ZoneAlertState::Clear;
-            state.clear_count = 0;
+            *state = ZoneState::default();
         }
     }
 
@@ -520,9 +571,29 @@ mod tests {
         assert_eq!(alerts.len(), 1);
         assert_eq!(alerts[0].zone_id, 1);
         assert!(alerts[0].distance > 450.0 && alerts[0].distance < 950.0);
+        assert!(alerts[0].position.is_relative_only());
         assert_eq!(processor.get_alert_state(1), ZoneAlertState::Alarm);
     }
 
+    #[test]
+    fn test_zone_alert_is_located_from_own_ship() {
+        let mut processor = GuardZoneProcessor::new();
+        processor.set_range_scale(1852.0);
+        processor.set_own_ship(Some((52.0, 4.0)), Some(90.0));
+        processor.add_zone(GuardZone::new_ring(1, 400.0, 1000.0));
+
+        let mut spoke = vec![0u8; 512];
+        spoke[200] = 200;
+        let alerts = processor.check_spoke(&spoke, 0.0, 1000);
+
+        // Dead ahead while heading east: same latitude, further east
+        let position = alerts[0].position;
+        assert_eq!(position.true_bearing, Some(90.0));
+        assert!((position.latitude.unwrap() - 52.0).abs() < 1e-4);
+        assert!(position.longitude.unwrap() > 4.0);
+        assert_eq!(position.distance, alerts[0].distance);
+    }
+
     #[test]
     fn test_zone_hysteresis() {
         let mut processor = GuardZoneProcessor::new();
@@ -535,19 +606,48 @@ mod tests {
         let mut spoke = vec![0u8; 512];
         spoke[200] = 200;
         processor.check_spoke(&spoke, 45.0, 1000);
+        assert!(processor.end_revolution(1500).is_empty());
         assert_eq!(processor.get_alert_state(1), ZoneAlertState::Alarm);
 
-        // Clear spoke - should not clear immediately (hysteresis)
+        // Clear scan - should not clear immediately (hysteresis)
         let clear_spoke = vec![0u8; 512];
         processor.check_spoke(&clear_spoke, 45.0, 2000);
+        assert!(processor.end_revolution(2500).is_empty());
         assert_eq!(processor.get_alert_state(1), ZoneAlertState::Alarm);
 
         processor.check_spoke(&clear_spoke, 45.0, 3000);
+        assert!(processor.end_revolution(3500).is_empty());
         assert_eq!(processor.get_alert_state(1), ZoneAlertState::Alarm);
 
-        // Third clear scan should clear the alarm
+        // Third clear scan should clear the alarm, where the target was last seen
         processor.check_spoke(&clear_spoke, 45.0, 4000);
+        let alerts = processor.end_revolution(4500);
         assert_eq!(processor.get_alert_state(1), ZoneAlertState::Clear);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].state, ZoneAlertState::Clear);
+        assert_eq!(alerts[0].timestamp, 4500);
+        assert_eq!(alerts[0].bearing, 45.0);
+    }
+
+    #[test]
+    fn test_zone_stays_in_alarm_while_each_scan_sees_the_target() {
+        let mut processor = GuardZoneProcessor::new();
+        processor.set_range_scale(1852.0);
+        processor.add_zone(GuardZone::new_ring(1, 400.0, 1000.0));
+
+        let mut target = vec![0u8; 512];
+        target[200] = 200;
+        let clear = vec![0u8; 512];
+        for scan in 0..5u64 {
+            // The target is in one spoke of many in the zone
+            let entered = processor.check_spoke(&target, 45.0, scan * 1000);
+            assert_eq!(entered.len(), if scan == 0 { 1 } else { 0 });
+            for bearing in 46..60 {
+                processor.check_spoke(&clear, bearing as f64, scan * 1000);
+            }
+            assert!(processor.end_revolution(scan * 1000 + 999).is_empty());
+            assert_eq!(processor.get_alert_state(1), ZoneAlertState::Alarm);
+        }
     }
 
     #[test]
@@ -583,6 +683,28 @@ mod tests {
         assert_eq!(processor.get_alert_state(1), ZoneAlertState::Clear);
     }
 
+    #[test]
+    fn test_zone_with_range_offset() {
+        let mut processor = GuardZoneProcessor::new();
+        processor.set_range_scale(1000.0);
+        processor.set_range_offset(24.0); // 2 m per sample over 512 samples
+
+        processor.add_zone(GuardZone::new_ring(1, 400.0, 500.0));
+
+        // The last sample that starts before the outer edge
+        let mut spoke = vec![0u8; 512];
+        spoke[249] = 200;
+        let alerts = processor.check_spoke(&spoke, 45.0, 1000);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].distance, 498.0);
+
+        // Starting on the outer edge is outside the zone
+        processor.clear_alerts();
+        let mut spoke = vec![0u8; 512];
+        spoke[250] = 200;
+        assert!(processor.check_spoke(&spoke, 45.0, 2000).is_empty());
+    }
+
     #[test]
     fn test_multiple_zones() {
         let mut processor = GuardZoneProcessor::new();
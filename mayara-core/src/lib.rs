@@ -48,6 +48,7 @@
 //! - [`io`] - Platform-agnostic I/O trait ([`IoProvider`])
 //! - [`locator`] - Radar discovery abstraction
 //! - [`arpa`] - Automatic Radar Plotting Aid (target tracking)
+//! - [`geo`] - WGS84 positions of targets from bearing and distance
 //!
 //! ## Feature Flags
 //!
@@ -111,14 +112,17 @@ pub mod controllers;
 pub mod dual_range;
 pub mod engine;
 pub mod error;
+pub mod geo;
 pub mod guard_zones;
 pub mod io;
 pub mod locator;
 pub mod models;
 pub mod protocol;
 pub mod radar;
+pub mod spoke_scale;
 pub mod state;
 pub mod trails;
+pub mod units;
 
 // Re-export commonly used types
 pub use brand::Brand;
@@ -131,5 +135,5 @@ pub use controllers::{
 pub use engine::{ManagedRadar, RadarController, RadarEngine};
 pub use error::ParseError;
 pub use io::{IoError, IoProvider, TcpSocketHandle, UdpSocketHandle};
-pub use locator::{BrandStatus, DiscoveredRadar, LocatorEvent, LocatorStatus, RadarLocator};
-pub use state::{ControlValueState, PowerState, RadarState};
+pub use locator::{BrandStatus, DiscoveredRadar, DiscoveryRetry, LocatorEvent, LocatorStatus, RadarLocator};
+pub use state::{ControlValueState, PowerState, RadarState, SettingsDiff, SettingsReportTracker};
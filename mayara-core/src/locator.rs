@@ -20,6 +20,44 @@ pub enum LocatorEvent {
     RadarDiscovered(RadarDiscovery),
     /// An existing radar's info was updated (e.g., model report received)
     RadarUpdated(RadarDiscovery),
+    /// No radar found yet, a new search attempt was started
+    Searching { attempt: u32 },
+    /// Still no radar found after the configured number of attempts
+    NothingFound { attempts: u32 },
+}
+
+/// Discovery retry schedule
+///
+/// While no radar has been found the locator retries discovery (re-sending
+/// the Furuno announce) with an exponentially increasing interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiscoveryRetry {
+    /// Interval before the first retry, in milliseconds
+    pub initial_interval_ms: u64,
+    /// Upper bound for the interval between retries, in milliseconds
+    pub max_interval_ms: u64,
+    /// Number of attempts after which `NothingFound` is reported
+    pub warn_after_attempts: u32,
+}
+
+impl Default for DiscoveryRetry {
+    fn default() -> Self {
+        Self {
+            initial_interval_ms: 2000,
+            max_interval_ms: 60000,
+            warn_after_attempts: 10,
+        }
+    }
+}
+
+impl DiscoveryRetry {
+    /// Interval to wait after the given attempt (1-based) before the next one
+    pub fn interval_ms(&self, attempt: u32) -> u64 {
+        let doublings = attempt.saturating_sub(1).min(32);
+        self.initial_interval_ms
+            .saturating_mul(1u64 << doublings)
+            .min(self.max_interval_ms.max(self.initial_interval_ms))
+    }
 }
 
 /// A discovered radar with its metadata
@@ -51,6 +89,9 @@ pub struct BrandStatus {
 pub struct LocatorStatus {
     /// Status of each brand's listener
     pub brands: Vec<BrandStatus>,
+    /// Search progress while no radar has been found, e.g. "searching (attempt 3)"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
 }
 
 /// Startup phase for staggered brand initialization
@@ -102,6 +143,13 @@ pub struct RadarLocator {
 
     /// Current startup phase for staggered initialization
     startup_phase: StartupPhase,
+
+    /// Retry schedule used while no radar has been found
+    retry: DiscoveryRetry,
+    /// Number of search attempts made so far
+    search_attempt: u32,
+    /// Time of the next search attempt
+    next_search_ms: Option<u64>,
 }
 
 impl RadarLocator {
@@ -118,9 +166,17 @@ impl RadarLocator {
             status: LocatorStatus::default(),
             furuno_interface: None,
             startup_phase: StartupPhase::NotStarted,
+            retry: DiscoveryRetry::default(),
+            search_attempt: 0,
+            next_search_ms: None,
         }
     }
 
+    /// Set the discovery retry schedule
+    pub fn set_discovery_retry(&mut self, retry: DiscoveryRetry) {
+        self.retry = retry;
+    }
+
     /// Set the interface IP to use for Furuno broadcasts.
     ///
     /// This is critical for multi-NIC setups to prevent broadcast packets
@@ -599,9 +655,41 @@ impl RadarLocator {
             }
         }
 
+        self.update_search(io, current_time_ms, &mut events);
+
         events
     }
 
+    /// Retry discovery with backoff while no radar has been found
+    fn update_search<I: IoProvider>(&mut self, io: &mut I, current_time_ms: u64, events: &mut Vec<LocatorEvent>) {
+        if !self.radars.is_empty() {
+            self.status.search = None;
+            self.next_search_ms = None;
+            return;
+        }
+
+        let next_search_ms = *self
+            .next_search_ms
+            .get_or_insert(current_time_ms + self.retry.interval_ms(1));
+        if current_time_ms < next_search_ms {
+            return;
+        }
+
+        self.search_attempt += 1;
+        self.next_search_ms = Some(current_time_ms + self.retry.interval_ms(self.search_attempt));
+        self.status.search = Some(format!("searching (attempt {})", self.search_attempt));
+        self.send_furuno_announce(io);
+
+        events.push(LocatorEvent::Searching {
+            attempt: self.search_attempt,
+        });
+        if self.search_attempt == self.retry.warn_after_attempts {
+            events.push(LocatorEvent::NothingFound {
+                attempts: self.search_attempt,
+            });
+        }
+    }
+
     fn poll_furuno<I: IoProvider>(
         &self,
         io: &mut I,
@@ -724,6 +812,21 @@ impl RadarLocator {
         }
     }
 
+    /// Forget the radars at `ip`, e.g. when they stopped sending, so that the
+    /// search starts over and their next beacon is reported as a discovery
+    /// again. Returns the number of radars forgotten.
+    pub fn forget(&mut self, ip: &str) -> usize {
+        let before = self.radars.len();
+        self.radars
+            .retain(|_, radar| radar.discovery.address.split(':').next() != Some(ip));
+        let forgotten = before - self.radars.len();
+        if forgotten > 0 {
+            self.search_attempt = 0;
+            self.next_search_ms = None;
+        }
+        forgotten
+    }
+
     fn make_radar_id(&self, discovery: &RadarDiscovery) -> String {
         if let Some(suffix) = &discovery.suffix {
             format!("{}-{}-{}", discovery.brand, discovery.name, suffix)
@@ -757,3 +860,58 @@ impl Default for RadarLocator {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discovery_backoff_schedule() {
+        let retry = DiscoveryRetry {
+            initial_interval_ms: 1000,
+            max_interval_ms: 30000,
+            warn_after_attempts: 5,
+        };
+        let schedule: Vec<u64> = (1..=7).map(|attempt| retry.interval_ms(attempt)).collect();
+        assert_eq!(schedule, vec![1000, 2000, 4000, 8000, 16000, 30000, 30000]);
+
+        // Large attempt counts do not overflow
+        assert_eq!(retry.interval_ms(u32::MAX), 30000);
+    }
+
+    #[test]
+    fn forgotten_radars_are_searched_for_again() {
+        fn discovery(name: &str, address: &str) -> DiscoveredRadar {
+            DiscoveredRadar {
+                discovery: RadarDiscovery {
+                    brand: Brand::Navico,
+                    model: None,
+                    name: name.to_string(),
+                    address: address.to_string(),
+                    data_port: 0,
+                    command_port: 0,
+                    spokes_per_revolution: 2048,
+                    max_spoke_len: 1024,
+                    pixel_values: 16,
+                    serial_number: None,
+                    nic_address: None,
+                    suffix: None,
+                    data_address: None,
+                    report_address: None,
+                    send_address: None,
+                },
+                last_seen_ms: 0,
+            }
+        }
+        let mut locator = RadarLocator::new();
+        locator.search_attempt = 3;
+        locator.radars.insert("a".into(), discovery("a", "192.168.1.10:6878"));
+        locator.radars.insert("b".into(), discovery("b", "192.168.1.11"));
+
+        assert_eq!(locator.forget("192.168.1.1"), 0);
+        assert_eq!(locator.search_attempt, 3);
+        assert_eq!(locator.forget("192.168.1.10"), 1);
+        assert_eq!(locator.radars.keys().collect::<Vec<_>>(), vec!["b"]);
+        assert_eq!(locator.search_attempt, 0);
+    }
+}
@@ -79,6 +79,8 @@ static CONTROLS_NXT: &[&str] = &[
     "noTransmitZones",
     "autoAcquire",         // Auto target acquisition
     "txChannel",           // TX channel selection
+    "ebl",                 // Markers kept in the server, not sent to the radar
+    "vrm",
     "bearingAlignment",    // Installation config - schema only, not in /state
     "antennaHeight",       // Installation config - schema only, not in /state
 ];
@@ -99,6 +101,8 @@ static CONTROLS_FAR: &[&str] = &[
     "interferenceRejection",
     "noTransmitZones",
     "txChannel",
+    "ebl",
+    "vrm",
     "bearingAlignment",    // Installation config - schema only, not in /state
     "antennaHeight",       // Installation config - schema only, not in /state
 ];
@@ -4,6 +4,8 @@
 //! range tables, and available controls. This information is used to build
 //! capability manifests for the v5 API.
 
+use serde::{Deserialize, Serialize};
+
 use crate::Brand;
 
 pub mod furuno;
@@ -68,6 +70,17 @@ pub static UNKNOWN_MODEL: ModelInfo = ModelInfo {
     controls: &[],
 };
 
+impl ModelInfo {
+    /// Number of intensity levels in the spokes of this model
+    pub fn pixel_values(&self) -> u8 {
+        match self.brand {
+            Brand::Furuno => 64,
+            Brand::Raymarine if self.family == "Quantum" => 128, // HD, the last bit is used for other data
+            Brand::Navico | Brand::Raymarine | Brand::Garmin => 16,
+        }
+    }
+}
+
 /// Look up a model by brand and model string
 ///
 /// Returns None if the model is not found in the database.
@@ -81,6 +94,44 @@ pub fn get_model(brand: Brand, model: &str) -> Option<&'static ModelInfo> {
     }
 }
 
+/// Whether the antenna is in a radome or an open array
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AntennaKind {
+    Dome,
+    OpenArray,
+}
+
+impl AntennaKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AntennaKind::Dome => "dome",
+            AntennaKind::OpenArray => "openArray",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "dome" => Some(AntennaKind::Dome),
+            "openArray" => Some(AntennaKind::OpenArray),
+            _ => None,
+        }
+    }
+}
+
+/// Get model information, for radars that only report their family taking
+/// the defaults of the antenna kind they reported
+pub fn get_model_with_antenna(
+    brand: Brand,
+    model: &str,
+    antenna: Option<AntennaKind>,
+) -> Option<&'static ModelInfo> {
+    match brand {
+        Brand::Navico => navico::get_model_for_antenna(model, antenna),
+        _ => get_model(brand, model),
+    }
+}
+
 /// Get all known models for a brand
 pub fn get_models_for_brand(brand: Brand) -> &'static [ModelInfo] {
     match brand {
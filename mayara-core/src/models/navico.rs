@@ -3,7 +3,7 @@
 //! This module contains specifications for Navico radar models
 //! (Simrad, Lowrance, B&G branded radars).
 
-use super::ModelInfo;
+use super::{AntennaKind, ModelInfo};
 use crate::Brand;
 
 /// Range table for HALO series (in meters)
@@ -68,6 +68,7 @@ static CONTROLS_HALO: &[&str] = &[
     "noiseRejection",
     "interferenceRejection",
     "localInterferenceRejection",
+    "lirf",                 // Interference source rejection flag
     "sidelobeSuppression",
     "birdMode",
     "noTransmitZones",
@@ -75,6 +76,12 @@ static CONTROLS_HALO: &[&str] = &[
     "antennaHeight",
     "scanSpeed",
     "accentLight",          // Pedestal lighting
+    "mainBangSuppression",  // Reduces the center blob
+    "autoResponse",         // Auto gain/sea reaction speed
+    "rotationDirection",    // Azimuth reporting order (read-only)
+    "warmupSeconds",        // Warmup countdown (read-only)
+    "blankingActive",       // No transmit sector blanked now (read-only)
+    "antennaKind",          // Dome or open array (read-only)
 ];
 
 /// Extended controls for 4G/3G series
@@ -90,6 +97,9 @@ static CONTROLS_4G: &[&str] = &[
     "noTransmitZones",
     "bearingAlignment",
     "antennaHeight",
+    "rotationDirection",
+    "warmupSeconds",
+    "blankingActive",
 ];
 
 /// All known Navico radar models
@@ -253,6 +263,17 @@ pub fn get_model(model: &str) -> Option<&'static ModelInfo> {
     MODELS.iter().find(|m| m.model == model)
 }
 
+/// Look up a Navico model, using the antenna kind for a HALO that does not
+/// report its variant: a dome has the limits of the HALO24, an open array
+/// those of the longest open array
+pub fn get_model_for_antenna(model: &str, antenna: Option<AntennaKind>) -> Option<&'static ModelInfo> {
+    match (model, antenna) {
+        ("HALO", Some(AntennaKind::Dome)) => get_model("HALO24"),
+        ("HALO", Some(AntennaKind::OpenArray)) => get_model("HALO6"),
+        _ => get_model(model),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
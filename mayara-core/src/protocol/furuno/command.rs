@@ -49,6 +49,10 @@ pub enum CommandId {
     /// Multi-purpose signal processing (feature=0: IntReject, feature=3: NoiseReduction)
     SignalProcessing = 0x67,
     Status = 0x69,
+    /// Electronic bearing line marker; unconfirmed, never sent by the server
+    Ebl = 0x70,
+    /// Variable range marker; unconfirmed, never sent by the server
+    Vrm = 0x71,
     BlindSector = 0x77,
     HeadingAlign = 0x81,
     MainBangSize = 0x83,
@@ -477,13 +481,88 @@ pub fn format_antenna_height_command(meters: i32) -> String {
     format_command(CommandMode::Set, CommandId::AntennaHeight, &[0, meters, 0])
 }
 
+/// Format EBL (electronic bearing line) command
+///
+/// The command id is not confirmed on hardware; the server keeps the
+/// markers itself and does not send this.
+///
+/// # Arguments
+/// * `degrees_x10` - Bearing of the line in tenths of degrees (0-3599)
+///
+/// # Returns
+/// Formatted command: `$S70,{degrees_x10},0\r\n`
+pub fn format_ebl_command(degrees_x10: i32) -> String {
+    format_command(CommandMode::Set, CommandId::Ebl, &[degrees_x10.rem_euclid(3600), 0])
+}
+
+/// Format VRM (variable range marker) command
+///
+/// The command id is not confirmed on hardware; the server keeps the
+/// markers itself and does not send this.
+///
+/// # Arguments
+/// * `meters` - Radius of the ring in meters
+///
+/// # Returns
+/// Formatted command: `$S71,{milli_nm},0\r\n`, the radius in 1/1000 NM
+pub fn format_vrm_command(meters: i32) -> String {
+    let milli_nm = (meters.max(0) as f64 * 1000.0 / 1852.0).round() as i32;
+    format_command(CommandMode::Set, CommandId::Vrm, &[milli_nm, 0])
+}
+
 // =============================================================================
 // Response Parsing
 // =============================================================================
 
+/// Outcome of checking the optional NMEA style `*hh` checksum of a line.
+/// Furuno's use of this checksum is assumed from NMEA 0183, not confirmed
+/// on a radar; lines without the suffix are accepted as they are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    /// The line carries no checksum
+    Absent,
+    Valid,
+    /// The line was corrupted and must not be decoded
+    Invalid,
+}
+
+/// XOR of all bytes between the leading `$` and the `*`
+pub fn line_checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |sum, b| sum ^ b)
+}
+
+/// The line split before its `*hh` suffix, if it ends in one
+fn split_checksum(line: &str) -> Option<(&str, &str)> {
+    let at = line.len().checked_sub(3)?;
+    (line.as_bytes()[at] == b'*').then(|| (&line[..at], &line[at + 1..]))
+}
+
+/// Check the `*hh` checksum at the end of a line, if there is one
+pub fn check_checksum(line: &str) -> ChecksumStatus {
+    let Some((body, sum)) = split_checksum(line.trim()) else {
+        return ChecksumStatus::Absent;
+    };
+    let body = body.strip_prefix('$').unwrap_or(body);
+    match u8::from_str_radix(sum, 16) {
+        Ok(sum) if sum == line_checksum(body) => ChecksumStatus::Valid,
+        _ => ChecksumStatus::Invalid,
+    }
+}
+
+/// The line without its checksum, or `None` when the checksum does not match
+pub fn strip_checksum(line: &str) -> Option<&str> {
+    let line = line.trim();
+    match check_checksum(line) {
+        ChecksumStatus::Absent => Some(line),
+        ChecksumStatus::Valid => split_checksum(line).map(|(body, _)| body),
+        ChecksumStatus::Invalid => None,
+    }
+}
+
 /// Parse a Furuno response line
 ///
-/// Response format: `${mode}{command_id},{args...}`
+/// Response format: `${mode}{command_id},{args...}`; a `*hh` checksum must
+/// have been checked and removed with [`strip_checksum`] first.
 ///
 /// # Returns
 /// Tuple of `(CommandMode, command_id, Vec<args>)` if valid
@@ -925,6 +1004,37 @@ pub fn parse_tx_channel_response(line: &str) -> Option<i32> {
     args.first().copied()
 }
 
+/// Parse EBL response
+///
+/// Response: `$N70,{degrees_x10},{screen}`
+///
+/// # Returns
+/// Bearing of the line in degrees
+#[inline(never)]
+pub fn parse_ebl_response(line: &str) -> Option<f64> {
+    let (mode, cmd_id, args) = parse_response(line)?;
+    if mode != CommandMode::New || cmd_id != CommandId::Ebl.as_hex() {
+        return None;
+    }
+    args.first().map(|&tenths| tenths as f64 / 10.0)
+}
+
+/// Parse VRM response
+///
+/// Response: `$N71,{milli_nm},{screen}` with the radius in 1/1000 NM
+///
+/// # Returns
+/// Radius of the ring in meters
+#[inline(never)]
+pub fn parse_vrm_response(line: &str) -> Option<i32> {
+    let (mode, cmd_id, args) = parse_response(line)?;
+    if mode != CommandMode::New || cmd_id != CommandId::Vrm.as_hex() {
+        return None;
+    }
+    args.first()
+        .map(|&milli_nm| (milli_nm as f64 * 1852.0 / 1000.0).round() as i32)
+}
+
 /// Format request for Blind Sector (no-transmit zones) settings
 ///
 /// # Returns
@@ -1013,6 +1123,17 @@ mod tests {
         assert_eq!(cmd, "$S69,2,0,0,60,300,0\r\n");
     }
 
+    #[test]
+    fn test_ebl_and_vrm() {
+        assert_eq!(format_ebl_command(453), "$S70,453,0\r\n");
+        assert_eq!(format_ebl_command(-10), "$S70,3590,0\r\n");
+        assert_eq!(format_vrm_command(2778), "$S71,1500,0\r\n");
+
+        assert_eq!(parse_ebl_response("$N70,453,0"), Some(45.3));
+        assert_eq!(parse_vrm_response("$N71,1500,0"), Some(2778));
+        assert_eq!(parse_vrm_response("$N70,1500,0"), None);
+    }
+
     #[test]
     fn test_format_status_standby() {
         let cmd = format_status_command(false);
@@ -1072,6 +1193,25 @@ mod tests {
         assert_eq!(args, vec![2, 0, 0, 60, 300, 0]);
     }
 
+    #[test]
+    fn test_checksum_validation() {
+        let good = "$N69,2,0,0,60,300,0*76";
+        assert_eq!(check_checksum(good), ChecksumStatus::Valid);
+        let (_, cmd_id, args) = parse_response(strip_checksum(good).unwrap()).unwrap();
+        assert_eq!(cmd_id, 0x69);
+        assert_eq!(args, vec![2, 0, 0, 60, 300, 0]);
+
+        // One flipped digit: must not decode to a bogus range
+        let corrupted = "$N69,2,0,0,60,900,0*76";
+        assert_eq!(check_checksum(corrupted), ChecksumStatus::Invalid);
+        assert_eq!(strip_checksum(corrupted), None);
+        assert_eq!(check_checksum("$N69,2*ZZ"), ChecksumStatus::Invalid);
+
+        // Only a `*hh` suffix is a checksum
+        assert_eq!(check_checksum("$N69,2,0,0,60,300,0"), ChecksumStatus::Absent);
+        assert_eq!(strip_checksum("$N69,2*1,0"), Some("$N69,2*1,0"));
+    }
+
     #[test]
     fn test_parse_status_response() {
         assert_eq!(parse_status_response("$N69,2,0,0,60,300,0"), Some(true));
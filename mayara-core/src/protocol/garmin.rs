@@ -8,7 +8,7 @@
 //! Unlike other brands, Garmin doesn't have a structured beacon packet.
 //! Discovery happens by receiving any packet on the report multicast address.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::ParseError;
 use crate::radar::{RadarDiscovery, RadarStatus};
@@ -53,6 +53,9 @@ pub const REPORT_SCAN_SPEED: u32 = 0x0916;
 /// Transmit state report
 pub const REPORT_TRANSMIT_STATE: u32 = 0x0919;
 /// Range report (meters)
+///
+/// The xHD and Fantom report the range in whole meters, the same unit as the
+/// range command, so a commanded range can be confirmed directly.
 pub const REPORT_RANGE: u32 = 0x091e;
 /// Autogain mode (0=manual, 2=auto)
 pub const REPORT_AUTOGAIN: u32 = 0x0924;
@@ -93,6 +96,17 @@ pub const REPORT_STATUS_CHANGE: u32 = 0x0993;
 /// Scanner message (contains model info)
 pub const REPORT_SCANNER_MESSAGE: u32 = 0x099b;
 
+/// Set in the packet type of the reports for the second range (B) of a dual
+/// range radar such as the Fantom Pro; range A uses the plain types.
+///
+/// Unverified: taken from a single capture, and not checked against a radar.
+/// Reports with the flag are only decoded, for the debug stream; the server
+/// sets no controls from them, see `Channel`.
+pub const CHANNEL_B_FLAG: u32 = 0x1000;
+
+/// Largest gain, sea and rain clutter value the radar reports
+pub const MAX_SETTING_VALUE: u32 = 255;
+
 // =============================================================================
 // Parsed Report Types
 // =============================================================================
@@ -153,6 +167,17 @@ impl TransmitState {
         }
     }
 
+    /// The value as sent by the radar
+    pub fn to_raw(self) -> u32 {
+        match self {
+            TransmitState::Off => 0,
+            TransmitState::Standby => 1,
+            TransmitState::Transmit => 2,
+            TransmitState::WarmingUp => 3,
+            TransmitState::Unknown(v) => v,
+        }
+    }
+
     pub fn to_radar_status(self) -> RadarStatus {
         match self {
             TransmitState::Off => RadarStatus::Off,
@@ -200,6 +225,72 @@ impl GainLevel {
     }
 }
 
+/// Range channel of a dual range radar; single range radars only use A.
+/// The server has no radar for range B of a Garmin, so its settings are
+/// decoded but not routed to any controls until `CHANNEL_B_FLAG` is verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Channel {
+    A,
+    B,
+}
+
+impl Channel {
+    /// The channel of a report and its packet type without the channel flag
+    pub fn split(packet_type: u32) -> (Channel, u32) {
+        if packet_type & CHANNEL_B_FLAG != 0 {
+            (Channel::B, packet_type & !CHANNEL_B_FLAG)
+        } else {
+            (Channel::A, packet_type)
+        }
+    }
+}
+
+/// The gain, sea and rain clutter reports, which exist once per channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelSetting {
+    GainMode(GainMode),
+    Gain(u32),
+    GainLevel(GainLevel),
+    SeaMode(u32),
+    Sea(u32),
+    RainMode(u32),
+    Rain(u32),
+}
+
+impl ChannelSetting {
+    /// A value on Garmin's 0-255 scale as a 0-100 percentage
+    pub fn normalize(raw: u32) -> u8 {
+        ((raw.min(MAX_SETTING_VALUE) * 100 + MAX_SETTING_VALUE / 2) / MAX_SETTING_VALUE) as u8
+    }
+}
+
+/// Parse a gain, sea or rain clutter report of either channel; `None` for
+/// all other reports
+pub fn parse_channel_setting(data: &[u8]) -> Option<(Channel, ChannelSetting)> {
+    if data.len() < 12 {
+        return None;
+    }
+    let packet_type = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    let len = u32::from_le_bytes(data[4..8].try_into().unwrap());
+    if len != 4 {
+        return None;
+    }
+    let value = u32::from_le_bytes(data[8..12].try_into().unwrap());
+
+    let (channel, packet_type) = Channel::split(packet_type);
+    let setting = match packet_type {
+        REPORT_AUTOGAIN => ChannelSetting::GainMode(GainMode::from_value(value)),
+        REPORT_GAIN => ChannelSetting::Gain(value),
+        REPORT_AUTOGAIN_LEVEL => ChannelSetting::GainLevel(GainLevel::from_value(value)),
+        REPORT_SEA_MODE => ChannelSetting::SeaMode(value),
+        REPORT_SEA_LEVEL => ChannelSetting::Sea(value),
+        REPORT_RAIN_MODE => ChannelSetting::RainMode(value),
+        REPORT_RAIN_LEVEL => ChannelSetting::Rain(value),
+        _ => return None,
+    };
+    Some((channel, setting))
+}
+
 // =============================================================================
 // Spoke Data Structures
 // =============================================================================
@@ -508,6 +599,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_channel_setting() {
+        let report = |packet_type: u32, value: u32| {
+            let mut data = packet_type.to_le_bytes().to_vec();
+            data.extend_from_slice(&4u32.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+            data
+        };
+
+        assert_eq!(
+            parse_channel_setting(&report(0x0925, 128)),
+            Some((Channel::A, ChannelSetting::Gain(128)))
+        );
+        assert_eq!(
+            parse_channel_setting(&report(0x193a, 40)),
+            Some((Channel::B, ChannelSetting::Sea(40)))
+        );
+        assert_eq!(
+            parse_channel_setting(&report(0x0924, 2)),
+            Some((Channel::A, ChannelSetting::GainMode(GainMode::Auto)))
+        );
+        assert_eq!(parse_channel_setting(&report(0x091e, 3000)), None);
+
+        assert_eq!(ChannelSetting::normalize(0), 0);
+        assert_eq!(ChannelSetting::normalize(128), 50);
+        assert_eq!(ChannelSetting::normalize(255), 100);
+        assert_eq!(ChannelSetting::normalize(1000), 100);
+    }
+
     #[test]
     fn test_create_discovery() {
         let disc = create_discovery("192.168.1.100");
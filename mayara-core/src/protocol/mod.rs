@@ -42,6 +42,50 @@ pub mod raymarine;
 #[cfg(feature = "garmin")]
 pub mod garmin;
 
+use serde::{Deserialize, Serialize};
+
+/// How the azimuth of a spoke is sent on the wire
+///
+/// Each brand has a fixed encoding, but while working out the protocol of a
+/// new unit it helps to try a different byte order or azimuth counter
+/// without recompiling; the server takes both from the radar preferences.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AzimuthEncoding {
+    /// The two azimuth bytes are most significant first
+    pub big_endian: bool,
+    /// Azimuth counts per rotation on the wire
+    pub modulus: u16,
+}
+
+impl AzimuthEncoding {
+    pub const fn little_endian(modulus: u16) -> Self {
+        AzimuthEncoding {
+            big_endian: false,
+            modulus,
+        }
+    }
+
+    /// This encoding with the byte order and modulus replaced where given
+    pub fn with_overrides(self, big_endian: Option<bool>, modulus: Option<u16>) -> Self {
+        AzimuthEncoding {
+            big_endian: big_endian.unwrap_or(self.big_endian),
+            modulus: modulus.filter(|&m| m > 0).unwrap_or(self.modulus),
+        }
+    }
+
+    /// The spoke index, 0..`spokes_per_revolution`, of a wire azimuth
+    pub fn decode(&self, bytes: [u8; 2], spokes_per_revolution: u16) -> u16 {
+        let raw = if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        };
+        let modulus = self.modulus.max(1) as u32;
+        ((raw as u32 % modulus) * spokes_per_revolution as u32 / modulus) as u16
+    }
+}
+
 /// Helper function to extract a null-terminated C string from bytes
 pub fn c_string(bytes: &[u8]) -> Option<String> {
     let null_pos = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
@@ -51,10 +95,68 @@ pub fn c_string(bytes: &[u8]) -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
+/// Order in which the scanner reports its azimuth, depends on the antenna
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RotationDirection {
+    #[default]
+    Clockwise,
+    CounterClockwise,
+}
+
+impl RotationDirection {
+    /// Decode Navico Report 04 byte 14; unknown values are treated as clockwise
+    pub fn from_byte(value: u8) -> Self {
+        match value {
+            1 => RotationDirection::CounterClockwise,
+            _ => RotationDirection::Clockwise,
+        }
+    }
+
+    pub fn as_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RotationDirection::Clockwise => "Clockwise",
+            RotationDirection::CounterClockwise => "Counter-clockwise",
+        }
+    }
+
+    /// Map a reported angle onto the clockwise order used by the
+    /// polar-to-Cartesian lookup
+    pub fn normalize(&self, angle: u16, spokes_per_revolution: u16) -> u16 {
+        match self {
+            RotationDirection::Clockwise => angle,
+            RotationDirection::CounterClockwise => {
+                (spokes_per_revolution - angle % spokes_per_revolution) % spokes_per_revolution
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_azimuth_encoding() {
+        let navico = AzimuthEncoding::little_endian(4096);
+        // 0x0102 little endian is 513 of 4096, spoke 256 of 2048
+        assert_eq!(navico.decode([0x02, 0x01], 2048), 256);
+
+        // Big endian reads the swapped value, 0x0201 = 1026 -> 513
+        let swapped = navico.with_overrides(Some(true), None);
+        assert_eq!(swapped.decode([0x02, 0x01], 2048), 513);
+        assert_eq!(swapped.decode([0x01, 0x02], 2048), navico.decode([0x02, 0x01], 2048));
+
+        // A counter of 2048 maps one to one, and wraps
+        let modulus = navico.with_overrides(None, Some(2048));
+        assert_eq!(modulus.decode([0x02, 0x01], 2048), 513);
+        assert_eq!(modulus.decode(2049u16.to_le_bytes(), 2048), 1);
+        assert_eq!(navico.with_overrides(None, Some(0)), navico);
+    }
+
     #[test]
     fn test_c_string() {
         assert_eq!(c_string(b"hello\0world"), Some("hello".to_string()));
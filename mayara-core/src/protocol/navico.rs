@@ -10,11 +10,13 @@
 //! - **4G**: Fourth generation with dual range capability
 //! - **HALO**: High-definition series with Doppler support
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use crate::error::ParseError;
 use crate::Brand;
+use crate::models::AntennaKind;
 use crate::radar::RadarDiscovery;
-use super::c_string;
+use super::{c_string, AzimuthEncoding};
+pub use super::RotationDirection;
 
 // =============================================================================
 // Constants
@@ -322,7 +324,10 @@ pub struct Report01 {
     pub what: u8,       // 0x01
     pub command: u8,    // 0xC4
     pub status: u8,
-    _u00: [u8; 15],
+    pub blanking_active: u8, // 3, non-zero while a no transmit sector is blanked
+    _u00: [u8; 2],
+    pub warmup_seconds: [u8; 2], // 6..8, seconds of warmup left, 0 when not reported
+    _u01: [u8; 10],
 }
 
 pub const REPORT_01_SIZE: usize = 18;
@@ -342,9 +347,10 @@ pub struct Report02 {
     pub sea_auto: u8,           // 13 = 0=off, 1=harbor, 2=offshore
     _u02: [u8; 3],              // 14..17
     pub sea: [u8; 4],           // 17..21
-    _u03: u8,                   // 21
+    pub rain_auto: u8,          // 21 (newer HALO firmware, 0 otherwise)
     pub rain: u8,               // 22
-    _u04: [u8; 11],             // 23..34
+    _u04: [u8; 10],             // 23..33
+    pub interference_auto: u8,  // 33 (firmware that manages it, 0 otherwise)
     pub interference_rejection: u8, // 34
     _u05: [u8; 3],              // 35..38
     pub target_expansion: u8,   // 38
@@ -363,7 +369,8 @@ pub struct Report03 {
     pub what: u8,               // 0x03
     pub command: u8,            // 0xC4
     pub model: u8,              // Model byte (0x00=HALO, 0x01=4G, 0x08=3G, 0x0E/0x0F=BR24)
-    _u00: [u8; 31],
+    pub antenna: u8,            // 3, HALO: 1 = dome, 2 = open array, otherwise not reported (unconfirmed)
+    _u00: [u8; 30],
     pub hours: [u8; 4],         // Operating hours
     _u01: [u8; 20],
     pub firmware_date: [u8; 32], // Wide chars (UTF-16)
@@ -383,7 +390,9 @@ pub struct Report04 {
     pub bearing_alignment: [u8; 2], // 6..8
     _u01: [u8; 2],              // 8..10
     pub antenna_height: [u8; 2],// 10..12
-    _u02: [u8; 7],              // 12..19
+    _u02: [u8; 2],              // 12..14
+    pub rotation_direction: u8, // 14 Azimuth reporting order, 0=clockwise, 1=counter-clockwise
+    _u02b: [u8; 4],             // 15..19
     pub accent_light: u8,       // 19 (HALO only)
     _u03a: [u8; 32],            // 20..52 (split for serde array limit)
     _u03b: [u8; 14],            // 52..66
@@ -395,7 +404,7 @@ pub const REPORT_04_SIZE: usize = 66;
 #[derive(Deserialize, Debug, Copy, Clone)]
 #[repr(C, packed)]
 pub struct SectorBlanking {
-    pub enabled: u8,
+    pub enabled: u8, // 0 = off, 1 = set by the user, SECTOR_LEARNED = learned by the radar
     pub start_angle: [u8; 2],
     pub end_angle: [u8; 2],
 }
@@ -436,17 +445,17 @@ pub struct Report08Base {
     pub interference_rejection: u8, // 3
     pub scan_speed: u8,             // 4
     pub sls_auto: u8,               // 5 sidelobe suppression auto
-    _field6: u8,                    // 6
-    _field7: u8,                    // 7
-    _field8: u8,                    // 8
+    pub lirf: u8,                   // 6 (HALO) local interference source rejection
+    pub sea_clutter_curve: u8,      // 7 (HALO) 0=standard, 1=steep, unconfirmed
+    pub pulse_length: u8,           // 8 (magnetron, unconfirmed) 0=short, 1=medium, 2=long
     pub side_lobe_suppression: u8,  // 9
     _field10: [u8; 2],              // 10-11
     pub noise_rejection: u8,        // 12
     pub target_sep: u8,             // 13
     pub sea_clutter: u8,            // 14 (HALO)
     pub auto_sea_clutter: i8,       // 15 (HALO)
-    _field16: u8,                   // 16
-    _field17: u8,                   // 17
+    pub main_bang_suppression: u8,  // 16 (HALO) 0..255
+    pub auto_response: u8,          // 17 (HALO) 0=slow, 1=medium, 2=fast
 }
 
 pub const REPORT_08_BASE_SIZE: usize = 18;
@@ -597,6 +606,243 @@ impl DopplerMode {
     }
 }
 
+// =============================================================================
+// Sea Clutter Auto Mode
+// =============================================================================
+
+/// Effective sea clutter mode, derived from the sea-auto field
+///
+/// The radar keeps reporting the raw `sea` value while an auto mode is
+/// active, so clients need this to know whether the manual value applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeaAutoMode {
+    #[default]
+    Manual,   // Manual sea value is applied
+    Harbor,   // Report 02 sea_auto = 1
+    Offshore, // Report 02 sea_auto = 2
+    Calm,     // HALO auto with sea state 0
+    Moderate, // HALO auto with sea state 1
+    Rough,    // HALO auto with sea state 2
+    Unknown,  // Auto with a preset we do not know
+}
+
+impl SeaAutoMode {
+    /// Decode Report 02 sea_auto (0=off, 1=harbor, 2=offshore)
+    pub fn from_sea_auto(sea_auto: u8) -> Self {
+        match sea_auto {
+            0 => SeaAutoMode::Manual,
+            1 => SeaAutoMode::Harbor,
+            2 => SeaAutoMode::Offshore,
+            _ => SeaAutoMode::Unknown,
+        }
+    }
+
+    /// Decode HALO sea auto, where the Report 08 sea state selects the preset
+    pub fn from_halo(sea_auto: u8, sea_state: u8) -> Self {
+        if sea_auto == 0 {
+            return SeaAutoMode::Manual;
+        }
+        match sea_state {
+            0 => SeaAutoMode::Calm,
+            1 => SeaAutoMode::Moderate,
+            2 => SeaAutoMode::Rough,
+            _ => SeaAutoMode::Unknown,
+        }
+    }
+
+    /// True when the manual sea value is applied (slider is live)
+    pub fn is_manual(&self) -> bool {
+        *self == SeaAutoMode::Manual
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SeaAutoMode::Manual => "manual",
+            SeaAutoMode::Harbor => "harbor",
+            SeaAutoMode::Offshore => "offshore",
+            SeaAutoMode::Calm => "calm",
+            SeaAutoMode::Moderate => "moderate",
+            SeaAutoMode::Rough => "rough",
+            SeaAutoMode::Unknown => "unknown",
+        }
+    }
+
+    /// Label for the UI, e.g. "Harbor"
+    pub fn label(&self) -> &'static str {
+        match self {
+            SeaAutoMode::Manual => "Manual",
+            SeaAutoMode::Harbor => "Harbor",
+            SeaAutoMode::Offshore => "Offshore",
+            SeaAutoMode::Calm => "Calm",
+            SeaAutoMode::Moderate => "Moderate",
+            SeaAutoMode::Rough => "Rough",
+            SeaAutoMode::Unknown => "Unknown",
+        }
+    }
+}
+
+/// HALO auto response: how quickly auto gain and auto sea follow changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoResponse {
+    Slow,
+    #[default]
+    Medium,
+    Fast,
+}
+
+impl AutoResponse {
+    /// Decode Report 08 auto response (0=slow, 1=medium, 2=fast)
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(AutoResponse::Slow),
+            1 => Some(AutoResponse::Medium),
+            2 => Some(AutoResponse::Fast),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AutoResponse::Slow => "Slow",
+            AutoResponse::Medium => "Medium",
+            AutoResponse::Fast => "Fast",
+        }
+    }
+}
+
+/// Noise rejection level, Report 08 offset 12
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoiseRejection {
+    #[default]
+    Off,
+    Low,
+    Medium,
+    High,
+}
+
+impl NoiseRejection {
+    /// Decode Report 08 noise rejection (0=off, 1=low, 2=medium, 3=high)
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(NoiseRejection::Off),
+            1 => Some(NoiseRejection::Low),
+            2 => Some(NoiseRejection::Medium),
+            3 => Some(NoiseRejection::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NoiseRejection::Off => "Off",
+            NoiseRejection::Low => "Low",
+            NoiseRejection::Medium => "Medium",
+            NoiseRejection::High => "High",
+        }
+    }
+}
+
+/// HALO sea clutter curve: how the sea filter falls off with range. Decoded
+/// from a single capture of one HALO and not confirmed on other models, so it
+/// is only shown by the capture decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeaClutterCurve {
+    /// Suits most conditions
+    #[default]
+    Standard,
+    /// Filters harder close in, for steep, breaking waves
+    Steep,
+}
+
+impl SeaClutterCurve {
+    /// Decode Report 08 sea clutter curve (0=standard, 1=steep)
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(SeaClutterCurve::Standard),
+            1 => Some(SeaClutterCurve::Steep),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SeaClutterCurve::Standard => "standard",
+            SeaClutterCurve::Steep => "steep",
+        }
+    }
+}
+
+/// Transmit pulse length of magnetron units: short pulses separate targets
+/// better, long pulses reach further. Report 08 offset 8 has not been
+/// confirmed against captures, so it is only shown by the capture decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PulseLength {
+    #[default]
+    Short,
+    Medium,
+    Long,
+}
+
+impl PulseLength {
+    /// Decode Report 08 pulse length (0=short, 1=medium, 2=long)
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(PulseLength::Short),
+            1 => Some(PulseLength::Medium),
+            2 => Some(PulseLength::Long),
+            _ => None,
+        }
+    }
+
+    /// The `pulseLength` value shown by the capture decoder
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PulseLength::Short => "short",
+            PulseLength::Medium => "medium",
+            PulseLength::Long => "long",
+        }
+    }
+}
+
+/// Antenna rotation speed as confirmed in Report 08
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanSpeed {
+    #[default]
+    Normal,
+    Fast,
+}
+
+impl ScanSpeed {
+    /// Decode Report 08 scan speed (0=normal, 1=fast)
+    pub fn from_byte(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ScanSpeed::Normal),
+            1 => Some(ScanSpeed::Fast),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The value of the `scanSpeed` enum control
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScanSpeed::Normal => "normal",
+            ScanSpeed::Fast => "fast",
+        }
+    }
+}
+
 // =============================================================================
 // Radar Status
 // =============================================================================
@@ -626,6 +872,46 @@ impl Status {
 // Parsed Data Structures
 // =============================================================================
 
+/// Parsed status report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParsedStatus {
+    pub status: Status,
+    /// Seconds of warmup left, only while warming up and when the firmware reports it
+    pub warmup_seconds: Option<u16>,
+    /// A no transmit sector is blanked right now, so part of the picture is
+    /// missing on purpose
+    pub blanking_active: bool,
+}
+
+impl ParsedStatus {
+    /// Human readable status, e.g. "Warming up (34s)"
+    pub fn description(&self) -> String {
+        match (self.status, self.warmup_seconds) {
+            (Status::Off, _) => "Off".to_string(),
+            (Status::Standby, _) => "Standby".to_string(),
+            (Status::Transmit, _) => "Transmit".to_string(),
+            (Status::Preparing, Some(seconds)) => format!("Warming up ({}s)", seconds),
+            (Status::Preparing, None) => "Warming up".to_string(),
+        }
+    }
+}
+
+/// Transmit state of one channel of a dual range radar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ChannelTransmit {
+    /// 0 for range A, 1 for range B
+    pub index: u8,
+    pub transmit: bool,
+}
+
+/// Dual range channel letters, in index order
+pub const CHANNELS: [&str; 2] = ["A", "B"];
+
+/// Index of a dual range channel from its letter
+pub fn channel_index(which: &str) -> Option<u8> {
+    CHANNELS.iter().position(|&c| c == which).map(|i| i as u8)
+}
+
 /// Parsed beacon result containing radar endpoint information
 #[derive(Debug, Clone)]
 pub struct ParsedBeacon {
@@ -664,16 +950,69 @@ pub struct ParsedControls {
     pub sea: i32,
     pub sea_auto: u8,
     pub rain: u8,
+    pub rain_auto: bool,
     pub interference_rejection: u8,
+    pub interference_auto: bool,
     pub target_expansion: u8,
     pub target_boost: u8,
 }
 
+impl ParsedControls {
+    /// Effective sea clutter mode for non-HALO radars
+    pub fn sea_auto_mode(&self) -> SeaAutoMode {
+        SeaAutoMode::from_sea_auto(self.sea_auto)
+    }
+
+    /// Whether the reported manual sea value is currently applied
+    pub fn sea_manual_active(&self) -> bool {
+        self.sea_auto_mode().is_manual()
+    }
+
+    /// Human readable interference rejection: "auto" or the level
+    pub fn interference_description(&self) -> &'static str {
+        if self.interference_auto {
+            return "auto";
+        }
+        match self.interference_rejection {
+            0 => "off",
+            1 => "low",
+            2 => "medium",
+            _ => "high",
+        }
+    }
+
+    /// Human readable gain, sea, rain and interference rejection, e.g.
+    /// "Gain auto, sea harbor, rain 30, interference low"
+    pub fn description(&self) -> String {
+        let gain = match self.gain_auto {
+            true => "auto".to_string(),
+            false => self.gain.to_string(),
+        };
+        let sea = match self.sea_auto_mode() {
+            SeaAutoMode::Manual => self.sea.to_string(),
+            mode => mode.as_str().to_string(),
+        };
+        let rain = match self.rain_auto {
+            true => "auto".to_string(),
+            false => self.rain.to_string(),
+        };
+        format!(
+            "Gain {}, sea {}, rain {}, interference {}",
+            gain,
+            sea,
+            rain,
+            self.interference_description()
+        )
+    }
+}
+
 /// Parsed Report 03 (model info)
 #[derive(Debug, Clone)]
 pub struct ParsedModelInfo {
     pub model: Model,
     pub model_byte: u8,
+    /// HALO only, when the radar reports it
+    pub antenna_kind: Option<AntennaKind>,
     pub operating_hours: u32,
     pub firmware_date: String,
     pub firmware_time: String,
@@ -685,12 +1024,20 @@ pub struct ParsedInstallation {
     pub bearing_alignment: i16,
     pub antenna_height: u16,
     pub accent_light: u8,
+    pub rotation_direction: RotationDirection,
 }
 
+/// Sector state of a blind sector the radar learned itself, from the
+/// shadow of a mast or other structure; unconfirmed
+pub const SECTOR_LEARNED: u8 = 0x02;
+
 /// Parsed sector blanking entry
 #[derive(Debug, Clone)]
 pub struct ParsedSectorBlanking {
+    /// Blanked by the user
     pub enabled: bool,
+    /// A blind sector masked by the radar, not by the user
+    pub learned: bool,
     pub start_angle: i16,
     pub end_angle: i16,
 }
@@ -708,12 +1055,24 @@ pub struct ParsedAdvancedSettings {
     pub sea_state: u8,
     pub local_interference_rejection: u8,
     pub scan_speed: u8,
+    /// Decoded `scan_speed`, `None` for values this decoder does not know
+    pub scan_speed_mode: Option<ScanSpeed>,
     pub sidelobe_suppression_auto: bool,
     pub sidelobe_suppression: u8,
     pub noise_rejection: u8,
+    /// Decoded `noise_rejection`, `None` for values this decoder does not know
+    pub noise_rejection_level: Option<NoiseRejection>,
+    /// Sea clutter curve (HALO), `None` for values this decoder does not know
+    pub sea_clutter_curve: Option<SeaClutterCurve>,
+    /// Pulse length (magnetron units), `None` for values this decoder does not know
+    pub pulse_length: Option<PulseLength>,
     pub target_separation: u8,
+    /// Local interference source rejection (HALO), separate from the level
+    pub lirf: bool,
     pub sea_clutter: u8,
     pub auto_sea_clutter: i8,
+    pub main_bang_suppression: u8,
+    pub auto_response: Option<AutoResponse>,
     pub doppler_state: Option<u8>,
     pub doppler_speed: Option<u16>,
 }
@@ -974,6 +1333,11 @@ pub fn parse_beacon_endpoints(data: &[u8]) -> Result<ParsedBeacon, ParseError> {
 
 /// Parse Report 01 (status)
 pub fn parse_report_01(data: &[u8]) -> Result<Status, ParseError> {
+    parse_status_report(data).map(|parsed| parsed.status)
+}
+
+/// Parse Report 01 (status) including the warmup countdown
+pub fn parse_status_report(data: &[u8]) -> Result<ParsedStatus, ParseError> {
     if data.len() < REPORT_01_SIZE {
         return Err(ParseError::TooShort {
             expected: REPORT_01_SIZE,
@@ -990,8 +1354,16 @@ pub fn parse_report_01(data: &[u8]) -> Result<Status, ParseError> {
         });
     }
 
-    Status::from_byte(report.status)
-        .ok_or(ParseError::InvalidPacket(format!("Unknown status: {}", report.status)))
+    let status = Status::from_byte(report.status)
+        .ok_or(ParseError::InvalidPacket(format!("Unknown status: {}", report.status)))?;
+    let warmup_seconds = u16::from_le_bytes(report.warmup_seconds);
+
+    Ok(ParsedStatus {
+        status,
+        warmup_seconds: (status == Status::Preparing && warmup_seconds > 0)
+            .then_some(warmup_seconds),
+        blanking_active: report.blanking_active != 0,
+    })
 }
 
 /// Parse Report 02 (controls)
@@ -1020,7 +1392,9 @@ pub fn parse_report_02(data: &[u8]) -> Result<ParsedControls, ParseError> {
         sea: i32::from_le_bytes(report.sea),
         sea_auto: report.sea_auto,
         rain: report.rain,
+        rain_auto: report.rain_auto > 0,
         interference_rejection: report.interference_rejection,
+        interference_auto: report.interference_auto > 0,
         target_expansion: report.target_expansion,
         target_boost: report.target_boost,
     })
@@ -1048,9 +1422,17 @@ pub fn parse_report_03(data: &[u8]) -> Result<ParsedModelInfo, ParseError> {
     let firmware_date = wide_string_to_string(&report.firmware_date);
     let firmware_time = wide_string_to_string(&report.firmware_time);
 
+    let model = Model::from_byte(report.model);
+    let antenna_kind = match (model, report.antenna) {
+        (Model::HALO, 1) => Some(AntennaKind::Dome),
+        (Model::HALO, 2) => Some(AntennaKind::OpenArray),
+        _ => None,
+    };
+
     Ok(ParsedModelInfo {
-        model: Model::from_byte(report.model),
+        model,
         model_byte: report.model,
+        antenna_kind,
         operating_hours: u32::from_le_bytes(report.hours),
         firmware_date,
         firmware_time,
@@ -1079,9 +1461,20 @@ pub fn parse_report_04(data: &[u8]) -> Result<ParsedInstallation, ParseError> {
         bearing_alignment: i16::from_le_bytes(report.bearing_alignment),
         antenna_height: u16::from_le_bytes(report.antenna_height),
         accent_light: report.accent_light,
+        rotation_direction: RotationDirection::from_byte(report.rotation_direction),
     })
 }
 
+fn parse_sector_blanking(b: &SectorBlanking) -> ParsedSectorBlanking {
+    let learned = b.enabled == SECTOR_LEARNED;
+    ParsedSectorBlanking {
+        enabled: b.enabled > 0 && !learned,
+        learned,
+        start_angle: i16::from_le_bytes(b.start_angle),
+        end_angle: i16::from_le_bytes(b.end_angle),
+    }
+}
+
 /// Parse Report 06 (blanking/name settings) - 68 byte variant (HALO 2006)
 pub fn parse_report_06_68(data: &[u8]) -> Result<ParsedBlanking, ParseError> {
     const REPORT_06_68_SIZE: usize = 68;
@@ -1102,11 +1495,7 @@ pub fn parse_report_06_68(data: &[u8]) -> Result<ParsedBlanking, ParseError> {
     }
 
     let name = c_string(&report.name);
-    let sectors = report.blanking.iter().map(|b| ParsedSectorBlanking {
-        enabled: b.enabled > 0,
-        start_angle: i16::from_le_bytes(b.start_angle),
-        end_angle: i16::from_le_bytes(b.end_angle),
-    }).collect();
+    let sectors = report.blanking.iter().map(parse_sector_blanking).collect();
 
     Ok(ParsedBlanking { name, sectors })
 }
@@ -1131,11 +1520,7 @@ pub fn parse_report_06_74(data: &[u8]) -> Result<ParsedBlanking, ParseError> {
     }
 
     let name = c_string(&report.name);
-    let sectors = report.blanking.iter().map(|b| ParsedSectorBlanking {
-        enabled: b.enabled > 0,
-        start_angle: i16::from_le_bytes(b.start_angle),
-        end_angle: i16::from_le_bytes(b.end_angle),
-    }).collect();
+    let sectors = report.blanking.iter().map(parse_sector_blanking).collect();
 
     Ok(ParsedBlanking { name, sectors })
 }
@@ -1175,19 +1560,178 @@ pub fn parse_report_08(data: &[u8]) -> Result<ParsedAdvancedSettings, ParseError
         sea_state: report.sea_state,
         local_interference_rejection: report.interference_rejection,
         scan_speed: report.scan_speed,
+        scan_speed_mode: ScanSpeed::from_byte(report.scan_speed),
         sidelobe_suppression_auto: report.sls_auto > 0,
         sidelobe_suppression: report.side_lobe_suppression,
         noise_rejection: report.noise_rejection,
+        noise_rejection_level: NoiseRejection::from_byte(report.noise_rejection),
+        sea_clutter_curve: SeaClutterCurve::from_byte(report.sea_clutter_curve),
+        pulse_length: PulseLength::from_byte(report.pulse_length),
         target_separation: report.target_sep,
+        lirf: report.lirf != 0,
         sea_clutter: report.sea_clutter,
         auto_sea_clutter: report.auto_sea_clutter,
+        main_bang_suppression: report.main_bang_suppression,
+        auto_response: AutoResponse::from_byte(report.auto_response),
         doppler_state,
         doppler_speed,
     })
 }
 
+/// Report type of the tracked target (MARPA) report of HALO radars
+///
+/// Not confirmed against captures: this type and the layout parsed by
+/// [`parse_target_report`] are a best guess at what a HALO with target
+/// tracking sends. Reports that do not fit are left undecoded. Only the
+/// server's capture decoder uses it, not the live report receiver.
+pub const REPORT_TARGETS: u8 = 0x13;
+
+/// Size of the report header: type, command and number of targets
+pub const TARGET_REPORT_HEADER_SIZE: usize = 3;
+
+/// Size of one target in the tracked target report
+pub const TARGET_ENTRY_SIZE: usize = 12;
+
+/// A target tracked by the radar itself
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedTarget {
+    pub id: u16,
+    /// Degrees from the bow, clockwise
+    pub bearing: f64,
+    /// Meters from the antenna
+    pub distance: f64,
+    /// Speed over ground in m/s, if the radar has computed it yet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f64>,
+    /// Course over ground in degrees true, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub course: Option<f64>,
+}
+
+/// Parse the tracked target report (0x13 0xC4)
+///
+/// After the 3 byte header each target is 12 bytes, all little endian: id
+/// (u16), bearing (u16, deci-degrees), distance (u32, decimeters), speed
+/// (u16, cm/s) and course (u16, deci-degrees), 0xFFFF when not known.
+pub fn parse_target_report(data: &[u8]) -> Result<Vec<TrackedTarget>, ParseError> {
+    if data.len() < TARGET_REPORT_HEADER_SIZE {
+        return Err(ParseError::TooShort {
+            expected: TARGET_REPORT_HEADER_SIZE,
+            actual: data.len(),
+        });
+    }
+    if data[0] != REPORT_TARGETS || data[1] != 0xC4 {
+        return Err(ParseError::InvalidHeader {
+            expected: vec![REPORT_TARGETS, 0xC4],
+            actual: data[..2].to_vec(),
+        });
+    }
+    let count = data[2] as usize;
+    let expected = TARGET_REPORT_HEADER_SIZE + count * TARGET_ENTRY_SIZE;
+    if data.len() < expected {
+        return Err(ParseError::TooShort {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let u16_at = |entry: &[u8], offset: usize| u16::from_le_bytes([entry[offset], entry[offset + 1]]);
+    let known = |value: u16| (value != 0xFFFF).then_some(value);
+    Ok(data[TARGET_REPORT_HEADER_SIZE..expected]
+        .chunks(TARGET_ENTRY_SIZE)
+        .map(|entry| TrackedTarget {
+            id: u16_at(entry, 0),
+            bearing: u16_at(entry, 2) as f64 / 10.,
+            distance: u32::from_le_bytes(entry[4..8].try_into().unwrap()) as f64 / 10.,
+            speed: known(u16_at(entry, 8)).map(|cm| cm as f64 / 100.),
+            course: known(u16_at(entry, 10)).map(|deci| deci as f64 / 10.),
+        })
+        .collect())
+}
+
+/// Sizes a report of this type can have, shortest first
+fn report_sizes(report_type: u8) -> &'static [usize] {
+    match report_type {
+        0x01 => &[REPORT_01_SIZE],
+        0x02 => &[REPORT_02_SIZE],
+        0x03 => &[REPORT_03_SIZE],
+        0x04 => &[REPORT_04_SIZE],
+        0x06 => &[68, 74],
+        0x08 => &[REPORT_08_BASE_SIZE, REPORT_08_EXTENDED_SIZE, REPORT_08_EXTENDED_SIZE + 1],
+        _ => &[],
+    }
+}
+
+/// Whether a report of a known type starts at the beginning of `data`
+fn starts_report(data: &[u8]) -> bool {
+    matches!(data, [t, 0xC4, ..] if !report_sizes(*t).is_empty())
+}
+
+/// Byte ranges of the reports in one datagram.
+///
+/// Some firmware batches several reports (e.g. status, controls and
+/// advanced settings) into a single datagram. A report ends where its known
+/// size says, if the next report header follows right after it. Anything
+/// that does not look like such a batch is returned as one report, so
+/// unknown or odd sized reports behave as before.
+pub fn report_ranges(data: &[u8]) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while start < data.len() {
+        let rest = &data[start..];
+        let size = match rest {
+            [t, 0xC4, ..] => report_sizes(*t)
+                .iter()
+                .copied()
+                .find(|&size| size < rest.len() && starts_report(&rest[size..])),
+            _ => None,
+        };
+        match size {
+            Some(size) => {
+                ranges.push(start..start + size);
+                start += size;
+            }
+            None => {
+                ranges.push(start..data.len());
+                break;
+            }
+        }
+    }
+    ranges
+}
+
+/// Furthest into a packet that [`find_report_start`] looks for a report
+pub const MAX_REPORT_PREFIX: usize = 16;
+
+/// Where a report of a known type starts in a packet that carries some
+/// framing bytes in front of it, as seen in captures from some setups.
+/// Only offsets after the start are tried; the report must fit in the rest.
+pub fn find_report_start(data: &[u8]) -> Option<usize> {
+    (1..=MAX_REPORT_PREFIX.min(data.len())).find(|&offset| {
+        let rest = &data[offset..];
+        starts_report(rest) && rest.len() >= report_sizes(rest[0])[0]
+    })
+}
+
+/// The reports in one datagram, see [`report_ranges`]
+pub fn split_reports(data: &[u8]) -> Vec<&[u8]> {
+    report_ranges(data).into_iter().map(|range| &data[range]).collect()
+}
+
+/// How Navico radars send the spoke azimuth: little endian, 4096 per rotation
+pub const AZIMUTH_ENCODING: AzimuthEncoding = AzimuthEncoding::little_endian(SPOKES_RAW);
+
 /// Parse spoke header (4G/HALO)
 pub fn parse_4g_spoke_header(data: &[u8]) -> Result<(u32, u16, Option<u16>), ParseError> {
+    parse_4g_spoke_header_with(data, AZIMUTH_ENCODING)
+}
+
+/// Parse spoke header (4G/HALO) with a nonstandard azimuth encoding
+pub fn parse_4g_spoke_header_with(
+    data: &[u8],
+    azimuth: AzimuthEncoding,
+) -> Result<(u32, u16, Option<u16>), ParseError> {
     if data.len() < SPOKE_HEADER_SIZE {
         return Err(ParseError::TooShort {
             expected: SPOKE_HEADER_SIZE,
@@ -1213,7 +1757,7 @@ pub fn parse_4g_spoke_header(data: &[u8]) -> Result<(u32, u16, Option<u16>), Par
     }
 
     let heading = u16::from_le_bytes(header.heading);
-    let angle = u16::from_le_bytes(header.angle) / 2;  // Convert from 4096 to 2048
+    let angle = azimuth.decode(header.angle, SPOKES_PER_REVOLUTION);
     let large_range = u16::from_le_bytes(header.large_range);
     let small_range = u16::from_le_bytes(header.small_range);
 
@@ -1233,8 +1777,66 @@ pub fn parse_4g_spoke_header(data: &[u8]) -> Result<(u32, u16, Option<u16>), Par
     Ok((range, angle, heading))
 }
 
+/// Meters per unit of the `small_range` of a 4G/HALO spoke header. The radar
+/// zooms by scaling it with `large_range / 512`, except that a `large_range`
+/// of 0x80 means the small range is in quarter meters.
+pub fn spoke_range_scale(large_range: u16) -> f64 {
+    if large_range == 0x80 {
+        0.25
+    } else {
+        large_range as f64 / 512.
+    }
+}
+
+/// Parse the range scale of a spoke header (4G/HALO), see `spoke_range_scale`
+pub fn parse_4g_spoke_range_scale(data: &[u8]) -> Result<f64, ParseError> {
+    if data.len() < SPOKE_HEADER_SIZE {
+        return Err(ParseError::TooShort {
+            expected: SPOKE_HEADER_SIZE,
+            actual: data.len(),
+        });
+    }
+
+    let header: Br4gSpokeHeader = bincode::deserialize(&data[..SPOKE_HEADER_SIZE])?;
+    Ok(spoke_range_scale(u16::from_le_bytes(header.large_range)))
+}
+
+/// Number of sub-frames (quadrants) that make up one interlaced rotation
+pub const INTERLACE_QUADRANTS: u8 = 4;
+
+/// Parse the interlace quadrant from a spoke header (4G/HALO)
+///
+/// In interlaced scan modes the radar sends each rotation as a number of
+/// sub-frames, each covering every fourth spoke. The sub-frame index is
+/// packed in the low two bits of the `rotation` word. When the radar is not
+/// interlacing that word is -1 and `None` is returned.
+pub fn parse_4g_spoke_quadrant(data: &[u8]) -> Result<Option<u8>, ParseError> {
+    if data.len() < SPOKE_HEADER_SIZE {
+        return Err(ParseError::TooShort {
+            expected: SPOKE_HEADER_SIZE,
+            actual: data.len(),
+        });
+    }
+
+    let header: Br4gSpokeHeader = bincode::deserialize(&data[..SPOKE_HEADER_SIZE])?;
+    let rotation = u16::from_le_bytes(header.rotation);
+
+    if rotation == 0xffff {
+        return Ok(None);
+    }
+    Ok(Some((rotation as u8) % INTERLACE_QUADRANTS))
+}
+
 /// Parse spoke header (BR24)
 pub fn parse_br24_spoke_header(data: &[u8]) -> Result<(u32, u16, Option<u16>), ParseError> {
+    parse_br24_spoke_header_with(data, AZIMUTH_ENCODING)
+}
+
+/// Parse spoke header (BR24) with a nonstandard azimuth encoding
+pub fn parse_br24_spoke_header_with(
+    data: &[u8],
+    azimuth: AzimuthEncoding,
+) -> Result<(u32, u16, Option<u16>), ParseError> {
     if data.len() < SPOKE_HEADER_SIZE {
         return Err(ParseError::TooShort {
             expected: SPOKE_HEADER_SIZE,
@@ -1259,7 +1861,7 @@ pub fn parse_br24_spoke_header(data: &[u8]) -> Result<(u32, u16, Option<u16>), P
     }
 
     let heading = u16::from_le_bytes(header.heading);
-    let angle = u16::from_le_bytes(header.angle) / 2;
+    let angle = azimuth.decode(header.angle, SPOKES_PER_REVOLUTION);
 
     // BR24 range calculation
     const BR24_RANGE_FACTOR: f64 = 10.0 / 1.414; // 10 m / sqrt(2)
@@ -1379,6 +1981,14 @@ pub fn create_interference_rejection_command(level: u8) -> Vec<u8> {
     vec![0x08, 0xc1, level]
 }
 
+/// Generate target expansion command (0=off, 1=on, 2=high on HALO)
+///
+/// HALO radars use command 0x12, older radars 0x09.
+pub fn create_target_expansion_command(level: u8, halo: bool) -> Vec<u8> {
+    let cmd_id = if halo { 0x12 } else { 0x09 };
+    vec![cmd_id, 0xc1, level]
+}
+
 /// Generate scan speed command
 pub fn create_scan_speed_command(speed: u8) -> Vec<u8> {
     vec![0x0f, 0xc1, speed]
@@ -1389,6 +1999,26 @@ pub fn create_doppler_command(mode: DopplerMode) -> Vec<u8> {
     vec![0x23, 0xc1, mode.as_byte()]
 }
 
+/// Generate main bang suppression command (HALO only, 0..255)
+pub fn create_main_bang_suppression_command(level: u8) -> Vec<u8> {
+    vec![0x32, 0xc1, level]
+}
+
+/// Generate auto response command (HALO only)
+pub fn create_auto_response_command(response: AutoResponse) -> Vec<u8> {
+    vec![0x33, 0xc1, response.as_byte()]
+}
+
+/// Generate noise rejection command
+pub fn create_noise_rejection_command(level: NoiseRejection) -> Vec<u8> {
+    vec![0x21, 0xc1, level.as_byte()]
+}
+
+/// Generate local interference source rejection (LIRF) command (HALO only)
+pub fn create_lirf_command(enabled: bool) -> Vec<u8> {
+    vec![0x34, 0xc1, enabled as u8]
+}
+
 // =============================================================================
 // Navigation Data Packet Formatting (send heading/SOG/COG to Navico radars)
 // =============================================================================
@@ -1555,6 +2185,72 @@ mod tests {
         assert_eq!(extract_heading(0x83E8), None);
     }
 
+    fn make_4g_spoke_header(angle: u16, rotation: u16) -> Vec<u8> {
+        let mut header = vec![0u8; SPOKE_HEADER_SIZE];
+        header[0] = SPOKE_HEADER_SIZE as u8;
+        header[1] = 0x02;
+        header[6..8].copy_from_slice(&0x80u16.to_le_bytes()); // large_range
+        header[8..10].copy_from_slice(&(angle * 2).to_le_bytes());
+        header[12..14].copy_from_slice(&(1852u16 * 4).to_le_bytes()); // small_range
+        header[14..16].copy_from_slice(&rotation.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn test_spoke_range_scale() {
+        // Quarter meters, as most ranges are sent
+        let header = make_4g_spoke_header(0, 0xffff);
+        assert_eq!(parse_4g_spoke_range_scale(&header).unwrap(), 0.25);
+
+        // Zoomed: the same small range at twice the scale
+        let mut header = make_4g_spoke_header(0, 0xffff);
+        header[6..8].copy_from_slice(&0x400u16.to_le_bytes());
+        header[12..14].copy_from_slice(&1852u16.to_le_bytes());
+        assert_eq!(parse_4g_spoke_range_scale(&header).unwrap(), 2.);
+        assert_eq!(parse_4g_spoke_header(&header).unwrap().0, 3704);
+
+        assert!(parse_4g_spoke_range_scale(&header[..10]).is_err());
+    }
+
+    #[test]
+    fn test_spoke_quadrant_interlaced() {
+        // An interlaced capture: four sub-frames, each holding every fourth spoke
+        let capture: Vec<(u16, u16)> = (0..INTERLACE_QUADRANTS as u16)
+            .flat_map(|q| (0..8u16).map(move |i| (i * 4 + q, q)))
+            .collect();
+
+        for (angle, rotation) in capture {
+            let header = make_4g_spoke_header(angle, rotation);
+            let (range, parsed_angle, _) = parse_4g_spoke_header(&header).unwrap();
+            assert_eq!(range, 1852);
+            assert_eq!(parsed_angle, angle);
+            assert_eq!(
+                parse_4g_spoke_quadrant(&header).unwrap(),
+                Some((angle % 4) as u8)
+            );
+        }
+
+        // Not interlaced
+        let header = make_4g_spoke_header(100, 0xffff);
+        assert_eq!(parse_4g_spoke_quadrant(&header).unwrap(), None);
+
+        assert!(parse_4g_spoke_quadrant(&header[..10]).is_err());
+    }
+
+    #[test]
+    fn test_spoke_header_big_endian_azimuth() {
+        let mut header = make_4g_spoke_header(0, 0xffff);
+        header[8..10].copy_from_slice(&[0x02, 0x01]);
+
+        let (_, angle, _) = parse_4g_spoke_header(&header).unwrap();
+        assert_eq!(angle, 0x0102 / 2);
+
+        let big_endian = AZIMUTH_ENCODING.with_overrides(Some(true), None);
+        let (range, angle, _) = parse_4g_spoke_header_with(&header, big_endian).unwrap();
+        assert_eq!(angle, 0x0201 / 2);
+        assert_eq!(range, 1852);
+    }
+
     #[test]
     fn test_unpack_spoke_data() {
         let packed = vec![0x12, 0x34, 0xAB];
@@ -1611,6 +2307,83 @@ mod tests {
         assert_eq!(Status::from_byte(3), None);
     }
 
+    #[test]
+    fn test_warmup_countdown() {
+        let mut data = vec![0x01, 0xC4, 0x05, 0x00, 0x00, 0x00];
+        data.extend_from_slice(&34u16.to_le_bytes()); // warmup_seconds = 34
+        data.extend_from_slice(&[0; 10]);
+        let parsed = parse_status_report(&data).unwrap();
+        assert_eq!(parsed.status, Status::Preparing);
+        assert_eq!(parsed.warmup_seconds, Some(34));
+        assert_eq!(parsed.description(), "Warming up (34s)");
+        assert_eq!(parse_report_01(&data).unwrap(), Status::Preparing);
+
+        // A leftover countdown is ignored once transmitting
+        data[2] = 0x02;
+        let parsed = parse_status_report(&data).unwrap();
+        assert_eq!(parsed.warmup_seconds, None);
+        assert_eq!(parsed.description(), "Transmit");
+        assert!(!parsed.blanking_active);
+    }
+
+    #[test]
+    fn test_blanking_active() {
+        let mut data = vec![0x01, 0xC4, 0x02, 0x01];
+        data.extend_from_slice(&[0; 14]);
+        let parsed = parse_status_report(&data).unwrap();
+        assert_eq!(parsed.status, Status::Transmit);
+        assert!(parsed.blanking_active);
+
+        data[3] = 0x00;
+        assert!(!parse_status_report(&data).unwrap().blanking_active);
+    }
+
+    #[test]
+    fn test_dual_range_channel_index() {
+        assert_eq!(channel_index("A"), Some(0));
+        assert_eq!(channel_index("B"), Some(1));
+        assert!(channel_index("C").is_none());
+    }
+
+    #[test]
+    fn test_sea_auto_mode() {
+        // HALO sea auto on with sea state Calm: slider is inactive
+        let mode = SeaAutoMode::from_halo(1, 0);
+        assert_eq!(mode, SeaAutoMode::Calm);
+        assert!(!mode.is_manual());
+
+        // Sea auto off: manual value applies
+        let mode = SeaAutoMode::from_halo(0, 0);
+        assert_eq!(mode, SeaAutoMode::Manual);
+        assert!(mode.is_manual());
+
+        assert_eq!(SeaAutoMode::from_sea_auto(0), SeaAutoMode::Manual);
+        assert_eq!(SeaAutoMode::from_sea_auto(1), SeaAutoMode::Harbor);
+        assert_eq!(SeaAutoMode::from_sea_auto(2), SeaAutoMode::Offshore);
+    }
+
+    #[test]
+    fn test_sea_auto_presets() {
+        let presets = [
+            (SeaAutoMode::from_sea_auto(1), SeaAutoMode::Harbor, "Harbor"),
+            (SeaAutoMode::from_sea_auto(2), SeaAutoMode::Offshore, "Offshore"),
+            (SeaAutoMode::from_halo(1, 0), SeaAutoMode::Calm, "Calm"),
+            (SeaAutoMode::from_halo(1, 1), SeaAutoMode::Moderate, "Moderate"),
+            (SeaAutoMode::from_halo(1, 2), SeaAutoMode::Rough, "Rough"),
+        ];
+        for (decoded, mode, label) in presets {
+            assert_eq!(decoded, mode);
+            assert_eq!(decoded.label(), label);
+            assert!(!decoded.is_manual());
+        }
+
+        // Presets of newer firmware we do not know are not passed off as known ones
+        assert_eq!(SeaAutoMode::from_sea_auto(3), SeaAutoMode::Unknown);
+        assert_eq!(SeaAutoMode::from_halo(1, 7), SeaAutoMode::Unknown);
+        assert_eq!(SeaAutoMode::Unknown.label(), "Unknown");
+        assert!(!SeaAutoMode::Unknown.is_manual());
+    }
+
     #[test]
     fn test_create_commands() {
         let status_cmd = create_status_command(true);
@@ -1624,6 +2397,142 @@ mod tests {
 
         let doppler_cmd = create_doppler_command(DopplerMode::Both);
         assert_eq!(doppler_cmd, vec![0x23, 0xc1, 1]);
+
+        assert_eq!(create_target_expansion_command(1, true), vec![0x12, 0xc1, 1]);
+        assert_eq!(create_target_expansion_command(2, false), vec![0x09, 0xc1, 2]);
+    }
+
+    #[test]
+    fn test_parse_target_report() {
+        let mut data = vec![REPORT_TARGETS, 0xC4, 2];
+        // Target 3 at 45.5 degrees, 1852 m, 5 m/s on 270 degrees
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.extend_from_slice(&455u16.to_le_bytes());
+        data.extend_from_slice(&18520u32.to_le_bytes());
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.extend_from_slice(&2700u16.to_le_bytes());
+        // Target 7 just acquired, no motion yet
+        data.extend_from_slice(&7u16.to_le_bytes());
+        data.extend_from_slice(&3100u16.to_le_bytes());
+        data.extend_from_slice(&9000u32.to_le_bytes());
+        data.extend_from_slice(&0xFFFFu16.to_le_bytes());
+        data.extend_from_slice(&0xFFFFu16.to_le_bytes());
+
+        let targets = parse_target_report(&data).unwrap();
+        assert_eq!(
+            targets,
+            vec![
+                TrackedTarget {
+                    id: 3,
+                    bearing: 45.5,
+                    distance: 1852.,
+                    speed: Some(5.),
+                    course: Some(270.),
+                },
+                TrackedTarget {
+                    id: 7,
+                    bearing: 310.,
+                    distance: 900.,
+                    speed: None,
+                    course: None,
+                },
+            ]
+        );
+
+        // A count beyond the data is an error, not a partial list
+        assert!(parse_target_report(&data[..20]).is_err());
+    }
+
+    #[test]
+    fn test_parse_report_06_learned_sector() {
+        let mut data = vec![0u8; 74];
+        data[0] = 0x06;
+        data[1] = 0xC4;
+        data[6..10].copy_from_slice(b"HALO");
+        // Sector 1 set by the user, 10.0 to 20.0 degrees
+        data[42] = 1;
+        data[43..45].copy_from_slice(&100i16.to_le_bytes());
+        data[45..47].copy_from_slice(&200i16.to_le_bytes());
+        // Sector 2 learned from the mast, 170.0 to 190.0 degrees
+        data[47] = SECTOR_LEARNED;
+        data[48..50].copy_from_slice(&1700i16.to_le_bytes());
+        data[50..52].copy_from_slice(&1900i16.to_le_bytes());
+
+        let parsed = parse_report_06_74(&data).unwrap();
+        assert_eq!(parsed.name.as_deref(), Some("HALO"));
+        assert!(parsed.sectors[0].enabled && !parsed.sectors[0].learned);
+        assert!(!parsed.sectors[1].enabled && parsed.sectors[1].learned);
+        assert_eq!((parsed.sectors[1].start_angle, parsed.sectors[1].end_angle), (1700, 1900));
+        assert!(!parsed.sectors[2].enabled && !parsed.sectors[2].learned);
+    }
+
+    #[test]
+    fn test_parse_report_02() {
+        let mut data = vec![0u8; REPORT_02_SIZE];
+        data[0] = 0x02;
+        data[1] = 0xC4;
+        data[2..6].copy_from_slice(&18520i32.to_le_bytes());
+        data[12] = 128; // gain
+        data[34] = 2; // interference rejection
+        data[38] = 1; // target expansion
+        data[42] = 2; // target boost
+
+        let controls = parse_report_02(&data).unwrap();
+        assert_eq!(controls.range, 18520);
+        assert_eq!(controls.gain, 128);
+        assert_eq!(controls.interference_rejection, 2);
+        assert_eq!(controls.target_expansion, 1);
+        assert_eq!(controls.target_boost, 2);
+        assert!(!controls.rain_auto);
+        assert!(!controls.interference_auto);
+        assert_eq!(controls.interference_description(), "medium");
+    }
+
+    #[test]
+    fn test_parse_report_02_rain_auto() {
+        let mut data = vec![0u8; REPORT_02_SIZE];
+        data[0] = 0x02;
+        data[1] = 0xC4;
+        data[8] = 1; // gain auto
+        data[13] = 1; // sea auto harbor
+        data[21] = 1; // rain auto
+        data[22] = 30; // rain
+
+        let controls = parse_report_02(&data).unwrap();
+        assert!(controls.rain_auto);
+        assert_eq!(controls.rain, 30);
+        assert_eq!(
+            controls.description(),
+            "Gain auto, sea harbor, rain auto, interference off"
+        );
+
+        data[21] = 0;
+        let controls = parse_report_02(&data).unwrap();
+        assert!(!controls.rain_auto);
+        assert_eq!(
+            controls.description(),
+            "Gain auto, sea harbor, rain 30, interference off"
+        );
+    }
+
+    #[test]
+    fn test_parse_report_02_interference_auto() {
+        let mut data = vec![0u8; REPORT_02_SIZE];
+        data[0] = 0x02;
+        data[1] = 0xC4;
+        data[33] = 1; // interference auto
+        data[34] = 2; // the level the radar picked
+
+        let controls = parse_report_02(&data).unwrap();
+        assert!(controls.interference_auto);
+        assert_eq!(controls.interference_rejection, 2);
+        assert_eq!(controls.interference_description(), "auto");
+        assert!(controls.description().ends_with(", interference auto"));
+
+        data[33] = 0;
+        let controls = parse_report_02(&data).unwrap();
+        assert!(!controls.interference_auto);
+        assert_eq!(controls.interference_description(), "medium");
     }
 
     #[test]
@@ -1665,6 +2574,58 @@ mod tests {
         assert_eq!(SPEED_PACKET_SIZE, 23);
     }
 
+    #[test]
+    fn test_parse_report_03() {
+        fn wide(s: &str) -> Vec<u8> {
+            let mut bytes: Vec<u8> = s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+            bytes.resize(32, 0);
+            bytes
+        }
+
+        // Report 03 packet: 0x03 0xC4 + model + data
+        let mut data = vec![0x03, 0xC4, 0x00]; // model = HALO
+        data.extend_from_slice(&[0; 31]); // antenna, _u00
+        data.extend_from_slice(&12345u32.to_le_bytes()); // hours = 12345
+        data.extend_from_slice(&[0; 20]); // _u01
+        data.extend_from_slice(&wide("Mar 20 2023"));
+        data.extend_from_slice(&wide("10:30:00"));
+        data.extend_from_slice(&[0; 7]); // _u02
+        assert_eq!(data.len(), REPORT_03_SIZE);
+
+        let parsed = parse_report_03(&data).unwrap();
+        assert_eq!(parsed.model, Model::HALO);
+        assert_eq!(parsed.operating_hours, 12345);
+        assert_eq!(parsed.firmware_date, "Mar 20 2023");
+        assert_eq!(parsed.firmware_time, "10:30:00");
+        assert_eq!(parsed.antenna_kind, None);
+
+        assert!(parse_report_03(&data[..REPORT_03_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn test_open_array_report_picks_open_array_capabilities() {
+        let mut data = vec![0u8; REPORT_03_SIZE];
+        data[..4].copy_from_slice(&[0x03, 0xC4, 0x00, 0x02]); // HALO, open array
+
+        let parsed = parse_report_03(&data).unwrap();
+        assert_eq!(parsed.antenna_kind, Some(AntennaKind::OpenArray));
+        let model = crate::models::get_model_with_antenna(Brand::Navico, "HALO", parsed.antenna_kind).unwrap();
+        assert_eq!(model.model, "HALO6");
+        assert_eq!(model.max_range, 133344);
+
+        data[3] = 0x01; // dome
+        let parsed = parse_report_03(&data).unwrap();
+        assert_eq!(parsed.antenna_kind, Some(AntennaKind::Dome));
+        let model = crate::models::get_model_with_antenna(Brand::Navico, "HALO", parsed.antenna_kind).unwrap();
+        assert_eq!(model.model, "HALO24");
+
+        // A known variant keeps its own capabilities, other families have no antenna kind
+        let model = crate::models::get_model_with_antenna(Brand::Navico, "HALO20+", Some(AntennaKind::OpenArray));
+        assert_eq!(model.unwrap().model, "HALO20+");
+        data[2] = 0x01; // 4G
+        assert_eq!(parse_report_03(&data).unwrap().antenna_kind, None);
+    }
+
     #[test]
     fn test_parse_report_04() {
         // Report 04 packet: 0x04 0xC4 + data
@@ -1683,6 +2644,27 @@ mod tests {
         assert_eq!(parsed.bearing_alignment, -50);
         assert_eq!(parsed.antenna_height, 100);
         assert_eq!(parsed.accent_light, 3);
+        assert_eq!(parsed.rotation_direction, RotationDirection::Clockwise);
+    }
+
+    #[test]
+    fn test_ccw_spokes_normalized_to_clockwise() {
+        let mut data = vec![0x04, 0xC4];
+        data.extend_from_slice(&[0; 12]);
+        data.push(1); // rotation_direction = counter-clockwise
+        data.extend_from_slice(&[0; 51]);
+        let parsed = parse_report_04(&data).unwrap();
+        assert_eq!(parsed.rotation_direction, RotationDirection::CounterClockwise);
+
+        // The scanner counts down while turning; after normalizing the
+        // spokes come in the increasing order the lookup expects
+        let reported = [0u16, 2047, 2046, 1024, 1];
+        let normalized: Vec<u16> = reported
+            .iter()
+            .map(|&a| parsed.rotation_direction.normalize(a, 2048))
+            .collect();
+        assert_eq!(normalized, vec![0, 1, 2, 1024, 2047]);
+        assert_eq!(RotationDirection::Clockwise.normalize(2047, 2048), 2047);
     }
 
     #[test]
@@ -1701,7 +2683,8 @@ mod tests {
             0x02,       // target_sep = 2
             0x30,       // sea_clutter = 48
             0x05,       // auto_sea_clutter = 5
-            0x00, 0x00, // fields 16-17
+            0x00,       // main_bang_suppression = 0
+            0x02,       // auto_response = fast
         ];
 
         let result = parse_report_08(&data);
@@ -1716,6 +2699,8 @@ mod tests {
         assert_eq!(parsed.target_separation, 2);
         assert_eq!(parsed.sea_clutter, 48);
         assert_eq!(parsed.auto_sea_clutter, 5);
+        assert_eq!(parsed.main_bang_suppression, 0);
+        assert_eq!(parsed.auto_response, Some(AutoResponse::Fast));
         assert!(parsed.doppler_state.is_none());
         assert!(parsed.doppler_speed.is_none());
     }
@@ -1747,4 +2732,136 @@ mod tests {
         assert_eq!(parsed.doppler_state, Some(1));
         assert_eq!(parsed.doppler_speed, Some(500));
     }
+
+    #[test]
+    fn test_parse_report_08_main_bang_suppression() {
+        let mut data = vec![0u8; REPORT_08_BASE_SIZE];
+        data[0] = 0x08;
+        data[1] = 0xC4;
+        data[16] = 0xC0; // main_bang_suppression = 192
+
+        let parsed = parse_report_08(&data).unwrap();
+        assert_eq!(parsed.main_bang_suppression, 192);
+
+        assert_eq!(create_main_bang_suppression_command(192), vec![0x32, 0xc1, 0xC0]);
+    }
+
+    #[test]
+    fn test_parse_report_08_auto_response() {
+        let mut data = vec![0u8; REPORT_08_BASE_SIZE];
+        data[0] = 0x08;
+        data[1] = 0xC4;
+        data[17] = 0x00; // auto_response = slow
+
+        let parsed = parse_report_08(&data).unwrap();
+        let response = parsed.auto_response.unwrap();
+        assert_eq!(response, AutoResponse::Slow);
+        assert_eq!(response.label(), "Slow");
+
+        data[17] = 0x07;
+        assert_eq!(parse_report_08(&data).unwrap().auto_response, None);
+
+        assert_eq!(
+            create_auto_response_command(AutoResponse::Medium),
+            vec![0x33, 0xc1, 0x01]
+        );
+    }
+
+    #[test]
+    fn test_parse_report_08_lirf() {
+        let mut data = vec![0u8; REPORT_08_BASE_SIZE];
+        data[0] = 0x08;
+        data[1] = 0xC4;
+        data[3] = 0x02; // interference level, independent of the flag
+        data[6] = 0x01; // lirf on
+
+        let parsed = parse_report_08(&data).unwrap();
+        assert!(parsed.lirf);
+        assert_eq!(parsed.local_interference_rejection, 2);
+
+        data[6] = 0x00;
+        assert!(!parse_report_08(&data).unwrap().lirf);
+
+        assert_eq!(create_lirf_command(true), vec![0x34, 0xc1, 0x01]);
+    }
+
+    #[test]
+    fn test_split_batched_reports() {
+        let mut status = vec![0u8; REPORT_01_SIZE];
+        status[0] = 0x01;
+        status[1] = 0xC4;
+        status[2] = 0x02; // transmit
+        let mut settings = vec![0u8; REPORT_08_EXTENDED_SIZE];
+        settings[0] = 0x08;
+        settings[1] = 0xC4;
+        settings[4] = 0x01; // fast scan
+
+        let batch = [status.clone(), settings.clone()].concat();
+        let reports = split_reports(&batch);
+        assert_eq!(reports, vec![&status[..], &settings[..]]);
+        assert!(parse_status_report(reports[0]).is_ok());
+        assert_eq!(parse_report_08(reports[1]).unwrap().scan_speed_mode, Some(ScanSpeed::Fast));
+
+        // A single report, or anything that is not a batch, stays whole
+        assert_eq!(split_reports(&status), vec![&status[..]]);
+        let odd = [status.clone(), vec![0xde, 0xad]].concat();
+        assert_eq!(split_reports(&odd), vec![&odd[..]]);
+    }
+
+    #[test]
+    fn test_parse_report_08_noise_rejection_and_sea_clutter_curve() {
+        let mut data = vec![0u8; REPORT_08_BASE_SIZE];
+        data[0] = 0x08;
+        data[1] = 0xC4;
+        data[7] = 0x01; // steep curve
+        data[12] = 0x02; // medium noise rejection
+
+        let parsed = parse_report_08(&data).unwrap();
+        assert_eq!(parsed.noise_rejection, 2);
+        assert_eq!(parsed.noise_rejection_level, Some(NoiseRejection::Medium));
+        assert_eq!(parsed.sea_clutter_curve, Some(SeaClutterCurve::Steep));
+
+        data[7] = 0x00;
+        data[12] = 0x09;
+        let parsed = parse_report_08(&data).unwrap();
+        assert_eq!(parsed.noise_rejection_level, None);
+        assert_eq!(parsed.sea_clutter_curve, Some(SeaClutterCurve::Standard));
+
+        assert_eq!(create_noise_rejection_command(NoiseRejection::High), vec![0x21, 0xc1, 0x03]);
+    }
+
+    #[test]
+    fn test_parse_report_08_fast_scan() {
+        let mut data = vec![0u8; REPORT_08_BASE_SIZE];
+        data[0] = 0x08;
+        data[1] = 0xC4;
+        data[4] = 0x01; // fast scan
+
+        let parsed = parse_report_08(&data).unwrap();
+        assert_eq!(parsed.scan_speed, 1);
+        assert_eq!(parsed.scan_speed_mode, Some(ScanSpeed::Fast));
+        assert_eq!(parsed.scan_speed_mode.unwrap().as_str(), "fast");
+
+        data[4] = 0x00;
+        assert_eq!(parse_report_08(&data).unwrap().scan_speed_mode, Some(ScanSpeed::Normal));
+        data[4] = 0x07;
+        assert_eq!(parse_report_08(&data).unwrap().scan_speed_mode, None);
+    }
+
+    #[test]
+    fn test_parse_report_08_long_pulse() {
+        let mut data = vec![0u8; REPORT_08_BASE_SIZE];
+        data[0] = 0x08;
+        data[1] = 0xC4;
+        data[8] = 0x02; // long pulse
+
+        let parsed = parse_report_08(&data).unwrap();
+        assert_eq!(parsed.pulse_length, Some(PulseLength::Long));
+        assert_eq!(parsed.pulse_length.unwrap().as_str(), "long");
+
+        data[8] = 0x00;
+        assert_eq!(parse_report_08(&data).unwrap().pulse_length, Some(PulseLength::Short));
+        data[8] = 0x05;
+        assert_eq!(parse_report_08(&data).unwrap().pulse_length, None);
+    }
 }
@@ -10,6 +10,9 @@
 //! - **Magnum**: 4kW and 12kW
 //! - **Quantum**: Q24, Q24C, Q24D (with Doppler)
 //! - **Cyclone/Cyclone Pro**: Next-gen solid state
+//!
+//! The HD and SHD magnetron series speak the RD protocol with a status
+//! report of their own; [`report_family`] tells the families apart.
 
 use serde::Deserialize;
 use crate::error::ParseError;
@@ -585,6 +588,9 @@ pub struct ParsedQuantumStatus {
     pub ranges: Vec<u32>,
 }
 
+/// Report ID of the Quantum status report, the first four bytes (LE)
+pub const QUANTUM_STATUS_REPORT: u32 = 0x00280002;
+
 /// Parse Quantum status report (0x00280002)
 pub fn parse_quantum_status(data: &[u8]) -> Result<ParsedQuantumStatus, ParseError> {
     const MIN_SIZE: usize = 228; // Minimum size for status report with ranges
@@ -707,18 +713,67 @@ pub fn parse_rd_frame_header(data: &[u8]) -> Result<ParsedRdFrame, ParseError> {
     })
 }
 
+// =============================================================================
+// Report Families
+// =============================================================================
+
+/// Status report ID of the RD series
+pub const RD_STATUS_REPORT: u32 = 0x010001;
+
+/// Status report ID of the HD and SHD magnetron series
+pub const HD_STATUS_REPORT: u32 = 0x018801;
+
+/// The protocol family a report belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFamily {
+    /// Analog RD series
+    Rd,
+    /// HD and SHD magnetron series, the RD protocol with its own status report
+    Hd,
+    /// Solid-state Quantum and Cyclone
+    Quantum,
+}
+
+impl ReportFamily {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFamily::Rd => "RD",
+            ReportFamily::Hd => "HD",
+            ReportFamily::Quantum => "Quantum",
+        }
+    }
+}
+
+/// The family of a report by its ID, `None` for beacons and unknown reports
+pub fn report_family(data: &[u8]) -> Option<ReportFamily> {
+    let id = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+    match id {
+        HD_STATUS_REPORT => Some(ReportFamily::Hd),
+        0x010001..=0x010006 => Some(ReportFamily::Rd),
+        0x280001..=0x280003 => Some(ReportFamily::Quantum),
+        _ => None,
+    }
+}
+
 // =============================================================================
 // RD Status Report
 // =============================================================================
 
+/// Offset of the range index in the HD status report
+const HD_RANGE_INDEX_OFFSET: usize = 296;
+
 /// Parsed RD status report
 #[derive(Debug, Clone)]
 pub struct ParsedRdStatus {
+    /// Range table, in thousandths of a nautical mile
     pub ranges: Vec<u32>,
     pub status: u8,
     pub warmup_time: u8,
     pub signal_strength: u8,
     pub range_id: u8,
+    /// Index of the range in use: `range_id`, or the byte at offset 296 of
+    /// HD reports
+    pub range_index: u8,
     pub auto_gain: bool,
     pub gain: u32,
     pub auto_sea: u8,
@@ -747,14 +802,20 @@ pub fn parse_rd_status(data: &[u8]) -> Result<ParsedRdStatus, ParseError> {
     }
 
     let field01 = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-    if field01 != 0x010001 && field01 != 0x018801 {
+    if field01 != RD_STATUS_REPORT && field01 != HD_STATUS_REPORT {
         return Err(ParseError::InvalidHeader {
             expected: vec![0x01, 0x00, 0x01, 0x00],
             actual: vec![data[0], data[1], data[2], data[3]],
         });
     }
 
-    let is_hd = field01 == 0x018801;
+    let is_hd = field01 == HD_STATUS_REPORT;
+    if is_hd && data.len() <= HD_RANGE_INDEX_OFFSET {
+        return Err(ParseError::TooShort {
+            expected: HD_RANGE_INDEX_OFFSET + 1,
+            actual: data.len(),
+        });
+    }
 
     // Parse ranges (11 u32 values starting at offset 4)
     let mut ranges = Vec::with_capacity(11);
@@ -782,6 +843,7 @@ pub fn parse_rd_status(data: &[u8]) -> Result<ParsedRdStatus, ParseError> {
     let interference_rejection = data[226];
     let target_expansion = data[230];
     let mbs_enabled = data[244] > 0;
+    let range_index = if is_hd { data[HD_RANGE_INDEX_OFFSET] } else { range_id };
 
     Ok(ParsedRdStatus {
         ranges,
@@ -789,6 +851,7 @@ pub fn parse_rd_status(data: &[u8]) -> Result<ParsedRdStatus, ParseError> {
         warmup_time,
         signal_strength,
         range_id,
+        range_index,
         auto_gain,
         gain,
         auto_sea,
@@ -807,6 +870,15 @@ pub fn parse_rd_status(data: &[u8]) -> Result<ParsedRdStatus, ParseError> {
     })
 }
 
+impl ParsedRdStatus {
+    /// The range in use in meters, `None` when the index is not in the table
+    pub fn range_meters(&self) -> Option<u32> {
+        self.ranges
+            .get(self.range_index as usize)
+            .map(|&range| (range as f64 * 1.852) as u32)
+    }
+}
+
 // =============================================================================
 // Spoke Data Decompression
 // =============================================================================
@@ -1039,6 +1111,43 @@ mod tests {
         assert_eq!(BaseModel::Quantum.to_string(), "Quantum");
     }
 
+    /// An HD status report transmitting at the fourth range of its table
+    fn hd_status_report() -> Vec<u8> {
+        let mut data = vec![0u8; 300];
+        data[..4].copy_from_slice(&HD_STATUS_REPORT.to_le_bytes());
+        for (i, range) in [125u32, 250, 500, 1000, 1500, 2000].iter().enumerate() {
+            data[4 + i * 4..8 + i * 4].copy_from_slice(&range.to_le_bytes());
+        }
+        data[180] = 0x01; // transmit
+        data[193] = 0; // not used by HD
+        data[296] = 3;
+        data
+    }
+
+    #[test]
+    fn test_parse_hd_status_report() {
+        let data = hd_status_report();
+        assert_eq!(report_family(&data), Some(ReportFamily::Hd));
+
+        let status = parse_rd_status(&data).unwrap();
+        assert!(status.is_hd);
+        assert_eq!(status.status, 0x01);
+        assert_eq!(status.range_index, 3);
+        assert_eq!(status.range_meters(), Some(1852));
+
+        // The same report from an RD takes the range index at 193
+        let mut rd = data.clone();
+        rd[..4].copy_from_slice(&RD_STATUS_REPORT.to_le_bytes());
+        rd[193] = 1;
+        assert_eq!(report_family(&rd), Some(ReportFamily::Rd));
+        assert_eq!(parse_rd_status(&rd).unwrap().range_meters(), Some(463));
+
+        // HD reports must reach the range index
+        assert!(matches!(parse_rd_status(&data[..296]), Err(ParseError::TooShort { .. })));
+        assert_eq!(report_family(&0x280002u32.to_le_bytes()), Some(ReportFamily::Quantum));
+        assert_eq!(report_family(&[0x01, 0x00]), None);
+    }
+
     #[test]
     fn test_parse_short_packet() {
         let result = parse_beacon_56(&[0u8; 10]);
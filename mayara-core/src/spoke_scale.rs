@@ -0,0 +1,115 @@
+//! Distance along a spoke
+//!
+//! A spoke of `spoke_len` cells covers the distance the radar reports as its
+//! range, corrected by the range offset of the radar's preferences, so that
+//! the ranges the labels show and the distances of the cells agree. Cell `i`
+//! covers `i..i + 1` in spoke radius, so its near edge is at
+//! `radius_to_meters(i)` and its middle at `radius_to_meters(i + 0.5)`.
+//!
+//! Distances that fall exactly on a cell edge are snapped to it before
+//! rounding, so 500 m at 2 m per cell is 250 cells and not 249.
+
+/// Radii closer than this to a whole cell count as on the cell edge
+const EDGE_TOLERANCE: f64 = 1e-6;
+
+/// Mapping between meters and cells of spokes of one range and length
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpokeScale {
+    /// Meters covered by the whole spoke, with the offset applied
+    meters: f64,
+    spoke_len: usize,
+}
+
+impl SpokeScale {
+    /// Scale of spokes of `spoke_len` cells at `range_meters`, with
+    /// `range_offset` meters added as for the range labels; `None` when the
+    /// spoke is empty or covers no distance
+    pub fn new(range_meters: f64, spoke_len: usize, range_offset: f64) -> Option<Self> {
+        let meters = range_meters + range_offset;
+        if spoke_len == 0 || meters.is_nan() || meters <= 0. {
+            return None;
+        }
+        Some(SpokeScale { meters, spoke_len })
+    }
+
+    pub fn spoke_len(&self) -> usize {
+        self.spoke_len
+    }
+
+    pub fn meters_per_cell(&self) -> f64 {
+        self.meters / self.spoke_len as f64
+    }
+
+    /// Meters from the antenna of a (fractional) radius in cells
+    pub fn radius_to_meters(&self, radius: f64) -> f64 {
+        radius * self.meters / self.spoke_len as f64
+    }
+
+    /// Radius in cells, possibly fractional, of a distance from the antenna
+    pub fn meters_to_radius(&self, meters: f64) -> f64 {
+        let radius = meters * self.spoke_len as f64 / self.meters;
+        let edge = radius.round();
+        if (radius - edge).abs() < EDGE_TOLERANCE {
+            edge
+        } else {
+            radius
+        }
+    }
+
+    /// Number of cells that start closer than `meters`, at most the spoke length
+    pub fn cells_before(&self, meters: f64) -> usize {
+        (self.meters_to_radius(meters).ceil().max(0.) as usize).min(self.spoke_len)
+    }
+
+    /// Number of cells that lie entirely within `meters`, at most the spoke length
+    pub fn cells_within(&self, meters: f64) -> usize {
+        (self.meters_to_radius(meters).floor().max(0.) as usize).min(self.spoke_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_and_meters_convert_both_ways() {
+        let scale = SpokeScale::new(1024., 512, 0.).unwrap();
+        assert_eq!(scale.meters_per_cell(), 2.);
+        assert_eq!(scale.radius_to_meters(10.5), 21.);
+        assert_eq!(scale.meters_to_radius(21.), 10.5);
+        assert_eq!(scale.cells_before(21.), 11);
+        assert_eq!(scale.cells_within(21.), 10);
+        // Beyond the spoke, and before the antenna
+        assert_eq!(scale.cells_before(5000.), 512);
+        assert_eq!(scale.cells_within(-3.), 0);
+
+        assert!(SpokeScale::new(1852., 0, 0.).is_none());
+        assert!(SpokeScale::new(0., 512, 0.).is_none());
+        assert!(SpokeScale::new(100., 512, -100.).is_none());
+    }
+
+    #[test]
+    fn offset_changes_the_distance_of_every_cell() {
+        // A radar that reports 1852 m where its spokes cover 1704 m
+        let scale = SpokeScale::new(1852., 1024, -148.).unwrap();
+        assert_eq!(scale.meters_per_cell(), 1704. / 1024.);
+        assert_eq!(scale.radius_to_meters(1024.), 1704.);
+        assert_eq!(scale.cells_before(852.), 512);
+        assert_eq!(scale.cells_within(1704.), 1024);
+    }
+
+    #[test]
+    fn distances_on_a_cell_edge_round_to_that_edge() {
+        // 1852 / 2048 m per cell does not divide evenly in floating point
+        let scale = SpokeScale::new(1852., 2048, 0.).unwrap();
+        for cell in [1usize, 3, 100, 999, 2047] {
+            let edge = scale.radius_to_meters(cell as f64);
+            assert_eq!(scale.cells_before(edge), cell);
+            assert_eq!(scale.cells_within(edge), cell);
+        }
+        let scale = SpokeScale::new(1024., 512, 0.).unwrap();
+        assert_eq!(scale.cells_before(500.), 250);
+        assert_eq!(scale.cells_within(500.), 250);
+        assert_eq!(scale.cells_before(500.5), 251);
+    }
+}
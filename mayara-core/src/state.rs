@@ -7,6 +7,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::Brand;
+
 use crate::protocol::furuno::command::{
     parse_bird_mode_response, parse_blind_sector_response, parse_gain_response,
     parse_main_bang_response, parse_rain_response, parse_range_response,
@@ -15,7 +17,7 @@ use crate::protocol::furuno::command::{
     parse_tx_channel_response, range_index_to_meters, ControlValue as ParsedControlValue,
 };
 
-/// Power state of the radar
+/// Power state of the radar, the same for every brand
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PowerState {
@@ -23,6 +25,7 @@ pub enum PowerState {
     Standby,
     Transmit,
     Warming,
+    Unknown,
 }
 
 impl Default for PowerState {
@@ -31,6 +34,37 @@ impl Default for PowerState {
     }
 }
 
+impl PowerState {
+    /// Decode the power or status value a radar of `brand` reports:
+    /// - Navico report 01 status byte: 0 off, 1 standby, 2 transmit, 5 warming up
+    /// - Garmin transmit state report: 0 off, 1 standby, 2 transmit, 3 warming up
+    /// - Raymarine status byte: 0 standby, 1 transmit, 2 warming up, 3 off
+    /// - Furuno `$N69` status: 1 standby, 2 transmit
+    pub fn from_raw(brand: Brand, raw: u32) -> Self {
+        match (brand, raw) {
+            (Brand::Navico, 0) | (Brand::Garmin, 0) | (Brand::Raymarine, 3) => PowerState::Off,
+            (Brand::Navico | Brand::Garmin | Brand::Furuno, 1) | (Brand::Raymarine, 0) => {
+                PowerState::Standby
+            }
+            (Brand::Navico | Brand::Garmin | Brand::Furuno, 2) | (Brand::Raymarine, 1) => {
+                PowerState::Transmit
+            }
+            (Brand::Navico, 5) | (Brand::Garmin, 3) | (Brand::Raymarine, 2) => PowerState::Warming,
+            _ => PowerState::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PowerState::Off => "off",
+            PowerState::Standby => "standby",
+            PowerState::Transmit => "transmit",
+            PowerState::Warming => "warming",
+            PowerState::Unknown => "unknown",
+        }
+    }
+}
+
 /// Control value with auto/manual mode (API format)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ControlValueState {
@@ -287,13 +321,7 @@ impl RadarState {
         let mut map = HashMap::new();
 
         // Power state
-        let power_str = match self.power {
-            PowerState::Off => "off",
-            PowerState::Standby => "standby",
-            PowerState::Transmit => "transmit",
-            PowerState::Warming => "warming",
-        };
-        map.insert("power".to_string(), serde_json::json!(power_str));
+        map.insert("power".to_string(), serde_json::json!(self.power.as_str()));
 
         // Range
         map.insert("range".to_string(), serde_json::json!(self.range));
@@ -394,6 +422,52 @@ impl RadarState {
     }
 }
 
+/// Fields that changed between two consecutive settings reports
+pub type SettingsDiff = HashMap<String, serde_json::Value>;
+
+/// Settings report diff tracker
+///
+/// Radars repeat their settings reports every few seconds, mostly unchanged.
+/// This remembers the last decoded value of each field and returns only the
+/// fields that differ, so no-op reports can be suppressed.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsReportTracker {
+    last: HashMap<String, serde_json::Value>,
+}
+
+impl SettingsReportTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare a decoded settings report against the previous values
+    ///
+    /// Fields not present in `report` are left untouched, so reports that
+    /// carry different subsets of the settings can share one tracker.
+    /// Returns `None` when nothing changed.
+    pub fn diff(&mut self, report: HashMap<String, serde_json::Value>) -> Option<SettingsDiff> {
+        let mut diff = SettingsDiff::new();
+
+        for (field, value) in report {
+            if self.last.get(&field) != Some(&value) {
+                self.last.insert(field.clone(), value.clone());
+                diff.insert(field, value);
+            }
+        }
+
+        if diff.is_empty() {
+            None
+        } else {
+            Some(diff)
+        }
+    }
+
+    /// Forget all previous values, so the next report is emitted in full
+    pub fn reset(&mut self) {
+        self.last.clear();
+    }
+}
+
 /// Generate all request commands to query current state
 ///
 /// Returns a vector of command strings that should be sent to the radar
@@ -431,6 +505,29 @@ pub fn generate_state_requests() -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn power_state_from_raw_per_brand() {
+        use PowerState::*;
+        let decode = |brand, raws: &[u32]| -> Vec<PowerState> {
+            raws.iter().map(|&raw| PowerState::from_raw(brand, raw)).collect()
+        };
+        assert_eq!(
+            decode(Brand::Navico, &[0, 1, 2, 5, 3]),
+            [Off, Standby, Transmit, Warming, Unknown]
+        );
+        assert_eq!(
+            decode(Brand::Garmin, &[0, 1, 2, 3, 4]),
+            [Off, Standby, Transmit, Warming, Unknown]
+        );
+        assert_eq!(
+            decode(Brand::Raymarine, &[3, 0, 1, 2, 4]),
+            [Off, Standby, Transmit, Warming, Unknown]
+        );
+        assert_eq!(decode(Brand::Furuno, &[1, 2, 0]), [Standby, Transmit, Unknown]);
+        assert_eq!(Warming.as_str(), "warming");
+        assert_eq!(serde_json::json!(Unknown), "unknown");
+    }
+
     #[test]
     fn test_update_from_status_response() {
         let mut state = RadarState::new();
@@ -604,4 +701,26 @@ mod tests {
         assert!(state.update_from_response("$NEC,2"));
         assert_eq!(state.tx_channel, 2);
     }
+
+    #[test]
+    fn test_settings_report_diff() {
+        let report = |gain: i32, sea: i32| {
+            HashMap::from([
+                ("gain".to_string(), serde_json::json!(gain)),
+                ("sea".to_string(), serde_json::json!(sea)),
+            ])
+        };
+        let mut tracker = SettingsReportTracker::new();
+
+        // First report is emitted in full
+        assert_eq!(tracker.diff(report(50, 30)).map(|d| d.len()), Some(2));
+
+        // Identical report produces no diff
+        assert!(tracker.diff(report(50, 30)).is_none());
+
+        // One changed field produces a single-field diff
+        let diff = tracker.diff(report(60, 30)).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff.get("gain"), Some(&serde_json::json!(60)));
+    }
 }
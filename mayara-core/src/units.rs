@@ -0,0 +1,146 @@
+//! Display units for values reported to clients
+//!
+//! Internally all bearings are in degrees. These preferences only change how
+//! they are reported to (and accepted from) API clients.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::arpa::{ArpaTarget, TargetPosition};
+use crate::capabilities::BlindSector;
+use crate::guard_zones::{GuardZone, ZoneShape};
+
+/// NATO mils in a full revolution
+pub const MILS_PER_REVOLUTION: f64 = 6400.0;
+
+/// Unit for reported bearings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BearingUnit {
+    #[default]
+    Degrees,
+    Mils,
+}
+
+impl BearingUnit {
+    /// Convert a bearing in degrees to this unit
+    pub fn from_degrees(&self, degrees: f64) -> f64 {
+        match self {
+            BearingUnit::Degrees => degrees,
+            BearingUnit::Mils => degrees * MILS_PER_REVOLUTION / 360.0,
+        }
+    }
+
+    /// Convert a bearing in this unit to degrees
+    pub fn to_degrees(&self, value: f64) -> f64 {
+        match self {
+            BearingUnit::Degrees => value,
+            BearingUnit::Mils => value * 360.0 / MILS_PER_REVOLUTION,
+        }
+    }
+
+    /// Value of a full revolution in this unit
+    pub fn revolution(&self) -> f64 {
+        self.from_degrees(360.0)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BearingUnit::Degrees => "degrees",
+            BearingUnit::Mils => "mils",
+        }
+    }
+
+    /// A target with its bearing and course in this unit
+    pub fn target_from_degrees(&self, target: &ArpaTarget) -> ArpaTarget {
+        let mut target = target.clone();
+        target.position = self.position_from_degrees(target.position);
+        target.motion.course = self.from_degrees(target.motion.course);
+        target
+    }
+
+    /// A target position with its bearing in this unit
+    pub fn position_from_degrees(&self, position: TargetPosition) -> TargetPosition {
+        TargetPosition {
+            bearing: self.from_degrees(position.bearing),
+            ..position
+        }
+    }
+
+    /// A blind sector with its bearings in this unit
+    pub fn sector_from_degrees(&self, sector: BlindSector) -> BlindSector {
+        BlindSector {
+            start: self.from_degrees(sector.start),
+            end: self.from_degrees(sector.end),
+        }
+    }
+
+    /// A guard zone with its bearings in this unit
+    pub fn zone_from_degrees(&self, zone: &GuardZone) -> GuardZone {
+        map_zone(zone, |b| self.from_degrees(b))
+    }
+
+    /// A guard zone given in this unit with its bearings in degrees
+    pub fn zone_to_degrees(&self, zone: &GuardZone) -> GuardZone {
+        map_zone(zone, |b| self.to_degrees(b))
+    }
+}
+
+/// A guard zone with its bearings converted by `convert`
+fn map_zone(zone: &GuardZone, convert: impl Fn(f64) -> f64) -> GuardZone {
+    let mut zone = zone.clone();
+    if let ZoneShape::Arc {
+        start_bearing,
+        end_bearing,
+        ..
+    } = &mut zone.shape
+    {
+        *start_bearing = convert(*start_bearing);
+        *end_bearing = convert(*end_bearing);
+    }
+    zone
+}
+
+impl FromStr for BearingUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "degrees" | "deg" => Ok(BearingUnit::Degrees),
+            "mils" | "mil" => Ok(BearingUnit::Mils),
+            _ => Err(format!("Unknown bearing unit '{}', use degrees or mils", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ninety_degrees_is_1600_mils() {
+        assert_eq!(BearingUnit::Mils.from_degrees(90.0), 1600.0);
+        assert_eq!(BearingUnit::Mils.to_degrees(1600.0), 90.0);
+        assert_eq!(BearingUnit::Mils.revolution(), MILS_PER_REVOLUTION);
+        assert_eq!(BearingUnit::Degrees.from_degrees(90.0), 90.0);
+        assert_eq!(BearingUnit::default(), BearingUnit::Degrees);
+        assert_eq!("mils".parse::<BearingUnit>(), Ok(BearingUnit::Mils));
+
+        let zone = GuardZone::new_arc(1, 90.0, 180.0, 100.0, 500.0);
+        let bearings = |zone: &GuardZone| match zone.shape {
+            ZoneShape::Arc {
+                start_bearing,
+                end_bearing,
+                ..
+            } => (start_bearing, end_bearing),
+            _ => panic!("Expected arc"),
+        };
+        let mils = BearingUnit::Mils.zone_from_degrees(&zone);
+        assert_eq!(bearings(&mils), (1600.0, 3200.0));
+        assert_eq!(bearings(&BearingUnit::Mils.zone_to_degrees(&mils)), (90.0, 180.0));
+
+        let sector = BearingUnit::Mils.sector_from_degrees(BlindSector { start: 180.0, end: 270.0 });
+        assert_eq!(sector, BlindSector { start: 3200.0, end: 4800.0 });
+    }
+}
@@ -0,0 +1,254 @@
+//! Persisted log of guard zone alarms
+//!
+//! The live alarm events tell a client that something entered a guard zone
+//! right now; this log keeps them, so the history is still there after the
+//! client or the server restarted. Every enter and exit is appended to
+//! `alarms.ndjson` in the data directory, one JSON object per line. Only the
+//! last `capacity` events are kept: when the file has grown to twice that it
+//! is rewritten with the recent ones.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use mayara_core::guard_zones::{ZoneAlert, ZoneAlertState};
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_project_dirs;
+
+const ALARM_LOG_FILE: &str = "alarms.ndjson";
+
+/// Default number of events kept
+pub const DEFAULT_ALARM_LOG_SIZE: usize = 1000;
+
+/// Whether a target entered or left the zone
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AlarmKind {
+    Enter,
+    Exit,
+}
+
+impl From<ZoneAlertState> for AlarmKind {
+    /// The transition into `state`
+    fn from(state: ZoneAlertState) -> Self {
+        match state {
+            ZoneAlertState::Alarm => AlarmKind::Enter,
+            ZoneAlertState::Clear => AlarmKind::Exit,
+        }
+    }
+}
+
+/// One logged alarm transition
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AlarmEvent {
+    pub timestamp: u64, // Milliseconds since the epoch
+    pub radar: String,
+    pub zone_id: u32,
+    pub kind: AlarmKind,
+    /// Bearing from the bow in degrees
+    pub bearing: f64,
+    /// Distance from the antenna in meters
+    pub distance: f64,
+    /// WGS84 position, when own-ship position and heading were known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+}
+
+impl AlarmEvent {
+    pub fn new(radar: &str, kind: AlarmKind, alert: &ZoneAlert) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        AlarmEvent {
+            timestamp,
+            radar: radar.to_string(),
+            zone_id: alert.zone_id,
+            kind,
+            bearing: alert.bearing,
+            distance: alert.distance,
+            latitude: alert.position.latitude,
+            longitude: alert.position.longitude,
+        }
+    }
+}
+
+pub struct AlarmLog {
+    path: PathBuf,
+    capacity: usize,
+    events: VecDeque<AlarmEvent>,
+    /// Lines in the file, which may be more than `events.len()`
+    lines: usize,
+}
+
+pub type SharedAlarmLog = Arc<Mutex<AlarmLog>>;
+
+impl AlarmLog {
+    pub fn default_path() -> PathBuf {
+        get_project_dirs().data_dir().join(ALARM_LOG_FILE)
+    }
+
+    /// Open the log at `path`, reading the events already in it; a missing
+    /// file is an empty log
+    pub fn open(path: &Path, capacity: usize) -> io::Result<Self> {
+        let mut events = VecDeque::new();
+        let mut lines = 0;
+        match File::open(path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+                    lines += 1;
+                    match serde_json::from_str(&line) {
+                        Ok(event) => events.push_back(event),
+                        Err(e) => warn!("Skipping alarm log line {}: {}", lines, e),
+                    }
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        while events.len() > capacity {
+            events.pop_front();
+        }
+        Ok(AlarmLog {
+            path: path.to_owned(),
+            capacity,
+            events,
+            lines,
+        })
+    }
+
+    /// Open the log in the data directory, starting empty when it cannot be read
+    pub fn open_default() -> SharedAlarmLog {
+        let path = Self::default_path();
+        let log = Self::open(&path, DEFAULT_ALARM_LOG_SIZE).unwrap_or_else(|e| {
+            warn!("Ignoring alarm log '{}': {}", path.display(), e);
+            AlarmLog {
+                path,
+                capacity: DEFAULT_ALARM_LOG_SIZE,
+                events: VecDeque::new(),
+                lines: 0,
+            }
+        });
+        Arc::new(Mutex::new(log))
+    }
+
+    /// Append an event, to memory and to the file
+    pub fn record(&mut self, event: AlarmEvent) -> io::Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        self.events.push_back(event);
+        if self.events.len() > self.capacity {
+            self.events.pop_front();
+        }
+        if self.lines + 1 >= 2 * self.capacity {
+            return self.rewrite();
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        let event = self.events.back().expect("just pushed");
+        writeln!(file, "{}", serde_json::to_string(event)?)?;
+        self.lines += 1;
+        Ok(())
+    }
+
+    fn rewrite(&mut self) -> io::Result<()> {
+        let mut text = String::new();
+        for event in &self.events {
+            text.push_str(&serde_json::to_string(event)?);
+            text.push('\n');
+        }
+        fs::write(&self.path, text)?;
+        self.lines = self.events.len();
+        Ok(())
+    }
+
+    /// The last `limit` events of `radar`, oldest first
+    pub fn recent(&self, radar: &str, limit: usize) -> Vec<AlarmEvent> {
+        let mut events: Vec<AlarmEvent> = self
+            .events
+            .iter()
+            .rev()
+            .filter(|e| e.radar == radar)
+            .take(limit)
+            .cloned()
+            .collect();
+        events.reverse();
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mayara_core::geo::TargetPosition;
+    use tempfile::TempDir;
+
+    fn alert(bearing: f64, distance: f64) -> ZoneAlert {
+        ZoneAlert {
+            zone_id: 1,
+            state: ZoneAlertState::Alarm,
+            timestamp: 0,
+            bearing,
+            distance,
+            intensity: 12,
+            position: TargetPosition::locate(bearing, distance, None, None),
+        }
+    }
+
+    #[test]
+    fn enter_and_exit_are_persisted_and_queryable() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(ALARM_LOG_FILE);
+
+        let mut log = AlarmLog::open(&path, 10).unwrap();
+        log.record(AlarmEvent::new("radar-1", AlarmKind::Enter, &alert(45., 900.)))
+            .unwrap();
+        log.record(AlarmEvent::new("radar-1", AlarmKind::Exit, &alert(50., 1010.)))
+            .unwrap();
+        log.record(AlarmEvent::new("radar-2", AlarmKind::Enter, &alert(10., 300.)))
+            .unwrap();
+
+        let reopened = AlarmLog::open(&path, 10).unwrap();
+        let events = reopened.recent("radar-1", 10);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].kind, AlarmKind::Enter);
+        assert_eq!(events[0].bearing, 45.);
+        assert_eq!(events[0].distance, 900.);
+        assert_eq!(events[1].kind, AlarmKind::Exit);
+        assert!(events[1].timestamp >= events[0].timestamp);
+        assert_eq!(reopened.recent("radar-1", 1), events[1..]);
+    }
+
+    #[test]
+    fn file_is_trimmed_to_capacity() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(ALARM_LOG_FILE);
+
+        let mut log = AlarmLog::open(&path, 3).unwrap();
+        for i in 0..10 {
+            log.record(AlarmEvent::new("radar-1", AlarmKind::Enter, &alert(i as f64, 500.)))
+                .unwrap();
+        }
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert!(lines < 6);
+        let bearings: Vec<f64> = AlarmLog::open(&path, 3)
+            .unwrap()
+            .recent("radar-1", 10)
+            .iter()
+            .map(|e| e.bearing)
+            .collect();
+        assert_eq!(bearings, vec![7., 8., 9.]);
+    }
+}
@@ -0,0 +1,265 @@
+//! Snapshots of the radar picture when a guard zone alarm fires
+//!
+//! An alarm tells that something entered a zone, but not what it was. With
+//! `--alarm-snapshots <DIR>` every alarm that fires (a target entering a
+//! zone) captures the next full rotation of the radar with the rotation
+//! locked snapshot, and writes it to the directory twice: as a PNG, painted
+//! as by `GET /snapshot?format=png`, and as the raw spokes, a RadarMessage in
+//! protobuf with one spoke per azimuth. Both files are named after the
+//! radar, the zone and the time of the alarm, e.g.
+//! `Navico-1234-A-zone1-20261014T101530.250Z.png`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::{info, warn};
+use protobuf::Message;
+
+use crate::alarm_log::{AlarmEvent, AlarmKind};
+use crate::navdata;
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::png::{self, SnapshotStyle};
+use crate::radar::range::RangeUnitPreference;
+use crate::radar::{snapshot, Legend, RadarError, RadarInfo};
+
+/// How long to wait for a rotation boundary and the rotation after it
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The files written for one alarm
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlarmSnapshot {
+    pub image: PathBuf,
+    pub spokes: PathBuf,
+}
+
+#[derive(Clone, Debug)]
+pub struct AlarmSnapshots {
+    dir: PathBuf,
+    units: RangeUnitPreference,
+}
+
+impl AlarmSnapshots {
+    /// Snapshots written to `dir`, with range rings labelled in `units`
+    /// unless the radar has its own preference
+    pub fn new(dir: &Path, units: RangeUnitPreference) -> Self {
+        AlarmSnapshots {
+            dir: dir.to_owned(),
+            units,
+        }
+    }
+
+    /// An alarm fired: capture the next rotation in the background. Exits
+    /// from a zone do not take a snapshot.
+    pub fn on_alarm(&self, info: &RadarInfo, event: &AlarmEvent) {
+        if event.kind != AlarmKind::Enter {
+            return;
+        }
+        let snapshots = self.clone();
+        let info = info.clone();
+        let event = event.clone();
+        tokio::spawn(async move {
+            match snapshots.capture(&info, &event).await {
+                Ok(snapshot) => info!(
+                    "{}: alarm snapshot written to {}",
+                    info.key(),
+                    snapshot.image.display()
+                ),
+                Err(e) => warn!("{}: no alarm snapshot: {}", info.key(), e),
+            }
+        });
+    }
+
+    /// Wait for the next rotation of the radar and write it for `event`
+    pub async fn capture(
+        &self,
+        info: &RadarInfo,
+        event: &AlarmEvent,
+    ) -> Result<AlarmSnapshot, RadarError> {
+        let message = snapshot::capture_rotation(info, CAPTURE_TIMEOUT).await?;
+        let style = SnapshotStyle {
+            units: info.range_units().unwrap_or(self.units),
+            heading: navdata::get_heading_true().map(f64::to_degrees),
+            ..Default::default()
+        };
+        Ok(self.write(
+            &info.key(),
+            event,
+            &message,
+            info.spokes_per_revolution,
+            &info.legend,
+            &style,
+        )?)
+    }
+
+    fn write(
+        &self,
+        radar: &str,
+        event: &AlarmEvent,
+        message: &RadarMessage,
+        spokes_per_revolution: u16,
+        legend: &Legend,
+        style: &SnapshotStyle,
+    ) -> io::Result<AlarmSnapshot> {
+        fs::create_dir_all(&self.dir)?;
+        let time = chrono::DateTime::from_timestamp_millis(event.timestamp as i64)
+            .unwrap_or_default()
+            .format("%Y%m%dT%H%M%S%.3fZ");
+        let stem = format!("{}-zone{}-{}", radar, event.zone_id, time);
+        let snapshot = AlarmSnapshot {
+            image: self.dir.join(format!("{}.png", stem)),
+            spokes: self.dir.join(format!("{}.pb", stem)),
+        };
+
+        let image = png::render_snapshot(message, spokes_per_revolution, legend, style);
+        fs::write(&snapshot.image, image.encode())?;
+        let spokes = message
+            .write_to_bytes()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(&snapshot.spokes, spokes)?;
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locator::LocatorId;
+    use crate::protos::RadarMessage::radar_message::Spoke;
+    use crate::settings::SharedControls;
+    use crate::{Brand, Session};
+    use mayara_core::geo::TargetPosition;
+    use mayara_core::guard_zones::{ZoneAlert, ZoneAlertState};
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn alarm_writes_the_image_and_the_spokes_of_the_next_rotation() {
+        let session = Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let info = RadarInfo::new(
+            session,
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            16,
+            64,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        );
+        let dir = TempDir::new().unwrap();
+        let snapshots = AlarmSnapshots::new(dir.path(), RangeUnitPreference::default());
+        let alert = ZoneAlert {
+            zone_id: 2,
+            state: ZoneAlertState::Alarm,
+            timestamp: 0,
+            bearing: 45.,
+            distance: 900.,
+            intensity: 12,
+            position: TargetPosition::locate(45., 900., None, None),
+        };
+        let mut event = AlarmEvent::new(&info.key(), AlarmKind::Enter, &alert);
+        event.timestamp = 1_791_973_530_250;
+
+        // Start mid rotation, the first full rotation after the alarm is captured
+        let rotations = async {
+            tokio::task::yield_now().await;
+            for i in 8..48u32 {
+                let mut spoke = Spoke::new();
+                spoke.angle = i % 16;
+                spoke.range = 1852;
+                spoke.data = vec![(i / 16) as u8 + 1; 64];
+                if spoke.angle == 0 {
+                    spoke.rotation_start = Some(true);
+                }
+                let mut message = RadarMessage::new();
+                message.spokes.push(spoke);
+                info.message_tx.send(message.write_to_bytes().unwrap()).ok();
+                tokio::task::yield_now().await;
+            }
+        };
+        let (snapshot, _) = tokio::join!(snapshots.capture(&info, &event), rotations);
+        let snapshot = snapshot.unwrap();
+
+        let name = snapshot.image.file_name().unwrap().to_str().unwrap();
+        assert!(name.ends_with("-zone2-20261014T102530.250Z.png"), "{}", name);
+        assert!(fs::read(&snapshot.image).unwrap().starts_with(b"\x89PNG"));
+        let spokes = RadarMessage::parse_from_bytes(&fs::read(&snapshot.spokes).unwrap()).unwrap();
+        assert_eq!(spokes.spokes.len(), 16);
+        assert!(spokes.spokes.iter().all(|s| s.data == vec![2; 64]));
+    }
+
+    #[tokio::test]
+    async fn guard_zone_alarm_on_the_spokes_writes_a_snapshot() {
+        use mayara_core::guard_zones::GuardZone;
+
+        let dir = TempDir::new().unwrap();
+        let session = Session::new_fake();
+        session.write().unwrap().alarm_snapshots =
+            Some(AlarmSnapshots::new(dir.path(), RangeUnitPreference::default()));
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let mut info = RadarInfo::new(
+            session,
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            16,
+            64,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        );
+        info.guard_zones.set(GuardZone::new_ring(1, 400., 1000.));
+
+        // A target at 90 degrees, ~720 m, in the first rotation
+        for rotation in 0..4 {
+            for angle in 0..16 {
+                let mut spoke = Spoke::new();
+                spoke.angle = angle;
+                spoke.range = 1852;
+                spoke.data = vec![0; 64];
+                if rotation == 0 && angle == 4 {
+                    spoke.data[25] = 15;
+                }
+                let mut message = RadarMessage::new();
+                message.spokes.push(spoke);
+                info.broadcast_radar_message(message);
+                tokio::task::yield_now().await;
+            }
+        }
+
+        let mut written = Vec::new();
+        for _ in 0..100 {
+            written = fs::read_dir(dir.path())
+                .unwrap()
+                .map(|e| e.unwrap().file_name().into_string().unwrap())
+                .collect();
+            if written.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        written.sort();
+        assert_eq!(written.len(), 2, "{:?}", written);
+        assert!(written[0].starts_with("Navico-1234-zone1-"), "{:?}", written);
+        assert!(written[0].ends_with(".pb"));
+        assert!(written[1].ends_with(".png"));
+    }
+}
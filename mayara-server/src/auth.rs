@@ -0,0 +1,109 @@
+//! Optional bearer token authentication for the control endpoints
+//!
+//! When the server is started with `--control-token`, every request that can
+//! change radar settings, stored application data, recordings or playback
+//! must present that token, either as an
+//! `Authorization: Bearer <token>` header or, for WebSockets where browsers
+//! cannot set headers, as a `token=<token>` query parameter.
+//! The spoke and status endpoints stay open.
+
+use http::{header::AUTHORIZATION, HeaderMap};
+
+/// Extract the token presented with a request, if any
+pub fn token_from_request(headers: &HeaderMap, query: Option<&str>) -> Option<String> {
+    if let Some(value) = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()) {
+        if let Some(token) = value.strip_prefix("Bearer ") {
+            return Some(token.trim().to_string());
+        }
+    }
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .map(percent_decode)
+}
+
+/// Undo the percent-encoding of a query value, so a token with reserved
+/// characters matches; malformed escapes are kept as they are.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|h| h.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Check a presented token against the configured one.
+///
+/// Without a configured token all requests are allowed.
+pub fn is_authorized(expected: Option<&str>, presented: Option<&str>) -> bool {
+    match (expected, presented) {
+        (None, _) => true,
+        (Some(expected), Some(presented)) => constant_time_eq(expected, presented),
+        (Some(_), None) => false,
+    }
+}
+
+/// Compare without returning early, so the time taken does not reveal
+/// how much of the token was guessed correctly.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn control_call_with_valid_token_is_accepted() {
+        let token = token_from_request(&bearer("s3cret"), None);
+        assert!(is_authorized(Some("s3cret"), token.as_deref()));
+
+        // WebSocket clients pass the token in the query string
+        let token = token_from_request(&HeaderMap::new(), Some("foo=1&token=s3cret"));
+        assert!(is_authorized(Some("s3cret"), token.as_deref()));
+
+        // and percent-encode the characters that are reserved there
+        let token = token_from_request(&HeaderMap::new(), Some("token=a%2Bb%3D%26c%"));
+        assert_eq!(token.as_deref(), Some("a+b=&c%"));
+
+        // No token configured: everything is allowed
+        assert!(is_authorized(None, None));
+    }
+
+    #[test]
+    fn control_call_with_invalid_token_is_rejected() {
+        let token = token_from_request(&bearer("guess"), None);
+        assert!(!is_authorized(Some("s3cret"), token.as_deref()));
+
+        let token = token_from_request(&bearer("s3cret-and-more"), None);
+        assert!(!is_authorized(Some("s3cret"), token.as_deref()));
+
+        let token = token_from_request(&HeaderMap::new(), None);
+        assert!(!is_authorized(Some("s3cret"), token.as_deref()));
+    }
+}
@@ -1,3 +1,5 @@
+use mayara_core::protocol::AzimuthEncoding;
+
 use crate::network::{self, create_udp_multicast_listen};
 use crate::protos::RadarMessage::radar_message::Spoke;
 use crate::protos::RadarMessage::RadarMessage;
@@ -6,7 +8,6 @@ use crate::util::PrintableSpoke;
 use crate::{radar::*, Session};
 
 use core::panic;
-use protobuf::Message;
 use std::net::SocketAddr;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::{io, time::Duration};
@@ -256,6 +257,9 @@ impl FurunoDataReceiver {
             DataUpdate::Ranges(ranges) => {
                 self.info.ranges = ranges;
             }
+            DataUpdate::ControlValue(_, cv) if cv.id == "clearNoiseFloor" => {
+                self.info.reset_noise_floor();
+            }
             DataUpdate::ControlValue(reply_tx, cv) => {
                 match self.trails.set_control_value(&self.info.controls, &cv) {
                     Ok(()) => {
@@ -296,13 +300,17 @@ impl FurunoDataReceiver {
         let mut message = RadarMessage::new();
         message.radar = self.info.id as u32;
 
+        let azimuth = self
+            .info
+            .azimuth_encoding(AzimuthEncoding::little_endian(self.info.spokes_per_revolution));
+        self.info.throttle_frame(sweep_count as usize);
         let mut sweep: &[u8] = &data[16..];
         for sweep_idx in 0..sweep_count {
             if sweep.len() < 5 {
                 log::error!("Unsufficient data for sweep {}", sweep_idx);
                 break;
             }
-            let angle = ((sweep[1] as u16) << 8) | sweep[0] as u16;
+            let angle = azimuth.decode([sweep[0], sweep[1]], self.info.spokes_per_revolution);
             let heading = ((sweep[3] as u16) << 8) | sweep[2] as u16;
             sweep = &sweep[4..];
 
@@ -323,9 +331,13 @@ impl FurunoDataReceiver {
             };
             sweep = &sweep[used..];
 
-            message
-                .spokes
-                .push(self.create_spoke(&metadata, angle, heading, &generic_spoke));
+            // Over the spoke budget: still decoded, as the next sweep may
+            // repeat it, but not worked on
+            if self.info.keep_spoke() {
+                message
+                    .spokes
+                    .push(self.create_spoke(&metadata, angle, heading, &generic_spoke));
+            }
 
             self.sweep_count += 1;
             if angle < self.prev_angle {
@@ -345,19 +357,7 @@ impl FurunoDataReceiver {
             self.prev_spoke = generic_spoke;
         }
 
-        let mut bytes = Vec::new();
-        message
-            .write_to_vec(&mut bytes)
-            .expect("Cannot write RadarMessage to vec");
-
-        match self.info.message_tx.send(bytes) {
-            Err(e) => {
-                log::trace!("{}: Dropping received spoke: {}", self.key, e);
-            }
-            Ok(count) => {
-                log::trace!("{}: sent to {} receivers", self.key, count);
-            }
-        }
+        self.info.broadcast_radar_message(message);
     }
 
     fn decode_sweep_encoding_0(sweep: &[u8]) -> (Vec<u8>, usize) {
@@ -495,7 +495,7 @@ impl FurunoDataReceiver {
         let mut spoke = Spoke::new();
         spoke.range = metadata.range;
         //        spoke.angle = (angle as usize * FURUNO_SPOKES / 8192) as u32;
-        spoke.angle = angle as u32;
+        spoke.angle = self.info.spoke_angle(angle) as u32;
         spoke.bearing = heading;
 
         (spoke.lat, spoke.lon) = crate::navdata::get_position_i64();
@@ -514,6 +514,9 @@ impl FurunoDataReceiver {
         if self.session.read().unwrap().args.replay {
             spoke.data[sweep.len() - 1] = 64;
         }
+        if let Some(len) = self.info.spoke_len_limit {
+            spoke.range = crate::radar::spoke::clamp_spoke(&mut spoke.data, spoke.range, len as usize);
+        }
 
         log::trace!(
             "Received {:04}/{:04} spoke {}",
@@ -522,6 +525,8 @@ impl FurunoDataReceiver {
             PrintableSpoke::new(&spoke.data)
         );
 
+        self.info.apply_azimuth_offset(&mut spoke);
+        self.info.apply_center_blanking(&mut spoke);
         self.trails.update_trails(&mut spoke, &self.info.legend);
 
         spoke
@@ -607,3 +612,51 @@ impl FurunoDataReceiver {
         metadata
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use super::*;
+    use crate::locator::LocatorId;
+    use mayara_core::Brand;
+
+    #[test]
+    fn spokes_are_clamped_to_the_configured_length() {
+        let session = crate::Session::new_fake();
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 10024);
+        let mut info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Furuno,
+            Brand::Furuno,
+            Some("RD003212"),
+            None,
+            64,
+            super::super::FURUNO_SPOKES,
+            FURUNO_SPOKE_LEN,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            super::super::settings::new(session.clone()),
+            true,
+        );
+        info.spoke_len_limit = Some(512);
+        let mut receiver = FurunoDataReceiver::new(session, info);
+
+        let metadata = FurunoSpokeMetadata {
+            sweep_count: 1,
+            sweep_len: 884,
+            encoding: 0,
+            have_heading: 0,
+            range: 496,
+        };
+        let spoke = receiver.create_spoke(&metadata, 0, 0, &[0x80; 884]);
+
+        assert_eq!(spoke.data.len(), 512);
+        assert_eq!(spoke.data[0], 0x20);
+        // The distance per cell is kept
+        assert_eq!(spoke.range, 496 * 512 / 884);
+    }
+}
@@ -12,7 +12,7 @@ use tokio_graceful_shutdown::SubsystemHandle;
 
 // Use unified controller and events from mayara-core
 use mayara_core::controllers::FurunoController;
-use mayara_core::ControllerEvent;
+use mayara_core::{ControllerEvent, SettingsReportTracker};
 
 use super::settings;
 use super::RadarModel;
@@ -36,6 +36,8 @@ pub struct FurunoReportReceiver {
     io: TokioIoProvider,
     /// Poll interval for the controller
     poll_interval: Duration,
+    /// Sends only the fields of the radar state that changed
+    settings_tracker: SettingsReportTracker,
 }
 
 impl FurunoReportReceiver {
@@ -63,6 +65,7 @@ impl FurunoReportReceiver {
             controller,
             io,
             poll_interval: Duration::from_millis(100), // 10Hz polling
+            settings_tracker: SettingsReportTracker::new(),
         }
     }
 
@@ -89,6 +92,11 @@ impl FurunoReportReceiver {
                 _ = poll_interval.tick() => {
                     // Poll the controller and handle events
                     let events = self.controller.poll(&mut self.io);
+                    // The controller keeps the command connection alive, a
+                    // radar that went away drops it
+                    if self.controller.is_connected() {
+                        self.info.mark_seen();
+                    }
                     for event in events {
                         self.handle_controller_event(event, &mut model_known);
                     }
@@ -166,13 +174,16 @@ impl FurunoReportReceiver {
         let state = self.controller.radar_state().clone();
         let mut changed = false;
 
+        // The radar state as decoded from the $N reports; the timestamp
+        // changes with every report, so it is not a setting
+        if let Ok(serde_json::Value::Object(mut fields)) = serde_json::to_value(&state) {
+            fields.remove("timestamp");
+            let diff = self.settings_tracker.diff(fields.into_iter().collect());
+            self.info.controls.send_settings_diff(diff);
+        }
+
         // Apply power state
-        let power_status = match state.power {
-            mayara_core::state::PowerState::Off => Status::Off,
-            mayara_core::state::PowerState::Standby => Status::Standby,
-            mayara_core::state::PowerState::Transmit => Status::Transmit,
-            mayara_core::state::PowerState::Warming => Status::Preparing,
-        };
+        let power_status = Status::from(state.power);
         changed |= self.set_value_changed("power", power_status as i32 as f32);
 
         // Apply range
@@ -251,7 +262,7 @@ impl FurunoReportReceiver {
                         }
                         // Extended controls - update immediately for responsive UI
                         "beamSharpening" | "birdMode" | "scanSpeed" | "mainBangSuppression"
-                        | "txChannel" | "interferenceRejection" | "noiseReduction" => {
+                        | "txChannel" | "interferenceRejection" | "noiseReduction" | "ebl" | "vrm" => {
                             self.set_value(&cv.id, num_value);
                             self.radars.update(&self.info);
                             log::debug!("{}: Updated extended control {} = {}", self.key, cv.id, num_value);
@@ -278,6 +289,11 @@ impl FurunoReportReceiver {
             return Ok(());
         }
 
+        // Markers are kept in the server: their command ids are unconfirmed
+        if id == "ebl" || id == "vrm" {
+            return Ok(());
+        }
+
         // Parse numeric value
         let num_value: i32 = value
             .parse::<f32>()
@@ -476,3 +492,55 @@ impl FurunoReportReceiver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use super::*;
+    use crate::locator::LocatorId;
+    use mayara_core::Brand;
+
+    fn receiver() -> FurunoReportReceiver {
+        let session = crate::Session::new_fake();
+        let radars = SharedRadars::new(session.clone());
+        session.write().unwrap().radars = Some(radars);
+
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 10010);
+        let info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Furuno,
+            Brand::Furuno,
+            Some("RD003212"),
+            None,
+            64,
+            super::super::FURUNO_SPOKES,
+            super::super::FURUNO_SPOKE_LEN,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            settings::new(session.clone()),
+            true,
+        );
+        FurunoReportReceiver::new(session, info)
+    }
+
+    #[test]
+    fn radar_state_changes_are_sent_as_settings_diffs() {
+        let mut receiver = receiver();
+        let mut rx = receiver.info.controls.settings_diff_subscribe();
+
+        receiver.apply_controller_state(false);
+        let diff = rx.try_recv().unwrap();
+        assert_eq!(diff["gain"]["value"], 50);
+        assert_eq!(diff["gain"]["mode"], "auto");
+        assert_eq!(diff["range"], 0);
+        assert!(!diff.contains_key("timestamp"));
+
+        // The same radar state again is not a change
+        receiver.apply_controller_state(false);
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -8,8 +8,10 @@ use tokio::net::UdpSocket;
 use tokio::time::sleep;
 use tokio_shutdown::Shutdown;
 
+use mayara_core::protocol::garmin::Channel;
+
 use crate::locator::RadarLocator;
-use crate::radar::{located, RadarLocationInfo, Radars};
+use crate::radar::{located, RadarInfo, RadarLocationInfo, Radars};
 use crate::util::join_multicast;
 
 mod report;
@@ -20,16 +22,29 @@ const GARMIN_SEND_PORT: u16 = 50101;
 const GARMIN_DATA_ADDRESS: SocketAddr =
     SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 254, 2, 0)), 50102);
 
-fn process_beacon(report: &[u8]) {
+/// Decode a report of the radar and set its controls from it. Only range A
+/// is applied: the range B reports of a dual range radar use the unverified
+/// `CHANNEL_B_FLAG`, and there is no radar for range B to route them to.
+fn process_beacon(reports: &mut report::GarminReportState, radar: Option<&RadarInfo>, report: &[u8]) {
     if report.len() < 2 {
         return;
     }
+    if let Some(info) = radar {
+        info.mark_seen();
+    }
 
-    report::process(report);
+    if let Some(decoded) = reports.decode(report) {
+        debug!("Garmin decoded {}", decoded);
+        if let Some(info) = radar {
+            reports.apply_to(&info.controls, Channel::A);
+        }
+    }
 }
 
 struct GarminLocator {
     found: bool,
+    radar: Option<RadarInfo>,
+    reports: report::GarminReportState,
     buf: Vec<u8>,
     sock: Option<UdpSocket>,
 }
@@ -58,8 +73,9 @@ async fn found(
     info: RadarLocationInfo,
     radars: &SharedRadars,
     shutdown: &Shutdown,
-) -> io::Result<()> {
-    if let Some(info) = located(info, radars) {
+) -> io::Result<Option<RadarInfo>> {
+    let located = located(info, radars);
+    if let Some(_info) = &located {
         // It's new, start the RadarProcessor thread
         // let shutdown = shutdown.clone();
         //tokio::spawn(async move {
@@ -67,7 +83,7 @@ async fn found(
         //});
         // TODO do something with the join handle
     }
-    Ok(())
+    Ok(located)
 }
 
 #[async_trait]
@@ -85,7 +101,7 @@ impl RadarLocator for GarminLocator {
                     match sock.recv_buf_from(&mut self.buf).await {
                         Ok((_len, from)) => {
                             if self.found {
-                                process_beacon(&self.buf);
+                                process_beacon(&mut self.reports, self.radar.as_ref(), &self.buf);
                             } else {
                                 let mut radar_send = from.clone();
 
@@ -100,7 +116,7 @@ impl RadarLocator for GarminLocator {
                                     GARMIN_REPORT_ADDRESS,
                                     radar_send,
                                 );
-                                found(location_info, &radars, &shutdown).await.unwrap();
+                                self.radar = found(location_info, &radars, &shutdown).await.unwrap();
 
                                 self.found = true;
                             }
@@ -123,6 +139,8 @@ impl RadarLocator for GarminLocator {
 pub fn create_locator() -> Box<dyn RadarLocator + Send> {
     let locator = GarminLocator {
         found: false,
+        radar: None,
+        reports: report::GarminReportState::default(),
         buf: Vec::with_capacity(2048),
         sock: None,
     };
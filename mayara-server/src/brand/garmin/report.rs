@@ -1,53 +1,302 @@
 use log::{debug, trace};
 
 // Use mayara-core for report parsing (pure, WASM-compatible)
-use mayara_core::protocol::garmin::{parse_report, Report};
-
-pub fn process(report: &[u8]) {
-    match parse_report(report) {
-        Ok(r) => {
-            match r {
-                Report::ScanSpeed(v) => debug!("Scan speed {}", v),
-                Report::TransmitState(state) => debug!("Transmit state {:?}", state),
-                Report::Range(m) => debug!("Range {} m", m),
-                Report::Gain { mode, value, level } => {
-                    debug!("Gain mode={:?} value={} level={:?}", mode, value, level);
-                }
-                Report::BearingAlignment(deg) => debug!("Bearing alignment {:.1}", deg),
-                Report::CrosstalkRejection(v) => debug!("Crosstalk rejection {}", v),
-                Report::RainClutter { mode, level } => {
-                    debug!("Rain clutter mode={} level={}", mode, level);
-                }
-                Report::SeaClutter { mode, level, auto_level } => {
-                    debug!("Sea clutter mode={} level={} auto_level={}", mode, level, auto_level);
-                }
-                Report::NoTransmitZone { mode, start_deg, end_deg } => {
-                    debug!("No transmit zone mode={} start={:.1} end={:.1}", mode, start_deg, end_deg);
-                }
-                Report::TimedIdle { mode, time, run_time } => {
-                    debug!("Timed idle mode={} time={} run_time={}", mode, time, run_time);
-                }
-                Report::ScannerStatus { status, change_in_ms } => {
-                    if change_in_ms > 0 {
-                        debug!("Scanner status change in {} ms", change_in_ms);
-                    } else {
-                        debug!("Scanner status {}", status);
-                    }
-                }
-                Report::ScannerMessage(msg) => debug!("Scanner message \"{}\"", msg),
-                Report::Unknown { packet_type, value, raw } => {
-                    trace!(
-                        "0x{:04X}: value 0x{:X} / {} len {}",
-                        packet_type,
-                        value,
-                        value,
-                        raw.len()
-                    );
+use mayara_core::protocol::garmin::{
+    parse_channel_setting, parse_report, Channel, ChannelSetting, GainLevel, GainMode, Report,
+};
+use mayara_core::{Brand, PowerState, SettingsReportTracker};
+
+use crate::radar::range::format_range;
+use crate::radar::Status;
+use crate::settings::SharedControls;
+
+/// Gain, sea and rain clutter as last reported for one channel, raw values
+/// on Garmin's 0-255 scale
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChannelSettings {
+    pub gain_auto: Option<bool>,
+    pub gain_auto_high: Option<bool>,
+    pub gain: Option<u32>,
+    pub sea_auto: Option<bool>,
+    pub sea: Option<u32>,
+    pub rain_auto: Option<bool>,
+    pub rain: Option<u32>,
+}
+
+impl ChannelSettings {
+    fn update(&mut self, setting: ChannelSetting) {
+        match setting {
+            ChannelSetting::GainMode(mode) => self.gain_auto = Some(mode == GainMode::Auto),
+            ChannelSetting::Gain(value) => self.gain = Some(value),
+            ChannelSetting::GainLevel(level) => self.gain_auto_high = Some(level == GainLevel::High),
+            ChannelSetting::SeaMode(mode) => self.sea_auto = Some(mode != 0),
+            ChannelSetting::Sea(value) => self.sea = Some(value),
+            ChannelSetting::RainMode(mode) => self.rain_auto = Some(mode != 0),
+            ChannelSetting::Rain(value) => self.rain = Some(value),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        fn control(auto: Option<bool>, raw: Option<u32>) -> serde_json::Value {
+            serde_json::json!({
+                "auto": auto,
+                "raw": raw,
+                "value": raw.map(ChannelSetting::normalize),
+            })
+        }
+        let mut gain = control(self.gain_auto, self.gain);
+        gain["autoHigh"] = serde_json::json!(self.gain_auto_high);
+        serde_json::json!({
+            "gain": gain,
+            "sea": control(self.sea_auto, self.sea),
+            "rain": control(self.rain_auto, self.rain),
+        })
+    }
+}
+
+/// Keeps what has been decoded from the Garmin report stream so far; the
+/// debug stream shows each report, `apply_to` sets the radar's controls
+#[derive(Debug, Default)]
+pub struct GarminReportState {
+    pub range_meters: Option<u32>,
+    /// Power state, named as for the other brands
+    pub power: Option<PowerState>,
+    /// Settings of range A and, on dual range radars, range B
+    pub channels: [ChannelSettings; 2],
+    /// Sends only the settings of range A that changed
+    settings_tracker: SettingsReportTracker,
+}
+
+impl GarminReportState {
+    /// Decode one report. Returns the decoded settings for the debug stream
+    /// when the report carries one we surface.
+    pub fn decode(&mut self, report: &[u8]) -> Option<serde_json::Value> {
+        if let Some((channel, setting)) = parse_channel_setting(report) {
+            debug!("Channel {:?} {:?}", channel, setting);
+            let settings = &mut self.channels[channel as usize];
+            settings.update(setting);
+            let mut decoded = settings.to_json();
+            decoded["channel"] = serde_json::json!(channel);
+            return Some(decoded);
+        }
+        match parse_report(report) {
+            Ok(Report::Range(meters)) => {
+                debug!("Range {} m", meters);
+                self.range_meters = Some(meters);
+                Some(serde_json::json!({
+                    "rangeMeters": meters,
+                    "range": format_range(meters as i32),
+                }))
+            }
+            Ok(Report::TransmitState(state)) => {
+                debug!("Transmit state {:?}", state);
+                let power = PowerState::from_raw(Brand::Garmin, state.to_raw());
+                self.power = Some(power);
+                Some(serde_json::json!({ "power": power }))
+            }
+            Ok(r) => {
+                log_report(r);
+                None
+            }
+            Err(e) => {
+                trace!("Failed to parse Garmin report: {}", e);
+                None
+            }
+        }
+    }
+}
+
+impl GarminReportState {
+    /// Set the controls of a radar, or of one channel of a dual range
+    /// radar, from what has been decoded so far. Gain, sea and rain are
+    /// sent raw; the controls scale them from Garmin's 0-255. The settings
+    /// of range A that changed are sent as a settings diff.
+    pub fn apply_to(&mut self, controls: &SharedControls, channel: Channel) {
+        let mut set = |id: &str, value: Option<u32>, auto: Option<bool>| {
+            if let Some(value) = value {
+                if let Err(e) = controls.set(id, value as f32, auto) {
+                    debug!("Garmin {}: {}", id, e);
                 }
             }
+        };
+        if channel == Channel::A {
+            set("range", self.range_meters, None);
+            set("power", self.power.and_then(power_value), None);
+        }
+        let settings = &self.channels[channel as usize];
+        set("gain", settings.gain, settings.gain_auto);
+        set("sea", settings.sea, settings.sea_auto);
+        set("rain", settings.rain, settings.rain_auto);
+
+        if channel == Channel::A {
+            let mut fields = std::collections::HashMap::new();
+            if let serde_json::Value::Object(decoded) = settings.to_json() {
+                fields.extend(decoded);
+            }
+            if let Some(meters) = self.range_meters {
+                fields.insert("range".to_string(), serde_json::json!(meters));
+            }
+            if let Some(power) = self.power {
+                fields.insert("power".to_string(), serde_json::json!(power));
+            }
+            controls.send_settings_diff(self.settings_tracker.diff(fields));
         }
-        Err(e) => {
-            trace!("Failed to parse Garmin report: {}", e);
+    }
+}
+
+/// The value of the power control for a power state
+fn power_value(power: PowerState) -> Option<u32> {
+    (power != PowerState::Unknown).then(|| Status::from(power) as u32)
+}
+
+fn log_report(r: Report) {
+    match r {
+        Report::ScanSpeed(v) => debug!("Scan speed {}", v),
+        Report::TransmitState(state) => debug!("Transmit state {:?}", state),
+        Report::Range(m) => debug!("Range {} m", m),
+        Report::Gain { mode, value, level } => {
+            debug!("Gain mode={:?} value={} level={:?}", mode, value, level);
+        }
+        Report::BearingAlignment(deg) => debug!("Bearing alignment {:.1}", deg),
+        Report::CrosstalkRejection(v) => debug!("Crosstalk rejection {}", v),
+        Report::RainClutter { mode, level } => {
+            debug!("Rain clutter mode={} level={}", mode, level);
+        }
+        Report::SeaClutter { mode, level, auto_level } => {
+            debug!("Sea clutter mode={} level={} auto_level={}", mode, level, auto_level);
+        }
+        Report::NoTransmitZone { mode, start_deg, end_deg } => {
+            debug!("No transmit zone mode={} start={:.1} end={:.1}", mode, start_deg, end_deg);
+        }
+        Report::TimedIdle { mode, time, run_time } => {
+            debug!("Timed idle mode={} time={} run_time={}", mode, time, run_time);
         }
+        Report::ScannerStatus { status, change_in_ms } => {
+            if change_in_ms > 0 {
+                debug!("Scanner status change in {} ms", change_in_ms);
+            } else {
+                debug!("Scanner status {}", status);
+            }
+        }
+        Report::ScannerMessage(msg) => debug!("Scanner message \"{}\"", msg),
+        Report::Unknown { packet_type, value, raw } => {
+            trace!(
+                "0x{:04X}: value 0x{:X} / {} len {}",
+                packet_type,
+                value,
+                value,
+                raw.len()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_report_decodes_to_meters() {
+        // Synthetic range report for 3 km, laid out as the protocol documents it
+        let data = [
+            0x1e, 0x09, 0x00, 0x00, // packet_type 0x091e
+            0x04, 0x00, 0x00, 0x00, // length
+            0xb8, 0x0b, 0x00, 0x00, // value = 3000
+        ];
+        let mut reports = GarminReportState::default();
+        let decoded = reports.decode(&data).unwrap();
+
+        assert_eq!(reports.range_meters, Some(3000));
+        assert_eq!(decoded["rangeMeters"], 3000);
+        assert_eq!(decoded["range"], "3 km");
+    }
+
+    #[test]
+    fn gain_report_decodes_per_channel_raw_and_normalized() {
+        // Synthetic gain report for range A at 50%, laid out as the protocol documents it
+        let data = [
+            0x25, 0x09, 0x00, 0x00, // packet_type 0x0925
+            0x04, 0x00, 0x00, 0x00, // length
+            0x80, 0x00, 0x00, 0x00, // value = 128
+        ];
+        let mut reports = GarminReportState::default();
+        let decoded = reports.decode(&data).unwrap();
+
+        assert_eq!(decoded["channel"], "A");
+        assert_eq!(decoded["gain"]["raw"], 128);
+        assert_eq!(decoded["gain"]["value"], 50);
+        assert_eq!(reports.channels[0].gain, Some(128));
+        assert_eq!(reports.channels[1], ChannelSettings::default());
+
+        // Sea clutter on range B leaves range A alone
+        let data = [
+            0x3a, 0x19, 0x00, 0x00, // packet_type 0x193a: 0x093a with the channel B flag
+            0x04, 0x00, 0x00, 0x00, // length
+            0xff, 0x00, 0x00, 0x00, // value = 255
+        ];
+        let decoded = reports.decode(&data).unwrap();
+        assert_eq!(decoded["channel"], "B");
+        assert_eq!(decoded["sea"]["value"], 100);
+        assert!(decoded["gain"]["raw"].is_null());
+        assert_eq!(reports.channels[0].sea, None);
+    }
+
+    #[test]
+    fn transmit_state_report_decodes_to_the_power_state() {
+        let data = [
+            0x19, 0x09, 0x00, 0x00, // packet_type 0x0919
+            0x04, 0x00, 0x00, 0x00, // length
+            0x03, 0x00, 0x00, 0x00, // value = warming up
+        ];
+        let mut reports = GarminReportState::default();
+        let decoded = reports.decode(&data).unwrap();
+
+        assert_eq!(decoded["power"], "warming");
+        assert_eq!(reports.power, Some(PowerState::Warming));
+
+        let mut data = data;
+        data[8] = 0x02;
+        assert_eq!(reports.decode(&data).unwrap()["power"], "transmit");
+    }
+
+    #[test]
+    fn decoded_reports_set_the_controls() {
+        use crate::control_factory::{gain_control_for_brand, sea_control_for_brand};
+        use crate::settings::Control;
+        use std::collections::HashMap;
+
+        let mut controls = HashMap::new();
+        controls.insert("range".to_string(), Control::new_numeric("range", 50., 74080.).unit("m"));
+        controls.insert("gain".to_string(), gain_control_for_brand(Brand::Garmin));
+        controls.insert("sea".to_string(), sea_control_for_brand(Brand::Garmin));
+        let controls = SharedControls::new(crate::Session::new_fake(), controls);
+
+        let mut reports = GarminReportState::default();
+        for data in [
+            [0x1e, 0x09, 0, 0, 4, 0, 0, 0, 0xb8, 0x0b, 0, 0], // range 3000 m
+            [0x19, 0x09, 0, 0, 4, 0, 0, 0, 0x02, 0, 0, 0],    // transmit
+            [0x25, 0x09, 0, 0, 4, 0, 0, 0, 0x66, 0, 0, 0],    // range A gain 102
+            [0x3a, 0x19, 0, 0, 4, 0, 0, 0, 0xff, 0, 0, 0],    // range B sea 255
+        ] {
+            reports.decode(&data).unwrap();
+        }
+        let mut rx = controls.settings_diff_subscribe();
+        reports.apply_to(&controls, Channel::A);
+
+        assert_eq!(controls.get("range").unwrap().value, Some(3000.));
+        assert_eq!(controls.get("power").unwrap().value, Some(2.));
+        assert_eq!(controls.get("gain").unwrap().value, Some(40.));
+        // Sea was only reported for range B
+        assert_eq!(controls.get("sea").unwrap().value, None);
+
+        let diff = rx.try_recv().unwrap();
+        assert_eq!(diff["range"], 3000);
+        assert_eq!(diff["power"], "transmit");
+        assert_eq!(diff["gain"]["raw"], 0x66);
+        assert!(diff["sea"]["raw"].is_null());
+
+        // Applying the same reports again changes nothing
+        reports.apply_to(&controls, Channel::A);
+        assert!(rx.try_recv().is_err());
     }
 }
@@ -7,7 +7,8 @@ use trail::TrailBuffer;
 
 // Use mayara-core for spoke header parsing (pure, WASM-compatible)
 use mayara_core::protocol::navico::{
-    parse_4g_spoke_header, parse_br24_spoke_header, SPOKE_HEADER_SIZE,
+    parse_4g_spoke_header_with, parse_4g_spoke_quadrant, parse_br24_spoke_header_with,
+    AZIMUTH_ENCODING, SPOKE_HEADER_SIZE,
 };
 
 use crate::brand::navico::NAVICO_SPOKE_LEN;
@@ -48,6 +49,7 @@ pub struct NavicoDataReceiver {
     pixel_to_blob: [[u8; BYTE_LOOKUP_LENGTH]; LOOKUP_DOPPLER_LENGTH],
     trails: TrailBuffer,
     prev_angle: u16,
+    prev_quadrant: u8,
     replay: bool,
 }
 
@@ -77,6 +79,7 @@ impl NavicoDataReceiver {
             pixel_to_blob,
             trails,
             prev_angle: 0,
+            prev_quadrant: 0,
             replay,
         }
     }
@@ -152,6 +155,9 @@ impl NavicoDataReceiver {
             DataUpdate::Ranges(_) => {
                 // Navico DataReceiver does not need to know what ranges are in use.
             }
+            DataUpdate::ControlValue(_, cv) if cv.id == "clearNoiseFloor" => {
+                self.info.reset_noise_floor();
+            }
             DataUpdate::ControlValue(reply_tx, cv) => {
                 match self.trails.set_control_value(&self.info.controls, &cv) {
                     Ok(()) => {
@@ -257,6 +263,7 @@ impl NavicoDataReceiver {
         let mut message = RadarMessage::new();
         message.radar = self.info.id as u32;
 
+        self.info.throttle_frame(spokes_in_frame);
         let mut offset: usize = FRAME_HEADER_LENGTH;
         for scanline in 0..spokes_in_frame {
             let header_slice = &data[offset..offset + RADAR_LINE_HEADER_LENGTH];
@@ -265,26 +272,40 @@ impl NavicoDataReceiver {
 
             if let Some((range, angle, heading)) = self.validate_header(header_slice, scanline) {
                 log::trace!("range {} angle {} heading {:?}", range, angle, heading);
+                let quadrant = self.spoke_quadrant(header_slice);
                 log::trace!(
                     "Received {:04} spoke {}",
                     scanline,
                     PrintableSpoke::new(spoke_slice)
                 );
-                let mut spoke = to_protobuf_spoke(
-                    &self.info,
-                    range,
-                    angle,
-                    heading,
-                    now,
-                    self.process_spoke(spoke_slice),
-                );
-                self.trails.update_trails(&mut spoke, &self.info.legend);
-                message.spokes.push(spoke);
+                // Over the spoke budget: still counted, but not worked on
+                if self.info.keep_spoke() {
+                    let mut spoke = to_protobuf_spoke(
+                        &self.info,
+                        range,
+                        angle,
+                        heading,
+                        now,
+                        self.process_spoke(spoke_slice),
+                    );
+                    spoke.quadrant = quadrant.map(|q| q as u32);
+                    self.info.apply_azimuth_offset(&mut spoke);
+                    self.info.apply_center_blanking(&mut spoke);
+                    self.trails.update_trails(&mut spoke, &self.info.legend);
+                    message.spokes.push(spoke);
+                }
 
-                if angle < self.prev_angle {
+                if let Some(quadrant) = quadrant {
+                    // Interlaced: a rotation ends when the sub-frames wrap around,
+                    // and spokes within a sub-frame are never consecutive.
+                    if quadrant < self.prev_quadrant {
+                        mark_full_rotation = true;
+                    }
+                    self.prev_quadrant = quadrant;
+                } else if angle < self.prev_angle {
                     mark_full_rotation = true;
                 }
-                if ((self.prev_angle + 1) % NAVICO_SPOKES as u16) != angle {
+                if quadrant.is_none() && ((self.prev_angle + 1) % NAVICO_SPOKES as u16) != angle {
                     self.statistics.missing_spokes +=
                         (angle + NAVICO_SPOKES as u16 - self.prev_angle - 1) as usize
                             % NAVICO_SPOKES as usize;
@@ -316,9 +337,10 @@ impl NavicoDataReceiver {
         scanline: usize,
     ) -> Option<(u32, SpokeBearing, Option<u16>)> {
         // Use core parsing functions
+        let azimuth = self.info.azimuth_encoding(AZIMUTH_ENCODING);
         let result = match self.info.locator_id {
-            LocatorId::Gen3Plus => parse_4g_spoke_header(header_slice),
-            LocatorId::GenBR24 => parse_br24_spoke_header(header_slice),
+            LocatorId::Gen3Plus => parse_4g_spoke_header_with(header_slice, azimuth),
+            LocatorId::GenBR24 => parse_br24_spoke_header_with(header_slice, azimuth),
             _ => {
                 panic!("Incorrect Navico type");
             }
@@ -328,7 +350,8 @@ impl NavicoDataReceiver {
             Ok((range, angle, heading)) => {
                 log::trace!("Received {:04} spoke: range={} angle={} heading={:?}",
                     scanline, range, angle, heading);
-                Some((range, angle, heading))
+                let direction = self.info.rotation_direction();
+                Some((range, direction.normalize(angle, NAVICO_SPOKES as u16), heading))
             }
             Err(e) => {
                 log::warn!("Invalid spoke header: {} data {:02X?}", e, &header_slice);
@@ -337,6 +360,13 @@ impl NavicoDataReceiver {
         }
     }
 
+    fn spoke_quadrant(&self, header_slice: &[u8]) -> Option<u8> {
+        match self.info.locator_id {
+            LocatorId::Gen3Plus => parse_4g_spoke_quadrant(header_slice).ok().flatten(),
+            _ => None,
+        }
+    }
+
     fn process_spoke(&self, spoke: &[u8]) -> GenericSpoke {
         let pixel_to_blob = &self.pixel_to_blob;
 
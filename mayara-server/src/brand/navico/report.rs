@@ -1,5 +1,6 @@
 use anyhow::{bail, Error};
 use std::cmp::min;
+use std::collections::HashMap;
 use std::io;
 use std::net::SocketAddr;
 use std::time::Duration;
@@ -21,6 +22,8 @@ use crate::Session;
 
 // Use unified controller from mayara-core
 use mayara_core::controllers::{NavicoController, NavicoModel};
+use mayara_core::capabilities::BlindSector;
+use mayara_core::{PowerState, SettingsReportTracker};
 
 use super::Model;
 
@@ -28,9 +31,9 @@ use crate::radar::Status;
 
 // Use mayara-core for report parsing and packet types (pure, WASM-compatible)
 use mayara_core::protocol::navico::{
-    parse_report_01, parse_report_02, parse_report_03, parse_report_04,
-    parse_report_06_68, parse_report_06_74, parse_report_08,
-    HaloHeadingPacket, HaloNavigationPacket, HaloSpeedPacket,
+    parse_status_report, parse_report_02, parse_report_03, parse_report_04,
+    parse_report_06_68, parse_report_06_74, parse_report_08, report_ranges,
+    HaloHeadingPacket, HaloNavigationPacket, HaloSpeedPacket, ParsedSectorBlanking,
     INFO_ADDR, INFO_PORT, SPEED_ADDR_A, SPEED_PORT_A,
 };
 
@@ -58,6 +61,8 @@ pub struct NavicoReportReceiver {
     info_request_timeout: Instant,
     report_request_timeout: Instant,
     reported_unknown: [bool; 256],
+    /// Last decoded settings, to emit only what changed
+    settings_tracker: SettingsReportTracker,
 }
 
 // Every 5 seconds we ask the radar for reports, so we can update our controls
@@ -162,6 +167,7 @@ impl NavicoReportReceiver {
             data_tx: data_update_tx,
             control_update_rx,
             reported_unknown: [false; 256],
+            settings_tracker: SettingsReportTracker::new(),
         }
     }
 
@@ -289,7 +295,19 @@ impl NavicoReportReceiver {
                 r = self.report_socket.as_ref().unwrap().recv_buf_from(&mut self.report_buf)  => {
                     match r {
                         Ok((_len, _addr)) => {
-                            if let Err(e) = self.process_report().await {
+                            let reports = report_ranges(&self.report_buf);
+                            if reports.len() > 1 {
+                                // Batched datagram, process each report on its own
+                                let packet = std::mem::take(&mut self.report_buf);
+                                for report in reports {
+                                    self.report_buf.clear();
+                                    self.report_buf.extend_from_slice(&packet[report]);
+                                    if let Err(e) = self.process_report().await {
+                                        log::error!("{}: {}", self.key, e);
+                                    }
+                                }
+                                self.report_buf = packet;
+                            } else if let Err(e) = self.process_report().await {
                                 log::error!("{}: {}", self.key, e);
                             }
                             self.report_buf.clear();
@@ -471,6 +489,15 @@ impl NavicoReportReceiver {
             "accentLight" => {
                 controller.set_accent_light(&mut self.io, value as u8);
             }
+            "autoResponse" => {
+                controller.set_auto_response(&mut self.io, value as u8);
+            }
+            "lirf" => {
+                controller.set_lirf(&mut self.io, value > 0.);
+            }
+            "mainBangSuppression" => {
+                controller.set_main_bang_suppression(&mut self.io, scale_100_to_byte(value));
+            }
             "noTransmitStart1" => {
                 let start = mod_deci_degrees(deci_value);
                 let end = get_angle_value("noTransmitEnd1", &self.info.controls);
@@ -750,6 +777,7 @@ impl NavicoReportReceiver {
     }
 
     async fn process_report(&mut self) -> Result<(), Error> {
+        self.info.mark_seen();
         let data = &self.report_buf;
 
         if data.len() < 2 {
@@ -768,10 +796,12 @@ impl NavicoReportReceiver {
                     }
                     _ => {
                         log::trace!("Unknown report 0x{:02x} 0xc6: {:02X?}", data[0], data);
+                        self.info.log_unknown_report(data);
                     }
                 }
             } else {
-                log::trace!("Unknown report {:02X?} dropped", data)
+                log::trace!("Unknown report {:02X?} dropped", data);
+                self.info.log_unknown_report(data);
             }
             return Ok(());
         }
@@ -814,6 +844,7 @@ impl NavicoReportReceiver {
                         data
                     );
                 }
+                self.info.log_unknown_report(data);
             }
         }
         Ok(())
@@ -821,19 +852,25 @@ impl NavicoReportReceiver {
 
     async fn process_report_01(&mut self) -> Result<(), Error> {
         // Use mayara-core parsing
-        let status = parse_report_01(&self.report_buf)
+        let report = parse_status_report(&self.report_buf)
             .map_err(|e| anyhow::anyhow!("{}: Report 01 parse error: {}", self.key, e))?;
 
-        log::debug!("{}: report 01 - status {:?}", self.key, status);
+        log::debug!("{}: report 01 - {}", self.key, report.description());
 
-        // Convert mayara_core::protocol::navico::Status to crate::radar::Status
-        let status = match status {
-            mayara_core::protocol::navico::Status::Off => Status::Off,
-            mayara_core::protocol::navico::Status::Standby => Status::Standby,
-            mayara_core::protocol::navico::Status::Transmit => Status::Transmit,
-            mayara_core::protocol::navico::Status::Preparing => Status::Preparing,
-        };
-        self.set_value("power", status as i32 as f32);
+        // Clients show a progress indicator while the countdown runs
+        let description = report.warmup_seconds.map(|_| report.description());
+        if let Err(e) = self.info.controls.set_with_description(
+            "warmupSeconds",
+            report.warmup_seconds.unwrap_or(0) as f32,
+            description,
+        ) {
+            log::error!("{}: {}", self.key, e);
+        }
+        // Lets clients tell a blanked sector from a lost signal
+        self.set_value("blankingActive", report.blanking_active as u8 as f32);
+
+        let power = PowerState::from_raw(mayara_core::Brand::Navico, report.status as u32);
+        self.set_value("power", Status::from(power) as i32 as f32);
         Ok(())
     }
 
@@ -843,6 +880,7 @@ impl NavicoReportReceiver {
             .map_err(|e| anyhow::anyhow!("{}: Report 02 parse error: {}", self.key, e))?;
 
         log::trace!("{}: report 02 - {:?}", self.key, report);
+        log::debug!("{}: report 02 - {}", self.key, report.description());
 
         let range = report.range;
         let mode = report.mode as i32;
@@ -850,11 +888,24 @@ impl NavicoReportReceiver {
         let gain = report.gain as i32;
         let sea_auto = report.sea_auto;
         let sea = report.sea;
+        let rain_auto = if report.rain_auto { 1u8 } else { 0u8 };
         let rain = report.rain as i32;
         let interference_rejection = report.interference_rejection as i32;
         let target_expansion = report.target_expansion as i32;
         let target_boost = report.target_boost as i32;
 
+        let diff = self.settings_tracker.diff(HashMap::from([
+            ("range".to_string(), serde_json::json!(range)),
+            ("gain".to_string(), serde_json::json!({"auto": report.gain_auto, "value": gain})),
+            ("sea".to_string(), serde_json::json!({"auto": sea_auto, "value": sea})),
+            ("rain".to_string(), serde_json::json!({"auto": report.rain_auto, "value": rain})),
+            ("interferenceRejection".to_string(), serde_json::json!(interference_rejection)),
+            ("interferenceAuto".to_string(), serde_json::json!(report.interference_auto)),
+            ("targetExpansion".to_string(), serde_json::json!(target_expansion)),
+            ("targetBoost".to_string(), serde_json::json!(target_boost)),
+        ]));
+        self.info.controls.send_settings_diff(diff);
+
         self.set_value("range", range as f32);
         if self.model == Model::HALO {
             self.set_value("mode", mode as f32);
@@ -868,7 +919,7 @@ impl NavicoReportReceiver {
                 .set_auto_state("sea", sea_auto > 0)
                 .unwrap(); // Only crashes if control not supported which would be an internal bug
         }
-        self.set_value("rain", rain as f32);
+        self.set_value_auto("rain", rain as f32, rain_auto);
         self.set_value(
             "interferenceRejection",
             interference_rejection as f32,
@@ -934,6 +985,11 @@ impl NavicoReportReceiver {
             }
         }
 
+        // Picks the capability defaults of a HALO that does not report its variant
+        if let Some(antenna_kind) = report.antenna_kind.filter(|_| self.model == Model::HALO) {
+            self.set_string("antennaKind", antenna_kind.as_str().to_string());
+        }
+
         let firmware = format!("{} {}", report.firmware_date, report.firmware_time);
         self.set_value("operatingHours", hours as f32);
         self.set_string("firmwareVersion", firmware);
@@ -950,6 +1006,8 @@ impl NavicoReportReceiver {
 
         self.set_value("bearingAlignment", report.bearing_alignment as f32);
         self.set_value("antennaHeight", report.antenna_height as f32);
+        self.set_value("rotationDirection", report.rotation_direction.as_byte() as f32);
+        self.info.set_rotation_direction(report.rotation_direction);
         if self.model == Model::HALO {
             self.set_value("accentLight", report.accent_light as f32);
         }
@@ -957,6 +1015,34 @@ impl NavicoReportReceiver {
         Ok(())
     }
 
+    /// User blanking goes to the no transmit controls, sectors the radar
+    /// masked itself to the blind sectors in the state
+    fn set_blanking(&mut self, sectors: &[ParsedSectorBlanking]) -> Result<(), Error> {
+        for (i, start, end) in super::BLANKING_SETS {
+            if i < sectors.len() {
+                let sector = &sectors[i];
+                let enabled = Some(sector.enabled);
+                self.info
+                    .controls
+                    .set_value_auto_enabled(&start, sector.start_angle as f32, None, enabled)?;
+                self.info
+                    .controls
+                    .set_value_auto_enabled(&end, sector.end_angle as f32, None, enabled)?;
+            }
+        }
+        let blind_sectors = sectors
+            .iter()
+            .filter(|sector| sector.learned)
+            .map(|sector| BlindSector {
+                start: sector.start_angle as f64 / 10.,
+                end: sector.end_angle as f64 / 10.,
+            })
+            .collect();
+        self.info.set_blind_sectors(blind_sectors);
+
+        Ok(())
+    }
+
     ///
     /// Blanking (No Transmit) report as seen on HALO 2006
     ///
@@ -971,20 +1057,7 @@ impl NavicoReportReceiver {
             self.set_string("modelName", name.clone());
         }
 
-        for (i, start, end) in super::BLANKING_SETS {
-            if i < report.sectors.len() {
-                let sector = &report.sectors[i];
-                let enabled = Some(sector.enabled);
-                self.info
-                    .controls
-                    .set_value_auto_enabled(&start, sector.start_angle as f32, None, enabled)?;
-                self.info
-                    .controls
-                    .set_value_auto_enabled(&end, sector.end_angle as f32, None, enabled)?;
-            }
-        }
-
-        Ok(())
+        self.set_blanking(&report.sectors)
     }
 
     ///
@@ -1004,20 +1077,7 @@ impl NavicoReportReceiver {
             self.model
         );
 
-        for (i, start, end) in super::BLANKING_SETS {
-            if i < report.sectors.len() {
-                let sector = &report.sectors[i];
-                let enabled = Some(sector.enabled);
-                self.info
-                    .controls
-                    .set_value_auto_enabled(&start, sector.start_angle as f32, None, enabled)?;
-                self.info
-                    .controls
-                    .set_value_auto_enabled(&end, sector.end_angle as f32, None, enabled)?;
-            }
-        }
-
-        Ok(())
+        self.set_blanking(&report.sectors)
     }
 
     async fn process_report_08(&mut self) -> Result<(), Error> {
@@ -1036,6 +1096,36 @@ impl NavicoReportReceiver {
         let target_sep = report.target_separation as i32;
         let sea_clutter = report.sea_clutter as i32;
         let auto_sea_clutter = report.auto_sea_clutter;
+        // Radar reports 0..255, the control is in percent
+        let main_bang_suppression = (report.main_bang_suppression as i32 * 100 + 127) / 255;
+
+        let diff = self.settings_tracker.diff(HashMap::from([
+            ("seaState".to_string(), serde_json::json!(sea_state)),
+            ("localInterferenceRejection".to_string(), serde_json::json!(local_interference_rejection)),
+            ("scanSpeed".to_string(), serde_json::json!(scan_speed)),
+            (
+                "scanSpeedLabel".to_string(),
+                serde_json::json!(report.scan_speed_mode.map(|mode| mode.as_str())),
+            ),
+            (
+                "sidelobeSuppression".to_string(),
+                serde_json::json!({"auto": report.sidelobe_suppression_auto, "value": sidelobe_suppression}),
+            ),
+            ("noiseRejection".to_string(), serde_json::json!(noise_reduction)),
+            (
+                "noiseRejectionLabel".to_string(),
+                serde_json::json!(report.noise_rejection_level.map(|level| level.label())),
+            ),
+            ("targetSeparation".to_string(), serde_json::json!(target_sep)),
+            ("seaClutter".to_string(), serde_json::json!({"auto": auto_sea_clutter, "value": sea_clutter})),
+            ("mainBangSuppression".to_string(), serde_json::json!(main_bang_suppression)),
+            (
+                "autoResponse".to_string(),
+                serde_json::json!(report.auto_response.map(|r| r.label())),
+            ),
+            ("lirf".to_string(), serde_json::json!(report.lirf)),
+        ]));
+        self.info.controls.send_settings_diff(diff);
 
         // Handle Doppler settings if present (extended report)
         if let (Some(doppler_state), Some(doppler_speed)) = (report.doppler_state, report.doppler_speed) {
@@ -1064,6 +1154,11 @@ impl NavicoReportReceiver {
 
         if self.model == Model::HALO {
             self.set_value("seaState", sea_state as f32);
+            self.set_value("mainBangSuppression", main_bang_suppression as f32);
+            if let Some(response) = report.auto_response {
+                self.set_value("autoResponse", response.as_byte() as f32);
+            }
+            self.set_value("lirf", if report.lirf { 1. } else { 0. });
             self.set_value_with_many_auto(
                 "sea",
                 sea_clutter as f32,
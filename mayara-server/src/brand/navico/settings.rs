@@ -56,6 +56,14 @@ pub fn new(session: Session, model: Option<&str>) -> SharedControls {
         "operatingHours".to_string(),
         control_factory::operating_hours_control(),
     );
+    controls.insert(
+        "warmupSeconds".to_string(),
+        control_factory::warmup_seconds_control(),
+    );
+    controls.insert(
+        "blankingActive".to_string(),
+        control_factory::blanking_active_control(),
+    );
 
     controls.insert(
         "rotationSpeed".to_string(),
@@ -66,6 +74,10 @@ pub fn new(session: Session, model: Option<&str>) -> SharedControls {
         "firmwareVersion".to_string(),
         control_factory::firmware_version_control(),
     );
+    controls.insert(
+        "rotationDirection".to_string(),
+        control_factory::rotation_direction_control(),
+    );
     controls.insert(
         "sidelobeSuppression".to_string(),
         control_factory::sidelobe_suppression_control_for_brand(Brand::Navico),
@@ -125,6 +137,13 @@ pub fn update_when_model_known(controls: &SharedControls, model: Model, radar_in
             "accentLight",
             control_factory::accent_light_control(),
         );
+        controls.insert("antennaKind", control_factory::antenna_kind_control());
+        controls.insert(
+            "mainBangSuppression",
+            control_factory::main_bang_suppression_control(),
+        );
+        controls.insert("autoResponse", control_factory::auto_response_control());
+        controls.insert("lirf", control_factory::lirf_control());
 
         // No-transmit zones use core definitions for consistent metadata
         for (zone_idx, start_id, end_id) in super::BLANKING_SETS {
@@ -18,6 +18,7 @@ use crate::Session;
 
 // Use unified controller from mayara-core
 use mayara_core::controllers::RaymarineController;
+use mayara_core::SettingsReportTracker;
 
 use super::BaseModel;
 
@@ -76,6 +77,7 @@ pub(crate) struct RaymarineReportReceiver {
     control_update_rx: broadcast::Receiver<ControlUpdate>,
     report_request_timeout: Instant,
     reported_unknown: HashMap<u32, bool>,
+    settings_tracker: SettingsReportTracker,
 
     // For data (spokes)
     statistics: Statistics,
@@ -125,6 +127,7 @@ impl RaymarineReportReceiver {
             report_request_timeout: Instant::now(),
             control_update_rx,
             reported_unknown: HashMap::new(),
+            settings_tracker: SettingsReportTracker::new(),
             statistics: Statistics::new(),
             pixel_stats: [0; 256],
             range_meters: 0,
@@ -456,6 +459,7 @@ impl RaymarineReportReceiver {
     }
 
     async fn process_report(&mut self, data: &[u8]) -> Result<(), Error> {
+        self.info.mark_seen();
         if data.len() < 4 {
             bail!("UDP report len {} dropped", data.len());
         }
@@ -489,6 +493,7 @@ impl RaymarineReportReceiver {
                     log::warn!("{}: Unknown report ID {:08X?}", self.key, id);
                     self.reported_unknown.insert(id, true);
                 }
+                self.info.log_unknown_report(data);
             }
         }
         Ok(())
@@ -500,3 +505,68 @@ impl RaymarineReportReceiver {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    use super::*;
+    use crate::brand::raymarine::settings;
+    use crate::locator::LocatorId;
+    use mayara_core::Brand;
+
+    fn receiver() -> RaymarineReportReceiver {
+        let session = crate::Session::new_fake();
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 5800);
+        let info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Raymarine,
+            Brand::Raymarine,
+            None,
+            None,
+            16,
+            2048,
+            512,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            settings::new(session.clone(), BaseModel::RD),
+            false,
+        );
+        let radars = SharedRadars::new(session.clone());
+        let mut receiver = RaymarineReportReceiver::new(session, info, radars);
+        receiver.state = ReceiverState::FixedRequestReceived;
+        receiver
+    }
+
+    /// A 250 byte RD status report, transmitting with manual gain
+    fn rd_status(gain: u8) -> Vec<u8> {
+        let mut data = vec![0u8; 250];
+        data[0..4].copy_from_slice(&0x010001u32.to_le_bytes());
+        data[180] = 1;
+        data[200] = gain;
+        data
+    }
+
+    #[tokio::test]
+    async fn status_reports_are_sent_as_settings_diffs() {
+        let mut receiver = receiver();
+        let mut rx = receiver.info.controls.settings_diff_subscribe();
+
+        receiver.process_report(&rd_status(40)).await.unwrap();
+        let diff = rx.try_recv().unwrap();
+        assert_eq!(diff["gain"], serde_json::json!({"auto": false, "value": 40}));
+        assert_eq!(diff["power"], 1);
+
+        // An unchanged report sends nothing, a changed one only what changed
+        receiver.process_report(&rd_status(40)).await.unwrap();
+        assert!(rx.try_recv().is_err());
+
+        receiver.process_report(&rd_status(55)).await.unwrap();
+        let diff = rx.try_recv().unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff["gain"]["value"], 55);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use mayara_core::protocol::raymarine::{
@@ -7,6 +8,7 @@ use mayara_core::protocol::raymarine::{
 
 use crate::brand::raymarine::report::LookupDoppler;
 use mayara_core::controllers::{RaymarineController, RaymarineVariant};
+use mayara_core::{Brand, PowerState};
 use crate::brand::raymarine::{hd_to_pixel_values, settings, RaymarineModel};
 use crate::protos::RadarMessage::RadarMessage;
 use crate::radar::range::{Range, Ranges};
@@ -61,32 +63,38 @@ pub(crate) fn process_frame(receiver: &mut RaymarineReportReceiver, data: &[u8])
         .ok();
     let mut message = RadarMessage::new();
 
-    let next_offset = QUANTUM_FRAME_HEADER_SIZE;
-    let data_len = header.data_len as usize;
-    let spoke_data = &data[next_offset..next_offset + data_len];
-
-    // Build doppler lookup table from pixel_to_blob for the core function
-    let doppler = LookupDoppler::Doppler as usize;
-    let doppler_lookup: [u8; 256] = core::array::from_fn(|i| receiver.pixel_to_blob[doppler][i]);
-
-    // Use core decompression
-    let unpacked = decompress_quantum_spoke(spoke_data, &doppler_lookup, returns_per_line as usize);
-
-    let mut spoke = to_protobuf_spoke(
-        &receiver.info,
-        receiver.range_meters * returns_per_line / returns_per_range,
-        azimuth,
-        None,
-        now,
-        unpacked,
-    );
-    for p in &spoke.data {
-        receiver.pixel_stats[*p as usize] += 1;
+    // One spoke per frame; over the spoke budget it is not worked on
+    receiver.info.throttle_frame(1);
+    if receiver.info.keep_spoke() {
+        let next_offset = QUANTUM_FRAME_HEADER_SIZE;
+        let data_len = header.data_len as usize;
+        let spoke_data = &data[next_offset..next_offset + data_len];
+
+        // Build doppler lookup table from pixel_to_blob for the core function
+        let doppler = LookupDoppler::Doppler as usize;
+        let doppler_lookup: [u8; 256] = core::array::from_fn(|i| receiver.pixel_to_blob[doppler][i]);
+
+        // Use core decompression
+        let unpacked = decompress_quantum_spoke(spoke_data, &doppler_lookup, returns_per_line as usize);
+
+        let mut spoke = to_protobuf_spoke(
+            &receiver.info,
+            receiver.range_meters * returns_per_line / returns_per_range,
+            azimuth,
+            None,
+            now,
+            unpacked,
+        );
+        for p in &spoke.data {
+            receiver.pixel_stats[*p as usize] += 1;
+        }
+        receiver.info.apply_azimuth_offset(&mut spoke);
+        receiver.info.apply_center_blanking(&mut spoke);
+        receiver
+            .trails
+            .update_trails(&mut spoke, &receiver.info.legend);
+        message.spokes.push(spoke);
     }
-    receiver
-        .trails
-        .update_trails(&mut spoke, &receiver.info.legend);
-    message.spokes.push(spoke);
 
     receiver.info.broadcast_radar_message(message);
 
@@ -117,17 +125,11 @@ pub(super) fn process_status_report(receiver: &mut RaymarineReportReceiver, data
     log::debug!("{}: Quantum report {:?}", receiver.key, report);
 
     // Update controls based on the report
-    let status = match report.status {
-        0x00 => Status::Standby,
-        0x01 => Status::Transmit,
-        0x02 => Status::Preparing,
-        0x03 => Status::Off,
-        _ => {
-            log::warn!("{}: Unknown status {}", receiver.key, report.status);
-            Status::Standby
-        }
-    };
-    receiver.set_value("power", status as i32 as f32);
+    let power = PowerState::from_raw(Brand::Raymarine, report.status as u32);
+    if power == PowerState::Unknown {
+        log::warn!("{}: Unknown status {}", receiver.key, report.status);
+    }
+    receiver.set_value("power", Status::from(power) as i32 as f32);
 
     if receiver.info.ranges.is_empty() {
         let mut ranges = Ranges::empty();
@@ -152,8 +154,25 @@ pub(super) fn process_status_report(receiver: &mut RaymarineReportReceiver, data
     receiver.range_meters = range_meters as u32;
     receiver.state = ReceiverState::StatusRequestReceived;
 
+    let mut fields = HashMap::from([
+        ("power".to_string(), serde_json::json!(report.status)),
+        ("range".to_string(), serde_json::json!(range_meters)),
+        ("mode".to_string(), serde_json::json!(report.mode)),
+        ("targetExpansion".to_string(), serde_json::json!(report.target_expansion)),
+        ("interferenceRejection".to_string(), serde_json::json!(report.interference_rejection)),
+        ("bearingAlignment".to_string(), serde_json::json!(report.bearing_offset)),
+        ("mainBangSuppression".to_string(), serde_json::json!(report.mbs_enabled)),
+    ]);
+
     let mode = report.mode as usize;
     if mode <= 3 {
+        let controls = &report.controls[mode];
+        fields.extend([
+            ("gain".to_string(), serde_json::json!({"auto": controls.gain_auto, "value": controls.gain})),
+            ("colorGain".to_string(), serde_json::json!({"auto": controls.color_gain_auto, "value": controls.color_gain})),
+            ("sea".to_string(), serde_json::json!({"auto": controls.sea_auto, "value": controls.sea})),
+            ("rain".to_string(), serde_json::json!({"enabled": controls.rain_enabled, "value": controls.rain})),
+        ]);
         receiver.set_value("mode", mode as f32);
         receiver.set_value_auto(
             "gain",
@@ -182,6 +201,9 @@ pub(super) fn process_status_report(receiver: &mut RaymarineReportReceiver, data
     receiver.set_value("interferenceRejection", report.interference_rejection as f32);
     receiver.set_value("bearingAlignment", report.bearing_offset as f32);
     receiver.set_value("mainBangSuppression", report.mbs_enabled as u8 as f32);
+
+    let diff = receiver.settings_tracker.diff(fields);
+    receiver.info.controls.send_settings_diff(diff);
 }
 
 pub(super) fn process_info_report(receiver: &mut RaymarineReportReceiver, data: &[u8]) {
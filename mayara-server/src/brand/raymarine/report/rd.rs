@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::mem::size_of;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -7,6 +8,7 @@ use mayara_core::protocol::raymarine::{
 };
 
 use mayara_core::controllers::{RaymarineController, RaymarineVariant};
+use mayara_core::{Brand, PowerState};
 use crate::brand::raymarine::{hd_to_pixel_values, settings, RaymarineModel};
 use crate::protos::RadarMessage::RadarMessage;
 use crate::radar::range::{Range, Ranges};
@@ -95,6 +97,7 @@ pub(crate) fn process_frame(receiver: &mut RaymarineReportReceiver, data: &[u8])
         .ok();
     let mut message = RadarMessage::new();
 
+    receiver.info.throttle_frame(nspokes as usize);
     let mut scanline = 0;
     let mut next_offset = RD_FRAME_HEADER_SIZE;
 
@@ -186,22 +189,27 @@ pub(crate) fn process_frame(receiver: &mut RaymarineReportReceiver, data: &[u8])
         let angle = (spoke_header_1.azimuth as u16 + receiver.info.spokes_per_revolution / 2)
             % receiver.info.spokes_per_revolution;
 
-        // Use core decompression function
-        let unpacked = decompress_rd_spoke(spoke, hd_type, returns_per_line);
-        log::trace!("process_spoke unpacked={}", unpacked.len());
-
-        let mut spoke = to_protobuf_spoke(
-            &receiver.info,
-            receiver.range_meters * 4,
-            angle,
-            None,
-            now,
-            unpacked,
-        );
-        receiver
-            .trails
-            .update_trails(&mut spoke, &receiver.info.legend);
-        message.spokes.push(spoke);
+        // Over the spoke budget: still counted, but not worked on
+        if receiver.info.keep_spoke() {
+            // Use core decompression function
+            let unpacked = decompress_rd_spoke(spoke, hd_type, returns_per_line);
+            log::trace!("process_spoke unpacked={}", unpacked.len());
+
+            let mut spoke = to_protobuf_spoke(
+                &receiver.info,
+                receiver.range_meters * 4,
+                angle,
+                None,
+                now,
+                unpacked,
+            );
+            receiver.info.apply_azimuth_offset(&mut spoke);
+            receiver.info.apply_center_blanking(&mut spoke);
+            receiver
+                .trails
+                .update_trails(&mut spoke, &receiver.info.legend);
+            message.spokes.push(spoke);
+        }
 
         next_offset += header3.length as usize - SPOKE_DATA_LENGTH;
 
@@ -267,17 +275,11 @@ pub(super) fn process_status_report(receiver: &mut RaymarineReportReceiver, data
         receiver.state = ReceiverState::StatusRequestReceived;
     }
 
-    let status = match report.status {
-        0x00 => Status::Standby,
-        0x01 => Status::Transmit,
-        0x02 => Status::Preparing,
-        0x03 => Status::Off,
-        _ => {
-            log::warn!("{}: Unknown status {}", receiver.key, report.status);
-            Status::Standby
-        }
-    };
-    receiver.set_value("power", status as i32 as f32);
+    let power = PowerState::from_raw(Brand::Raymarine, report.status as u32);
+    if power == PowerState::Unknown {
+        log::warn!("{}: Unknown status {}", receiver.key, report.status);
+    }
+    receiver.set_value("power", Status::from(power) as i32 as f32);
 
     if receiver.info.ranges.is_empty() {
         let mut ranges = Ranges::empty();
@@ -296,7 +298,7 @@ pub(super) fn process_status_report(receiver: &mut RaymarineReportReceiver, data
             receiver.info.ranges
         );
     }
-    let range_index = if report.is_hd { data[296] } else { report.range_id } as usize;
+    let range_index = report.range_index as usize;
     let range_meters = receiver.info.ranges.get_distance(range_index);
     receiver.range_meters = range_meters as u32;
     log::info!("{}: range_meters={}", receiver.key, range_meters);
@@ -314,6 +316,21 @@ pub(super) fn process_status_report(receiver: &mut RaymarineReportReceiver, data
     receiver.set_value("mainBangSuppression", report.mbs_enabled as u8);
     receiver.set_value_enabled("warmupTime", report.warmup_time, report.warmup_time);
     receiver.set_value("signalStrength", report.signal_strength);
+
+    let diff = receiver.settings_tracker.diff(HashMap::from([
+        ("power".to_string(), serde_json::json!(report.status)),
+        ("range".to_string(), serde_json::json!(range_meters)),
+        ("gain".to_string(), serde_json::json!({"auto": report.auto_gain, "value": report.gain})),
+        ("sea".to_string(), serde_json::json!({"auto": report.auto_sea, "value": report.sea})),
+        ("rain".to_string(), serde_json::json!({"enabled": report.rain_enabled, "value": report.rain})),
+        ("ftc".to_string(), serde_json::json!({"enabled": report.ftc_enabled, "value": report.ftc})),
+        ("tune".to_string(), serde_json::json!({"auto": report.auto_tune, "value": report.tune})),
+        ("targetExpansion".to_string(), serde_json::json!(report.target_expansion)),
+        ("interferenceRejection".to_string(), serde_json::json!(report.interference_rejection)),
+        ("bearingAlignment".to_string(), serde_json::json!(report.bearing_offset)),
+        ("mainBangSuppression".to_string(), serde_json::json!(report.mbs_enabled)),
+    ]));
+    receiver.info.controls.send_settings_diff(diff);
 }
 
 #[derive(Deserialize, Debug, Clone, Copy)]
@@ -0,0 +1,145 @@
+//! Capability documents as static files, for clients that run offline
+//!
+//! A client that is developed or used away from the boat cannot ask a radar
+//! for its capabilities. The same document that
+//! `GET /v2/api/radars/{radar_id}/capabilities` returns can be written to a
+//! JSON file and bundled with the client instead: for a radar that the
+//! server found through `GET /v2/api/radars/{radar_id}/capabilities/export`,
+//! and for any model of the built-in database with
+//! `--export-capabilities <FILE> --export-model <BRAND>:<MODEL>`, which
+//! needs no radar at all. Next to the controls and their ranges and modes
+//! the file holds the range table with the labels of `format_range_table`.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use mayara_core::capabilities::builder::build_capabilities_from_model_with_key;
+use mayara_core::capabilities::{CapabilityManifest, SupportedFeature};
+use mayara_core::models::{self, ModelInfo};
+use serde::Serialize;
+
+use crate::radar::range::{format_range_table, FormattedRange, Ranges};
+
+/// A capability document with the labelled range table
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityExport {
+    #[serde(flatten)]
+    pub capabilities: CapabilityManifest,
+    pub range_table: Vec<FormattedRange>,
+}
+
+impl CapabilityExport {
+    /// The document of a radar, with its own ranges; the ranges of the model
+    /// while the radar has not reported any
+    pub fn new(capabilities: CapabilityManifest, ranges: &Ranges) -> Self {
+        let range_table = if ranges.is_empty() {
+            model_ranges(&capabilities.characteristics.supported_ranges)
+        } else {
+            format_range_table(ranges)
+        };
+        CapabilityExport {
+            capabilities,
+            range_table,
+        }
+    }
+
+    /// The document of a model of the built-in database
+    pub fn for_model(model: &ModelInfo) -> Self {
+        let capabilities = build_capabilities_from_model_with_key(
+            model,
+            model.model,
+            None,
+            supported_features(model),
+            model.spokes_per_revolution,
+            model.max_spoke_length,
+        );
+        Self::new(capabilities, &Ranges::empty())
+    }
+
+    /// Write the document as pretty printed JSON
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+}
+
+/// The features this server offers for a radar of the model
+pub fn supported_features(model: &ModelInfo) -> Vec<SupportedFeature> {
+    let mut features = vec![
+        SupportedFeature::Arpa,
+        SupportedFeature::GuardZones,
+        SupportedFeature::Trails,
+    ];
+    if model.has_dual_range {
+        features.push(SupportedFeature::DualRange);
+    }
+    features
+}
+
+/// Look up a model given as `<brand>:<model>`, e.g. `navico:HALO20+`
+pub fn find_model(spec: &str) -> Result<&'static ModelInfo, String> {
+    let (brand, model) = spec
+        .split_once(':')
+        .ok_or_else(|| format!("Expected <brand>:<model>, not '{}'", spec))?;
+    let brand = mayara_core::Brand::try_from(brand)?;
+    models::get_model(brand, model).ok_or_else(|| {
+        let known: Vec<&str> = models::get_models_for_brand(brand)
+            .iter()
+            .map(|m| m.model)
+            .collect();
+        format!(
+            "Unknown {} model '{}', known models are {}",
+            brand,
+            model,
+            known.join(", ")
+        )
+    })
+}
+
+fn model_ranges(ranges: &[u32]) -> Vec<FormattedRange> {
+    let ranges: Vec<i32> = ranges.iter().map(|&r| r as i32).collect();
+    format_range_table(&Ranges::new_by_distance(&ranges))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn exported_model_is_valid_json_with_the_range_table() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("capabilities.json");
+        let write = {
+            let path = path.clone();
+            // Building the controls needs more than the default test stack in debug builds
+            move || {
+                let model = find_model("navico:HALO20+").unwrap();
+                CapabilityExport::for_model(model).write(&path).unwrap();
+                model.range_table.len()
+            }
+        };
+        let ranges = std::thread::Builder::new()
+            .stack_size(16 * 1024 * 1024)
+            .spawn(write)
+            .unwrap()
+            .join()
+            .unwrap();
+
+        let doc: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(doc["make"], "Navico");
+        assert_eq!(doc["model"], "HALO20+");
+        assert!(doc["controls"].as_array().is_some_and(|c| !c.is_empty()));
+        let table = doc["rangeTable"].as_array().unwrap();
+        assert_eq!(table.len(), ranges);
+        assert!(table.iter().all(|r| r["label"].is_string() && r["meters"].is_i64()));
+        assert!(table.iter().any(|r| r["label"] == "1 km"));
+
+        assert!(find_model("navico").is_err());
+        assert!(find_model("acme:HALO20+").is_err());
+        assert!(find_model("navico:HALO99").unwrap_err().contains("HALO20+"));
+    }
+}
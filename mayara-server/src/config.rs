@@ -9,7 +9,7 @@ use std::io::{BufReader, BufWriter, Write};
 use std::path::PathBuf;
 use std::time::SystemTime;
 
-use crate::radar::range::Ranges;
+use crate::radar::range::{RangeUnitPreference, Ranges};
 use crate::radar::RadarInfo;
 
 pub fn get_project_dirs() -> ProjectDirs {
@@ -25,11 +25,72 @@ pub struct Radar {
     // Data that is computed and not immediately known when starting
     pub model_name: Option<String>, // Descriptive model name (4G, HALO)
     pub ranges: Option<Vec<i32>>,   // Detected ranges
+
+    // Supplied by the user, for radars whose ranges we cannot detect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_override: Option<Vec<i32>>, // Range table in meters, increasing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_spoke_len: Option<u16>, // Truncate or pad spokes sent to clients
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub mirror_azimuth: bool, // Antenna mounted reversed, bearings are mirrored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range_units: Option<RangeUnitPreference>, // Overrides the global range unit preference
+}
+
+impl Radar {
+    /// The ranges to use for this radar: the detected ranges if there are any,
+    /// otherwise the user supplied override table.
+    pub fn effective_ranges(&self) -> Option<Ranges> {
+        if let Some(ranges) = &self.ranges {
+            if ranges.len() > 0 {
+                return Some(Ranges::new_by_distance(ranges));
+            }
+        }
+        match self.range_override.as_deref().map(Ranges::new_from_override) {
+            Some(Ok(ranges)) => Some(ranges),
+            Some(Err(e)) => {
+                warn!("Ignoring range override for radar {}: {}", self.id, e);
+                None
+            }
+            None => None,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, Clone)]
 pub struct Config {
     pub radars: HashMap<String, Radar>,
+    /// Transient discovery key -> stable (serial number based) key
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+impl Config {
+    /// Resolve the key under which a radar's settings are persisted
+    ///
+    /// When the serial number based `stable_key` is known, the discovery key
+    /// is recorded as an alias for it so later sessions that have not yet
+    /// decoded the serial number find the same settings.
+    pub fn resolve_key(&mut self, discovery_key: &str, stable_key: Option<&str>) -> String {
+        match stable_key {
+            Some(stable_key) => {
+                if discovery_key != stable_key {
+                    self.aliases
+                        .insert(discovery_key.to_string(), stable_key.to_string());
+                    // Move settings stored before the serial number was known
+                    if let Some(radar) = self.radars.remove(discovery_key) {
+                        self.radars.entry(stable_key.to_string()).or_insert(radar);
+                    }
+                }
+                stable_key.to_string()
+            }
+            None => self
+                .aliases
+                .get(discovery_key)
+                .cloned()
+                .unwrap_or_else(|| discovery_key.to_string()),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -47,9 +108,7 @@ impl Persistence {
         settings_path.push("settings.json");
 
         let mut this = Persistence {
-            config: Config {
-                radars: HashMap::new(),
-            },
+            config: Config::default(),
             timestamp: SystemTime::UNIX_EPOCH,
             path: settings_path,
         };
@@ -141,10 +200,16 @@ impl Persistence {
     pub fn store(&mut self, radar_info: &RadarInfo) {
         let mut modified = false;
 
+        let aliases = self.config.aliases.len();
+        let key = self.resolve_info_key(radar_info);
+        if self.config.aliases.len() != aliases {
+            modified = true;
+        }
+
         let radar = self
             .config
             .radars
-            .entry(radar_info.key())
+            .entry(key)
             .or_insert(Radar::default());
 
         if let Some(user_name) = radar_info.controls.user_name() {
@@ -172,25 +237,100 @@ impl Persistence {
             radar.id = radar_info.id;
             modified = true;
         }
+        if radar.range_units != radar_info.range_units() {
+            radar.range_units = radar_info.range_units();
+            modified = true;
+        }
 
         if modified {
             self.save();
         }
     }
 
-    pub fn update_info_from_persistence(&self, info: &mut RadarInfo) {
-        if let Some(p) = self.config.radars.get(&info.key()) {
+    fn resolve_info_key(&mut self, radar_info: &RadarInfo) -> String {
+        let stable_key = radar_info.serial_no.as_ref().map(|_| radar_info.config_key());
+        self.config.resolve_key(&radar_info.key(), stable_key.as_deref())
+    }
+
+    pub fn update_info_from_persistence(&mut self, info: &mut RadarInfo) {
+        let key = self.resolve_info_key(info);
+        if let Some(p) = self.config.radars.get(&key) {
             if p.model_name.is_some() {
                 info.controls
                     .set_model_name(p.model_name.as_ref().unwrap().clone());
             }
-            if let Some(ranges) = &p.ranges {
-                if ranges.len() > 0 {
-                    info.ranges = Ranges::new_by_distance(ranges);
-                }
+            if let Some(ranges) = p.effective_ranges() {
+                info.ranges = ranges;
+            }
+            if let Some(len) = p.max_spoke_len.filter(|&len| len > 0) {
+                info.spoke_len_limit = Some(len);
             }
+            info.mirror_azimuth = p.mirror_azimuth;
+            info.set_range_units(p.range_units);
             info.controls.set_user_name(p.user_name.clone());
             info.id = p.id;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radar::range::format_range_table;
+
+    #[test]
+    fn range_override_used_when_none_detected() {
+        let radar = Radar {
+            range_override: Some(vec![463, 926, 1852, 5556]),
+            ..Radar::default()
+        };
+        let ranges = radar.effective_ranges().unwrap();
+        let labels: Vec<String> = format_range_table(&ranges)
+            .into_iter()
+            .map(|r| r.label)
+            .collect();
+        assert_eq!(labels, vec!["1/4 nm", "1/2 nm", "1 nm", "3 nm"]);
+
+        // Detected ranges take precedence over the override
+        let radar = Radar {
+            ranges: Some(vec![500, 1000]),
+            ..radar
+        };
+        assert_eq!(radar.effective_ranges().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn range_override_is_validated() {
+        assert!(Ranges::new_from_override(&[]).is_err());
+        assert!(Ranges::new_from_override(&[0, 1852]).is_err());
+        assert!(Ranges::new_from_override(&[1852, 926]).is_err());
+        assert!(Ranges::new_from_override(&[926, 926]).is_err());
+        assert!(Ranges::new_from_override(&[926, 1852]).is_ok());
+
+        let radar = Radar {
+            range_override: Some(vec![1852, -1]),
+            ..Radar::default()
+        };
+        assert!(radar.effective_ranges().is_none());
+    }
+
+    #[test]
+    fn same_serial_resolves_to_same_key() {
+        let mut config = Config::default();
+
+        // First session: discovered at one address, serial decoded later
+        let first = config.resolve_key("Garmin-172.31.3.1:50100", None);
+        assert_eq!(first, "Garmin-172.31.3.1:50100");
+        let first = config.resolve_key("Garmin-172.31.3.1:50100", Some("Garmin-8F6A21"));
+
+        // Second session: different discovery id, same serial
+        let second = config.resolve_key("Garmin-172.31.3.7:50100", Some("Garmin-8F6A21"));
+        assert_eq!(first, second);
+
+        // A later session finds the stable key before the serial is decoded
+        assert_eq!(
+            config.resolve_key("Garmin-172.31.3.7:50100", None),
+            "Garmin-8F6A21"
+        );
+    }
+}
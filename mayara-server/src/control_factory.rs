@@ -322,6 +322,24 @@ pub fn operating_hours_control() -> Control {
     build_control(&core_def)
 }
 
+/// Build warmup seconds control (read-only)
+pub fn warmup_seconds_control() -> Control {
+    let core_def = controls::control_warmup_seconds();
+    build_control(&core_def)
+}
+
+/// Build blanking active control (read-only)
+pub fn blanking_active_control() -> Control {
+    let core_def = controls::control_blanking_active();
+    build_control(&core_def)
+}
+
+/// Build antenna kind control (read-only)
+pub fn antenna_kind_control() -> Control {
+    let core_def = controls::control_antenna_kind();
+    build_control(&core_def)
+}
+
 /// Build transmit hours control (read-only)
 pub fn transmit_hours_control() -> Control {
     let core_def = controls::control_transmit_hours();
@@ -382,6 +400,18 @@ pub fn sea_state_control() -> Control {
     build_control(&core_def)
 }
 
+/// Build auto response control
+pub fn auto_response_control() -> Control {
+    let core_def = controls::control_auto_response();
+    build_control(&core_def)
+}
+
+/// Build rotation direction control (read-only)
+pub fn rotation_direction_control() -> Control {
+    let core_def = controls::control_rotation_direction();
+    build_control(&core_def)
+}
+
 /// Build accent light control
 pub fn accent_light_control() -> Control {
     let core_def = controls::control_accent_light();
@@ -394,6 +424,12 @@ pub fn local_interference_rejection_control() -> Control {
     build_control(&core_def)
 }
 
+/// Build LIRF (interference source rejection) control
+pub fn lirf_control() -> Control {
+    let core_def = controls::control_lirf();
+    build_control(&core_def)
+}
+
 /// Build main bang suppression control
 pub fn main_bang_suppression_control() -> Control {
     let core_def = controls::control_main_bang_suppression();
@@ -31,7 +31,7 @@
 use std::net::{Ipv4Addr, SocketAddrV4};
 use std::time::Duration;
 
-use mayara_core::locator::{LocatorEvent, RadarLocator};
+use mayara_core::locator::{DiscoveryRetry, LocatorEvent, RadarLocator};
 use mayara_core::radar::RadarDiscovery;
 use mayara_core::Brand as CoreBrand;
 use tokio::sync::mpsc;
@@ -67,6 +67,8 @@ pub struct CoreLocatorAdapter {
     poll_interval: Duration,
     /// Session to update with locator status
     session: crate::Session,
+    /// IP addresses of radars that went offline, to search for again
+    forget_rx: Option<mpsc::UnboundedReceiver<String>>,
 }
 
 impl CoreLocatorAdapter {
@@ -83,9 +85,16 @@ impl CoreLocatorAdapter {
             discovery_tx,
             poll_interval,
             session,
+            forget_rx: None,
         }
     }
 
+    /// Forget the radars at the IP addresses received on `forget_rx`, so
+    /// that discovery searches for them again.
+    pub fn set_forget_rx(&mut self, forget_rx: mpsc::UnboundedReceiver<String>) {
+        self.forget_rx = Some(forget_rx);
+    }
+
     /// Create with default poll interval (100ms).
     pub fn with_default_interval(session: crate::Session, discovery_tx: mpsc::Sender<LocatorMessage>) -> Self {
         Self::new(session, discovery_tx, Duration::from_millis(100))
@@ -106,6 +115,16 @@ impl CoreLocatorAdapter {
             log::warn!("No NIC found for Furuno subnet (172.31.x.x) - broadcasts may go to wrong interface");
         }
 
+        let retry = {
+            let session = self.session.read().unwrap();
+            DiscoveryRetry {
+                initial_interval_ms: session.args.discovery_interval.max(1) * 1000,
+                warn_after_attempts: session.args.discovery_attempts,
+                ..DiscoveryRetry::default()
+            }
+        };
+        self.locator.set_discovery_retry(retry);
+
         self.locator.start(&mut self.io);
 
         // Update session with locator status
@@ -122,6 +141,39 @@ impl CoreLocatorAdapter {
         self.locator.poll(&mut self.io)
     }
 
+    /// Copy the locator status into the session, for the interfaces API.
+    fn update_session_status(&self) {
+        if let Ok(mut session) = self.session.write() {
+            session.locator_status = self.locator.status().clone();
+        }
+    }
+
+    /// Log a prominent warning listing everything that was tried.
+    fn warn_nothing_found(&self, attempts: u32) {
+        let interface = self.session.read().unwrap().args.interface.clone();
+        let tried: Vec<String> = self
+            .locator
+            .status()
+            .brands
+            .iter()
+            .map(|b| match (&b.multicast, b.port) {
+                (Some(group), Some(port)) => format!("{} {}:{} ({})", b.brand, group, port, b.status),
+                (None, Some(port)) => format!("{} port {} ({})", b.brand, port, b.status),
+                _ => format!("{} ({})", b.brand, b.status),
+            })
+            .collect();
+
+        log::warn!("No radar found after {} discovery attempts!", attempts);
+        log::warn!(
+            "Interface: {}",
+            interface.as_deref().unwrap_or("all interfaces")
+        );
+        for t in tried {
+            log::warn!("  tried {}", t);
+        }
+        log::warn!("Check that the radar is powered on and on the same network as this server");
+    }
+
     /// Send a Furuno announce packet.
     ///
     /// Call this before attempting TCP connections to Furuno radars.
@@ -154,6 +206,7 @@ impl CoreLocatorAdapter {
         // Set up polling interval
         let mut poll_timer = interval(self.poll_interval);
         poll_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut forget_rx = self.forget_rx.take();
 
         loop {
             tokio::select! {
@@ -161,6 +214,16 @@ impl CoreLocatorAdapter {
                     log::info!("CoreLocatorAdapter: Shutdown requested");
                     break;
                 }
+                Some(ip) = async {
+                    match forget_rx.as_mut() {
+                        Some(rx) => rx.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    let forgotten = self.locator.forget(&ip);
+                    log::info!("CoreLocatorAdapter: Searching again for {} radar(s) at {}", forgotten, ip);
+                    self.update_session_status();
+                }
                 _ = poll_timer.tick() => {
                     // Poll the core locator
                     let events = self.poll();
@@ -182,6 +245,15 @@ impl CoreLocatorAdapter {
                                 );
                                 LocatorMessage::RadarUpdated(discovery)
                             }
+                            LocatorEvent::Searching { attempt } => {
+                                log::info!("CoreLocatorAdapter: No radar found yet, searching (attempt {})", attempt);
+                                self.update_session_status();
+                                continue;
+                            }
+                            LocatorEvent::NothingFound { attempts } => {
+                                self.warn_nothing_found(attempts);
+                                continue;
+                            }
                         };
 
                         if self.discovery_tx.send(message).await.is_err() {
@@ -211,10 +283,11 @@ impl CoreLocatorAdapter {
 /// use tokio::sync::mpsc;
 ///
 /// let (tx, mut rx) = mpsc::channel::<LocatorMessage>(32);
+/// let (forget_tx, forget_rx) = mpsc::unbounded_channel::<String>();
 ///
 /// // Add to subsystem
 /// subsys.start(SubsystemBuilder::new("core-locator", |s| {
-///     create_locator_subsystem(session.clone(), tx, s)
+///     create_locator_subsystem(session.clone(), tx, forget_rx, s)
 /// }));
 ///
 /// // Receive discoveries
@@ -230,9 +303,11 @@ impl CoreLocatorAdapter {
 pub async fn create_locator_subsystem(
     session: crate::Session,
     discovery_tx: mpsc::Sender<LocatorMessage>,
+    forget_rx: mpsc::UnboundedReceiver<String>,
     subsys: SubsystemHandle,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let adapter = CoreLocatorAdapter::with_default_interval(session, discovery_tx);
+    let mut adapter = CoreLocatorAdapter::with_default_interval(session, discovery_tx);
+    adapter.set_forget_rx(forget_rx);
     adapter.run(subsys).await
 }
 
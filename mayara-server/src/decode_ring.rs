@@ -0,0 +1,318 @@
+//! In-memory ring of recently decoded radar messages
+//!
+//! The last `--decode-ring` settings reports and control values decoded from
+//! the radars are kept in memory, so an operator can download what happened
+//! just before an incident without having enabled logging beforehand.
+//! The ring is served as NDJSON, oldest message first.
+//!
+//! With `--dump-decoded` every message is also written to stdout as it is
+//! decoded, one JSON object per line, for debugging over SSH without a
+//! browser.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::Brand;
+
+/// Default number of messages kept
+pub const DEFAULT_DECODE_RING_SIZE: usize = 1000;
+
+/// Default number of seconds exported on either side of a bookmark
+pub const DEFAULT_BOOKMARK_SECONDS: u64 = 10;
+
+/// Longest export on either side of a bookmark; a ring of the default size
+/// rarely holds more than a minute of messages
+pub const MAX_BOOKMARK_SECONDS: u64 = 60;
+
+/// One decoded message as kept in the ring
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedMessage {
+    pub timestamp: u64, // Milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub radar: Option<String>,
+    pub kind: &'static str, // "settings", "control" or "capture"
+    pub message: serde_json::Value,
+}
+
+impl DecodedMessage {
+    pub fn new(radar: Option<&str>, kind: &'static str, message: serde_json::Value) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        DecodedMessage {
+            timestamp,
+            radar: radar.map(String::from),
+            kind,
+            message,
+        }
+    }
+}
+
+/// Kinds of decoded messages
+const KINDS: [&str; 3] = ["settings", "control", "capture"];
+
+/// Which messages `--dump-decoded` prints, parsed from a comma separated list
+/// of brands and kinds, e.g. `navico,control`. An empty list prints all.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DumpFilter {
+    brands: Vec<Brand>,
+    kinds: Vec<&'static str>,
+}
+
+impl DumpFilter {
+    pub fn matches(&self, message: &DecodedMessage) -> bool {
+        let brand_matches = self.brands.is_empty()
+            || message.radar.as_deref().is_some_and(|radar| {
+                let prefix = radar.split('-').next().unwrap_or(radar);
+                self.brands
+                    .iter()
+                    .any(|brand| prefix.eq_ignore_ascii_case(&brand.to_string()))
+            });
+        let kind_matches = self.kinds.is_empty() || self.kinds.contains(&message.kind);
+        brand_matches && kind_matches
+    }
+}
+
+impl FromStr for DumpFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = DumpFilter::default();
+        for term in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            if let Some(kind) = KINDS.iter().find(|k| k.eq_ignore_ascii_case(term)) {
+                filter.kinds.push(kind);
+            } else if let Ok(brand) = <Brand as ValueEnum>::from_str(term, true) {
+                filter.brands.push(brand);
+            } else {
+                return Err(format!(
+                    "'{}' is neither a brand nor one of {}",
+                    term,
+                    KINDS.join(", ")
+                ));
+            }
+        }
+        Ok(filter)
+    }
+}
+
+/// Where `--dump-decoded` writes the messages that pass its filter
+struct Dump {
+    filter: DumpFilter,
+    out: Box<dyn Write + Send>,
+}
+
+impl fmt::Debug for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dump")
+            .field("filter", &self.filter)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct DecodeRing {
+    capacity: usize,
+    messages: VecDeque<DecodedMessage>,
+    dump: Option<Dump>,
+}
+
+pub type SharedDecodeRing = Arc<Mutex<DecodeRing>>;
+
+impl DecodeRing {
+    pub fn new(capacity: usize) -> Self {
+        DecodeRing {
+            capacity,
+            messages: VecDeque::with_capacity(capacity.min(DEFAULT_DECODE_RING_SIZE)),
+            dump: None,
+        }
+    }
+
+    /// Also write every message that passes `filter` to `out` as a JSON line
+    pub fn set_dump(&mut self, filter: DumpFilter, out: Box<dyn Write + Send>) {
+        self.dump = Some(Dump { filter, out });
+    }
+
+    pub fn new_shared(capacity: usize) -> SharedDecodeRing {
+        Arc::new(Mutex::new(Self::new(capacity)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Add a message, dropping the oldest one when the ring is full.
+    /// A ring with capacity 0 keeps nothing, but still dumps.
+    pub fn push(&mut self, message: DecodedMessage) {
+        if let Some(dump) = &mut self.dump {
+            if dump.filter.matches(&message) {
+                if let Ok(line) = serde_json::to_string(&message) {
+                    writeln!(dump.out, "{}", line).ok();
+                    dump.out.flush().ok();
+                }
+            }
+        }
+        if self.capacity == 0 {
+            return;
+        }
+        while self.messages.len() >= self.capacity {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    /// The messages at or after `since` (milliseconds since the epoch) as
+    /// NDJSON, one message per line, oldest first
+    pub fn to_ndjson(&self, since: Option<u64>) -> String {
+        let mut ndjson = String::new();
+        self.append_ndjson(&mut ndjson, |m| {
+            since.map_or(true, |since| m.timestamp >= since)
+        });
+        ndjson
+    }
+
+    /// Export of the messages within `window_ms` either side of `at`
+    /// (milliseconds since the epoch), for attaching to a bug report. The
+    /// first line describes the bookmark, the messages follow oldest first.
+    pub fn bookmark_ndjson(&self, label: &str, at: u64, window_ms: u64) -> String {
+        let header = serde_json::json!({
+            "bookmark": { "label": label, "timestamp": at, "windowMs": window_ms }
+        });
+        let mut ndjson = header.to_string();
+        ndjson.push('\n');
+        self.append_ndjson(&mut ndjson, |m| m.timestamp.abs_diff(at) <= window_ms);
+        ndjson
+    }
+
+    fn append_ndjson(&self, ndjson: &mut String, filter: impl Fn(&DecodedMessage) -> bool) {
+        for message in self.messages.iter().filter(|m| filter(m)) {
+            if let Ok(line) = serde_json::to_string(message) {
+                ndjson.push_str(&line);
+                ndjson.push('\n');
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_keeps_last_n_in_order() {
+        let mut ring = DecodeRing::new(3);
+        for i in 0..5 {
+            let mut message = DecodedMessage::new(Some("nav1"), "control", serde_json::json!(i));
+            message.timestamp = 1000 + i;
+            ring.push(message);
+        }
+        assert_eq!(ring.len(), 3);
+
+        let ndjson = ring.to_ndjson(None);
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        for (line, expected) in lines.iter().zip(2..5) {
+            assert_eq!(line["message"], expected);
+            assert_eq!(line["timestamp"], 1000 + expected);
+            assert_eq!(line["radar"], "nav1");
+        }
+
+        assert_eq!(ring.to_ndjson(Some(1004)).lines().count(), 1);
+    }
+
+    #[test]
+    fn bookmark_exports_window_around_mark() {
+        let mut ring = DecodeRing::new(100);
+        for i in 1..=20 {
+            let mut message = DecodedMessage::new(Some("nav1"), "control", serde_json::json!(i));
+            message.timestamp = i * 1000;
+            ring.push(message);
+        }
+
+        let ndjson = ring.bookmark_ndjson("odd echo", 10_000, 3000);
+        let lines: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines[0]["bookmark"]["label"], "odd echo");
+        assert_eq!(lines[0]["bookmark"]["timestamp"], 10_000);
+        let timestamps: Vec<u64> = lines[1..]
+            .iter()
+            .map(|l| l["timestamp"].as_u64().unwrap())
+            .collect();
+        assert_eq!(timestamps, (7..=13).map(|s| s * 1000).collect::<Vec<_>>());
+    }
+
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn dump_prints_matching_reports() {
+        let buffer = SharedBuffer::default();
+        let mut ring = DecodeRing::new(0);
+        ring.set_dump("navico,settings".parse().unwrap(), Box::new(buffer.clone()));
+
+        // A synthetic settings report as Controls records it
+        let report = serde_json::json!({ "gain": { "value": 50 } });
+        ring.push(DecodedMessage::new(
+            Some("Navico-1403100652-A"),
+            "settings",
+            report.clone(),
+        ));
+        ring.push(DecodedMessage::new(
+            Some("Furuno-6424"),
+            "settings",
+            report.clone(),
+        ));
+        ring.push(DecodedMessage::new(
+            Some("Navico-1403100652-A"),
+            "control",
+            report,
+        ));
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["radar"], "Navico-1403100652-A");
+        assert_eq!(lines[0]["message"]["gain"]["value"], 50);
+        assert!(ring.is_empty());
+
+        assert!(""
+            .parse::<DumpFilter>()
+            .unwrap()
+            .matches(&DecodedMessage::new(
+                None,
+                "capture",
+                serde_json::json!(null)
+            )));
+        assert!("nonsense".parse::<DumpFilter>().is_err());
+    }
+}
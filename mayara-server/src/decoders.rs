@@ -0,0 +1,1036 @@
+//! Registry of protocol decoders for captured traffic
+//!
+//! Each decoder turns raw packets of one brand into a JSON description using
+//! the mayara-core parsers. Normally the decoder is picked by the port the
+//! traffic arrived on; an operator can force a specific decoder instead, for
+//! radars on a nonstandard port.
+//!
+//! A decoded message that reports a range in meters as `rangeMeters` also
+//! gets a `formatted` label in the range units of the session.
+
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use mayara_core::units::BearingUnit;
+use mayara_core::PowerState;
+
+use crate::decode_ring::DecodedMessage;
+use crate::radar::range::{format_range_in, RangeUnitPreference};
+use crate::radar::RadarError;
+use crate::Brand;
+
+/// Which way a captured packet travelled
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    /// Reports, spokes and beacons sent by the radar
+    #[default]
+    FromRadar,
+    /// Commands and requests sent to the radar
+    ToRadar,
+}
+
+pub trait ProtocolDecoder: Send + Sync {
+    /// Name used to select the decoder, e.g. "navico"
+    fn name(&self) -> &'static str;
+
+    fn brand(&self) -> Brand;
+
+    /// Whether traffic on this port is this decoder's protocol
+    fn handles_port(&self, port: u16) -> bool;
+
+    fn decode(&self, data: &[u8]) -> serde_json::Value;
+
+    /// The separate messages in a packet, for protocols that batch several
+    /// into one datagram
+    fn split<'a>(&self, data: &'a [u8]) -> Vec<&'a [u8]> {
+        vec![data]
+    }
+
+    /// Decode every message in a packet, one `DecodedMessage` each
+    fn decode_all(&self, data: &[u8], direction: Direction) -> Vec<DecodedMessage> {
+        decode_split(self, data, direction)
+    }
+}
+
+#[cfg(feature = "navico")]
+pub struct NavicoDecoder;
+
+#[cfg(feature = "navico")]
+impl NavicoDecoder {
+    fn decode_report(&self, data: &[u8]) -> serde_json::Value {
+        use mayara_core::protocol::navico::*;
+
+        if let [REPORT_TARGETS, 0xC4, ..] = data {
+            if let Ok(targets) = parse_target_report(data) {
+                return json!({ "decoder": self.name(), "length": data.len(), "targets": targets });
+            }
+        }
+        let parsed = match data {
+            [0x01, 0xC4, ..] => parse_report_01(data).map(|r| format!("{:?}", r)),
+            [0x02, 0xC4, ..] => parse_report_02(data).map(|r| format!("{:?}", r)),
+            [0x03, 0xC4, ..] => parse_report_03(data).map(|r| format!("{:?}", r)),
+            [0x04, 0xC4, ..] => parse_report_04(data).map(|r| format!("{:?}", r)),
+            [0x08, 0xC4, ..] => parse_report_08(data).map(|r| format!("{:?}", r)),
+            _ => parse_beacon_endpoints(data).map(|r| format!("{:?}", r)),
+        };
+        let mut message = decoded(self.name(), data, parsed);
+        if let (Some(controls), Some(object)) = (image_controls(data), message.as_object_mut()) {
+            object.insert("imageControls".to_string(), controls);
+        }
+        if let (Some(alignment), Some(object)) = (overlay_alignment(data), message.as_object_mut()) {
+            object.insert("overlayAlignment".to_string(), alignment);
+        }
+        if let [0x02, 0xC4, ..] = data {
+            if let (Ok(controls), Some(object)) = (parse_report_02(data), message.as_object_mut()) {
+                object.insert("rangeRaw".to_string(), json!(controls.range));
+                object.insert("rangeMeters".to_string(), json!(controls.range / 10)); // Decimeters
+                object.insert("description".to_string(), json!(controls.description()));
+                object.insert("rainAuto".to_string(), json!(controls.rain_auto));
+                object.insert("seaAutoMode".to_string(), json!(controls.sea_auto_mode().label()));
+            }
+        }
+        // HALO picks its sea auto preset by the sea state of the settings report
+        if let [0x08, 0xC4, ..] = data {
+            if let (Ok(settings), Some(object)) = (parse_report_08(data), message.as_object_mut()) {
+                let preset = SeaAutoMode::from_halo(1, settings.sea_state);
+                object.insert("seaAutoPreset".to_string(), json!(preset.label()));
+                object.insert(
+                    "pulseLength".to_string(),
+                    json!(settings.pulse_length.map(|p| p.as_str())),
+                );
+                object.insert(
+                    "seaClutterCurve".to_string(),
+                    json!(settings.sea_clutter_curve.map(|c| c.as_str())),
+                );
+            }
+        }
+        let power = match data {
+            [0x01, 0xC4, status, ..] if parse_report_01(data).is_ok() => {
+                Some(PowerState::from_raw(mayara_core::Brand::Navico, *status as u32))
+            }
+            _ => None,
+        };
+        with_power(message, power)
+    }
+}
+
+/// The bearing alignment of a Navico installation report, in degrees, for
+/// lining the image up with a chart. Navico radars report no range offset.
+#[cfg(feature = "navico")]
+fn overlay_alignment(data: &[u8]) -> Option<serde_json::Value> {
+    match data {
+        [0x04, 0xC4, ..] => mayara_core::protocol::navico::parse_report_04(data).ok().map(|r| {
+            json!({
+                "bearingOffset": r.bearing_alignment as f64 / 10.,
+                "bearingOffsetRaw": r.bearing_alignment,
+            })
+        }),
+        _ => None,
+    }
+}
+
+/// The fine image controls of a Navico report, under their control names, so
+/// clients can show them together in one panel
+#[cfg(feature = "navico")]
+fn image_controls(data: &[u8]) -> Option<serde_json::Value> {
+    use mayara_core::protocol::navico::*;
+
+    match data {
+        [0x02, 0xC4, ..] => parse_report_02(data).ok().map(|r| {
+            json!({
+                "interferenceRejection": r.interference_rejection,
+                "interferenceAuto": r.interference_auto,
+                "interference": r.interference_description(),
+                "targetExpansion": r.target_expansion,
+                "targetBoost": r.target_boost,
+            })
+        }),
+        [0x08, 0xC4, ..] => parse_report_08(data).ok().map(|r| {
+            json!({
+                "mainBangSuppression": r.main_bang_suppression,
+                "localInterferenceRejection": r.local_interference_rejection,
+                "noiseRejection": r.noise_rejection,
+                "sidelobeSuppression": r.sidelobe_suppression,
+                "sidelobeSuppressionAuto": r.sidelobe_suppression_auto,
+            })
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "navico")]
+impl ProtocolDecoder for NavicoDecoder {
+    fn name(&self) -> &'static str {
+        "navico"
+    }
+
+    fn brand(&self) -> Brand {
+        Brand::Navico
+    }
+
+    fn handles_port(&self, port: u16) -> bool {
+        use mayara_core::protocol::navico::*;
+        [BR24_BEACON_PORT, GEN3_BEACON_PORT, INFO_PORT, SPEED_PORT_A, SPEED_PORT_B].contains(&port)
+    }
+
+    /// Reports behind a few framing bytes are found by scanning for their
+    /// header, and marked with the `offset` they were found at
+    fn decode(&self, data: &[u8]) -> serde_json::Value {
+        let mut message = self.decode_report(data);
+        if message.get("error").is_some() {
+            if let Some(offset) = mayara_core::protocol::navico::find_report_start(data) {
+                let mut found = self.decode_report(&data[offset..]);
+                if let Some(object) = found.as_object_mut().filter(|o| !o.contains_key("error")) {
+                    object.insert("length".to_string(), json!(data.len()));
+                    object.insert("offset".to_string(), json!(offset));
+                    message = found;
+                }
+            }
+        }
+        message
+    }
+
+    fn split<'a>(&self, data: &'a [u8]) -> Vec<&'a [u8]> {
+        mayara_core::protocol::navico::split_reports(data)
+    }
+
+    /// Spoke frames carry up to 32 spokes; each becomes its own message,
+    /// with the range scale of its header and the meters per cell it gives
+    fn decode_all(&self, data: &[u8], direction: Direction) -> Vec<DecodedMessage> {
+        use mayara_core::protocol::navico::*;
+        use mayara_core::spoke_scale::SpokeScale;
+
+        let is_spoke_frame = direction == Direction::FromRadar
+            && data.len() >= FRAME_HEADER_SIZE + SPOKE_LINE_SIZE
+            && (data.len() - FRAME_HEADER_SIZE) % SPOKE_LINE_SIZE == 0
+            && data[FRAME_HEADER_SIZE] == SPOKE_HEADER_SIZE as u8;
+        if !is_spoke_frame {
+            return decode_split(self, data, direction);
+        }
+
+        data[FRAME_HEADER_SIZE..]
+            .chunks(SPOKE_LINE_SIZE)
+            .enumerate()
+            .map(|(index, spoke)| {
+                let message = match parse_4g_spoke_header(spoke) {
+                    Ok((range, angle, heading)) => json!({
+                        "decoder": self.name(),
+                        "length": spoke.len(),
+                        "spoke": index,
+                        "angle": angle,
+                        "range": range,
+                        "rangeScale": parse_4g_spoke_range_scale(spoke).ok(),
+                        "metersPerCell": SpokeScale::new(range as f64, MAX_SPOKE_LEN as usize, 0.)
+                            .map(|scale| scale.meters_per_cell()),
+                        "heading": heading,
+                    }),
+                    Err(e) => json!({
+                        "decoder": self.name(),
+                        "length": spoke.len(),
+                        "spoke": index,
+                        "error": e.to_string(),
+                    }),
+                };
+                capture(message, direction)
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "furuno")]
+pub struct FurunoDecoder;
+
+#[cfg(feature = "furuno")]
+impl ProtocolDecoder for FurunoDecoder {
+    fn name(&self) -> &'static str {
+        "furuno"
+    }
+
+    fn brand(&self) -> Brand {
+        Brand::Furuno
+    }
+
+    // Command responses arrive over a TCP port negotiated at login, so only
+    // the beacon port is known up front
+    fn handles_port(&self, port: u16) -> bool {
+        port == mayara_core::protocol::furuno::BEACON_PORT
+    }
+
+    fn decode(&self, data: &[u8]) -> serde_json::Value {
+        use mayara_core::protocol::furuno::command::{
+            parse_response, strip_checksum, CommandId, CommandMode,
+        };
+
+        let text = String::from_utf8_lossy(data);
+        let responses: Vec<String> = text
+            .lines()
+            .filter_map(|line| match strip_checksum(line) {
+                None => Some(format!("Unknown (checksum mismatch): {}", line.trim())),
+                Some(line) => parse_response(line)
+                    .map(|(mode, command, args)| format!("{:?} {:02X} {:?}", mode, command, args)),
+            })
+            .collect();
+        // Lines with a bad checksum are not decoded any further
+        let lines: Vec<&str> = text.lines().filter_map(strip_checksum).collect();
+        let parsed = if responses.is_empty() {
+            Err("No Furuno command responses".to_string())
+        } else {
+            Ok(responses.join("; "))
+        };
+        let settings = furuno_settings(&lines);
+        let power = lines.iter().find_map(|line| match parse_response(line) {
+            Some((CommandMode::New, command, args)) if command == CommandId::Status.as_hex() => args
+                .first()
+                .map(|&status| PowerState::from_raw(mayara_core::Brand::Furuno, status as u32)),
+            _ => None,
+        });
+        let mut message = decoded(self.name(), data, parsed);
+        if let (Some(settings), Some(object)) = (settings, message.as_object_mut()) {
+            object.insert("settings".to_string(), settings);
+        }
+        with_power(message, power)
+    }
+}
+
+/// Gain, sea and rain in the responses, raw as sent by the radar and scaled
+/// to the 0..100 of the controls; `None` when there are none
+#[cfg(feature = "furuno")]
+fn furuno_settings(lines: &[&str]) -> Option<serde_json::Value> {
+    use mayara_core::capabilities::controls::*;
+    use mayara_core::capabilities::ControlDefinition;
+    use mayara_core::protocol::furuno::command::{
+        parse_gain_response, parse_rain_response, parse_sea_response, ControlValue,
+    };
+
+    let setting = |control: ControlValue, definition: ControlDefinition| {
+        let scale = definition
+            .wire_hints
+            .and_then(|hints| hints.scale_factor)
+            .unwrap_or(100.0) as f64;
+        json!({
+            "raw": control.value,
+            "value": (control.value as f64 * 100.0 / scale).round(),
+            "auto": control.auto,
+        })
+    };
+    let brand = mayara_core::Brand::Furuno;
+    let mut settings = serde_json::Map::new();
+    for &line in lines {
+        if let Some(gain) = parse_gain_response(line) {
+            settings.insert("gain".to_string(), setting(gain, control_gain_for_brand(brand)));
+        } else if let Some(sea) = parse_sea_response(line) {
+            settings.insert("sea".to_string(), setting(sea, control_sea_for_brand(brand)));
+        } else if let Some(rain) = parse_rain_response(line) {
+            settings.insert("rain".to_string(), setting(rain, control_rain_for_brand(brand)));
+        }
+    }
+    (!settings.is_empty()).then_some(serde_json::Value::Object(settings))
+}
+
+#[cfg(feature = "raymarine")]
+pub struct RaymarineDecoder;
+
+#[cfg(feature = "raymarine")]
+impl ProtocolDecoder for RaymarineDecoder {
+    fn name(&self) -> &'static str {
+        "raymarine"
+    }
+
+    fn brand(&self) -> Brand {
+        Brand::Raymarine
+    }
+
+    fn handles_port(&self, port: u16) -> bool {
+        port == mayara_core::protocol::raymarine::BEACON_PORT
+    }
+
+    /// Reports get the protocol `family` they belong to, RD and HD status
+    /// reports the range in use as `rangeMeters`, and Quantum status reports
+    /// the settings of the current mode under `quantum`
+    fn decode(&self, data: &[u8]) -> serde_json::Value {
+        use mayara_core::protocol::raymarine::*;
+
+        let mut message = self.decode_report(data);
+        if let (Some(family), Some(object)) = (report_family(data), message.as_object_mut()) {
+            object.insert("family".to_string(), json!(family.as_str()));
+        }
+        message
+    }
+}
+
+#[cfg(feature = "raymarine")]
+impl RaymarineDecoder {
+    fn decode_report(&self, data: &[u8]) -> serde_json::Value {
+        use mayara_core::protocol::raymarine::*;
+
+        if let [a, b, c, d, ..] = *data {
+            if u32::from_le_bytes([a, b, c, d]) == QUANTUM_STATUS_REPORT {
+                let status = parse_quantum_status(data);
+                let quantum = status.as_ref().ok().map(quantum_settings);
+                let power = status.as_ref().ok().map(|s| raymarine_power(s.status));
+                let mut message = decoded(self.name(), data, status.map(|r| format!("{:?}", r)));
+                if let (Some(quantum), Some(object)) = (quantum, message.as_object_mut()) {
+                    object.insert("quantum".to_string(), quantum);
+                }
+                return with_power(message, power);
+            }
+        }
+        let (parsed, power) = match data.len() {
+            56 => (parse_beacon_56(data).map(|r| format!("{:?}", r)), None),
+            36 => (parse_beacon_36(data).map(|r| format!("{:?}", r)), None),
+            _ => {
+                let status = parse_rd_status(data);
+                let power = status.as_ref().ok().map(|s| raymarine_power(s.status));
+                let range = status.as_ref().ok().map(|s| (s.range_index, s.range_meters()));
+                let mut message = decoded(self.name(), data, status.map(|r| format!("{:?}", r)));
+                if let (Some((index, meters)), Some(object)) = (range, message.as_object_mut()) {
+                    object.insert("rangeIndex".to_string(), json!(index));
+                    object.insert("rangeMeters".to_string(), json!(meters));
+                }
+                return with_power(message, power);
+            }
+        };
+        with_power(decoded(self.name(), data, parsed), power)
+    }
+}
+
+#[cfg(feature = "raymarine")]
+fn raymarine_power(status: u8) -> PowerState {
+    PowerState::from_raw(mayara_core::Brand::Raymarine, status as u32)
+}
+
+/// Gain, color gain and sea of the current Quantum mode, raw as sent by the
+/// radar and scaled to the 0..100 of the controls
+#[cfg(feature = "raymarine")]
+fn quantum_settings(status: &mayara_core::protocol::raymarine::ParsedQuantumStatus) -> serde_json::Value {
+    use mayara_core::capabilities::controls::*;
+    use mayara_core::capabilities::ControlDefinition;
+
+    let Some(controls) = status.controls.get(status.mode as usize) else {
+        return json!({ "mode": status.mode });
+    };
+    let setting = |raw: u8, auto: bool, definition: ControlDefinition| {
+        let scale = definition
+            .wire_hints
+            .and_then(|hints| hints.scale_factor)
+            .unwrap_or(100.0) as f64;
+        json!({ "raw": raw, "value": (raw as f64 * 100.0 / scale).round(), "auto": auto })
+    };
+    let brand = mayara_core::Brand::Raymarine;
+    json!({
+        "mode": status.mode,
+        "gain": setting(controls.gain, controls.gain_auto, control_gain_for_brand(brand)),
+        "colorGain": setting(
+            controls.color_gain,
+            controls.color_gain_auto,
+            control_color_gain_for_brand(brand)
+        ),
+        "sea": setting(controls.sea, controls.sea_auto, control_sea_for_brand(brand)),
+    })
+}
+
+#[cfg(feature = "garmin")]
+pub struct GarminDecoder;
+
+#[cfg(feature = "garmin")]
+impl ProtocolDecoder for GarminDecoder {
+    fn name(&self) -> &'static str {
+        "garmin"
+    }
+
+    fn brand(&self) -> Brand {
+        Brand::Garmin
+    }
+
+    fn handles_port(&self, port: u16) -> bool {
+        port == mayara_core::protocol::garmin::REPORT_PORT
+    }
+
+    /// Transmit state reports also get the `power` state, range reports the
+    /// range as `rangeMeters`
+    fn decode(&self, data: &[u8]) -> serde_json::Value {
+        use mayara_core::protocol::garmin::{parse_report, Report};
+
+        let report = parse_report(data);
+        let (power, range) = match &report {
+            Ok(Report::TransmitState(state)) => (
+                Some(PowerState::from_raw(mayara_core::Brand::Garmin, state.to_raw())),
+                None,
+            ),
+            Ok(Report::Range(meters)) => (None, Some(*meters)),
+            _ => (None, None),
+        };
+        let mut message = decoded(self.name(), data, report.map(|r| format!("{:?}", r)));
+        if let (Some(meters), Some(object)) = (range, message.as_object_mut()) {
+            object.insert("rangeMeters".to_string(), json!(meters));
+        }
+        with_power(message, power)
+    }
+}
+
+/// Add the power state of a status report, the same for every brand
+fn with_power(mut message: serde_json::Value, power: Option<PowerState>) -> serde_json::Value {
+    if let (Some(power), Some(object)) = (power, message.as_object_mut()) {
+        object.insert("power".to_string(), json!(power));
+    }
+    message
+}
+
+/// Decode each of the messages `decoder` splits a packet into
+fn decode_split<D: ProtocolDecoder + ?Sized>(
+    decoder: &D,
+    data: &[u8],
+    direction: Direction,
+) -> Vec<DecodedMessage> {
+    decoder
+        .split(data)
+        .into_iter()
+        .map(|message| capture(decoder.decode(message), direction))
+        .collect()
+}
+
+fn capture(mut message: serde_json::Value, direction: Direction) -> DecodedMessage {
+    if let Some(object) = message.as_object_mut() {
+        object.insert("direction".to_string(), json!(direction));
+    }
+    DecodedMessage::new(None, "capture", message)
+}
+
+fn decoded<E: ToString>(decoder: &str, data: &[u8], parsed: Result<String, E>) -> serde_json::Value {
+    match parsed {
+        Ok(parsed) => json!({ "decoder": decoder, "length": data.len(), "parsed": parsed }),
+        Err(e) => json!({ "decoder": decoder, "length": data.len(), "error": e.to_string() }),
+    }
+}
+
+/// A decoder as listed by the API
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DecoderInfo {
+    pub name: &'static str,
+    pub brand: String,
+}
+
+pub struct DecoderRegistry {
+    decoders: Vec<Box<dyn ProtocolDecoder>>,
+    forced: Option<&'static str>,
+    range_units: RangeUnitPreference,
+    bearing_unit: BearingUnit,
+}
+
+/// Fields of decoded messages that hold a bearing in degrees
+const BEARING_FIELDS: [&str; 2] = ["bearing", "bearingOffset"];
+
+pub type SharedDecoderRegistry = Arc<RwLock<DecoderRegistry>>;
+
+impl DecoderRegistry {
+    /// All decoders for the brands this server is built with
+    pub fn new() -> Self {
+        let mut decoders: Vec<Box<dyn ProtocolDecoder>> = Vec::new();
+        #[cfg(feature = "navico")]
+        decoders.push(Box::new(NavicoDecoder));
+        #[cfg(feature = "furuno")]
+        decoders.push(Box::new(FurunoDecoder));
+        #[cfg(feature = "raymarine")]
+        decoders.push(Box::new(RaymarineDecoder));
+        #[cfg(feature = "garmin")]
+        decoders.push(Box::new(GarminDecoder));
+
+        DecoderRegistry {
+            decoders,
+            forced: None,
+            range_units: RangeUnitPreference::default(),
+            bearing_unit: BearingUnit::default(),
+        }
+    }
+
+    /// Label decoded ranges in `units`
+    pub fn with_range_units(mut self, units: RangeUnitPreference) -> Self {
+        self.range_units = units;
+        self
+    }
+
+    pub fn set_range_units(&mut self, units: RangeUnitPreference) {
+        self.range_units = units;
+    }
+
+    /// Report decoded bearings in `unit`
+    pub fn with_bearing_unit(mut self, unit: BearingUnit) -> Self {
+        self.bearing_unit = unit;
+        self
+    }
+
+    pub fn list(&self) -> Vec<DecoderInfo> {
+        self.decoders
+            .iter()
+            .map(|d| DecoderInfo {
+                name: d.name(),
+                brand: d.brand().to_string(),
+            })
+            .collect()
+    }
+
+    pub fn forced(&self) -> Option<&'static str> {
+        self.forced
+    }
+
+    /// Force all traffic through the named decoder, or go back to picking
+    /// the decoder by port with `None`
+    pub fn force(&mut self, name: Option<&str>) -> Result<(), RadarError> {
+        self.forced = match name {
+            None => None,
+            Some(name) => Some(
+                self.get(name)
+                    .ok_or_else(|| RadarError::Unsupported(format!("Decoder '{}'", name)))?
+                    .name(),
+            ),
+        };
+        Ok(())
+    }
+
+    fn get(&self, name: &str) -> Option<&dyn ProtocolDecoder> {
+        self.decoders
+            .iter()
+            .find(|d| d.name() == name)
+            .map(|d| d.as_ref())
+    }
+
+    /// The forced decoder, otherwise the one that handles `port`
+    pub fn select(&self, port: Option<u16>) -> Option<&dyn ProtocolDecoder> {
+        match self.forced {
+            Some(name) => self.get(name),
+            None => {
+                let port = port?;
+                self.decoders
+                    .iter()
+                    .find(|d| d.handles_port(port))
+                    .map(|d| d.as_ref())
+            }
+        }
+    }
+
+    /// Decode a captured packet; `None` when no decoder applies
+    pub fn decode(&self, port: Option<u16>, data: &[u8]) -> Option<DecodedMessage> {
+        let decoder = self.select(port)?;
+        let message = self.label_range(decoder.decode(data));
+        Some(DecodedMessage::new(None, "capture", self.in_bearing_unit(message)))
+    }
+
+    /// Decode a captured packet that may hold several messages, one
+    /// `DecodedMessage` per message; empty when no decoder applies
+    pub fn decode_batch(&self, port: Option<u16>, data: &[u8], direction: Direction) -> Vec<DecodedMessage> {
+        match self.select(port) {
+            Some(decoder) => decoder
+                .decode_all(data, direction)
+                .into_iter()
+                .map(|mut decoded| {
+                    decoded.message = self.in_bearing_unit(self.label_range(decoded.message));
+                    decoded
+                })
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn label_range(&self, mut message: serde_json::Value) -> serde_json::Value {
+        let meters = message.get("rangeMeters").and_then(|m| m.as_i64());
+        if let (Some(meters), Some(object)) = (meters, message.as_object_mut()) {
+            let label = format_range_in(meters as i32, self.range_units);
+            object.insert("formatted".to_string(), json!(label));
+        }
+        message
+    }
+
+    /// The message with the bearings at any depth in the bearing unit
+    fn in_bearing_unit(&self, mut message: serde_json::Value) -> serde_json::Value {
+        if self.bearing_unit != BearingUnit::Degrees {
+            convert_bearings(&mut message, self.bearing_unit);
+        }
+        message
+    }
+}
+
+fn convert_bearings(value: &mut serde_json::Value, unit: BearingUnit) {
+    match value {
+        serde_json::Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value.as_f64() {
+                    Some(degrees) if BEARING_FIELDS.contains(&key.as_str()) => {
+                        *value = json!(unit.from_degrees(degrees));
+                    }
+                    _ => convert_bearings(value, unit),
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(|v| convert_bearings(v, unit)),
+        _ => {}
+    }
+}
+
+impl Default for DecoderRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn forced_decoder_is_used_for_any_port() {
+        let mut registry = DecoderRegistry::new();
+        let data = [0xde, 0xad, 0xbe, 0xef];
+
+        // Port 1234 is nobody's, so auto detection finds nothing
+        assert!(registry.decode(Some(1234), &data).is_none());
+
+        registry.force(Some("navico")).unwrap();
+        assert_eq!(registry.forced(), Some("navico"));
+        let message = registry.decode(Some(1234), &data).unwrap();
+        assert_eq!(message.message["decoder"], "navico");
+        assert_eq!(message.message["length"], 4);
+
+        assert!(registry.force(Some("nonexistent")).is_err());
+        assert_eq!(registry.forced(), Some("navico"));
+
+        registry.force(None).unwrap();
+        assert!(registry.decode(Some(1234), &data).is_none());
+        assert!(registry.list().iter().any(|d| d.name == "navico"));
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn batched_navico_packet_decodes_to_each_report() {
+        let registry = DecoderRegistry::new();
+        let port = mayara_core::protocol::navico::INFO_PORT;
+
+        let mut status = vec![0u8; 18];
+        status[..3].copy_from_slice(&[0x01, 0xC4, 0x01]); // standby
+        let mut settings = vec![0u8; 18];
+        settings[..2].copy_from_slice(&[0x08, 0xC4]);
+        let batch = [status, settings].concat();
+
+        let messages = registry.decode_batch(Some(port), &batch, Direction::FromRadar);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message["length"], 18);
+        assert!(messages[0].message["parsed"].as_str().unwrap().contains("Standby"));
+        assert!(messages[1].message["parsed"].as_str().unwrap().contains("scan_speed"));
+
+        // The single packet decoder still sees the whole datagram
+        assert_eq!(registry.decode(Some(port), &batch).unwrap().message["length"], 36);
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn navico_settings_report_groups_the_image_controls() {
+        let port = mayara_core::protocol::navico::INFO_PORT;
+        let mut settings = vec![0u8; 18];
+        settings[..2].copy_from_slice(&[0x08, 0xC4]);
+        settings[3] = 2; // local interference rejection
+        settings[5] = 1; // sidelobe suppression auto
+        settings[9] = 80; // sidelobe suppression
+        settings[12] = 1; // noise rejection
+        settings[16] = 192; // main bang suppression
+
+        let message = DecoderRegistry::new().decode(Some(port), &settings).unwrap();
+        let controls = message.message["imageControls"].as_object().unwrap();
+        let mut keys: Vec<&str> = controls.keys().map(|k| k.as_str()).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "localInterferenceRejection",
+                "mainBangSuppression",
+                "noiseRejection",
+                "sidelobeSuppression",
+                "sidelobeSuppressionAuto"
+            ]
+        );
+        assert_eq!(controls["mainBangSuppression"], 192);
+        assert_eq!(controls["localInterferenceRejection"], 2);
+        assert_eq!(controls["noiseRejection"], 1);
+        assert_eq!(controls["sidelobeSuppression"], 80);
+        assert_eq!(controls["sidelobeSuppressionAuto"], true);
+
+        // The controls report carries the rest of the panel
+        let mut controls = vec![0u8; 99];
+        controls[..2].copy_from_slice(&[0x02, 0xC4]);
+        controls[38] = 1;
+        controls[42] = 2;
+        let message = DecoderRegistry::new().decode(Some(port), &controls).unwrap();
+        let image = &message.message["imageControls"];
+        assert_eq!(image["targetExpansion"], 1);
+        assert_eq!(image["targetBoost"], 2);
+        assert_eq!(image["interferenceRejection"], 0);
+        assert_eq!(image["interferenceAuto"], false);
+        assert_eq!(image["interference"], "off");
+
+        // Interference rejection in auto mode
+        controls[33] = 1;
+        controls[34] = 3;
+        let message = DecoderRegistry::new().decode(Some(port), &controls).unwrap();
+        let image = &message.message["imageControls"];
+        assert_eq!(image["interferenceRejection"], 3);
+        assert_eq!(image["interferenceAuto"], true);
+        assert_eq!(image["interference"], "auto");
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn navico_sea_auto_presets_are_labelled() {
+        let port = Some(mayara_core::protocol::navico::INFO_PORT);
+        let registry = DecoderRegistry::new();
+
+        let mut controls = vec![0u8; 99];
+        controls[..2].copy_from_slice(&[0x02, 0xC4]);
+        for (sea_auto, label) in [(0, "Manual"), (1, "Harbor"), (2, "Offshore"), (9, "Unknown")] {
+            controls[13] = sea_auto;
+            let message = registry.decode(port, &controls).unwrap().message;
+            assert_eq!(message["seaAutoMode"], label, "sea auto {}", sea_auto);
+        }
+
+        let mut settings = vec![0u8; 18];
+        settings[..2].copy_from_slice(&[0x08, 0xC4]);
+        for (sea_state, label) in [(0, "Calm"), (1, "Moderate"), (2, "Rough"), (5, "Unknown")] {
+            settings[2] = sea_state;
+            let message = registry.decode(port, &settings).unwrap().message;
+            assert_eq!(message["seaAutoPreset"], label, "sea state {}", sea_state);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn navico_range_is_decoded_in_meters_with_a_label() {
+        let port = Some(mayara_core::protocol::navico::INFO_PORT);
+        let mut controls = vec![0u8; 99];
+        controls[..2].copy_from_slice(&[0x02, 0xC4]);
+        controls[2..6].copy_from_slice(&18520i32.to_le_bytes());
+
+        let mut registry = DecoderRegistry::new();
+        let message = registry.decode(port, &controls).unwrap().message;
+        assert_eq!(message["rangeRaw"], 18520);
+        assert_eq!(message["rangeMeters"], 1852);
+        assert_eq!(message["formatted"], "1 nm");
+
+        // The label follows the unit preference
+        registry.set_range_units(RangeUnitPreference::Metric);
+        let messages = registry.decode_batch(port, &controls, Direction::FromRadar);
+        assert_eq!(messages[0].message["rangeMeters"], 1852);
+        assert_eq!(messages[0].message["formatted"], "1.85 km");
+    }
+
+    #[test]
+    #[cfg(feature = "garmin")]
+    fn garmin_range_report_decodes_to_meters() {
+        // Synthetic range report for 3 km
+        let data = [0x1e, 0x09, 0, 0, 4, 0, 0, 0, 0xb8, 0x0b, 0, 0];
+        let message = GarminDecoder.decode(&data);
+        assert_eq!(message["rangeMeters"], 3000);
+
+        let registry = DecoderRegistry::new();
+        let port = Some(mayara_core::protocol::garmin::REPORT_PORT);
+        let decoded = registry.decode_batch(port, &data, Direction::FromRadar);
+        assert_eq!(decoded[0].message["formatted"], "3 km");
+    }
+
+    #[test]
+    #[cfg(feature = "raymarine")]
+    fn raymarine_hd_status_report_decodes_power_and_range() {
+        use mayara_core::protocol::raymarine::HD_STATUS_REPORT;
+
+        // An HD open array transmitting at 1 nm, the fourth range of its table
+        let mut data = vec![0u8; 300];
+        data[..4].copy_from_slice(&HD_STATUS_REPORT.to_le_bytes());
+        for (i, range) in [125u32, 250, 500, 1000, 1500].iter().enumerate() {
+            data[4 + i * 4..8 + i * 4].copy_from_slice(&range.to_le_bytes());
+        }
+        data[180] = 0x01; // transmit
+        data[296] = 3;
+
+        let message = RaymarineDecoder.decode(&data);
+        assert_eq!(message["family"], "HD");
+        assert_eq!(message["power"], "transmit");
+        assert_eq!(message["rangeIndex"], 3);
+        assert_eq!(message["rangeMeters"], 1852);
+
+        // Standby, at the first range
+        data[180] = 0x00;
+        data[296] = 0;
+        let message = RaymarineDecoder.decode(&data);
+        assert_eq!(message["power"], "standby");
+        assert_eq!(message["rangeMeters"], 231);
+        assert!(message.get("error").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "raymarine")]
+    fn quantum_status_report_decodes_gain_color_gain_and_sea() {
+        use mayara_core::protocol::raymarine::QUANTUM_STATUS_REPORT;
+
+        // A Quantum status report in harbour mode (1), laid out as captured
+        let mut data = vec![0u8; 228];
+        data[..4].copy_from_slice(&QUANTUM_STATUS_REPORT.to_le_bytes());
+        data[4] = 0x01; // transmitting
+        data[21] = 1; // mode
+        data[30..38].copy_from_slice(&[0, 62, 1, 40, 0, 204, 0, 0]);
+
+        let message = RaymarineDecoder.decode(&data);
+        let quantum = &message["quantum"];
+        assert_eq!(quantum["mode"], 1);
+        assert_eq!(quantum["gain"], json!({ "raw": 62, "value": 62.0, "auto": false }));
+        assert_eq!(quantum["colorGain"], json!({ "raw": 40, "value": 40.0, "auto": true }));
+        assert_eq!(quantum["sea"], json!({ "raw": 204, "value": 80.0, "auto": false }));
+        assert!(message["parsed"].as_str().unwrap().contains("ParsedQuantumStatus"));
+
+        // A truncated report is an error without settings
+        let message = RaymarineDecoder.decode(&data[..100]);
+        assert!(message.get("error").is_some());
+        assert!(message.get("quantum").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "furuno")]
+    fn furuno_drs_settings_decode_gain_sea_and_rain() {
+        // Responses of a DRS4D-NXT to the state requests, as captured
+        let data = b"$N63,0,64,0,80,0\r\n$N64,1,35,50,0,0,0\r\n$N65,0,0,0,0,0,0\r\n$N69,2,0,0,60,300,0\r\n";
+
+        let message = FurunoDecoder.decode(data);
+        let settings = &message["settings"];
+        assert_eq!(settings["gain"], json!({ "raw": 64, "value": 64.0, "auto": false }));
+        assert_eq!(settings["sea"], json!({ "raw": 35, "value": 35.0, "auto": true }));
+        assert_eq!(settings["rain"], json!({ "raw": 0, "value": 0.0, "auto": false }));
+        assert!(message["parsed"].as_str().unwrap().contains("New 63 [0, 64, 0, 80, 0]"));
+
+        // Other responses carry no settings
+        let message = FurunoDecoder.decode(b"$N69,2,0,0,60,300,0\r\n");
+        assert!(message.get("settings").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn navico_installation_report_gives_the_overlay_alignment() {
+        use mayara_core::protocol::navico::REPORT_04_SIZE;
+
+        // Antenna mounted 2.5 degrees to port of the bow
+        let mut data = vec![0u8; REPORT_04_SIZE];
+        data[..2].copy_from_slice(&[0x04, 0xC4]);
+        data[6..8].copy_from_slice(&(-25i16).to_le_bytes());
+        data[10..12].copy_from_slice(&400u16.to_le_bytes());
+
+        let message = NavicoDecoder.decode(&data);
+        assert_eq!(
+            message["overlayAlignment"],
+            json!({ "bearingOffset": -2.5, "bearingOffsetRaw": -25 })
+        );
+
+        // Other reports carry no alignment
+        let mut settings = vec![0u8; 18];
+        settings[..2].copy_from_slice(&[0x08, 0xC4]);
+        assert!(NavicoDecoder.decode(&settings).get("overlayAlignment").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn report_behind_framing_bytes_is_found_by_scanning() {
+        let port = mayara_core::protocol::navico::INFO_PORT;
+        let mut settings = vec![0u8; 18];
+        settings[..2].copy_from_slice(&[0x08, 0xC4]);
+        let data = [vec![0xAA, 0x55, 0x00, 0x12], settings.clone()].concat();
+
+        let message = DecoderRegistry::new().decode(Some(port), &data).unwrap();
+        assert!(message.message["parsed"].as_str().unwrap().contains("scan_speed"));
+        assert_eq!(message.message["offset"], 4);
+        assert_eq!(message.message["length"], 22);
+
+        // Reports at the start carry no offset
+        let message = DecoderRegistry::new().decode(Some(port), &settings).unwrap();
+        assert!(message.message.get("offset").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn navico_target_report_decodes_to_a_list_of_targets() {
+        use mayara_core::protocol::navico::*;
+
+        let mut data = vec![REPORT_TARGETS, 0xC4, 2];
+        for (id, bearing) in [(1u16, 900u16), (2, 1800)] {
+            data.extend_from_slice(&id.to_le_bytes());
+            data.extend_from_slice(&bearing.to_le_bytes());
+            data.extend_from_slice(&5000u32.to_le_bytes());
+            data.extend_from_slice(&[0xFF; 4]);
+        }
+
+        let message = DecoderRegistry::new().decode(Some(INFO_PORT), &data).unwrap();
+        let targets = message.message["targets"].as_array().unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[1]["id"], 2);
+        assert_eq!(targets[1]["bearing"], 180.0);
+        assert_eq!(targets[1]["distance"], 500.0);
+        assert!(targets[1].get("speed").is_none());
+
+        let registry = DecoderRegistry::new().with_bearing_unit(BearingUnit::Mils);
+        let message = registry.decode(Some(INFO_PORT), &data).unwrap();
+        assert_eq!(message.message["targets"][1]["bearing"], 3200.0);
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn spoke_frame_decodes_to_one_message_per_spoke() {
+        use mayara_core::protocol::navico::*;
+
+        let mut frame = vec![0u8; FRAME_HEADER_SIZE];
+        for n in 0..3u16 {
+            let mut spoke = vec![0u8; SPOKE_LINE_SIZE];
+            spoke[0] = SPOKE_HEADER_SIZE as u8;
+            spoke[1] = 0x02; // valid status
+            spoke[8..10].copy_from_slice(&(n * 2 * 10).to_le_bytes()); // 4096 based angle
+            spoke[12..14].copy_from_slice(&0xffffu16.to_le_bytes()); // no range
+            spoke[6..8].copy_from_slice(&0x80u16.to_le_bytes());
+            frame.extend_from_slice(&spoke);
+        }
+
+        let messages = NavicoDecoder.decode_all(&frame, Direction::FromRadar);
+        assert_eq!(messages.len(), 3);
+        for (n, message) in messages.iter().enumerate() {
+            assert_eq!(message.message["spoke"], n);
+            assert_eq!(message.message["angle"], n * 10);
+            assert_eq!(message.message["direction"], "fromRadar");
+        }
+
+        // Sent to the radar the same bytes are not spokes
+        assert_eq!(NavicoDecoder.decode_all(&frame, Direction::ToRadar).len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn zoomed_spoke_range_gives_its_meters_per_cell() {
+        use mayara_core::protocol::navico::*;
+
+        let mut frame = vec![0u8; FRAME_HEADER_SIZE];
+        let mut spoke = vec![0u8; SPOKE_LINE_SIZE];
+        spoke[0] = SPOKE_HEADER_SIZE as u8;
+        spoke[1] = 0x02;
+        spoke[6..8].copy_from_slice(&0x400u16.to_le_bytes()); // twice the small range
+        spoke[12..14].copy_from_slice(&1852u16.to_le_bytes());
+        frame.extend_from_slice(&spoke);
+
+        let messages = NavicoDecoder.decode_all(&frame, Direction::FromRadar);
+        let message = &messages[0].message;
+        assert_eq!(message["range"], 3704);
+        assert_eq!(message["rangeScale"], 2.0);
+        assert_eq!(message["metersPerCell"], 3704.0 / 1024.0);
+
+        // Unscaled quarter meters
+        spoke[6..8].copy_from_slice(&0x80u16.to_le_bytes());
+        spoke[12..14].copy_from_slice(&(1852u16 * 4).to_le_bytes());
+        let frame = [vec![0u8; FRAME_HEADER_SIZE], spoke].concat();
+        let messages = NavicoDecoder.decode_all(&frame, Direction::FromRadar);
+        assert_eq!(messages[0].message["rangeScale"], 0.25);
+        assert_eq!(messages[0].message["metersPerCell"], 1852.0 / 1024.0);
+    }
+}
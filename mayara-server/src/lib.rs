@@ -113,19 +113,31 @@ use std::{
 use tokio::sync::{broadcast, mpsc};
 use tokio_graceful_shutdown::{SubsystemBuilder, SubsystemHandle};
 
+pub mod alarm_log;
+pub mod alarm_snapshot;
+pub mod auth;
 pub mod brand;
+pub mod capability_export;
 pub mod config;
 pub mod control_factory;
 pub mod core_locator;
+pub mod decode_ring;
+pub mod decoders;
 pub mod locator;
 pub mod navdata;
 pub mod network;
+pub mod openapi;
+pub mod pcap;
+pub mod preferences;
 pub mod protos;
 pub mod radar;
+pub mod radar_document;
 pub mod recording;
 pub mod settings;
+pub mod settings_history;
 pub mod storage;
 pub mod tokio_io;
+pub mod unknown_log;
 pub mod util;
 use rust_embed::RustEmbed;
 use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -197,6 +209,107 @@ pub struct Cli {
     #[arg(long, default_value_t = false)]
     pub stationary: bool,
 
+    /// Initial interval between discovery attempts while no radar is found, in seconds
+    #[arg(long, default_value_t = 2)]
+    pub discovery_interval: u64,
+
+    /// Number of discovery attempts after which a warning is logged
+    #[arg(long, default_value_t = 10)]
+    pub discovery_attempts: u32,
+
+    /// Seconds without spokes or reports after which a radar is marked
+    /// offline and searched for again, 0 disables
+    #[arg(long, default_value_t = 10)]
+    pub offline_timeout: u64,
+
+    /// Seconds without a message from a control WebSocket client after
+    /// which the server closes the session, 0 keeps idle sessions open
+    #[arg(long, default_value_t = 1800)]
+    pub control_idle_timeout: u64,
+
+    /// Bearer token required by the control endpoints; without it they are open
+    #[arg(long)]
+    pub control_token: Option<String>,
+
+    /// Smooth bursty radars: release spokes at a steady rate, holding each
+    /// for at most this many milliseconds
+    #[arg(long)]
+    pub spoke_smoothing: Option<u64>,
+
+    /// Fill gaps of at most this many missing azimuths with interpolated
+    /// spokes, 0 disables
+    #[arg(long, default_value_t = 0)]
+    pub interpolate_gaps: u32,
+
+    /// Subtract a noise floor learned per azimuth and distance over about
+    /// this many rotations from the spokes, 0 disables
+    #[arg(long, default_value_t = 0)]
+    pub noise_floor: u32,
+
+    /// Limit the spokes processed per second, over all radars together;
+    /// beyond it every radar drops an equal share of its spokes
+    #[arg(long)]
+    pub max_spoke_rate: Option<u32>,
+
+    /// Largest capture accepted for replay over HTTP, in megabytes
+    #[arg(long, default_value_t = pcap::DEFAULT_MAX_PCAP_SIZE_MB)]
+    pub max_pcap_size: u64,
+
+    /// Minimum time between control commands sent to a radar, in
+    /// milliseconds; commands for the same control are merged meanwhile.
+    /// 0 disables the throttle
+    #[arg(long, default_value_t = 100)]
+    pub command_interval: u64,
+
+    /// Number of recently decoded messages kept for download, 0 disables
+    #[arg(long, default_value_t = decode_ring::DEFAULT_DECODE_RING_SIZE)]
+    pub decode_ring: usize,
+
+    /// Print every decoded message as a JSON line on stdout and do not start
+    /// the web server. Optionally only those matching a comma separated list
+    /// of brands and kinds (settings, control), e.g. `navico,control`
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "",
+        value_name = "FILTER",
+        conflicts_with = "output"
+    )]
+    pub dump_decoded: Option<decode_ring::DumpFilter>,
+
+    /// Write the next rotation as a PNG and as raw spokes to this directory
+    /// whenever a guard zone alarm fires
+    #[arg(long, value_name = "DIR")]
+    pub alarm_snapshots: Option<std::path::PathBuf>,
+
+    /// Append every packet the decoders could not decode, and every unknown
+    /// report of a radar, to this file, one JSON line with the raw bytes
+    /// each, to submit for decoder coverage
+    #[arg(long, value_name = "FILE")]
+    pub log_unknown: Option<std::path::PathBuf>,
+
+    /// Size in megabytes after which `--log-unknown` stops writing
+    #[arg(long, default_value_t = unknown_log::DEFAULT_UNKNOWN_LOG_SIZE_MB)]
+    pub log_unknown_size: u64,
+
+    /// Write the capability document of the `--export-model` radar model to
+    /// this file and exit, for clients that run offline
+    #[arg(long, value_name = "FILE", requires = "export_model")]
+    pub export_capabilities: Option<std::path::PathBuf>,
+
+    /// Radar model for `--export-capabilities`, as <BRAND>:<MODEL>, e.g. navico:HALO20+
+    #[arg(long, value_name = "BRAND:MODEL", requires = "export_capabilities")]
+    pub export_model: Option<String>,
+
+    /// Unit for bearings reported by the API: degrees or mils (6400 per revolution)
+    #[arg(long, default_value = "degrees")]
+    pub bearing_unit: mayara_core::units::BearingUnit,
+
+    /// Units for range labels: auto, nautical, metric or statute.
+    /// Can be changed at runtime through the API.
+    #[arg(long, default_value = "auto")]
+    pub range_units: radar::range::RangeUnitPreference,
+
     /// Multi-radar mode keeps locators running even when one radar is found
     #[arg(long, default_value_t = false)]
     pub multiple_radar: bool,
@@ -207,6 +320,12 @@ pub struct Cli {
     /// Default is now the unified core locator from mayara-core.
     #[arg(long, default_value_t = false)]
     pub legacy_locator: bool,
+
+    /// Debug builds only: every spokes WebSocket sleeps this many
+    /// milliseconds after each message, so it falls behind the radar and
+    /// skips messages, to test how clients cope
+    #[arg(long, hide = true, value_name = "MS")]
+    pub debug_spoke_lag: Option<u64>,
 }
 
 #[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -328,6 +447,20 @@ pub struct SessionInner {
     pub radars: Option<SharedRadars>,
     /// Locator status from core (updated by CoreLocatorAdapter)
     pub locator_status: mayara_core::LocatorStatus,
+    /// Recently decoded messages, see `decode_ring`
+    pub decode_ring: decode_ring::SharedDecodeRing,
+    /// Guard zone enter and exit events, see `alarm_log`
+    pub alarm_log: alarm_log::SharedAlarmLog,
+    /// Snapshots taken when an alarm fires, see `alarm_snapshot`
+    pub alarm_snapshots: Option<alarm_snapshot::AlarmSnapshots>,
+    /// Decoders for captured traffic, see `decoders`
+    pub decoders: decoders::SharedDecoderRegistry,
+    /// Packets the decoders did not understand, see `unknown_log`
+    pub unknown_log: Option<unknown_log::SharedUnknownLog>,
+    /// Units for range labels, unless a radar has its own preference
+    pub range_units: radar::range::RangeUnitPreference,
+    /// Spoke rate limit shared by all radars, see `radar::throttle`
+    pub spoke_budget: Option<radar::throttle::SharedSpokeBudget>,
 }
 
 #[derive(Clone)]
@@ -356,11 +489,49 @@ impl Session {
         Self::new_base(Cli::parse_from(["my_program"]))
     }
 
-    fn new_base(args: Cli) -> Self {
+    /// A session without radars that does not start anything, for tools and tests
+    pub fn new_base(args: Cli) -> Self {
         let (tx_interface_request, _) = broadcast::channel(10);
+        let decode_ring = decode_ring::DecodeRing::new_shared(args.decode_ring);
+        if let Some(filter) = &args.dump_decoded {
+            decode_ring
+                .lock()
+                .unwrap()
+                .set_dump(filter.clone(), Box::new(std::io::stdout()));
+        }
+        let unknown_log = args.log_unknown.as_ref().and_then(|path| {
+            match unknown_log::UnknownLog::open_shared(path, args.log_unknown_size) {
+                Ok(log) => Some(log),
+                Err(e) => {
+                    log::warn!("Not logging unknown packets to '{}': {}", path.display(), e);
+                    None
+                }
+            }
+        });
+        let range_units = args.range_units;
+        let bearing_unit = args.bearing_unit;
+        let alarm_snapshots = args
+            .alarm_snapshots
+            .as_ref()
+            .map(|dir| alarm_snapshot::AlarmSnapshots::new(dir, range_units));
+        let spoke_budget = args
+            .max_spoke_rate
+            .filter(|&rate| rate > 0)
+            .map(radar::throttle::SpokeBudget::new_shared);
         let selfref = Session {
             inner: Arc::new(RwLock::new(SessionInner {
                 args,
+                decode_ring,
+                alarm_log: alarm_log::AlarmLog::open_default(),
+                alarm_snapshots,
+                decoders: Arc::new(RwLock::new(
+                    decoders::DecoderRegistry::new()
+                        .with_range_units(range_units)
+                        .with_bearing_unit(bearing_unit),
+                )),
+                unknown_log,
+                range_units,
+                spoke_budget,
                 tx_interface_request,
                 radars: None,
                 locator_status: mayara_core::LocatorStatus::default(),
@@ -18,7 +18,9 @@
 // use network_interface::{NetworkInterface, NetworkInterfaceConfig};
 use serde::Serialize;
 // use tokio::sync::{broadcast, mpsc};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::{interval, MissedTickBehavior};
 // use tokio::{net::UdpSocket, sync::mpsc::Sender, task::JoinSet, time::sleep};
 // use tokio::net::UdpSocket;
 use tokio_graceful_shutdown::SubsystemHandle;
@@ -349,15 +351,21 @@ impl Locator {
         log::info!("Starting locator with CoreLocatorAdapter");
 
         let (discovery_tx, mut discovery_rx) = mpsc::channel(32);
+        let (forget_tx, forget_rx) = mpsc::unbounded_channel();
         let radars = self.radars.clone();
         let session = self.session.clone();
 
         // Spawn the core locator subsystem
         let session_for_locator = session.clone();
         subsys.start(SubsystemBuilder::new("CoreLocator", move |s| {
-            create_locator_subsystem(session_for_locator, discovery_tx, s)
+            create_locator_subsystem(session_for_locator, discovery_tx, forget_rx, s)
         }));
 
+        // Radars that go silent are marked offline and searched for again
+        let offline_timeout = Duration::from_secs(session.read().unwrap().args.offline_timeout);
+        let mut liveness_timer = interval(Duration::from_secs(1));
+        liveness_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
         // Process discoveries from the core locator
         loop {
             tokio::select! {
@@ -365,6 +373,11 @@ impl Locator {
                     log::info!("Locator shutdown requested");
                     break;
                 }
+                _ = liveness_timer.tick(), if !offline_timeout.is_zero() => {
+                    for info in radars.check_liveness(offline_timeout) {
+                        let _ = forget_tx.send(info.addr.ip().to_string());
+                    }
+                }
                 msg = discovery_rx.recv() => {
                     match msg {
                         Some(LocatorMessage::RadarDiscovered(discovery)) => {
@@ -10,7 +10,7 @@ use web::Web;
 
 mod web;
 
-use mayara_server::{network, Cli, Session, VERSION};
+use mayara_server::{capability_export, network, Cli, Session, VERSION};
 
 fn main() -> Result<()> {
     // Build tokio runtime with larger stack size for worker threads
@@ -35,6 +35,15 @@ async fn async_main() -> Result<()> {
         .filter_module("polling", log::LevelFilter::Info)
         .init();
 
+    if let (Some(path), Some(model)) = (&args.export_capabilities, &args.export_model) {
+        let model = capability_export::find_model(model).map_err(|e| miette::miette!(e))?;
+        capability_export::CapabilityExport::for_model(model)
+            .write(path)
+            .map_err(|e| miette::miette!("Cannot write {}: {}", path.display(), e))?;
+        info!("Capabilities of {} written to {}", model.display_name, path.display());
+        return Ok(());
+    }
+
     network::set_replay(args.replay);
 
     info!("Mayara {} loglevel {}", VERSION, log_level);
@@ -56,6 +65,9 @@ async fn async_main() -> Result<()> {
     if args.output {
         warn!("Output mode activated; 'protobuf' formatted RadarMessage sent to stdout");
     }
+    if args.dump_decoded.is_some() {
+        warn!("Dump mode activated; decoded messages are sent to stdout, no webserver");
+    }
     if args.nmea0183 {
         warn!(
             "NMEA0183 mode activated; will load GPS position, heading and date/time from {}",
@@ -66,9 +78,12 @@ async fn async_main() -> Result<()> {
     }
 
     Toplevel::new(|s| async move {
+        let dump_decoded = args.dump_decoded.is_some();
         let session = Session::new(&s, args).await;
-        let web = Web::new(session.clone());
-        s.start(SubsystemBuilder::new("Webserver", move |a| web.run(a)));
+        if !dump_decoded {
+            let web = Web::new(session.clone());
+            s.start(SubsystemBuilder::new("Webserver", move |a| web.run(a)));
+        }
     })
     .catch_signals()
     .handle_shutdown_requests(Duration::from_millis(5000))
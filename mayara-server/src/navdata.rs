@@ -17,6 +17,8 @@ use tokio::{io::AsyncWriteExt, net::TcpStream};
 use tokio::{io::BufReader, sync::mpsc::Receiver};
 use tokio_graceful_shutdown::SubsystemHandle;
 
+use mayara_core::geo::TargetPosition;
+
 use crate::{
     radar::{GeoPosition, RadarError},
     Session,
@@ -29,7 +31,7 @@ static POSITION_LON: AtomicF64 = AtomicF64::new(f64::NAN);
 static COG: AtomicF64 = AtomicF64::new(f64::NAN);
 static SOG: AtomicF64 = AtomicF64::new(f64::NAN);
 
-pub(crate) fn get_heading_true() -> Option<f64> {
+pub fn get_heading_true() -> Option<f64> {
     let heading = HEADING_TRUE.load(Ordering::Acquire);
     if !heading.is_nan() {
         return Some(heading);
@@ -76,7 +78,27 @@ pub(crate) fn set_position(lat: Option<f64>, lon: Option<f64>) {
     }
 }
 
-pub(crate) fn get_cog() -> Option<f64> {
+/// Position of a target seen at `bearing` (degrees from the bow) and
+/// `distance` (meters), on the chart when own-ship position and heading are
+/// known and relative to the radar otherwise
+pub fn locate_target(bearing: f64, distance: f64) -> TargetPosition {
+    let (own_position, heading) = own_ship();
+    TargetPosition::locate(bearing, distance, own_position, heading)
+}
+
+/// Own-ship position (lat, lon) and heading in degrees true, when known
+pub(crate) fn own_ship() -> (Option<(f64, f64)>, Option<f64>) {
+    let own_position = POSITION_VALID.load(Ordering::Acquire).then(|| {
+        (
+            POSITION_LAT.load(Ordering::Acquire),
+            POSITION_LON.load(Ordering::Acquire),
+        )
+    });
+    let heading = get_heading_true().map(|h| h.to_degrees());
+    (own_position, heading)
+}
+
+pub fn get_cog() -> Option<f64> {
     let cog = COG.load(Ordering::Acquire);
     if !cog.is_nan() {
         return Some(cog);
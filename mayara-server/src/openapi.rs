@@ -0,0 +1,834 @@
+//! OpenAPI description of the REST API
+//!
+//! The document is written by hand and served at `/v2/api/openapi.json`, so
+//! integrators can generate typed bindings for the control, status and
+//! capabilities endpoints. Keep it in sync when adding or changing endpoints.
+
+use serde_json::{json, Map, Value};
+
+/// Version of the OpenAPI specification the document follows
+pub const OPENAPI_VERSION: &str = "3.0.3";
+
+fn radar_id_param() -> Value {
+    json!({
+        "name": "radar_id",
+        "in": "path",
+        "required": true,
+        "schema": { "type": "string" },
+        "example": "radar-1"
+    })
+}
+
+fn json_response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": {
+            "application/json": {
+                "schema": { "$ref": format!("#/components/schemas/{}", schema) }
+            }
+        }
+    })
+}
+
+fn text_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "text/plain": { "schema": { "type": "string" } } }
+    })
+}
+
+fn paths() -> Value {
+    let mut paths = Map::new();
+    paths.insert(
+        "/v2/api/radars".to_string(),
+        json!({
+            "get": {
+                "summary": "List the discovered radars",
+                "responses": {
+                    "200": {
+                        "description": "Radars by id",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "object",
+                                    "additionalProperties": { "$ref": "#/components/schemas/Radar" }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/capabilities".to_string(),
+        json!({
+            "get": {
+                "summary": "Get the capabilities of a radar: model, controls, constraints and the intensity legend (bit depth and recommended colormap)",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": { "description": "Radar capabilities", "content": { "application/json": { "schema": { "type": "object" } } } },
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/capabilities/export".to_string(),
+        json!({
+            "get": {
+                "summary": "Download the capabilities of a radar as a file, for clients that run offline",
+                "description": "The capabilities document with `rangeTable`, the ranges of the radar as formatted ranges. The same file is written for a model of the built-in database by `--export-capabilities <FILE> --export-model <BRAND>:<MODEL>`.",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": { "description": "Radar capabilities with the range table", "content": { "application/json": { "schema": { "type": "object" } } } },
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/state".to_string(),
+        json!({
+            "get": {
+                "summary": "Get the current value of all controls of a radar",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": json_response("Radar state", "RadarState"),
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/controls/{control_id}".to_string(),
+        json!({
+            "put": {
+                "summary": "Set a control value",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [
+                    radar_id_param(),
+                    {
+                        "name": "control_id",
+                        "in": "path",
+                        "required": true,
+                        "schema": { "type": "string" },
+                        "example": "gain"
+                    }
+                ],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/SetControlRequest" }
+                        }
+                    }
+                },
+                "responses": {
+                    "401": text_response("Missing or invalid control token"),
+                    "200": { "description": "Control value sent to the radar" },
+                    "400": text_response("Unknown control, or the radar rejected the value (see ControlError)"),
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/control".to_string(),
+        json!({
+            "get": {
+                "summary": "WebSocket carrying ControlValue messages in both directions; the server also pushes the fields of the settings reports that changed, as decoded from the radar, e.g. {\"settingsDiff\":{\"gain\":{\"auto\":false,\"value\":55}}}",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "401": text_response("Missing or invalid control token"),
+                    "101": { "description": "Switching to the WebSocket protocol" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/status".to_string(),
+        json!({
+            "get": {
+                "summary": "WebSocket pushing the settings and status of the radar as JSON; the first message has all values, later ones only those that changed, e.g. {\"gain\":55}",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "101": { "description": "Switching to the WebSocket protocol" },
+                    "404": { "description": "No such radar" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/spokes".to_string(),
+        json!({
+            "get": {
+                "summary": "WebSocket carrying RadarMessage protobuf spokes",
+                "parameters": [
+                    radar_id_param(),
+                    {
+                        "name": "raster",
+                        "in": "query",
+                        "required": false,
+                        "description": "Send one cartesian image per rotation of this many pixels square (64-2048) instead of spokes",
+                        "schema": { "type": "integer", "minimum": 64, "maximum": 2048 }
+                    },
+                    { "name": "headingLine", "in": "query", "required": false, "description": "Draw a line from the center to the bow into the raster images", "schema": { "type": "boolean", "default": false } },
+                    { "name": "bearingMarkers", "in": "query", "required": false, "description": "Draw a marker at the edge of the raster images every this many degrees, true bearings when the heading is known", "schema": { "type": "integer", "minimum": 1 } },
+                    { "name": "bearingStart", "in": "query", "required": false, "description": "Only send spokes from this bearing relative to the bow, in the configured bearing unit", "schema": { "type": "number" } },
+                    { "name": "bearingEnd", "in": "query", "required": false, "description": "Only send spokes up to this bearing; below bearingStart the window wraps through the bow", "schema": { "type": "number" } },
+                    { "name": "minRadius", "in": "query", "required": false, "description": "Zero cells closer than this many meters", "schema": { "type": "number" } },
+                    { "name": "maxRadius", "in": "query", "required": false, "description": "Cut spokes off at this many meters", "schema": { "type": "number" } }
+                ],
+                "responses": {
+                    "101": { "description": "Switching to the WebSocket protocol" },
+                    "503": { "description": "The radar is initializing, it has not reported its spoke geometry yet" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/dualRange/composite".to_string(),
+        json!({
+            "get": {
+                "summary": "WebSocket with one cartesian image per rotation that combines both channels of a dual range radar, the short range over the inner part of the long range",
+                "parameters": [
+                    radar_id_param(),
+                    { "name": "size", "in": "query", "required": false, "description": "Pixels square of the image (64-2048)", "schema": { "type": "integer", "minimum": 64, "maximum": 2048, "default": 512 } }
+                ],
+                "responses": {
+                    "101": { "description": "Switching to the WebSocket protocol" },
+                    "404": { "description": "No such radar, or a radar with a single range channel" },
+                    "503": { "description": "A channel is initializing, it has not reported its spoke geometry yet" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/spokes/events".to_string(),
+        json!({
+            "get": {
+                "summary": "Spokes as server-sent events, for clients that cannot use WebSockets",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": { "description": "`spoke` events with a base64 RadarMessage protobuf each, and `status` events with the `power` or `link` value as JSON", "content": { "text/event-stream": { "schema": { "type": "string" } } } },
+                    "404": { "description": "No such radar" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/snapshot".to_string(),
+        json!({
+            "get": {
+                "summary": "Capture the next full rotation as one RadarMessage, one spoke per azimuth, or as a PNG image",
+                "parameters": [
+                    radar_id_param(),
+                    { "name": "format", "in": "query", "required": false, "schema": { "type": "string", "enum": ["protobuf", "png"] } },
+                    { "name": "size", "in": "query", "required": false, "description": "PNG width and height in pixels", "schema": { "type": "integer", "default": 512 } }
+                ],
+                "responses": {
+                    "200": { "description": "RadarMessage protobuf, or a head up PNG with range rings in the radar's range units and a north arrow when the heading is known", "content": { "application/x-protobuf": { "schema": { "type": "string", "format": "binary" } }, "image/png": { "schema": { "type": "string", "format": "binary" } } } },
+                    "404": text_response("No such radar"),
+                    "504": text_response("No complete rotation received in time")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/alarms".to_string(),
+        json!({
+            "get": {
+                "summary": "Recent guard zone enter and exit events, oldest first; kept across restarts",
+                "parameters": [
+                    radar_id_param(),
+                    { "name": "limit", "in": "query", "required": false, "description": "Return at most this many of the most recent events", "schema": { "type": "integer", "default": 1000 } }
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Alarm events",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "timestamp": { "type": "integer", "description": "Milliseconds since the epoch" },
+                                            "radar": { "type": "string" },
+                                            "zoneId": { "type": "integer" },
+                                            "kind": { "type": "string", "enum": ["enter", "exit"] },
+                                            "bearing": { "type": "number", "description": "Relative to the bow, in the configured bearing unit" },
+                                            "distance": { "type": "number", "description": "Meters" },
+                                            "latitude": { "type": "number", "description": "WGS84, only when own-ship position and heading were known" },
+                                            "longitude": { "type": "number", "description": "WGS84, only when own-ship position and heading were known" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/settings/history".to_string(),
+        json!({
+            "get": {
+                "summary": "Recent setting changes reported by the radar, oldest first; kept in memory",
+                "description": "Derived from the settings reports, so changes made on another display are included. The first value of a setting after startup is not a change.",
+                "parameters": [
+                    radar_id_param(),
+                    { "name": "setting", "in": "query", "required": false, "description": "Only changes of this setting, e.g. gain", "schema": { "type": "string" } },
+                    { "name": "limit", "in": "query", "required": false, "description": "Return at most this many of the most recent changes", "schema": { "type": "integer", "default": 200 } }
+                ],
+                "responses": {
+                    "200": {
+                        "description": "Setting changes",
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "type": "array",
+                                    "items": {
+                                        "type": "object",
+                                        "properties": {
+                                            "timestamp": { "type": "integer", "description": "Milliseconds since the epoch" },
+                                            "setting": { "type": "string" },
+                                            "old": { "description": "Value before the change, as in the settings reports" },
+                                            "new": { "description": "Value after the change" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "404": { "description": "No such radar" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/testPattern".to_string(),
+        json!({
+            "post": {
+                "summary": "Send one revolution of the rendering test pattern",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "401": text_response("Missing or invalid control token"),
+                    "204": { "description": "Test pattern sent" },
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/targetScript".to_string(),
+        json!({
+            "post": {
+                "summary": "Play a timeline of fake targets, one revolution per second",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [radar_id_param()],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/TargetScript" }
+                        }
+                    }
+                },
+                "responses": {
+                    "401": text_response("Missing or invalid control token"),
+                    "202": { "description": "Target script started" },
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/interfaces".to_string(),
+        json!({
+            "get": {
+                "summary": "List the network interfaces and the radars found on them",
+                "responses": {
+                    "200": { "description": "Interfaces", "content": { "application/json": { "schema": { "type": "object" } } } }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/debug/decoded".to_string(),
+        json!({
+            "get": {
+                "summary": "Download the recently decoded settings reports and control values as NDJSON",
+                "parameters": [{
+                    "name": "seconds",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "integer" },
+                    "description": "Only messages from the last this many seconds"
+                }],
+                "responses": {
+                    "200": { "description": "One DecodedMessage per line, oldest first", "content": { "application/x-ndjson": { "schema": { "type": "string" } } } }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/debug/decoded/bookmark".to_string(),
+        json!({
+            "post": {
+                "summary": "Bookmark this moment and export the decoded messages around it",
+                "description": "Responds after `seconds` have passed, with the messages from `seconds` before to `seconds` after the request. `seconds` is capped at 60.",
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "type": "object",
+                                "required": ["label"],
+                                "properties": {
+                                    "label": { "type": "string", "example": "ghost target at 045" },
+                                    "seconds": { "type": "integer", "default": 10, "maximum": 60 }
+                                }
+                            }
+                        }
+                    }
+                },
+                "responses": {
+                    "200": { "description": "A bookmark line with the label, then one DecodedMessage per line, oldest first", "content": { "application/x-ndjson": { "schema": { "type": "string" } } } },
+                    "401": text_response("Missing or invalid control token")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/debug/replay".to_string(),
+        json!({
+            "post": {
+                "summary": "Replay an uploaded pcap into this server as if the radar was live",
+                "description": "Only available when the server runs with --replay. The UDP packets are sent to the local radar receivers with their captured timing divided by `speed`.",
+                "security": [{ "bearerAuth": [] }],
+                "parameters": [
+                    { "name": "speed", "in": "query", "required": false, "schema": { "type": "number", "default": 1.0 } }
+                ],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/vnd.tcpdump.pcap": { "schema": { "type": "string", "format": "binary" } } }
+                },
+                "responses": {
+                    "202": { "description": "Replay started, with the number of packets and the duration in milliseconds" },
+                    "400": { "description": "Not a pcap file, or an invalid speed" },
+                    "401": text_response("Missing or invalid control token"),
+                    "403": { "description": "The server does not run with --replay" },
+                    "413": { "description": "The capture is larger than --max-pcap-size megabytes" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/rangeUnits".to_string(),
+        json!({
+            "get": {
+                "summary": "Get the global units of range labels",
+                "responses": {
+                    "200": { "description": "Range units", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/RangeUnits" } } } }
+                }
+            },
+            "put": {
+                "summary": "Set the global units of range labels, effective immediately",
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": { "$ref": "#/components/schemas/RangeUnits" } } }
+                },
+                "responses": {
+                    "204": { "description": "Range units changed" },
+                    "400": text_response("Unknown range unit"),
+                    "401": text_response("Missing or invalid control token")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/rangeUnits".to_string(),
+        json!({
+            "get": {
+                "summary": "Get the radar's own range units, null when it follows the global units",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": { "description": "Range units", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/RangeUnits" } } } },
+                    "404": text_response("No such radar")
+                }
+            },
+            "put": {
+                "summary": "Set the radar's own range units, null to follow the global units",
+                "parameters": [radar_id_param()],
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": { "$ref": "#/components/schemas/RangeUnits" } } }
+                },
+                "responses": {
+                    "204": { "description": "Range units changed" },
+                    "400": text_response("Unknown range unit"),
+                    "401": text_response("Missing or invalid control token"),
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/document".to_string(),
+        json!({
+            "get": {
+                "summary": "Everything the server decoded about a radar, as one document",
+                "description": "Built from the latest reports, nothing is sent to the radar: `power`; `settings`, every control with its `value` and, when reported, `auto`, `enabled` and `description`; `range` with `raw` (the wire value), `meters` and `formatted`; `rotation` with `periodMs` and `rpm`; `radar` with `brand`, `model`, `antenna`, `serial` and `firmware`; and `channels`, the `power` and `range` of each channel of a dual range radar.",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": { "description": "The radar document", "content": { "application/json": { "schema": { "type": "object" } } } },
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/azimuthOffset".to_string(),
+        json!({
+            "get": {
+                "summary": "Get the software azimuth offset of the radar",
+                "parameters": [radar_id_param()],
+                "responses": {
+                    "200": { "description": "Offset in degrees clockwise, 0..360", "content": { "application/json": { "schema": { "type": "object", "properties": { "degrees": { "type": "number" } } } } } },
+                    "404": text_response("No such radar")
+                }
+            },
+            "put": {
+                "summary": "Turn the spokes of the radar by this many degrees in software, without sending anything to the radar; lasts until restart",
+                "parameters": [radar_id_param()],
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/json": { "schema": { "type": "object", "required": ["degrees"], "properties": { "degrees": { "type": "number" } } } } }
+                },
+                "responses": {
+                    "204": { "description": "Offset changed" },
+                    "401": text_response("Missing or invalid control token"),
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/radars/{radar_id}/rangePresets".to_string(),
+        json!({
+            "get": {
+                "summary": "Common ranges up to the radar's largest range, for quick select buttons",
+                "description": "Presets such as 1/8, 1/4, 1/2, 1, 2, 3 and 6 nm (or 250 m to 100 km with metric units), each snapped to the nearest range the radar supports and labelled in the radar's range units, or in `units`. With `compact` the labels are short tokens such as `0.25NM` or `1.5k`. Presets without a supported range close to them are left out.",
+                "parameters": [
+                    radar_id_param(),
+                    { "name": "compact", "in": "query", "required": false, "schema": { "type": "boolean", "default": false } },
+                    { "name": "units", "in": "query", "required": false, "schema": { "type": "string", "enum": ["auto", "nautical", "metric", "statute"] } }
+                ],
+                "responses": {
+                    "200": { "description": "Presets in increasing order", "content": { "application/json": { "schema": { "type": "array", "items": { "$ref": "#/components/schemas/FormattedRange" } } } } },
+                    "404": text_response("No such radar")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/decoders".to_string(),
+        json!({
+            "get": {
+                "summary": "List the protocol decoders and the forced decoder, if any",
+                "responses": {
+                    "200": { "description": "Decoders", "content": { "application/json": { "schema": { "type": "object" } } } }
+                }
+            },
+            "put": {
+                "summary": "Force all captured traffic through one decoder, or `null` to pick by port",
+                "security": [{ "bearerAuth": [] }],
+                "requestBody": {
+                    "required": true,
+                    "content": {
+                        "application/json": {
+                            "schema": {
+                                "type": "object",
+                                "properties": { "forced": { "type": "string", "nullable": true } }
+                            }
+                        }
+                    }
+                },
+                "responses": {
+                    "401": text_response("Missing or invalid control token"),
+                    "204": { "description": "Decoder selection changed" },
+                    "400": text_response("No such decoder")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/decoders/decode".to_string(),
+        json!({
+            "post": {
+                "summary": "Decode one captured packet sent as the raw request body",
+                "parameters": [{
+                    "name": "port",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "integer" },
+                    "description": "Port the packet was captured on, selects the decoder unless one is forced"
+                }, {
+                    "name": "direction",
+                    "in": "query",
+                    "required": false,
+                    "schema": { "type": "string", "enum": ["fromRadar", "toRadar"], "default": "fromRadar" },
+                    "description": "Which way the packet went; Navico spoke frames from the radar decode to one entry per spoke"
+                }],
+                "requestBody": {
+                    "required": true,
+                    "content": { "application/octet-stream": { "schema": { "type": "string", "format": "binary" } } }
+                },
+                "responses": {
+                    "200": { "description": "An array with one entry per message in the packet; packets that batch several reports give several. A Navico report found behind framing bytes has the `offset` it starts at. Navico controls and settings reports add their fine image controls, such as main bang and sidelobe suppression, under `imageControls`. Navico installation reports give the bearing alignment under `overlayAlignment`. Navico spokes give the `rangeScale` of their header and the `metersPerCell` it results in. Furuno gain, sea and rain responses are also given raw and on the 0-100 control scale under `settings`", "content": { "application/json": { "schema": { "type": "array", "items": { "type": "object" } } } } },
+                    "404": text_response("No decoder for this port")
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/v2/api/openapi.json".to_string(),
+        json!({
+            "get": {
+                "summary": "This document",
+                "responses": {
+                    "200": { "description": "OpenAPI document", "content": { "application/json": { "schema": { "type": "object" } } } }
+                }
+            }
+        }),
+    );
+    Value::Object(paths)
+}
+
+fn schemas() -> Value {
+    let mut schemas = Map::new();
+    schemas.insert(
+        "Radar".to_string(),
+        json!({
+            "type": "object",
+            "required": ["id", "name", "brand", "spokesPerRevolution", "maxSpokeLen", "streamUrl", "controlUrl", "legend"],
+            "properties": {
+                "id": { "type": "string" },
+                "name": { "type": "string" },
+                "brand": { "type": "string" },
+                "model": { "type": "string" },
+                "spokesPerRevolution": { "type": "integer" },
+                "maxSpokeLen": { "type": "integer", "description": "Length of the spokes sent to clients" },
+                "radarSpokeLen": { "type": "integer", "description": "Length the radar sends, only when spokes are clamped" },
+                "streamUrl": { "type": "string" },
+                "controlUrl": { "type": "string" },
+                "legend": { "type": "object" }
+            }
+        }),
+    );
+    schemas.insert(
+        "RadarState".to_string(),
+        json!({
+            "type": "object",
+            "required": ["id", "timestamp", "status", "controls"],
+            "properties": {
+                "id": { "type": "string" },
+                "timestamp": { "type": "string", "format": "date-time" },
+                "status": {
+                    "type": "string",
+                    "description": "Power state, or \"initializing\" until the radar has reported its spoke length"
+                },
+                "controls": {
+                    "type": "object",
+                    "additionalProperties": {}
+                },
+                "disabledControls": {
+                    "type": "array",
+                    "items": { "type": "object" }
+                },
+                "rangeLabel": { "type": "string" },
+                "rpm": { "type": "number", "description": "Rotations per minute, estimated from the measured rotation period" },
+                "blindSectors": {
+                    "type": "array",
+                    "description": "Sectors the radar masks itself, e.g. behind a mast, in degrees from the bow; user blanking is in the noTransmit controls",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "start": { "type": "number" },
+                            "end": { "type": "number" }
+                        }
+                    }
+                },
+                "link": {
+                    "type": "string",
+                    "enum": ["online", "offline"],
+                    "description": "offline when no spokes or reports arrived for --offline-timeout seconds"
+                },
+                "overlayAlignment": {
+                    "type": "object",
+                    "description": "Offsets for lining the image up with a chart: the bearing alignment of the radar (the bearingAlignment control) and the range_offset preference",
+                    "properties": {
+                        "bearingOffset": { "type": "number", "description": "Degrees, positive clockwise" },
+                        "rangeOffset": { "type": "number", "description": "Meters" }
+                    }
+                }
+            }
+        }),
+    );
+    schemas.insert(
+        "SetControlRequest".to_string(),
+        json!({
+            "type": "object",
+            "required": ["value"],
+            "properties": {
+                "value": {
+                    "description": "A number, string, boolean, or {\"mode\": \"auto\"|\"manual\", \"value\": N}"
+                }
+            }
+        }),
+    );
+    schemas.insert(
+        "TargetScript".to_string(),
+        json!({
+            "type": "object",
+            "required": ["events"],
+            "properties": {
+                "events": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["atMs", "target", "action"],
+                        "properties": {
+                            "atMs": { "type": "integer", "description": "Milliseconds from the start of the script" },
+                            "target": { "type": "integer" },
+                            "action": { "type": "string", "enum": ["appear", "move", "disappear"] },
+                            "bearing": { "type": "number", "description": "Degrees, for appear and move" },
+                            "distance": { "type": "number", "description": "Meters, for appear and move" }
+                        }
+                    }
+                }
+            }
+        }),
+    );
+    schemas.insert(
+        "ControlValue".to_string(),
+        json!({
+            "type": "object",
+            "required": ["id", "value"],
+            "properties": {
+                "id": { "type": "string" },
+                "value": { "type": "string" },
+                "auto": { "type": "boolean" },
+                "enabled": { "type": "boolean" },
+                "error": { "type": "string" }
+            }
+        }),
+    );
+    schemas.insert(
+        "ControlError".to_string(),
+        json!({
+            "description": "A ControlValue sent back when the radar rejects a value; the reason is in `error`",
+            "allOf": [
+                { "$ref": "#/components/schemas/ControlValue" },
+                { "type": "object", "required": ["error"] }
+            ]
+        }),
+    );
+    schemas.insert(
+        "FormattedRange".to_string(),
+        json!({
+            "type": "object",
+            "required": ["index", "meters", "label", "unit"],
+            "properties": {
+                "index": { "type": "integer" },
+                "meters": { "type": "integer" },
+                "label": { "type": "string", "example": "1/4 nm" },
+                "unit": { "type": "string", "enum": ["nm", "km", "m", "mi"] }
+            }
+        }),
+    );
+    schemas.insert(
+        "RangeUnits".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "units": { "type": "string", "nullable": true, "enum": ["auto", "nautical", "metric", "statute", null] }
+            }
+        }),
+    );
+    Value::Object(schemas)
+}
+
+/// Build the OpenAPI document for the REST API
+pub fn openapi_document() -> Value {
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": "Mayara radar server API",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": paths(),
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "bearerAuth": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "Only required when the server runs with --control-token"
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_is_valid_json_with_known_endpoints() {
+        let text = serde_json::to_string(&openapi_document()).unwrap();
+        let doc: Value = serde_json::from_str(&text).unwrap();
+
+        assert_eq!(doc["openapi"], OPENAPI_VERSION);
+        for path in [
+            "/v2/api/radars",
+            "/v2/api/radars/{radar_id}/capabilities",
+            "/v2/api/radars/{radar_id}/state",
+            "/v2/api/radars/{radar_id}/controls/{control_id}",
+            "/v2/api/interfaces",
+        ] {
+            assert!(doc["paths"].get(path).is_some(), "missing path {}", path);
+        }
+        for schema in ["FormattedRange", "ControlError", "ControlValue"] {
+            assert!(
+                doc["components"]["schemas"].get(schema).is_some(),
+                "missing schema {}",
+                schema
+            );
+        }
+
+        // Every reference must resolve to a schema
+        for reference in text.split("\"$ref\":\"").skip(1) {
+            let name = reference
+                .split('"')
+                .next()
+                .unwrap()
+                .trim_start_matches("#/components/schemas/");
+            assert!(
+                doc["components"]["schemas"].get(name).is_some(),
+                "dangling reference {}",
+                name
+            );
+        }
+    }
+}
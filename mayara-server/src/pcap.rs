@@ -0,0 +1,544 @@
+//! Replay of captured radar traffic into the running server
+//!
+//! A user's `tcpdump` capture can be uploaded to `POST /v2/api/debug/replay`
+//! of a server started with `--replay`. The UDP datagrams in it are sent
+//! again, with their original spacing divided by the replay speed, to the
+//! addresses they were captured on: multicast groups on this host only
+//! (TTL 0), unicast to the same port on the loopback address. The normal
+//! radar receivers pick them up, so discovery, decoding and the web UI all
+//! behave as with the live radar.
+//!
+//! Only the classic libpcap format is read, with Ethernet, Linux cooked
+//! (SLL and SLL2), BSD loopback or raw IPv4 link layers. Fragmented
+//! datagrams, common for spoke frames, are reassembled; everything that is
+//! not UDP over IPv4 is skipped.
+//!
+//! Captures can be far larger than the memory of a small device, so they are
+//! never held whole: an upload is spooled to a temporary file, refused once
+//! it grows beyond `--max-pcap-size`, and [`PcapReader`] reads the packets
+//! from it one by one while they are replayed.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::io::AsyncWriteExt;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// Default for `--max-pcap-size`, in megabytes
+pub const DEFAULT_MAX_PCAP_SIZE_MB: u64 = 256;
+
+/// Packets read ahead of the replay
+const PACKET_QUEUE: usize = 256;
+
+/// Largest frame accepted, the maximum snapshot length of libpcap
+const MAX_FRAME: usize = 262_144;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const IP_PROTOCOL_UDP: u8 = 17;
+
+#[derive(Error, Debug)]
+pub enum PcapError {
+    #[error("Not a pcap file (magic {0:08x}); pcapng is not supported, save as pcap")]
+    BadMagic(u32),
+    #[error("Unsupported link type {0}")]
+    UnsupportedLinkType(u32),
+    #[error("Capture is truncated")]
+    Truncated,
+    #[error("Frame of {0} bytes is larger than any capture, the file is damaged")]
+    BadFrame(usize),
+    #[error("Capture is larger than the limit of {0} bytes, see --max-pcap-size")]
+    TooLarge(u64),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+/// One UDP datagram from the capture
+#[derive(Clone, Debug, PartialEq)]
+pub struct PcapPacket {
+    /// Time since the first packet of the capture
+    pub at: Duration,
+    pub source: SocketAddrV4,
+    pub destination: SocketAddrV4,
+    pub payload: Vec<u8>,
+}
+
+fn u32_at(data: &[u8], offset: usize, big_endian: bool) -> u32 {
+    let bytes: [u8; 4] = data[offset..offset + 4].try_into().unwrap();
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+/// Fill `buf` from `reader`; `Ok(false)` when the input ended before the
+/// first byte, `Truncated` when it ended halfway
+fn read_record(reader: &mut impl Read, buf: &mut [u8]) -> Result<bool, PcapError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(PcapError::Truncated),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+fn be16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().unwrap()))
+}
+
+/// Fragments of one IPv4 datagram that is being reassembled
+#[derive(Default)]
+struct Fragments {
+    parts: Vec<(usize, Vec<u8>)>,
+    total: Option<usize>,
+}
+
+impl Fragments {
+    fn assemble(&mut self) -> Option<Vec<u8>> {
+        let total = self.total?;
+        self.parts.sort_by_key(|(offset, _)| *offset);
+        let mut datagram = Vec::with_capacity(total);
+        for (offset, part) in &self.parts {
+            if *offset > datagram.len() {
+                return None; // A hole, more to come
+            }
+            let skip = datagram.len() - offset;
+            datagram.extend_from_slice(part.get(skip..).unwrap_or_default());
+        }
+        (datagram.len() >= total).then_some(datagram)
+    }
+}
+
+/// Reads the UDP datagrams of a pcap file one at a time, holding only the
+/// current frame and incomplete fragments in memory
+pub struct PcapReader<R> {
+    reader: R,
+    big_endian: bool,
+    nanos: bool,
+    link_type: u32,
+    first: Option<Duration>,
+    fragments: HashMap<(Ipv4Addr, Ipv4Addr, u16), Fragments>,
+    frame: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Read the file header
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut header = [0u8; 24];
+        if !read_record(&mut reader, &mut header)? {
+            return Err(PcapError::Truncated);
+        }
+        let magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let (big_endian, nanos) = match magic {
+            0xa1b2c3d4 => (false, false),
+            0xa1b23c4d => (false, true),
+            0xd4c3b2a1 => (true, false),
+            0x4d3cb2a1 => (true, true),
+            _ => return Err(PcapError::BadMagic(magic)),
+        };
+        let link_type = u32_at(&header, 20, big_endian) & 0x0fff_ffff;
+        // Fail now rather than on the first frame
+        ipv4_payload(link_type, &[])?;
+        Ok(PcapReader {
+            reader,
+            big_endian,
+            nanos,
+            link_type,
+            first: None,
+            fragments: HashMap::new(),
+            frame: Vec::new(),
+            done: false,
+        })
+    }
+
+    fn next_packet(&mut self) -> Result<Option<PcapPacket>, PcapError> {
+        let mut header = [0u8; 16];
+        while read_record(&mut self.reader, &mut header)? {
+            let seconds = u32_at(&header, 0, self.big_endian) as u64;
+            let fraction = u32_at(&header, 4, self.big_endian) as u64;
+            let captured = u32_at(&header, 8, self.big_endian) as usize;
+            if captured > MAX_FRAME {
+                return Err(PcapError::BadFrame(captured));
+            }
+            self.frame.resize(captured, 0);
+            if !read_record(&mut self.reader, &mut self.frame)? && captured > 0 {
+                return Err(PcapError::Truncated);
+            }
+
+            let timestamp = Duration::from_secs(seconds)
+                + if self.nanos {
+                    Duration::from_nanos(fraction)
+                } else {
+                    Duration::from_micros(fraction)
+                };
+            let first = *self.first.get_or_insert(timestamp);
+
+            let Some(ip) = ipv4_payload(self.link_type, &self.frame)? else {
+                continue;
+            };
+            if let Some((source, destination, payload)) = udp_datagram(ip, &mut self.fragments) {
+                return Ok(Some(PcapPacket {
+                    at: timestamp.saturating_sub(first),
+                    source,
+                    destination,
+                    payload,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<PcapPacket, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_packet().transpose();
+        self.done = !matches!(next, Some(Ok(_)));
+        next
+    }
+}
+
+/// Read all UDP datagrams from a pcap file in memory
+pub fn parse_pcap(data: &[u8]) -> Result<Vec<PcapPacket>, PcapError> {
+    PcapReader::new(data)?.collect()
+}
+
+static SPOOL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// An uploaded capture, written to a temporary file as it arrives and
+/// removed again when dropped
+pub struct SpooledCapture {
+    path: PathBuf,
+    file: tokio::fs::File,
+    size: u64,
+    limit: u64,
+}
+
+impl SpooledCapture {
+    /// Start a spool file that refuses to grow beyond `limit` bytes
+    pub async fn create(limit: u64) -> io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "mayara-replay-{}-{}.pcap",
+            std::process::id(),
+            SPOOL_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = tokio::fs::File::create(&path).await?;
+        Ok(SpooledCapture {
+            path,
+            file,
+            size: 0,
+            limit,
+        })
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> Result<(), PcapError> {
+        self.size += chunk.len() as u64;
+        if self.size > self.limit {
+            return Err(PcapError::TooLarge(self.limit));
+        }
+        self.file.write_all(chunk).await?;
+        Ok(())
+    }
+
+    /// Finish writing and open the capture for reading
+    pub async fn reader(&mut self) -> Result<PcapReader<BufReader<File>>, PcapError> {
+        self.file.flush().await?;
+        PcapReader::new(BufReader::new(File::open(&self.path)?))
+    }
+}
+
+impl Drop for SpooledCapture {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            log::debug!("Cannot remove '{}': {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Number of packets in a capture and the time of the last one
+pub fn summarize<R: Read>(reader: PcapReader<R>) -> Result<(usize, Duration), PcapError> {
+    let mut count = 0;
+    let mut last = Duration::ZERO;
+    for packet in reader {
+        count += 1;
+        last = packet?.at;
+    }
+    Ok((count, last))
+}
+
+/// Read the packets in a blocking thread, a bounded queue ahead of the replay
+pub fn stream<R: Read + Send + 'static>(reader: PcapReader<R>) -> mpsc::Receiver<Result<PcapPacket, PcapError>> {
+    let (tx, rx) = mpsc::channel(PACKET_QUEUE);
+    tokio::task::spawn_blocking(move || {
+        for packet in reader {
+            if tx.blocking_send(packet).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// The IPv4 packet in a captured frame, `None` for other protocols
+fn ipv4_payload(link_type: u32, frame: &[u8]) -> Result<Option<&[u8]>, PcapError> {
+    let (ethertype, header) = match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut header = 14;
+            let mut ethertype = be16(frame, 12);
+            while ethertype == Some(ETHERTYPE_VLAN) {
+                ethertype = be16(frame, header + 2);
+                header += 4;
+            }
+            (ethertype, header)
+        }
+        LINKTYPE_LINUX_SLL => (be16(frame, 14), 16),
+        LINKTYPE_LINUX_SLL2 => (be16(frame, 0), 20),
+        // AF_INET in host byte order of the capturing machine
+        LINKTYPE_NULL => {
+            let inet = frame.first() == Some(&2) || frame.get(3) == Some(&2);
+            (inet.then_some(ETHERTYPE_IPV4), 4)
+        }
+        LINKTYPE_RAW | LINKTYPE_IPV4 => (Some(ETHERTYPE_IPV4), 0),
+        _ => return Err(PcapError::UnsupportedLinkType(link_type)),
+    };
+    if ethertype != Some(ETHERTYPE_IPV4) {
+        return Ok(None);
+    }
+    Ok(frame.get(header..).filter(|ip| ip.first().is_some_and(|v| v >> 4 == 4)))
+}
+
+/// The UDP datagram in an IPv4 packet, once all its fragments are in
+fn udp_datagram(
+    ip: &[u8],
+    fragments: &mut HashMap<(Ipv4Addr, Ipv4Addr, u16), Fragments>,
+) -> Option<(SocketAddrV4, SocketAddrV4, Vec<u8>)> {
+    let header = ((ip.first()? & 0x0f) as usize) * 4;
+    let total = (be16(ip, 2)? as usize).min(ip.len());
+    if ip.get(9)? != &IP_PROTOCOL_UDP || header < 20 || total < header {
+        return None;
+    }
+    let id = be16(ip, 4)?;
+    let flags = be16(ip, 6)?;
+    let more_fragments = flags & 0x2000 != 0;
+    let fragment_offset = ((flags & 0x1fff) as usize) * 8;
+    let source = Ipv4Addr::new(ip[12], ip[13], ip[14], ip[15]);
+    let destination = Ipv4Addr::new(ip[16], ip[17], ip[18], ip[19]);
+    let body = &ip[header..total];
+
+    let datagram = if !more_fragments && fragment_offset == 0 {
+        body.to_vec()
+    } else {
+        let key = (source, destination, id);
+        let entry = fragments.entry(key).or_default();
+        entry.parts.push((fragment_offset, body.to_vec()));
+        if !more_fragments {
+            entry.total = Some(fragment_offset + body.len());
+        }
+        let datagram = entry.assemble()?;
+        fragments.remove(&key);
+        datagram
+    };
+
+    let source_port = be16(&datagram, 0)?;
+    let destination_port = be16(&datagram, 2)?;
+    let length = (be16(&datagram, 4)? as usize).clamp(8, datagram.len());
+    Some((
+        SocketAddrV4::new(source, source_port),
+        SocketAddrV4::new(destination, destination_port),
+        datagram[8..length].to_vec(),
+    ))
+}
+
+/// A socket for replaying: multicast stays on this host and is looped back
+/// to the server's own receivers
+pub async fn replay_socket() -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0))).await?;
+    socket.set_multicast_ttl_v4(0)?;
+    socket.set_multicast_loop_v4(true)?;
+    Ok(socket)
+}
+
+/// Where a captured packet is sent to reach the local receivers
+fn replay_destination(destination: SocketAddrV4) -> SocketAddrV4 {
+    if destination.ip().is_multicast() || destination.ip().is_broadcast() {
+        destination
+    } else {
+        SocketAddrV4::new(Ipv4Addr::LOCALHOST, destination.port())
+    }
+}
+
+/// Send the packets with their captured spacing divided by `speed`.
+/// Returns how many were sent.
+pub async fn replay(
+    mut packets: mpsc::Receiver<Result<PcapPacket, PcapError>>,
+    speed: f64,
+    socket: &UdpSocket,
+) -> Result<usize, PcapError> {
+    let start = Instant::now();
+    let mut sent = 0;
+    while let Some(packet) = packets.recv().await {
+        let packet = packet?;
+        tokio::time::sleep_until(start + packet.at.div_f64(speed)).await;
+        match socket
+            .send_to(&packet.payload, replay_destination(packet.destination))
+            .await
+        {
+            Ok(_) => sent += 1,
+            Err(e) => log::debug!("Replay to {} failed: {}", packet.destination, e),
+        }
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pcap with one Ethernet/IPv4/UDP frame per (microseconds, destination, payload)
+    fn pcap(frames: &[(u32, SocketAddrV4, &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&0xa1b2c3d4u32.to_le_bytes());
+        data.extend_from_slice(&2u16.to_le_bytes());
+        data.extend_from_slice(&4u16.to_le_bytes());
+        data.extend_from_slice(&[0; 8]);
+        data.extend_from_slice(&65535u32.to_le_bytes());
+        data.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+        for (micros, destination, payload) in frames {
+            let mut frame = vec![0u8; 12];
+            frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+            let total = (20 + 8 + payload.len()) as u16;
+            frame.extend_from_slice(&[0x45, 0]);
+            frame.extend_from_slice(&total.to_be_bytes());
+            frame.extend_from_slice(&[0, 1, 0, 0, 1, IP_PROTOCOL_UDP, 0, 0]);
+            frame.extend_from_slice(&[192, 168, 1, 10]);
+            frame.extend_from_slice(&destination.ip().octets());
+            frame.extend_from_slice(&6678u16.to_be_bytes());
+            frame.extend_from_slice(&destination.port().to_be_bytes());
+            frame.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+            frame.extend_from_slice(&[0, 0]);
+            frame.extend_from_slice(payload);
+
+            data.extend_from_slice(&100u32.to_le_bytes());
+            data.extend_from_slice(&micros.to_le_bytes());
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            data.extend_from_slice(&frame);
+        }
+        data
+    }
+
+    #[test]
+    fn parses_udp_datagrams_with_relative_times() {
+        let to = SocketAddrV4::new(Ipv4Addr::new(236, 6, 7, 9), 6679);
+        let packets = parse_pcap(&pcap(&[(0, to, b"one"), (250_000, to, b"two")])).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].at, Duration::ZERO);
+        assert_eq!(packets[1].at, Duration::from_millis(250));
+        assert_eq!(packets[1].destination, to);
+        assert_eq!(packets[1].source, SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 10), 6678));
+        assert_eq!(packets[1].payload, b"two");
+
+        assert!(matches!(parse_pcap(&[0; 24]), Err(PcapError::BadMagic(0))));
+    }
+
+    /// Counts how much of the capture has been read
+    struct Counting<'a> {
+        data: &'a [u8],
+        read: usize,
+    }
+
+    impl Read for Counting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = (&self.data[self.read..]).read(buf)?;
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn packets_are_read_as_they_are_needed() {
+        let to = SocketAddrV4::new(Ipv4Addr::new(236, 6, 7, 9), 6679);
+        let payload = [7u8; 1000];
+        let frames: Vec<_> = (0..20).map(|i| (i * 1000, to, &payload[..])).collect();
+        let data = pcap(&frames);
+
+        let mut reader = PcapReader::new(Counting { data: &data, read: 0 }).unwrap();
+        let packet = reader.next().unwrap().unwrap();
+        assert_eq!(packet.payload.len(), 1000);
+        // Only the file header and the first frame were read
+        assert!(reader.reader.read < 24 + 2 * (16 + 1042), "{}", reader.reader.read);
+        assert_eq!(reader.count(), 19);
+
+        // A capture cut off in a frame is reported, after the complete ones
+        let results: Vec<_> = PcapReader::new(&data[..data.len() - 10]).unwrap().collect();
+        assert_eq!(results.len(), 20);
+        assert!(matches!(results[19], Err(PcapError::Truncated)));
+    }
+
+    #[tokio::test]
+    async fn uploads_beyond_the_limit_are_refused() {
+        let to = SocketAddrV4::new(Ipv4Addr::new(236, 6, 7, 9), 6679);
+        let data = pcap(&[(0, to, b"one"), (1000, to, b"two")]);
+
+        let mut capture = SpooledCapture::create(data.len() as u64 - 1).await.unwrap();
+        let (head, tail) = data.split_at(24);
+        capture.write(head).await.unwrap();
+        assert!(matches!(capture.write(tail).await, Err(PcapError::TooLarge(_))));
+        let path = capture.path.clone();
+        drop(capture);
+        assert!(!path.exists());
+
+        let mut capture = SpooledCapture::create(data.len() as u64).await.unwrap();
+        capture.write(&data).await.unwrap();
+        let (count, last) = summarize(capture.reader().await.unwrap()).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(last, Duration::from_millis(1));
+    }
+
+    #[tokio::test]
+    async fn uploaded_capture_reaches_the_local_receivers() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = receiver.local_addr().unwrap().port();
+        // Captured as unicast to a display on the boat network
+        let to = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 20), port);
+        let data = pcap(&[(0, to, b"report"), (100_000, to, b"spoke")]);
+        let packets = stream(PcapReader::new(io::Cursor::new(data)).unwrap());
+
+        let socket = replay_socket().await.unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(replay(packets, 2.0, &socket).await.unwrap(), 2);
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        let mut buf = [0u8; 64];
+        let len = receiver.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"report");
+        let len = receiver.recv(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"spoke");
+    }
+}
@@ -0,0 +1,224 @@
+//! Per-radar preferences kept in a hand editable TOML file
+//!
+//! Next to `settings.json` (which the server rewrites as it learns about the
+//! radars, including the range units set through the API) the config
+//! directory can hold `radars.toml` with preferences set by the user. There
+//! is one table per radar, keyed by the stable radar key (brand and serial
+//! number, e.g. `Navico-1403100652`); the schema is documented in
+//! docs/user-guide/radar-preferences.md.
+//!
+//! The preferences are applied when a radar is registered, after the
+//! persisted settings. The server only reads the file.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::{info, warn};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::config::get_project_dirs;
+use crate::radar::intensity::IntensityCurve;
+use crate::radar::raster::Orientation;
+use crate::radar::range::Ranges;
+use crate::radar::RadarInfo;
+
+/// Name of the preferences file in the config directory
+pub const PREFERENCES_FILE: &str = "radars.toml";
+
+#[derive(Error, Debug)]
+pub enum PreferencesError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid preferences: {0}")]
+    Parse(#[from] toml::de::Error),
+}
+
+/// The preferences of one radar; everything is optional
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct RadarPreferences {
+    /// Meters added to the range labels; center blanking and regions of
+    /// interest are measured with it too. Spoke ranges are sent unchanged.
+    #[serde(default)]
+    pub range_offset: Option<i32>,
+    /// Range table in meters, increasing, used instead of the detected ranges
+    #[serde(default)]
+    pub range_table: Option<Vec<i32>>,
+    /// Gamma applied to spoke intensities, below 1 lifts faint echoes
+    #[serde(default)]
+    pub intensity_gamma: Option<f64>,
+    /// Contrast applied to spoke intensities after the gamma, 1 is unchanged
+    #[serde(default)]
+    pub intensity_contrast: Option<f64>,
+    /// Meters around the antenna blanked in every spoke, hides the main bang
+    #[serde(default)]
+    pub center_blanking: Option<f64>,
+    /// Read the spoke azimuth most significant byte first, for new hardware
+    #[serde(default)]
+    pub azimuth_big_endian: Option<bool>,
+    /// Azimuth counts per rotation in the spoke headers, for new hardware
+    #[serde(default)]
+    pub azimuth_modulus: Option<u16>,
+    /// Which way is up in raster images, head up by default
+    #[serde(default)]
+    pub orientation: Option<Orientation>,
+    /// Degrees added to every spoke angle in software, for new hardware
+    #[serde(default)]
+    pub azimuth_offset: Option<f64>,
+}
+
+impl RadarPreferences {
+    pub(crate) fn apply_to(&self, info: &mut RadarInfo) {
+        if let Some(table) = &self.range_table {
+            match Ranges::new_from_override(table) {
+                Ok(ranges) => info.ranges = ranges,
+                Err(e) => warn!("{}: ignoring range table preference: {}", info.key(), e),
+            }
+        }
+        info.range_offset = self.range_offset.unwrap_or(0);
+
+        let curve = IntensityCurve::new(
+            self.intensity_gamma.unwrap_or(1.0),
+            self.intensity_contrast.unwrap_or(1.0),
+            info.pixel_values,
+        );
+        info.intensity_curve = (!curve.is_identity()).then_some(curve);
+        info.center_blanking = self.center_blanking.filter(|&radius| radius > 0.);
+        info.azimuth_big_endian = self.azimuth_big_endian;
+        info.azimuth_modulus = self.azimuth_modulus;
+        info.orientation = self.orientation.unwrap_or_default();
+        info.set_azimuth_offset(self.azimuth_offset.unwrap_or(0.));
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, PartialEq)]
+struct PreferencesFile {
+    #[serde(default)]
+    radars: BTreeMap<String, RadarPreferences>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Preferences {
+    file: PreferencesFile,
+}
+
+impl Preferences {
+    pub fn default_path() -> PathBuf {
+        get_project_dirs().config_dir().join(PREFERENCES_FILE)
+    }
+
+    /// Read the preferences at `path`; a missing file means no preferences
+    pub fn load(path: &Path) -> Result<Self, PreferencesError> {
+        let file = match fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => PreferencesFile::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Preferences { file })
+    }
+
+    /// Read the preferences file in the config directory, ignoring it when
+    /// it cannot be read
+    pub fn load_default() -> Self {
+        let path = Self::default_path();
+        match Self::load(&path) {
+            Ok(preferences) => {
+                if !preferences.file.radars.is_empty() {
+                    info!("Loaded radar preferences from '{}'", path.display());
+                }
+                preferences
+            }
+            Err(e) => {
+                warn!("Ignoring radar preferences '{}': {}", path.display(), e);
+                Preferences {
+                    file: PreferencesFile::default(),
+                }
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&RadarPreferences> {
+        self.file.radars.get(key)
+    }
+
+    /// Apply the preferences of this radar, looked up by its stable key
+    pub(crate) fn apply(&self, info: &mut RadarInfo) {
+        let preferences = self
+            .get(&info.config_key())
+            .or_else(|| self.get(&info.key()))
+            .or_else(|| self.get(&info.brand.to_string()))
+            .cloned();
+        if let Some(preferences) = preferences {
+            info!("{}: applying preferences {:?}", info.key(), preferences);
+            preferences.apply_to(info);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locator::LocatorId;
+    use crate::radar::range::RangeUnitPreference;
+    use crate::settings::SharedControls;
+    use crate::{Brand, Session};
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+    use tempfile::TempDir;
+
+    #[test]
+    fn preferences_are_read_and_apply_to_formatting() {
+        let session = Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let mut info = RadarInfo::new(
+            session,
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1403100652"),
+            None,
+            16,
+            2048,
+            512,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(PREFERENCES_FILE);
+        let preferences = Preferences::load(&path).unwrap();
+        assert!(preferences.get(&info.key()).is_none());
+
+        let text = format!(
+            "[radars.\"{}\"]\n\
+             range_offset = -148\n\
+             range_table = [926, 1852, 3704]\n\
+             center_blanking = 30.0\n\
+             azimuth_modulus = 4096\n\
+             orientation = \"northUp\"\n",
+            info.key()
+        );
+        fs::write(&path, text).unwrap();
+        let preferences = Preferences::load(&path).unwrap();
+        let radar = preferences.get(&info.key()).unwrap();
+        assert_eq!(radar.range_offset, Some(-148));
+        assert_eq!(radar.orientation, Some(Orientation::NorthUp));
+        assert_eq!(radar.intensity_gamma, None);
+
+        preferences.apply(&mut info);
+        assert_eq!(info.ranges.len(), 3);
+        assert_eq!(info.center_blanking, Some(30.));
+        assert_eq!(info.azimuth_modulus, Some(4096));
+        assert_eq!(info.format_range(2000, RangeUnitPreference::Auto), "1 nm");
+
+        fs::write(&path, "radars = 3").unwrap();
+        assert!(Preferences::load(&path).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Integrity header for spoke frames.
+//!
+//! A client that negotiates the `crc` feature (e.g. `mayara.v1+delta+crc`)
+//! receives every binary frame of the spokes stream with a header in front
+//! of the payload:
+//!
+//! ```text
+//! crc: u32 LE, azimuth: u16 LE, length: u32 LE, length bytes of payload
+//! ```
+//!
+//! `azimuth` is the angle of the first spoke in the frame, or `0xFFFF` for
+//! frames without spokes such as raster images. `crc` is the CRC-32 (as used
+//! by PNG and zlib) of everything after it: the azimuth, the length and the
+//! payload. A client that finds a frame truncated or with a wrong checksum
+//! drops it; with delta encoding the cells it missed are sent again at the
+//! next keyframe.
+
+use protobuf::Message;
+
+use crate::protos::RadarMessage::RadarMessage;
+
+use super::png::crc32;
+
+/// Azimuth of frames that carry no spokes
+pub const NO_AZIMUTH: u16 = u16::MAX;
+
+pub const HEADER_LEN: usize = 10;
+
+/// The payload with the integrity header in front of it
+pub fn frame(azimuth: u16, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&[0; 4]);
+    frame.extend_from_slice(&azimuth.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(payload);
+    let crc = crc32(&frame[4..]);
+    frame[..4].copy_from_slice(&crc.to_le_bytes());
+    frame
+}
+
+/// The azimuth and payload of an intact frame, `None` when it is truncated
+/// or corrupted
+pub fn verify(frame: &[u8]) -> Option<(u16, &[u8])> {
+    if frame.len() < HEADER_LEN {
+        return None;
+    }
+    let crc = u32::from_le_bytes(frame[0..4].try_into().unwrap());
+    let azimuth = u16::from_le_bytes(frame[4..6].try_into().unwrap());
+    let length = u32::from_le_bytes(frame[6..10].try_into().unwrap()) as usize;
+    let payload = &frame[HEADER_LEN..];
+    if payload.len() != length || crc32(&frame[4..]) != crc {
+        return None;
+    }
+    Some((azimuth, payload))
+}
+
+/// Angle of the first spoke of a RadarMessage protobuf
+pub fn first_azimuth(message: &[u8]) -> u16 {
+    RadarMessage::parse_from_bytes(message)
+        .ok()
+        .and_then(|m| m.spokes.first().map(|s| s.angle as u16))
+        .unwrap_or(NO_AZIMUTH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::RadarMessage::radar_message::Spoke;
+
+    #[test]
+    fn intact_frames_verify_and_mutated_ones_do_not() {
+        let mut spoke = Spoke::new();
+        spoke.angle = 1234;
+        spoke.data = vec![7; 64];
+        let mut message = RadarMessage::new();
+        message.spokes.push(spoke);
+        let payload = message.write_to_bytes().unwrap();
+
+        let framed = frame(first_azimuth(&payload), &payload);
+        assert_eq!(framed.len(), HEADER_LEN + payload.len());
+        assert_eq!(verify(&framed), Some((1234, payload.as_slice())));
+
+        // A flipped bit in the payload, in the azimuth, and a truncated frame
+        let mut mutated = framed.clone();
+        mutated[HEADER_LEN + 20] ^= 0x01;
+        assert_eq!(verify(&mutated), None);
+        let mut mutated = framed.clone();
+        mutated[4] ^= 0x80;
+        assert_eq!(verify(&mutated), None);
+        assert_eq!(verify(&framed[..framed.len() - 1]), None);
+        assert_eq!(verify(&framed[..4]), None);
+
+        assert_eq!(first_azimuth(&RadarMessage::new().write_to_bytes().unwrap()), NO_AZIMUTH);
+    }
+}
@@ -0,0 +1,129 @@
+//! Throttling of control commands sent to a radar.
+//!
+//! Dragging a slider in a client sends a new value many times per second,
+//! faster than some radars accept commands, and the radar then drops some of
+//! them. Commands are therefore queued per radar: a new command for a control
+//! that is still waiting replaces the waiting one, and commands are handed to
+//! the brand's command handler at most once per `--command-interval`.
+//! The last value of a drag is always sent.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::settings::ControlUpdate;
+
+pub(crate) struct CommandQueue {
+    interval: Duration,
+    pending: VecDeque<ControlUpdate>, // At most one per control, oldest first
+    last_sent: Option<Instant>,
+}
+
+impl CommandQueue {
+    pub(crate) fn new(interval: Duration) -> Self {
+        CommandQueue {
+            interval,
+            pending: VecDeque::new(),
+            last_sent: None,
+        }
+    }
+
+    /// Queue a command, replacing a waiting command for the same control
+    pub(crate) fn push(&mut self, update: ControlUpdate) {
+        match self
+            .pending
+            .iter_mut()
+            .find(|p| p.control_value.id == update.control_value.id)
+        {
+            Some(pending) => *pending = update,
+            None => self.pending.push_back(update),
+        }
+    }
+
+    /// How long until the next command may be sent; `None` when nothing waits
+    pub(crate) fn wait(&self, now: Instant) -> Option<Duration> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(match self.last_sent {
+            Some(sent) => (sent + self.interval).saturating_duration_since(now),
+            None => Duration::ZERO,
+        })
+    }
+
+    /// The next command, if one waits and the interval has passed
+    pub(crate) fn pop(&mut self, now: Instant) -> Option<ControlUpdate> {
+        if self.wait(now)? > Duration::ZERO {
+            return None;
+        }
+        self.last_sent = Some(now);
+        self.pending.pop_front()
+    }
+}
+
+/// Start a task that feeds queued commands to `control_update_tx`, returns
+/// the sender that takes the place of `control_update_tx` for the radar.
+pub(crate) fn spawn_command_queue(
+    key: String,
+    interval: Duration,
+    control_update_tx: broadcast::Sender<ControlUpdate>,
+) -> mpsc::UnboundedSender<ControlUpdate> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut queue = CommandQueue::new(interval);
+
+    tokio::spawn(async move {
+        loop {
+            let wait = queue.wait(Instant::now());
+            tokio::select! {
+                update = rx.recv() => match update {
+                    Some(update) => queue.push(update),
+                    None => break,
+                },
+                _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() => {
+                    if let Some(update) = queue.pop(Instant::now()) {
+                        if control_update_tx.send(update).is_err() {
+                            log::trace!("{}: Dropping command, no command handler", key);
+                        }
+                    }
+                }
+            }
+        }
+        log::debug!("{}: command queue stopped", key);
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::ControlValue;
+
+    #[test]
+    fn slider_drag_is_coalesced_and_rate_limited() {
+        let interval = Duration::from_millis(100);
+        let mut queue = CommandQueue::new(interval);
+        let (reply_tx, _reply_rx) = mpsc::channel(1);
+        let start = Instant::now();
+
+        // Ten gain commands 10 ms apart, checking for due commands every ms
+        let mut sent = Vec::new();
+        for ms in 0..500u64 {
+            let now = start + Duration::from_millis(ms);
+            if ms % 10 == 0 && ms < 100 {
+                queue.push(ControlUpdate {
+                    reply_tx: reply_tx.clone(),
+                    control_value: ControlValue::new("gain", (ms / 10 * 10).to_string()),
+                });
+            }
+            while let Some(update) = queue.pop(now) {
+                sent.push((ms, update.control_value.value));
+            }
+        }
+
+        // The first command goes out at once, the rest collapse into one
+        assert_eq!(sent, vec![(0, "0".to_string()), (100, "90".to_string())]);
+        assert!(sent.len() as u64 <= 1 + 100 / interval.as_millis() as u64);
+        assert_eq!(queue.wait(start + Duration::from_secs(1)), None);
+    }
+}
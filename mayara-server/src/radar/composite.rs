@@ -0,0 +1,221 @@
+//! Combined image of the two channels of a dual range radar.
+//!
+//! A dual range radar sends two independent spoke streams, range A and
+//! range B, each with its own range and so its own meters per cell. The
+//! compositor keeps the last spoke of each channel at every angle and paints
+//! both into one cartesian image that covers the longer of the two ranges:
+//! first the long range channel over the whole image, then the short range
+//! channel, with its finer cells, over the inner part it covers.
+//!
+//! Frames have the layout of the raster stream (see [`super::raster`]),
+//! with the range of the long range channel in the header, and are sent
+//! when the leading channel starts a rotation. The leader is the first
+//! channel that starts one; when the other channel starts `LEADER_MISSES`
+//! rotations in a row without one from the leader, it takes over, so the
+//! image keeps coming when a channel stops transmitting. The image is head
+//! up.
+
+use protobuf::Message;
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::raster::{MAX_RASTER_SIZE, MIN_RASTER_SIZE, RASTER_HEADER_LEN};
+use crate::radar::trail::cartesian::PolarToCartesianLookup;
+
+/// Number of channels of a dual range radar, range A and range B
+pub const CHANNEL_COUNT: usize = 2;
+
+/// Rotations of the other channel after which it takes the lead
+const LEADER_MISSES: u32 = 2;
+
+#[derive(Clone)]
+struct Cells {
+    range: u32,
+    data: Vec<u8>,
+}
+
+/// Per client compositor of the spokes of both channels
+pub struct Compositor {
+    size: usize,
+    spokes: usize,
+    lookup: PolarToCartesianLookup,
+    channels: [Vec<Option<Cells>>; CHANNEL_COUNT],
+    ranges: [u32; CHANNEL_COUNT],
+    leader: Option<usize>,
+    misses: u32,
+}
+
+impl Compositor {
+    /// A compositor for square images of `size` pixels, clamped and rounded
+    /// as for the raster stream
+    pub fn new(size: u16, spokes_per_revolution: u16) -> Self {
+        let size = (size.clamp(MIN_RASTER_SIZE, MAX_RASTER_SIZE) & !1) as usize;
+        let spokes = spokes_per_revolution.max(1) as usize;
+        Compositor {
+            size,
+            spokes,
+            lookup: PolarToCartesianLookup::new(spokes, size / 2)
+                .expect("composite is at least MIN_RASTER_SIZE"),
+            channels: [vec![None; spokes], vec![None; spokes]],
+            ranges: [0; CHANNEL_COUNT],
+            leader: None,
+            misses: 0,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Store one spoke of `channel` (0 for range A); returns a frame when
+    /// this spoke starts a rotation of the leading channel
+    pub fn add_spoke(&mut self, channel: usize, spoke: &Spoke) -> Option<Vec<u8>> {
+        if channel >= CHANNEL_COUNT {
+            return None;
+        }
+        let frame = if spoke.rotation_start == Some(true) && self.leads(channel) {
+            self.frame()
+        } else {
+            None
+        };
+
+        // Delta spokes need the previous spoke to decode, use full spokes
+        if spoke.delta != Some(true) && !spoke.data.is_empty() && spoke.range > 0 {
+            let angle = spoke.angle as usize % self.spokes;
+            self.channels[channel][angle] = Some(Cells {
+                range: spoke.range,
+                data: spoke.data.clone(),
+            });
+            self.ranges[channel] = spoke.range;
+        }
+        frame
+    }
+
+    /// Composite a serialized RadarMessage of `channel`
+    pub fn encode_message(&mut self, channel: usize, bytes: &[u8]) -> Option<Vec<u8>> {
+        let message = RadarMessage::parse_from_bytes(bytes).ok()?;
+        let mut frame = None;
+        for spoke in message.spokes.iter() {
+            if let Some(finished) = self.add_spoke(channel, spoke) {
+                frame = Some(finished);
+            }
+        }
+        frame
+    }
+
+    /// Whether a rotation start of `channel` sends a frame
+    fn leads(&mut self, channel: usize) -> bool {
+        match self.leader {
+            Some(leader) if leader == channel => {
+                self.misses = 0;
+                true
+            }
+            Some(_) => {
+                self.misses += 1;
+                if self.misses < LEADER_MISSES {
+                    return false;
+                }
+                self.leader = Some(channel);
+                self.misses = 0;
+                true
+            }
+            None => {
+                self.leader = Some(channel);
+                true
+            }
+        }
+    }
+
+    /// The combined image of the spokes received so far, `None` before any
+    fn frame(&self) -> Option<Vec<u8>> {
+        let range = self.ranges.iter().copied().max().filter(|&r| r > 0)?;
+        let mut frame = Vec::with_capacity(RASTER_HEADER_LEN + self.size * self.size);
+        frame.extend_from_slice(&(self.size as u16).to_le_bytes());
+        frame.extend_from_slice(&(self.size as u16).to_le_bytes());
+        frame.extend_from_slice(&range.to_le_bytes());
+        frame.resize(RASTER_HEADER_LEN + self.size * self.size, 0);
+
+        // Long range first, so the short range channel ends up on top
+        let mut order = [0, 1];
+        order.sort_by_key(|&c| std::cmp::Reverse(self.ranges[c]));
+        for channel in order {
+            self.paint(channel, range, &mut frame[RASTER_HEADER_LEN..]);
+        }
+        Some(frame)
+    }
+
+    /// Paint the spokes of `channel` into an image that covers `range` meters
+    fn paint(&self, channel: usize, range: u32, image: &mut [u8]) {
+        let radius = self.size / 2;
+        let image_range = range as u64;
+        for (angle, cells) in self.channels[channel].iter().enumerate() {
+            let Some(cells) = cells else {
+                continue;
+            };
+            let len = cells.data.len() as u64;
+            // Pixel r is at r * range / radius meters, within the spoke
+            // while that is less than the range of the spoke
+            for r in 0..radius {
+                if r as u64 * image_range >= cells.range as u64 * radius as u64 {
+                    break;
+                }
+                let Some(point) = self.lookup.try_get_point_int(angle, r) else {
+                    break;
+                };
+                let row = (radius as i32 - point.x as i32) as usize;
+                let col = (radius as i32 + point.y as i32) as usize;
+                let cell = r as u64 * image_range * len / (radius as u64 * cells.range as u64);
+                if row < self.size && col < self.size {
+                    image[row * self.size + col] = cells.data[(cell as usize).min(cells.data.len() - 1)];
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoke(angle: u32, range: u32, data: Vec<u8>, rotation_start: bool) -> Spoke {
+        let mut spoke = Spoke::new();
+        spoke.angle = angle;
+        spoke.range = range;
+        spoke.data = data;
+        if rotation_start {
+            spoke.rotation_start = Some(true);
+        }
+        spoke
+    }
+
+    #[test]
+    fn short_and_long_range_echoes_land_at_their_combined_radius() {
+        let mut compositor = Compositor::new(64, 2048);
+
+        // Range A at 1 nm with an echo dead ahead at half range (926 m),
+        // range B at 4 nm with an echo due east at 3/4 range (5556 m)
+        let mut near = vec![0u8; 256];
+        near[128] = 15;
+        let mut far = vec![0u8; 512];
+        far[384] = 15;
+
+        assert!(compositor.add_spoke(0, &spoke(0, 1852, near, true)).is_none());
+        assert!(compositor.add_spoke(1, &spoke(512, 7408, far, true)).is_none());
+        let frame = compositor
+            .add_spoke(0, &spoke(0, 1852, vec![0; 256], true))
+            .unwrap();
+
+        assert_eq!(frame.len(), RASTER_HEADER_LEN + 64 * 64);
+        assert_eq!(u32::from_le_bytes([frame[4], frame[5], frame[6], frame[7]]), 7408);
+        let image = &frame[RASTER_HEADER_LEN..];
+        // 7408 m over 32 pixels: 926 m is 4 pixels up, 5556 m 24 pixels right
+        assert_eq!(image[(32 - 4) * 64 + 32], 15);
+        assert_eq!(image[32 * 64 + 32 + 24], 15);
+        assert_eq!(image.iter().filter(|&&p| p != 0).count(), 2);
+
+        // Range B rotations only come out once range A has fallen silent
+        assert!(compositor.add_spoke(1, &spoke(0, 7408, vec![0; 512], true)).is_none());
+        assert!(compositor.add_spoke(1, &spoke(0, 7408, vec![0; 512], true)).is_some());
+        assert!(compositor.add_spoke(0, &spoke(0, 1852, vec![0; 256], true)).is_none());
+    }
+}
@@ -0,0 +1,218 @@
+//! Delta encoding of spokes for low bandwidth links.
+//!
+//! A client that requests the `mayara-delta` WebSocket subprotocol receives
+//! spokes that only contain the cells that changed since the previous spoke
+//! sent at the same angle. Such spokes have `delta` set, and their `data` is
+//! a sequence of runs:
+//!
+//! ```text
+//! start: u16 LE, length: u16 LE, length bytes of cell values
+//! ```
+//!
+//! Every `keyframe_interval` rotations all spokes are sent in full again, so a
+//! client that joins late or drops a message recovers within a few rotations.
+
+use std::collections::HashMap;
+
+use protobuf::Message;
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+use crate::protos::RadarMessage::RadarMessage;
+
+/// WebSocket subprotocol that selects delta encoded spokes
+pub const DELTA_SUBPROTOCOL: &str = "mayara-delta";
+
+/// Number of rotations between keyframes
+pub const DEFAULT_KEYFRAME_INTERVAL: u32 = 10;
+
+/// Unchanged cells between two changed runs that are cheaper to resend than
+/// to start a new run for (a run header is 4 bytes)
+const RUN_MERGE_GAP: usize = 4;
+
+const RUN_HEADER_LEN: usize = 4;
+
+/// Encode the changes from `previous` to `current` as runs
+///
+/// Both spokes must have the same length.
+pub fn encode_runs(previous: &[u8], current: &[u8]) -> Vec<u8> {
+    let mut runs = Vec::new();
+    let mut i = 0;
+
+    while i < current.len() {
+        if previous[i] == current[i] {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut end = i + 1; // exclusive end of this run
+        let mut j = end;
+        while j < current.len() && j - end <= RUN_MERGE_GAP {
+            if previous[j] != current[j] {
+                end = j + 1;
+            }
+            j += 1;
+        }
+        let end = end.min(start + u16::MAX as usize);
+
+        runs.extend_from_slice(&(start as u16).to_le_bytes());
+        runs.extend_from_slice(&((end - start) as u16).to_le_bytes());
+        runs.extend_from_slice(&current[start..end]);
+        i = end;
+    }
+    runs
+}
+
+/// Apply runs made by `encode_runs` to the previous spoke at that angle.
+///
+/// Returns `None` if the runs are malformed or do not fit the spoke.
+pub fn apply_runs(spoke: &mut [u8], mut runs: &[u8]) -> Option<()> {
+    while !runs.is_empty() {
+        if runs.len() < RUN_HEADER_LEN {
+            return None;
+        }
+        let start = u16::from_le_bytes([runs[0], runs[1]]) as usize;
+        let len = u16::from_le_bytes([runs[2], runs[3]]) as usize;
+        let cells = runs.get(RUN_HEADER_LEN..RUN_HEADER_LEN + len)?;
+        spoke.get_mut(start..start + len)?.copy_from_slice(cells);
+        runs = &runs[RUN_HEADER_LEN + len..];
+    }
+    Some(())
+}
+
+/// Per client encoder, remembers the last spoke sent at each angle
+pub struct DeltaEncoder {
+    previous: HashMap<u32, Vec<u8>>,
+    keyframe_interval: u32,
+    rotations: u32,
+}
+
+impl DeltaEncoder {
+    pub fn new(keyframe_interval: u32) -> Self {
+        DeltaEncoder {
+            previous: HashMap::new(),
+            keyframe_interval: keyframe_interval.max(1),
+            rotations: 0,
+        }
+    }
+
+    /// Delta encode a single spoke in place
+    pub fn encode_spoke(&mut self, spoke: &mut Spoke) {
+        if spoke.rotation_start == Some(true) {
+            self.rotations += 1;
+            if self.rotations >= self.keyframe_interval {
+                // Forgetting what was sent makes the next rotation a keyframe
+                self.rotations = 0;
+                self.previous.clear();
+            }
+        }
+
+        match self.previous.get_mut(&spoke.angle) {
+            Some(previous) if previous.len() == spoke.data.len() => {
+                let runs = encode_runs(previous, &spoke.data);
+                previous.copy_from_slice(&spoke.data);
+                spoke.data = runs;
+                spoke.delta = Some(true);
+            }
+            _ => {
+                self.previous.insert(spoke.angle, spoke.data.clone());
+            }
+        }
+    }
+
+    /// Delta encode a serialized RadarMessage as broadcast by the radar
+    pub fn encode_message(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut message = RadarMessage::parse_from_bytes(bytes).ok()?;
+        for spoke in message.spokes.iter_mut() {
+            self.encode_spoke(spoke);
+        }
+        message.write_to_bytes().ok()
+    }
+}
+
+/// Client side counterpart of `DeltaEncoder`, turns delta spokes back into
+/// full spokes
+#[derive(Default)]
+pub struct DeltaDecoder {
+    previous: HashMap<u32, Vec<u8>>,
+}
+
+impl DeltaDecoder {
+    /// Reconstruct a spoke in place; returns `None` when a delta arrives for
+    /// an angle without a keyframe yet
+    pub fn decode_spoke(&mut self, spoke: &mut Spoke) -> Option<()> {
+        if spoke.delta == Some(true) {
+            let previous = self.previous.get_mut(&spoke.angle)?;
+            apply_runs(previous, &spoke.data)?;
+            spoke.data = previous.clone();
+            spoke.delta = None;
+        } else {
+            self.previous.insert(spoke.angle, spoke.data.clone());
+        }
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoke(angle: u32, data: Vec<u8>) -> Spoke {
+        let mut spoke = Spoke::new();
+        spoke.angle = angle;
+        spoke.data = data;
+        spoke
+    }
+
+    #[test]
+    fn keyframe_plus_delta_round_trip() {
+        let mut encoder = DeltaEncoder::new(DEFAULT_KEYFRAME_INTERVAL);
+        let mut decoder = DeltaDecoder::default();
+
+        let keyframe: Vec<u8> = (0..512).map(|i| (i / 32) as u8).collect();
+        let mut changed = keyframe.clone();
+        changed[10] = 15;
+        changed[12] = 15;
+        changed[400..420].fill(0);
+
+        let mut first = spoke(100, keyframe.clone());
+        encoder.encode_spoke(&mut first);
+        assert_eq!(first.delta, None);
+        decoder.decode_spoke(&mut first).unwrap();
+        assert_eq!(first.data, keyframe);
+
+        let mut second = spoke(100, changed.clone());
+        encoder.encode_spoke(&mut second);
+        assert_eq!(second.delta, Some(true));
+        assert!(second.data.len() < 40);
+        decoder.decode_spoke(&mut second).unwrap();
+        assert_eq!(second.data, changed);
+
+        // An unchanged spoke carries no runs at all
+        let mut third = spoke(100, changed.clone());
+        encoder.encode_spoke(&mut third);
+        assert!(third.data.is_empty());
+        decoder.decode_spoke(&mut third).unwrap();
+        assert_eq!(third.data, changed);
+    }
+
+    #[test]
+    fn keyframe_every_interval() {
+        let mut encoder = DeltaEncoder::new(2);
+        let mut deltas = Vec::new();
+        for rotation in 0..4 {
+            let mut s = spoke(0, vec![rotation as u8; 8]);
+            s.rotation_start = Some(true);
+            encoder.encode_spoke(&mut s);
+            deltas.push(s.delta.is_some());
+        }
+        assert_eq!(deltas, vec![false, false, true, false]);
+    }
+
+    #[test]
+    fn malformed_runs_are_rejected() {
+        let mut data = vec![0u8; 8];
+        assert!(apply_runs(&mut data, &[6, 0, 4, 0, 1, 2, 3, 4]).is_none());
+        assert!(apply_runs(&mut data, &[0, 0, 4, 0, 1]).is_none());
+        assert!(apply_runs(&mut data, &[0, 0]).is_none());
+    }
+}
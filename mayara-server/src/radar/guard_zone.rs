@@ -0,0 +1,110 @@
+//! Guard zone alarms on the live spokes.
+//!
+//! The zones set through `/guardZones` are checked against every spoke the
+//! radar sends, after the noise floor, intensity curve and center blanking,
+//! so a zone alarms on what the clients see. The zone sensitivity is on a
+//! 0..255 scale: the radar's pixel values are scaled up to it, Doppler
+//! returns count as the strongest and target trail history not at all.
+//! A zone is clear again after three rotations in a row saw nothing in it.
+//!
+//! Every enter and exit is appended to the alarm log, see `alarm_log`.
+
+use std::sync::{Arc, RwLock};
+
+use mayara_core::guard_zones::{GuardZone, GuardZoneProcessor, GuardZoneStatus, ZoneAlert};
+
+use super::Legend;
+use crate::navdata;
+use crate::protos::RadarMessage::radar_message::Spoke;
+
+#[derive(Clone, Debug, Default)]
+pub struct GuardZones {
+    processor: Arc<RwLock<GuardZoneProcessor>>, // Shared by all clones of the RadarInfo
+}
+
+impl GuardZones {
+    pub fn status(&self) -> Vec<GuardZoneStatus> {
+        self.processor.read().unwrap().get_all_zone_status()
+    }
+
+    pub fn get(&self, zone_id: u32) -> Option<GuardZoneStatus> {
+        self.processor.read().unwrap().get_zone_status(zone_id)
+    }
+
+    /// Add a zone, or replace the zone with the same id
+    pub fn set(&self, zone: GuardZone) {
+        self.processor.write().unwrap().add_zone(zone);
+    }
+
+    pub fn remove(&self, zone_id: u32) -> bool {
+        self.processor.write().unwrap().remove_zone(zone_id)
+    }
+
+    /// Check a spoke at `bearing` (degrees from the bow) against the zones;
+    /// `rotation_start` ends the scan before it. Returns the zones entered
+    /// and left.
+    pub(crate) fn check(
+        &self,
+        spoke: &Spoke,
+        bearing: f64,
+        rotation_start: bool,
+        pixel_values: u8,
+        legend: &Legend,
+        range_offset: f64,
+    ) -> Vec<ZoneAlert> {
+        if self.processor.read().unwrap().zone_count() == 0 {
+            return Vec::new();
+        }
+        let timestamp = spoke.time.unwrap_or(0);
+        let (own_position, heading) = navdata::own_ship();
+        let mut processor = self.processor.write().unwrap();
+        let mut alerts = if rotation_start {
+            processor.end_revolution(timestamp)
+        } else {
+            Vec::new()
+        };
+        processor.set_range_scale(spoke.range as f64);
+        processor.set_range_offset(range_offset);
+        processor.set_own_ship(own_position, heading);
+        let data = intensities(&spoke.data, pixel_values, legend);
+        alerts.extend(processor.check_spoke(&data, bearing, timestamp));
+        alerts
+    }
+}
+
+/// The spoke cells on the 0..255 scale of the zone sensitivity
+fn intensities(data: &[u8], pixel_values: u8, legend: &Legend) -> Vec<u8> {
+    let max = pixel_values.saturating_sub(1).max(1) as u16;
+    data.iter()
+        .map(|&v| {
+            if v < pixel_values {
+                (v as u16 * 255 / max).min(255) as u8
+            } else if v == legend.doppler_approaching || v == legend.doppler_receding {
+                u8::MAX
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_scaled_to_the_sensitivity_range() {
+        let legend = Legend {
+            pixels: Vec::new(),
+            border: 255,
+            doppler_approaching: 16,
+            doppler_receding: 17,
+            history_start: 18,
+            strong_return: 10,
+        };
+        assert_eq!(
+            intensities(&[0, 1, 15, 16, 17, 18, 30], 16, &legend),
+            vec![0, 17, 255, 255, 255, 0, 0]
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Version handshake for the spokes WebSocket.
+//!
+//! A client offers one or more `Sec-WebSocket-Protocol` tokens of the form
+//!
+//! ```text
+//! mayara.v<version>[+<feature>...]      e.g. mayara.v1+delta
+//! ```
+//!
+//! The server picks the highest version it speaks, echoes that token, and
+//! sends the chosen configuration as the first (text) frame:
+//!
+//! ```json
+//! {"version":1,"features":["delta"]}
+//! ```
+//!
+//! Features the server does not know are dropped from the configuration, so
+//! a client must only rely on what the first frame lists. Clients that offer
+//! no usable token, including versions newer than the server, get the legacy
+//! stream: no configuration frame and plain RadarMessage protobufs. The bare
+//! `mayara-delta` subprotocol keeps working as before.
+//!
+//! With the `crc` feature every binary frame starts with an integrity
+//! header, see [`super::checksum`].
+
+use serde::Serialize;
+
+use super::delta::DELTA_SUBPROTOCOL;
+
+/// Prefix of the versioned spoke stream subprotocols
+pub const SPOKE_SUBPROTOCOL_PREFIX: &str = "mayara.v";
+
+/// Highest spoke stream version this server speaks
+pub const SPOKE_STREAM_VERSION: u32 = 1;
+
+/// Delta encoded spokes, see [`super::delta`]
+pub const FEATURE_DELTA: &str = "delta";
+
+/// Frames with a checksum header, see [`super::checksum`]
+pub const FEATURE_CRC: &str = "crc";
+
+const SUPPORTED_FEATURES: &[&str] = &[FEATURE_DELTA, FEATURE_CRC];
+
+/// Negotiated stream configuration, sent to the client as the first frame
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct StreamConfig {
+    pub version: u32,
+    pub features: Vec<String>,
+    /// The offered token that is echoed back to the client
+    #[serde(skip)]
+    pub subprotocol: String,
+}
+
+impl StreamConfig {
+    pub fn has(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// How spokes are sent to one client
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpokeFormat {
+    /// Plain RadarMessage protobufs, no handshake
+    Legacy,
+    /// The `mayara-delta` subprotocol, no handshake
+    LegacyDelta,
+    Negotiated(StreamConfig),
+}
+
+impl SpokeFormat {
+    /// The subprotocol to accept for this format, if any
+    pub fn subprotocol(&self) -> Option<String> {
+        match self {
+            SpokeFormat::Legacy => None,
+            SpokeFormat::LegacyDelta => Some(DELTA_SUBPROTOCOL.to_string()),
+            SpokeFormat::Negotiated(config) => Some(config.subprotocol.clone()),
+        }
+    }
+
+    pub fn delta(&self) -> bool {
+        match self {
+            SpokeFormat::Legacy => false,
+            SpokeFormat::LegacyDelta => true,
+            SpokeFormat::Negotiated(config) => config.has(FEATURE_DELTA),
+        }
+    }
+
+    pub fn crc(&self) -> bool {
+        match self {
+            SpokeFormat::Negotiated(config) => config.has(FEATURE_CRC),
+            _ => false,
+        }
+    }
+
+    /// The first frame to send, for negotiated streams
+    pub fn config_frame(&self) -> Option<String> {
+        match self {
+            SpokeFormat::Negotiated(config) => serde_json::to_string(config).ok(),
+            _ => None,
+        }
+    }
+}
+
+fn parse_offer(token: &str) -> Option<(u32, Vec<&str>)> {
+    let mut parts = token.strip_prefix(SPOKE_SUBPROTOCOL_PREFIX)?.split('+');
+    let version = parts.next()?.parse().ok()?;
+    Some((version, parts.collect()))
+}
+
+/// Choose the spoke format from the value of the `Sec-WebSocket-Protocol`
+/// request header (a comma separated list of tokens)
+pub fn negotiate(offered: Option<&str>) -> SpokeFormat {
+    let tokens: Vec<&str> = offered
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let best = tokens
+        .iter()
+        .filter_map(|token| parse_offer(token).map(|(version, features)| (token, version, features)))
+        .filter(|(_, version, _)| (1..=SPOKE_STREAM_VERSION).contains(version))
+        .max_by_key(|(_, version, _)| *version);
+
+    if let Some((token, version, features)) = best {
+        let features = SUPPORTED_FEATURES
+            .iter()
+            .filter(|supported| features.contains(*supported))
+            .map(|f| f.to_string())
+            .collect();
+        return SpokeFormat::Negotiated(StreamConfig {
+            version,
+            features,
+            subprotocol: token.to_string(),
+        });
+    }
+
+    if tokens.contains(&DELTA_SUBPROTOCOL) {
+        SpokeFormat::LegacyDelta
+    } else {
+        SpokeFormat::Legacy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiates_supported_version_and_features() {
+        let format = negotiate(Some("mayara.v1+delta+zstd, mayara-delta"));
+        let SpokeFormat::Negotiated(config) = &format else {
+            panic!("expected a negotiated stream, got {:?}", format);
+        };
+        assert_eq!(config.version, 1);
+        assert_eq!(config.features, vec!["delta".to_string()]);
+        assert_eq!(format.subprotocol().as_deref(), Some("mayara.v1+delta+zstd"));
+        assert_eq!(
+            format.config_frame().as_deref(),
+            Some(r#"{"version":1,"features":["delta"]}"#)
+        );
+        assert!(format.delta());
+        assert!(!format.crc());
+
+        let format = negotiate(Some("mayara.v1+crc+delta"));
+        assert_eq!(
+            format.config_frame().as_deref(),
+            Some(r#"{"version":1,"features":["delta","crc"]}"#)
+        );
+        assert!(format.crc());
+    }
+
+    #[test]
+    fn unsupported_version_falls_back_to_legacy() {
+        let format = negotiate(Some("mayara.v99+delta"));
+        assert_eq!(format, SpokeFormat::Legacy);
+        assert_eq!(format.subprotocol(), None);
+        assert_eq!(format.config_frame(), None);
+
+        assert_eq!(negotiate(None), SpokeFormat::Legacy);
+        assert_eq!(negotiate(Some("mayara.v99, mayara-delta")), SpokeFormat::LegacyDelta);
+    }
+}
@@ -0,0 +1,67 @@
+//! Gamma and contrast curve for spoke intensities.
+//!
+//! Weak returns are hard to see on a linear scale. A radar can be given an
+//! `intensity_gamma` and `intensity_contrast` in its preferences; the spokes
+//! are then passed through the curve before they are broadcast, so every
+//! client (and the rasterizer) sees the adjusted values. The default is the
+//! identity, and zero (no echo) always stays zero.
+
+/// Lookup table mapping the normal intensity values of a radar
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IntensityCurve {
+    table: Vec<u8>,
+}
+
+impl IntensityCurve {
+    /// A curve for a radar with `pixel_values` intensity values (0 is no echo).
+    ///
+    /// The intensity is scaled to 0..1 and raised to the power `gamma`, so a
+    /// gamma below 1 lifts faint echoes. `contrast` then stretches the result
+    /// around the middle value; 1 leaves it as is.
+    pub(crate) fn new(gamma: f64, contrast: f64, pixel_values: u8) -> Self {
+        let max = pixel_values.saturating_sub(1) as f64;
+        let table = (0..pixel_values)
+            .map(|value| {
+                if value == 0 || max == 0. {
+                    return value;
+                }
+                let x = value as f64 / max;
+                let y = 0.5 + (x.powf(gamma) - 0.5) * contrast;
+                (y.clamp(0., 1.) * max).round() as u8
+            })
+            .collect();
+        IntensityCurve { table }
+    }
+
+    pub(crate) fn is_identity(&self) -> bool {
+        self.table.iter().enumerate().all(|(i, &v)| i == v as usize)
+    }
+
+    /// Map the cells of a spoke; values beyond the normal intensities (Doppler,
+    /// target history) are special legend entries and are left alone
+    pub(crate) fn apply(&self, data: &mut [u8]) {
+        for cell in data.iter_mut() {
+            if let Some(&mapped) = self.table.get(*cell as usize) {
+                *cell = mapped;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamma_lifts_mid_intensity() {
+        assert!(IntensityCurve::new(1.0, 1.0, 65).is_identity());
+
+        let curve = IntensityCurve::new(0.5, 1.0, 65);
+        assert!(!curve.is_identity());
+
+        // 32 / 64 = 0.5, 0.5 ^ 0.5 = 0.7071, * 64 = 45.25
+        let mut data = vec![0, 32, 64, 70];
+        curve.apply(&mut data);
+        assert_eq!(data, vec![0, 45, 64, 70]);
+    }
+}
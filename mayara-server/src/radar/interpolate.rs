@@ -0,0 +1,110 @@
+//! Filling of small azimuth gaps.
+//!
+//! On lossy networks a spoke now and then goes missing, which leaves a radial
+//! wedge in the rendered image. With `--interpolate-gaps <N>` a gap of at most
+//! N azimuths between two spokes of the same rotation is filled with spokes
+//! interpolated between the two neighbours, marked as `interpolated`.
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+
+#[derive(Clone, Debug)]
+pub(crate) struct GapFiller {
+    max_gap: u32,
+    previous: Option<Spoke>,
+}
+
+impl GapFiller {
+    pub(crate) fn new(max_gap: u32) -> Self {
+        GapFiller {
+            max_gap,
+            previous: None,
+        }
+    }
+
+    /// Returns `spokes` with the gaps before each spoke filled in
+    pub(crate) fn fill(&mut self, spokes: Vec<Spoke>, spokes_per_revolution: u32) -> Vec<Spoke> {
+        let mut filled = Vec::with_capacity(spokes.len());
+        for spoke in spokes {
+            if let Some(previous) = &self.previous {
+                filled.extend(self.interpolate(previous, &spoke, spokes_per_revolution));
+            }
+            self.previous = Some(spoke.clone());
+            filled.push(spoke);
+        }
+        filled
+    }
+
+    fn interpolate(&self, previous: &Spoke, next: &Spoke, spokes_per_revolution: u32) -> Vec<Spoke> {
+        let n = spokes_per_revolution;
+        if n == 0
+            || next.rotation_start == Some(true)
+            || previous.delta == Some(true)
+            || next.delta == Some(true)
+            || previous.range != next.range
+        {
+            return Vec::new();
+        }
+
+        // The azimuth runs backwards when mirrored, take the short way round
+        let forward = (next.angle + n - previous.angle % n) % n;
+        let (steps, backwards) = if forward <= n / 2 {
+            (forward, false)
+        } else {
+            (n - forward, true)
+        };
+        if steps < 2 || steps - 1 > self.max_gap {
+            return Vec::new();
+        }
+
+        let len = previous.data.len().min(next.data.len());
+        (1..steps)
+            .map(|i| {
+                let weight = i as f32 / steps as f32;
+                let offset = if backwards { n - i } else { i };
+                let mut spoke = Spoke::new();
+                spoke.angle = (previous.angle + offset) % n;
+                spoke.bearing = previous.bearing.map(|b| (b + offset) % n);
+                spoke.range = previous.range;
+                spoke.time = previous.time;
+                spoke.lat = previous.lat;
+                spoke.lon = previous.lon;
+                spoke.interpolated = Some(true);
+                spoke.data = previous.data[..len]
+                    .iter()
+                    .zip(&next.data[..len])
+                    .map(|(&a, &b)| (a as f32 + (b as f32 - a as f32) * weight).round() as u8)
+                    .collect();
+                spoke
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoke(angle: u32, data: Vec<u8>) -> Spoke {
+        let mut spoke = Spoke::new();
+        spoke.angle = angle;
+        spoke.range = 1852;
+        spoke.data = data;
+        spoke
+    }
+
+    #[test]
+    fn single_missing_azimuth_is_filled() {
+        let mut filler = GapFiller::new(2);
+
+        let filled = filler.fill(vec![spoke(10, vec![0, 100]), spoke(12, vec![10, 200])], 2048);
+        assert_eq!(filled.len(), 3);
+        assert_eq!(filled[1].angle, 11);
+        assert_eq!(filled[1].interpolated, Some(true));
+        assert_eq!(filled[1].data, vec![5, 150]);
+        assert_eq!(filled[2].interpolated, None);
+
+        // Gaps larger than the limit are left alone
+        let filled = filler.fill(vec![spoke(16, vec![0, 0])], 2048);
+        assert_eq!(filled.len(), 1);
+    }
+}
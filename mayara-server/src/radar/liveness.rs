@@ -0,0 +1,110 @@
+//! Detect radars that disappear.
+//!
+//! A radar that is switched off or loses its network connection just goes
+//! quiet. Every spoke or report from a radar marks it as seen; when nothing
+//! has been seen for `--offline-timeout` seconds the radar is marked offline,
+//! and the locator forgets it so that discovery starts searching again. The
+//! receivers keep listening, so the first traffic after the radar returns
+//! marks it online again. Both transitions are logged and sent to the
+//! clients as the `link` value. Playback radars never go offline, and the
+//! test pattern and target script spokes are not traffic from the radar.
+
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Whether traffic from a radar is arriving
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkState {
+    Online,
+    Offline,
+}
+
+impl LinkState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkState::Online => "online",
+            LinkState::Offline => "offline",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Liveness {
+    last_seen: Instant,
+    state: LinkState,
+}
+
+impl Liveness {
+    /// A radar that was just found is online
+    pub(crate) fn new(now: Instant) -> Self {
+        Liveness {
+            last_seen: now,
+            state: LinkState::Online,
+        }
+    }
+
+    pub(crate) fn state(&self) -> LinkState {
+        self.state
+    }
+
+    /// Traffic arrived at `now`; returns the new state when it changed
+    pub(crate) fn seen(&mut self, now: Instant) -> Option<LinkState> {
+        self.last_seen = self.last_seen.max(now);
+        self.transition(LinkState::Online)
+    }
+
+    /// Returns the new state when nothing has been seen for `timeout` at `now`
+    pub(crate) fn check(&mut self, now: Instant, timeout: Duration) -> Option<LinkState> {
+        if now.saturating_duration_since(self.last_seen) < timeout {
+            return None;
+        }
+        self.transition(LinkState::Offline)
+    }
+
+    fn transition(&mut self, state: LinkState) -> Option<LinkState> {
+        if self.state == state {
+            return None;
+        }
+        self.state = state;
+        Some(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_marks_offline_and_traffic_marks_online_again() {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(10);
+        let mut liveness = Liveness::new(start);
+
+        // Spokes arriving every second keep the radar online
+        for s in 1..30 {
+            let now = start + Duration::from_secs(s);
+            assert_eq!(liveness.seen(now), None);
+            assert_eq!(liveness.check(now, timeout), None);
+        }
+        assert_eq!(liveness.state(), LinkState::Online);
+
+        // Silence: offline once, after the timeout
+        let last = start + Duration::from_secs(29);
+        assert_eq!(liveness.check(last + Duration::from_secs(9), timeout), None);
+        assert_eq!(
+            liveness.check(last + timeout, timeout),
+            Some(LinkState::Offline)
+        );
+        assert_eq!(liveness.check(last + timeout * 2, timeout), None);
+        assert_eq!(liveness.state(), LinkState::Offline);
+
+        // Traffic resumes
+        let back = last + timeout * 3;
+        assert_eq!(liveness.seen(back), Some(LinkState::Online));
+        assert_eq!(liveness.seen(back), None);
+        assert_eq!(liveness.check(back + Duration::from_secs(1), timeout), None);
+        assert_eq!(liveness.state(), LinkState::Online);
+    }
+}
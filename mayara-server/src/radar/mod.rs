@@ -5,23 +5,50 @@ use protobuf::Message;
 use serde::ser::{SerializeMap, Serializer};
 use serde::Serialize;
 use std::str::FromStr;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{
     collections::HashMap,
     fmt::{self, Display, Write},
     net::{Ipv4Addr, SocketAddrV4},
     sync::{Arc, RwLock},
 };
+use mayara_core::capabilities::{BlindSector, IntensityLegend};
+use mayara_core::guard_zones::ZoneAlert;
+use mayara_core::PowerState;
+use mayara_core::protocol::navico::{channel_index, ChannelTransmit, CHANNELS};
+use mayara_core::protocol::{AzimuthEncoding, RotationDirection};
 use thiserror::Error;
 use tokio_graceful_shutdown::SubsystemHandle;
 
-pub(crate) mod range;
+pub mod checksum;
+pub(crate) mod command_queue;
+pub mod composite;
+pub mod delta;
+pub mod guard_zone;
+pub mod handshake;
+pub(crate) mod intensity;
+pub(crate) mod interpolate;
+pub mod liveness;
+pub(crate) mod noise_floor;
+pub(crate) mod pacer;
+pub mod range;
+pub mod png;
+pub mod raster;
+pub mod roi;
+pub mod snapshot;
 pub(crate) mod spoke;
 pub(crate) mod target;
+pub mod target_script;
+pub(crate) mod test_pattern;
+pub mod throttle;
 pub(crate) mod trail;
 
+use crate::alarm_log::AlarmEvent;
 use crate::config::Persistence;
+use crate::decoders::Direction;
 use crate::locator::LocatorId;
+use crate::preferences::Preferences;
+use crate::protos::RadarMessage::radar_message::Spoke;
 use crate::protos::RadarMessage::RadarMessage;
 use crate::settings::{ControlError, ControlUpdate, ControlValue, SharedControls};
 use crate::{Brand, Session, TargetMode};
@@ -67,6 +94,10 @@ pub enum RadarError {
     LoginFailed,
     #[error("Invalid port number")]
     InvalidPort,
+    #[error("Invalid range table: {0}")]
+    InvalidRangeTable(String),
+    #[error("{0} is not supported")]
+    Unsupported(String),
     #[cfg(windows)]
     #[error("OS error: {0}")]
     OSError(String),
@@ -172,6 +203,18 @@ impl fmt::Display for GeoPosition {
     }
 }
 
+/// Build the stable key for a radar from its serial number
+pub(crate) fn stable_key(brand: Brand, serial_no: &str, which: Option<&str>) -> String {
+    let mut key = brand.to_string();
+    key.push_str("-");
+    key.push_str(serial_no);
+    if let Some(which) = which {
+        key.push_str("-");
+        key.push_str(which);
+    }
+    key
+}
+
 #[derive(Clone, Debug)]
 pub struct RadarInfo {
     session: Session,
@@ -184,6 +227,17 @@ pub struct RadarInfo {
     pub(crate) pixel_values: u8,          // How many values per pixel, 0..220 or so
     pub spokes_per_revolution: u16,       // How many spokes per rotation
     pub max_spoke_len: u16,               // Fixed for some radars, variable for others
+    pub(crate) spoke_len_limit: Option<u16>, // Configured length of spokes sent to clients
+    range_units: Arc<RwLock<Option<range::RangeUnitPreference>>>, // Overrides the global preference, shared by all clones
+    pub(crate) mirror_azimuth: bool,      // Antenna mounted reversed, reflect all angles
+    pub(crate) range_offset: i32,         // Meters added to range labels, from the preferences
+    pub(crate) intensity_curve: Option<intensity::IntensityCurve>, // Gamma/contrast from the preferences
+    pub(crate) center_blanking: Option<f64>, // Meters around the antenna zeroed in every spoke
+    pub(crate) azimuth_big_endian: Option<bool>, // Spoke azimuth byte order from the preferences
+    pub(crate) azimuth_modulus: Option<u16>, // Spoke azimuth counter per rotation from the preferences
+    pub(crate) orientation: raster::Orientation, // Which way is up in raster images, from the preferences
+    azimuth_offset: Arc<RwLock<f64>>,     // Degrees added to every spoke angle, shared by all clones
+    rotation_direction: Arc<RwLock<RotationDirection>>, // As the radar reports its azimuth, shared by all clones
     pub(crate) addr: SocketAddrV4,        // The IP address of the radar
     pub(crate) nic_addr: Ipv4Addr,        // IPv4 address of NIC via which radar can be reached
     pub(crate) spoke_data_addr: SocketAddrV4, // Where the radar will send data spokes
@@ -195,9 +249,18 @@ pub struct RadarInfo {
     pub(crate) range_detection: Option<RangeDetection>, // if Some, then ranges are flexible, detected and persisted
     pub(crate) doppler: bool,                           // Does it support Doppler?
     rotation_timestamp: Instant,
+    rotation_period: Arc<RwLock<spoke::RotationPeriodEstimator>>, // Shared by all clones, for the status
+    rotation_tracker: spoke::RotationTracker, // Marks the first spoke of each rotation
+    blind_sectors: Arc<RwLock<Vec<BlindSector>>>, // Learned by the radar, shared by all clones
+    liveness: Arc<RwLock<liveness::Liveness>>, // Traffic seen from the radar, shared by all clones
+    pub guard_zones: guard_zone::GuardZones, // Checked on every spoke, shared by all clones
 
     // Channels
     pub message_tx: tokio::sync::broadcast::Sender<Vec<u8>>, // Serialized RadarMessage
+    pacer_tx: Option<tokio::sync::mpsc::UnboundedSender<Vec<u8>>>, // Set with --spoke-smoothing
+    gap_filler: Option<interpolate::GapFiller>, // Set with --interpolate-gaps
+    noise_floor: Option<noise_floor::NoiseFloor>, // Set with --noise-floor
+    throttle: Option<throttle::SpokeThrottle>, // Set with --max-spoke-rate
 }
 
 impl RadarInfo {
@@ -221,24 +284,24 @@ impl RadarInfo {
         let (message_tx, _message_rx) = tokio::sync::broadcast::channel(32);
 
         let legend = default_legend(session.clone(), false, pixel_values);
+        let spoke_smoothing = session.read().unwrap().args.spoke_smoothing;
+        let interpolate_gaps = session.read().unwrap().args.interpolate_gaps;
+        let noise_floor = session.read().unwrap().args.noise_floor;
+        let spoke_budget = session.read().unwrap().spoke_budget.clone();
 
-        let info = RadarInfo {
+        let mut info = RadarInfo {
             session,
-            key: {
-                let mut key = brand.to_string();
-
-                if let Some(serial_no) = serial_no {
-                    key.push_str("-");
-                    key.push_str(serial_no);
-                } else {
+            key: match serial_no {
+                Some(serial_no) => stable_key(brand, serial_no, which),
+                None => {
+                    let mut key = brand.to_string();
                     write!(key, "-{}", &addr).unwrap();
+                    if let Some(which) = which {
+                        key.push_str("-");
+                        key.push_str(which);
+                    }
+                    key
                 }
-
-                if let Some(which) = which {
-                    key.push_str("-");
-                    key.push_str(which);
-                }
-                key
             },
             id: usize::MAX,
             locator_id,
@@ -248,6 +311,17 @@ impl RadarInfo {
             pixel_values,
             spokes_per_revolution: spokes_per_revolution as u16,
             max_spoke_len: max_spoke_len as u16,
+            spoke_len_limit: None,
+            range_units: Arc::new(RwLock::new(None)),
+            mirror_azimuth: false,
+            range_offset: 0,
+            intensity_curve: None,
+            center_blanking: None,
+            azimuth_big_endian: None,
+            azimuth_modulus: None,
+            orientation: raster::Orientation::default(),
+            azimuth_offset: Arc::new(RwLock::new(0.)),
+            rotation_direction: Arc::new(RwLock::new(RotationDirection::default())),
             addr,
             nic_addr,
             spoke_data_addr,
@@ -260,7 +334,26 @@ impl RadarInfo {
             controls,
             doppler,
             rotation_timestamp: Instant::now() - Duration::from_secs(2),
+            rotation_period: Arc::new(RwLock::new(spoke::RotationPeriodEstimator::default())),
+            rotation_tracker: spoke::RotationTracker::default(),
+            blind_sectors: Arc::new(RwLock::new(Vec::new())),
+            liveness: Arc::new(RwLock::new(liveness::Liveness::new(Instant::now()))),
+            guard_zones: guard_zone::GuardZones::default(),
+            pacer_tx: None,
+            gap_filler: (interpolate_gaps > 0).then(|| interpolate::GapFiller::new(interpolate_gaps)),
+            noise_floor: (noise_floor > 0).then(|| {
+                noise_floor::NoiseFloor::new(noise_floor, max_spoke_len as u16)
+            }),
+            throttle: spoke_budget.map(throttle::SpokeThrottle::new),
         };
+        info.controls.set_radar_key(&info.key);
+        if let Some(ms) = spoke_smoothing.filter(|&ms| ms > 0) {
+            info.pacer_tx = Some(pacer::spawn_pacer(
+                info.key.clone(),
+                Duration::from_millis(ms),
+                info.message_tx.clone(),
+            ));
+        }
 
         log::debug!("Created RadarInfo {:?}", info);
         info
@@ -278,6 +371,17 @@ impl RadarInfo {
         self.key.to_owned()
     }
 
+    /// Key under which the persisted settings of this radar are stored
+    ///
+    /// Once the serial number is known this is derived from it, so it stays
+    /// the same even if the discovery key (address based) changes.
+    pub fn config_key(&self) -> String {
+        match &self.serial_no {
+            Some(serial_no) => stable_key(self.brand, serial_no, self.which.as_deref()),
+            None => self.key(),
+        }
+    }
+
     pub fn set_doppler(&mut self, doppler: bool) {
         if doppler != self.doppler {
             self.legend = default_legend(self.session.clone(), doppler, self.pixel_values);
@@ -286,6 +390,17 @@ impl RadarInfo {
         self.doppler = doppler;
     }
 
+    /// The intensity legend published in the capabilities, with the Doppler
+    /// cell values this radar's legend actually uses
+    pub fn intensity_legend(&self) -> IntensityLegend {
+        let legend = IntensityLegend::new(self.pixel_values);
+        if self.doppler {
+            legend.with_doppler(self.legend.doppler_approaching, self.legend.doppler_receding)
+        } else {
+            legend
+        }
+    }
+
     pub fn set_pixel_values(&mut self, pixel_values: u8) {
         if pixel_values != self.pixel_values {
             self.legend = default_legend(self.session.clone(), self.doppler, pixel_values);
@@ -304,6 +419,7 @@ impl RadarInfo {
             diff,
             rpm
         );
+        self.rotation_period.write().unwrap().add(diff);
 
         if diff < 10000. && diff > 300. {
             let _ = self.controls.set_string("rotation_speed", rpm);
@@ -327,6 +443,7 @@ impl RadarInfo {
             diff,
             rpm
         );
+        self.rotation_period.write().unwrap().add(diff);
 
         if diff < 10000. && diff > 300. {
             let _ = self.controls.set_string("rotation_speed", rpm);
@@ -336,6 +453,71 @@ impl RadarInfo {
         }
     }
 
+    /// Rotations per minute, estimated from the measured rotation periods
+    pub fn rpm(&self) -> Option<f64> {
+        self.rotation_period.read().unwrap().rpm()
+    }
+
+    /// Milliseconds per rotation, the estimate the RPM is derived from
+    pub fn rotation_period_ms(&self) -> Option<f64> {
+        self.rotation_period.read().unwrap().period_ms()
+    }
+
+    /// "A" or "B" for a channel of a dual range radar
+    pub fn which(&self) -> Option<&str> {
+        self.which.as_deref()
+    }
+
+    /// The serial number the radar was found with
+    pub fn serial_no(&self) -> Option<&str> {
+        self.serial_no.as_deref()
+    }
+
+    /// Sectors the radar reports as masked by obstructions
+    pub fn blind_sectors(&self) -> Vec<BlindSector> {
+        self.blind_sectors.read().unwrap().clone()
+    }
+
+    /// Whether traffic from the radar is arriving, see `liveness`
+    pub fn link_state(&self) -> liveness::LinkState {
+        self.liveness.read().unwrap().state()
+    }
+
+    /// Spokes or reports arrived from the radar
+    pub(crate) fn mark_seen(&self) {
+        let changed = self.liveness.write().unwrap().seen(Instant::now());
+        if let Some(state) = changed {
+            log::info!("{}: traffic resumed, radar is online again", self.key);
+            self.controls.send_link_state(state);
+        }
+    }
+
+    /// Mark the radar offline when nothing arrived for `timeout`; returns
+    /// true when it just went offline
+    pub(crate) fn check_liveness(&self, now: Instant, timeout: Duration) -> bool {
+        if self.locator_id == LocatorId::Playback {
+            return false; // A recording has no network traffic to lose
+        }
+        let changed = self.liveness.write().unwrap().check(now, timeout);
+        if let Some(state) = changed {
+            log::warn!(
+                "{}: nothing received for {}s, radar is offline",
+                self.key,
+                timeout.as_secs()
+            );
+            self.controls.send_link_state(state);
+        }
+        changed.is_some()
+    }
+
+    pub(crate) fn set_blind_sectors(&self, sectors: Vec<BlindSector>) {
+        let mut current = self.blind_sectors.write().unwrap();
+        if *current != sectors {
+            log::info!("{}: blind sectors learned by the radar {:?}", self.key(), sectors);
+            *current = sectors;
+        }
+    }
+
     pub(crate) fn set_ranges(&mut self, ranges: Ranges) -> Result<(), RadarError> {
         self.controls
             .set_valid_ranges("range", &ranges)?;
@@ -343,7 +525,59 @@ impl RadarInfo {
         Ok(())
     }
 
-    pub(crate) fn broadcast_radar_message(&self, message: RadarMessage) {
+    pub(crate) fn broadcast_radar_message(&mut self, message: RadarMessage) {
+        self.mark_seen();
+        self.broadcast_spokes(message);
+    }
+
+    /// Broadcast spokes without counting them as traffic from the radar, for
+    /// the spokes the server makes up: the test pattern and target scripts
+    pub(crate) fn broadcast_spokes(&mut self, mut message: RadarMessage) {
+        // The receivers have dropped what is over the spoke budget
+        let throttling = self.throttle.as_ref().is_some_and(|t| t.is_dropping());
+        if self.throttle.is_some() && message.spokes.is_empty() {
+            return;
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .ok();
+        let spokes = self.spokes_per_revolution.max(1) as u32;
+        for spoke in message.spokes.iter_mut() {
+            if spoke.time.is_none() {
+                spoke.time = now;
+            }
+            if let Some(noise_floor) = &mut self.noise_floor {
+                noise_floor.apply(spoke, self.pixel_values, self.spokes_per_revolution);
+            }
+            if let Some(curve) = &self.intensity_curve {
+                curve.apply(&mut spoke.data);
+            }
+            // Mirroring is its own inverse: track the rotation in the order
+            // the radar sends the spokes
+            let angle = self.spoke_angle(spoke.angle as SpokeBearing);
+            let rotation_start = self.rotation_tracker.is_rotation_start(angle);
+            if rotation_start {
+                spoke.rotation_start = Some(true);
+            }
+            let alerts = self.guard_zones.check(
+                spoke,
+                spoke.angle as f64 * 360. / spokes as f64,
+                rotation_start,
+                self.pixel_values,
+                &self.legend,
+                self.range_offset as f64,
+            );
+            self.record_alarms(alerts);
+        }
+        // The spokes the throttle drops are not gaps to fill
+        if let Some(gap_filler) = self.gap_filler.as_mut().filter(|_| !throttling) {
+            message.spokes = gap_filler.fill(
+                std::mem::take(&mut message.spokes),
+                self.spokes_per_revolution as u32,
+            );
+        }
+
         let mut bytes = Vec::new();
         message
             .write_to_vec(&mut bytes)
@@ -352,6 +586,12 @@ impl RadarInfo {
         // Send the message to all receivers, normally the web client(s)
         // We send raw bytes to avoid encoding overhead in each web client.
         // This strategy will change when clients want different protocols.
+        if let Some(pacer_tx) = &self.pacer_tx {
+            if pacer_tx.send(bytes).is_err() {
+                log::trace!("{}: Spoke pacer has stopped", self.key);
+            }
+            return;
+        }
         match self.message_tx.send(bytes) {
             Err(e) => {
                 log::trace!("{}: Dropping received spoke: {}", self.key, e);
@@ -362,6 +602,189 @@ impl RadarInfo {
         }
     }
 
+    /// Append a target entering or leaving a guard zone to the alarm log,
+    /// and take the alarm snapshot when `--alarm-snapshots` is set
+    fn record_alarms(&self, alerts: Vec<ZoneAlert>) {
+        if alerts.is_empty() {
+            return;
+        }
+        let radar_id = format!("radar-{}", self.id);
+        let (alarm_log, alarm_snapshots) = {
+            let session = self.session.read().unwrap();
+            (session.alarm_log.clone(), session.alarm_snapshots.clone())
+        };
+        for alert in alerts {
+            let event = AlarmEvent::new(&radar_id, alert.state.into(), &alert);
+            log::info!(
+                "{}: guard zone {} {:?} at {:.1} deg {:.0} m",
+                self.key,
+                event.zone_id,
+                event.kind,
+                event.bearing,
+                event.distance
+            );
+            if let Some(alarm_snapshots) = &alarm_snapshots {
+                alarm_snapshots.on_alarm(self, &event);
+            }
+            if let Err(e) = alarm_log.lock().unwrap().record(event) {
+                log::warn!("{}: cannot write the alarm log: {}", self.key, e);
+            }
+        }
+    }
+
+    /// Write a report the receiver does not know to the `--log-unknown` file
+    pub(crate) fn log_unknown_report(&self, data: &[u8]) {
+        let unknown_log = self.session.read().unwrap().unknown_log.clone();
+        if let Some(unknown_log) = unknown_log {
+            let port = Some(self.report_addr.port());
+            let result = unknown_log
+                .lock()
+                .unwrap()
+                .record_unknown(Some(self.brand), port, Direction::FromRadar, data);
+            if let Err(e) = result {
+                log::warn!("{}: cannot write the unknown packet log: {}", self.key, e);
+            }
+        }
+    }
+
+    /// The brand's spoke azimuth encoding, with the overrides from the preferences
+    pub(crate) fn azimuth_encoding(&self, default: AzimuthEncoding) -> AzimuthEncoding {
+        default.with_overrides(self.azimuth_big_endian, self.azimuth_modulus)
+    }
+
+    /// Start learning the noise floor from scratch, see `noise_floor`
+    pub(crate) fn reset_noise_floor(&mut self) {
+        if let Some(noise_floor) = &mut self.noise_floor {
+            noise_floor.reset();
+        }
+    }
+
+    /// This radar's own range unit preference, if it overrides the global one
+    pub fn range_units(&self) -> Option<range::RangeUnitPreference> {
+        *self.range_units.read().unwrap()
+    }
+
+    pub fn set_range_units(&self, units: Option<range::RangeUnitPreference>) {
+        *self.range_units.write().unwrap() = units;
+    }
+
+    /// Label for a range of this radar, in its own units if it has a preference
+    /// and otherwise in `default_units`
+    pub fn format_range(&self, meters: i32, default_units: range::RangeUnitPreference) -> String {
+        range::format_range_in(
+            meters + self.range_offset,
+            self.range_units().unwrap_or(default_units),
+        )
+    }
+
+    /// Meters added to the range labels of this radar, from its preferences
+    pub fn range_offset(&self) -> i32 {
+        self.range_offset
+    }
+
+    pub fn orientation(&self) -> raster::Orientation {
+        self.orientation
+    }
+
+    /// Length of the spokes sent to clients: the configured limit if there is
+    /// one, otherwise the length the radar itself sends.
+    pub fn output_spoke_len(&self) -> u16 {
+        self.spoke_len_limit.unwrap_or(self.max_spoke_len)
+    }
+
+    /// True while the radar has not told how many spokes or cells it sends;
+    /// nothing that depends on the spoke geometry can be set up yet
+    pub fn is_initializing(&self) -> bool {
+        self.output_spoke_len() == 0 || self.spokes_per_revolution == 0
+    }
+
+    /// Degrees, 0..360, added to the angle of every spoke as it is received
+    pub fn azimuth_offset(&self) -> f64 {
+        *self.azimuth_offset.read().unwrap()
+    }
+
+    /// Turn a spoke just decoded by the receiver by the azimuth offset, so
+    /// that trails, ARPA and guard zones all see the turned picture
+    pub(crate) fn apply_azimuth_offset(&self, spoke: &mut Spoke) {
+        let spokes = self.spokes_per_revolution.max(1) as u32;
+        let offset = (self.azimuth_offset() * spokes as f64 / 360.).round() as u32 % spokes;
+        if offset != 0 {
+            spoke.angle = (spoke.angle + offset) % spokes;
+            spoke.bearing = spoke.bearing.map(|b| (b + offset) % spokes);
+        }
+    }
+
+    /// Count a frame of `spokes` that just arrived against the
+    /// `--max-spoke-rate` budget. The receivers follow with `keep_spoke` for
+    /// each of its spokes, before converting it or adding it to the trails.
+    pub(crate) fn throttle_frame(&mut self, spokes: usize) {
+        if let Some(throttle) = &mut self.throttle {
+            throttle.measure(spokes, Instant::now());
+        }
+    }
+
+    /// Whether the next spoke of the frame fits in the spoke budget
+    pub(crate) fn keep_spoke(&mut self) -> bool {
+        self.throttle.as_mut().map_or(true, |t| t.keep_next())
+    }
+
+    /// Zero the cells within the `center_blanking` radius; the receivers do
+    /// this before the trails see the spoke
+    pub(crate) fn apply_center_blanking(&self, spoke: &mut Spoke) {
+        if let Some(radius) = self.center_blanking {
+            spoke::blank_center(&mut spoke.data, spoke.range, self.range_offset, radius);
+        }
+    }
+
+    /// Turn the picture by `degrees` clockwise in software, without sending
+    /// anything to the radar; for aligning the image of new hardware
+    pub fn set_azimuth_offset(&self, degrees: f64) {
+        *self.azimuth_offset.write().unwrap() = degrees.rem_euclid(360.);
+    }
+
+    /// The angle to use for a spoke the radar sent at `angle`, mirrored when
+    /// the antenna is mounted reversed
+    pub(crate) fn spoke_angle(&self, angle: SpokeBearing) -> SpokeBearing {
+        if self.mirror_azimuth {
+            spoke::mirror_angle(angle, self.spokes_per_revolution)
+        } else {
+            angle
+        }
+    }
+
+    /// The order in which the radar reports its azimuth, as decoded from its
+    /// reports. Counter-clockwise spokes are normalized before plotting.
+    pub(crate) fn rotation_direction(&self) -> RotationDirection {
+        *self.rotation_direction.read().unwrap()
+    }
+
+    pub(crate) fn set_rotation_direction(&self, direction: RotationDirection) {
+        *self.rotation_direction.write().unwrap() = direction;
+    }
+
+    /// Broadcast one revolution of the rendering test pattern at the current range
+    pub fn send_test_pattern(&mut self) {
+        let range = self
+            .controls
+            .get("range")
+            .and_then(|c| c.value)
+            .filter(|r| *r > 0.)
+            .unwrap_or(NAUTICAL_MILE as f32);
+        test_pattern::emit_test_pattern(self, range as u32);
+    }
+
+    /// Play a script of fake targets at the current range, one revolution
+    /// per second, until its last event
+    pub fn run_target_script(&self, script: target_script::TargetScript) {
+        let range = self
+            .controls
+            .get("range")
+            .and_then(|c| c.value)
+            .filter(|r| *r > 0.)
+            .unwrap_or(NAUTICAL_MILE as f32);
+        target_script::spawn_script(self.clone(), range as u32, script);
+    }
+
     ///
     ///  forward_output is activated in all starts of radars when cli args.output
     ///  is true:
@@ -438,6 +861,7 @@ impl SharedRadars {
             radars: Arc::new(RwLock::new(Radars {
                 info: HashMap::new(),
                 persistent_data: Persistence::new(),
+                preferences: Preferences::load_default(),
             })),
         }
     }
@@ -469,6 +893,7 @@ impl SharedRadars {
             radars
                 .persistent_data
                 .update_info_from_persistence(&mut new_info);
+            radars.preferences.apply(&mut new_info);
 
             if new_info.id == usize::MAX {
                 new_info.id = max_id + 1;
@@ -547,12 +972,59 @@ impl SharedRadars {
         None
     }
 
+    /// Transmit state of each channel of the dual range unit `info` belongs
+    /// to, range A first, as the status reports of the channels last told;
+    /// empty for radars with a single channel
+    pub fn channel_transmit(&self, info: &RadarInfo) -> Vec<ChannelTransmit> {
+        let Some(base) = info.which.as_deref().and_then(|which| info.key.strip_suffix(which)) else {
+            return Vec::new();
+        };
+        let radars = self.radars.read().unwrap();
+        CHANNELS
+            .iter()
+            .filter_map(|&which| {
+                let channel = radars.info.get(&format!("{}{}", base, which))?;
+                let power = channel.controls.get("power").and_then(|c| c.value);
+                Some(ChannelTransmit {
+                    index: channel_index(which)?,
+                    transmit: power == Some(Status::Transmit as i32 as f32),
+                })
+            })
+            .collect()
+    }
+
+    /// The channels of the dual range unit `info` belongs to that have been
+    /// found, range A first; empty for radars with a single channel
+    pub fn channels(&self, info: &RadarInfo) -> Vec<RadarInfo> {
+        let Some(base) = info.which.as_deref().and_then(|which| info.key.strip_suffix(which)) else {
+            return Vec::new();
+        };
+        let radars = self.radars.read().unwrap();
+        CHANNELS
+            .iter()
+            .filter_map(|&which| radars.info.get(&format!("{}{}", base, which)).cloned())
+            .collect()
+    }
+
     /// Get radar by internal key (e.g., "Playback-filename" or "Furuno-serial-A")
     pub fn get_by_key(&self, key: &str) -> Option<RadarInfo> {
         let radars = self.radars.read().unwrap();
         radars.info.get(key).cloned()
     }
 
+    /// Mark the radars that have been silent for `timeout` offline, and
+    /// return the ones that just went offline
+    pub fn check_liveness(&self, timeout: Duration) -> Vec<RadarInfo> {
+        let now = Instant::now();
+        let radars = self.radars.read().unwrap();
+        radars
+            .info
+            .values()
+            .filter(|info| info.check_liveness(now, timeout))
+            .cloned()
+            .collect()
+    }
+
     pub fn remove(&self, key: &str) {
         let mut radars = self.radars.write().unwrap();
 
@@ -739,6 +1211,7 @@ fn model_name_to_radar_model(name: &str) -> crate::brand::furuno::RadarModel {
 struct Radars {
     pub info: HashMap<String, RadarInfo>,
     pub persistent_data: Persistence,
+    pub preferences: Preferences,
 }
 
 pub struct Statistics {
@@ -802,6 +1275,42 @@ impl FromStr for Status {
     }
 }
 
+impl From<PowerState> for Status {
+    fn from(power: PowerState) -> Self {
+        match power {
+            PowerState::Off => Status::Off,
+            PowerState::Standby | PowerState::Unknown => Status::Standby,
+            PowerState::Transmit => Status::Transmit,
+            PowerState::Warming => Status::Preparing,
+        }
+    }
+}
+
+impl From<Status> for PowerState {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Off => PowerState::Off,
+            Status::Standby => PowerState::Standby,
+            Status::Transmit => PowerState::Transmit,
+            Status::Preparing => PowerState::Warming,
+        }
+    }
+}
+
+/// The power state held by the value of a power control, a `Status`
+pub fn power_state(value: Option<f32>) -> PowerState {
+    let Some(value) = value else {
+        return PowerState::Unknown;
+    };
+    let status = match value as i32 {
+        0 => Status::Off,
+        2 => Status::Transmit,
+        3 => Status::Preparing,
+        _ => Status::Standby,
+    };
+    status.into()
+}
+
 // The actual values are not arbitrary: these are the exact values as reported
 // by HALO radars, simplifying the navico::report code.
 #[derive(Copy, Clone, Debug, Primitive)]
@@ -973,7 +1482,245 @@ fn default_legend(session: Session, doppler: bool, pixel_values: u8) -> Legend {
 
 #[cfg(test)]
 mod tests {
-    use super::default_legend;
+    use super::{default_legend, RadarInfo, RadarMessage};
+    use crate::locator::LocatorId;
+    use crate::protos::RadarMessage::radar_message::Spoke;
+    use crate::settings::SharedControls;
+    use crate::Brand;
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    #[test]
+    fn azimuth_offset_turns_the_spoke_angles() {
+        let session = crate::Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let info = RadarInfo::new(
+            session,
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            2048,
+            64,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        );
+        let turn = |info: &RadarInfo, angle: u32| {
+            let mut spoke = Spoke::new();
+            spoke.angle = angle;
+            spoke.bearing = Some(angle);
+            info.apply_azimuth_offset(&mut spoke);
+            (spoke.angle, spoke.bearing)
+        };
+
+        assert_eq!(turn(&info, 100), (100, Some(100)));
+        // A quarter turn is 512 of 2048 spokes, and wraps past the bow
+        info.clone().set_azimuth_offset(90.);
+        assert_eq!(info.azimuth_offset(), 90.);
+        assert_eq!(turn(&info, 100), (612, Some(612)));
+        assert_eq!(turn(&info, 1800), (264, Some(264)));
+
+        info.set_azimuth_offset(-90.);
+        assert_eq!(info.azimuth_offset(), 270.);
+        assert_eq!(turn(&info, 100), (1636, Some(1636)));
+    }
+
+    #[test]
+    fn center_blanking_clears_the_spoke_in_the_receiver() {
+        let session = crate::Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let mut info = RadarInfo::new(
+            session,
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            2048,
+            256,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        );
+        let mut spoke = Spoke::new();
+        spoke.range = 256;
+        spoke.data = vec![15; 256];
+        info.apply_center_blanking(&mut spoke);
+        assert!(spoke.data.iter().all(|&c| c == 15));
+
+        // 1 m per cell
+        info.center_blanking = Some(30.);
+        info.apply_center_blanking(&mut spoke);
+        assert!(spoke.data[..30].iter().all(|&c| c == 0));
+        assert!(spoke.data[30..].iter().all(|&c| c == 15));
+    }
+
+    #[test]
+    fn liveness_skips_playback_and_made_up_spokes() {
+        use super::liveness::LinkState;
+        use std::time::{Duration, Instant};
+
+        let session = crate::Session::new_fake();
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let radar = |locator_id| {
+            RadarInfo::new(
+                session.clone(),
+                locator_id,
+                Brand::Navico,
+                Some("1234"),
+                None,
+                16,
+                2048,
+                64,
+                addr,
+                Ipv4Addr::LOCALHOST,
+                addr,
+                addr,
+                addr,
+                SharedControls::new(session.clone(), HashMap::new()),
+                false,
+            )
+        };
+        let later = Instant::now() + Duration::from_secs(3600);
+        let timeout = Duration::from_secs(10);
+
+        assert!(!radar(LocatorId::Playback).check_liveness(later, timeout));
+
+        let mut info = radar(LocatorId::Gen3Plus);
+        assert!(info.check_liveness(later, timeout));
+        info.send_test_pattern();
+        assert_eq!(info.link_state(), LinkState::Offline);
+        info.broadcast_radar_message(RadarMessage::new());
+        assert_eq!(info.link_state(), LinkState::Online);
+    }
+
+    #[test]
+    fn unknown_reports_are_written_to_the_unknown_log() {
+        use crate::unknown_log::UnknownLog;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("unknown.ndjson");
+        let session = crate::Session::new_fake();
+        session.write().unwrap().unknown_log = Some(UnknownLog::open_shared(&path, 1).unwrap());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 6679);
+        let info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            2048,
+            64,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            SharedControls::new(session, HashMap::new()),
+            false,
+        );
+
+        info.log_unknown_report(&[0x77, 0xc4, 0x01]);
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let line: serde_json::Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(line["data"], "77c401");
+        assert_eq!(line["brand"], Brand::Navico.to_string());
+        assert_eq!(line["port"], 6679);
+        assert_eq!(line["direction"], "fromRadar");
+    }
+
+    #[test]
+    fn guard_zone_enter_and_exit_are_logged_from_the_spokes() {
+        use crate::alarm_log::{AlarmKind, AlarmLog};
+        use mayara_core::guard_zones::GuardZone;
+        use std::sync::{Arc, Mutex};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let session = crate::Session::new_fake();
+        session.write().unwrap().alarm_log = Arc::new(Mutex::new(
+            AlarmLog::open(&dir.path().join("alarms.ndjson"), 10).unwrap(),
+        ));
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let mut info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            16,
+            64,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        );
+        info.id = 3;
+        // Zones set through the API reach the spoke receiver's clone
+        info.clone().guard_zones.set(GuardZone::new_ring(1, 400., 1000.));
+
+        // A target at 90 degrees, ~720 m, for one rotation, then five empty ones
+        for rotation in 0..6 {
+            for angle in 0..16 {
+                let mut spoke = Spoke::new();
+                spoke.angle = angle;
+                spoke.range = 1852;
+                spoke.data = vec![0; 64];
+                if rotation == 0 && angle == 4 {
+                    spoke.data[25] = 15;
+                }
+                let mut message = RadarMessage::new();
+                message.spokes.push(spoke);
+                info.broadcast_radar_message(message);
+            }
+        }
+
+        let events = session
+            .read()
+            .unwrap()
+            .alarm_log
+            .lock()
+            .unwrap()
+            .recent("radar-3", 10);
+        let kinds: Vec<AlarmKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![AlarmKind::Enter, AlarmKind::Exit]);
+        for event in &events {
+            assert_eq!(event.zone_id, 1);
+            assert_eq!(event.bearing, 90.);
+            assert!((700. ..750.).contains(&event.distance), "{}", event.distance);
+        }
+    }
+
+    #[test]
+    fn power_state_follows_the_status_of_the_power_control() {
+        use super::{power_state, Status};
+        use mayara_core::PowerState;
+
+        for power in [PowerState::Off, PowerState::Standby, PowerState::Transmit, PowerState::Warming] {
+            let value = Status::from(power) as i32 as f32;
+            assert_eq!(power_state(Some(value)), power);
+        }
+        assert_eq!(Status::from(PowerState::Unknown), Status::Standby);
+        assert_eq!(power_state(None), PowerState::Unknown);
+    }
 
     #[test]
     fn legend() {
@@ -0,0 +1,175 @@
+//! Adaptive noise floor.
+//!
+//! Receiver noise and sea clutter differ with distance and bearing, so a
+//! single threshold either hides faint targets or lets the noise through.
+//! With `--noise-floor <N>` the floor is learned for every (azimuth, distance)
+//! cell over roughly N rotations and subtracted from the spokes before they
+//! are broadcast. The floor drops at once to a lower echo and rises only by
+//! 1/N of the difference to a higher one, so it follows the quietest returns
+//! of the cell: persistent noise is removed while a target that was not there
+//! before keeps its strength. The floor never rises above a quarter of the
+//! intensity range, so strong stationary echoes such as land stay visible.
+//!
+//! The floor is kept per spoke cell, limited to `MAX_AZIMUTH_BINS` azimuths
+//! by the spoke length, and starts over when the range or the number of
+//! spokes per revolution changes. Some radars only report that number once
+//! their model is known, so it is passed with every spoke.
+
+use std::fmt;
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+
+/// Radars with more spokes share a bin between neighbouring azimuths
+const MAX_AZIMUTH_BINS: usize = 2048;
+
+/// Fixed point fraction bits of the floor
+const FRACTION_BITS: u32 = 8;
+
+#[derive(Clone)]
+pub(crate) struct NoiseFloor {
+    rotations: u32,
+    spokes_per_revolution: u16,
+    max_spoke_len: u16,
+    azimuth_bins: usize,
+    range: u32,
+    /// Per bin, in 1/256 intensity; empty until the first spoke
+    floor: Vec<u16>,
+}
+
+// The floor itself is too big to log with the RadarInfo
+impl fmt::Debug for NoiseFloor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NoiseFloor")
+            .field("rotations", &self.rotations)
+            .field("bins", &(self.azimuth_bins, self.max_spoke_len))
+            .finish()
+    }
+}
+
+impl NoiseFloor {
+    pub(crate) fn new(rotations: u32, max_spoke_len: u16) -> Self {
+        NoiseFloor {
+            rotations: rotations.max(1),
+            spokes_per_revolution: 0,
+            max_spoke_len,
+            azimuth_bins: 1,
+            range: 0,
+            floor: Vec::new(),
+        }
+    }
+
+    /// Forget what was learned, e.g. after maneuvering
+    pub(crate) fn reset(&mut self) {
+        self.floor.clear();
+    }
+
+    /// Subtract the floor from the normal intensities of the spoke and learn
+    /// from it; values beyond the normal intensities (Doppler, target
+    /// history) are left alone
+    pub(crate) fn apply(
+        &mut self,
+        spoke: &mut Spoke,
+        pixel_values: u8,
+        spokes_per_revolution: u16,
+    ) {
+        if spoke.delta == Some(true) || spokes_per_revolution == 0 {
+            return;
+        }
+        if spokes_per_revolution != self.spokes_per_revolution {
+            self.spokes_per_revolution = spokes_per_revolution;
+            self.azimuth_bins = (spokes_per_revolution as usize).clamp(1, MAX_AZIMUTH_BINS);
+            self.floor.clear();
+        }
+        // Some radars only learn their spoke length from the first spokes
+        if spoke.data.len() > self.max_spoke_len as usize {
+            self.max_spoke_len = spoke.data.len().min(u16::MAX as usize) as u16;
+            self.floor.clear();
+        }
+        let len = self.max_spoke_len as usize;
+        if spoke.range != self.range || self.floor.is_empty() {
+            self.range = spoke.range;
+            self.floor.clear();
+            self.floor.resize(self.azimuth_bins * len, 0);
+        }
+        let bin = (spoke.angle % self.spokes_per_revolution as u32) as usize * self.azimuth_bins
+            / self.spokes_per_revolution as usize;
+        let floors = &mut self.floor[bin * len..(bin + 1) * len];
+        let ceiling = ((pixel_values.saturating_sub(1) / 4) as u16) << FRACTION_BITS;
+
+        for (cell, floor) in spoke.data.iter_mut().zip(floors.iter_mut()) {
+            if *cell >= pixel_values {
+                continue;
+            }
+            let value = (*cell as u16) << FRACTION_BITS;
+            let subtract = ((*floor + (1 << (FRACTION_BITS - 1))) >> FRACTION_BITS) as u8;
+            if value <= *floor {
+                *floor = value;
+            } else {
+                let rise = ((value - *floor) as u32 / self.rotations).max(1) as u16;
+                *floor = (*floor + rise).min(ceiling);
+            }
+            *cell = cell.saturating_sub(subtract);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoke(angle: u32, data: Vec<u8>) -> Spoke {
+        let mut spoke = Spoke::new();
+        spoke.angle = angle;
+        spoke.range = 1852;
+        spoke.data = data;
+        spoke
+    }
+
+    #[test]
+    fn persistent_noise_is_suppressed_and_a_transient_target_survives() {
+        let mut noise_floor = NoiseFloor::new(4, 4);
+        // Noise rising with distance, with a little flicker
+        let noise = |rotation: u32| vec![1, 2, 3 + (rotation % 2) as u8, 3];
+
+        let mut cleaned = Vec::new();
+        for rotation in 0..40 {
+            let mut s = spoke(100, noise(rotation));
+            noise_floor.apply(&mut s, 16, 2048);
+            cleaned = s.data;
+        }
+        assert!(cleaned.iter().all(|&v| v <= 1), "{:?}", cleaned);
+
+        // A target appears for one rotation in the noisiest cell
+        let mut s = spoke(100, vec![1, 2, 12, 3]);
+        noise_floor.apply(&mut s, 16, 2048);
+        assert!(s.data[2] >= 9, "{:?}", s.data);
+        assert!(s.data[0] == 0 && s.data[1] == 0);
+
+        // Other azimuths have their own floor, Doppler values are left alone
+        let mut s = spoke(1000, vec![3, 3, 17, 3]);
+        noise_floor.apply(&mut s, 16, 2048);
+        assert_eq!(s.data, vec![3, 3, 17, 3]);
+    }
+
+    #[test]
+    fn the_floor_follows_the_spokes_per_revolution_known_later() {
+        let mut noise_floor = NoiseFloor::new(4, 4);
+        // Nothing is learned while the spoke count is still unknown
+        let mut s = spoke(100, vec![3, 3, 3, 3]);
+        noise_floor.apply(&mut s, 16, 0);
+        assert_eq!(s.data, vec![3, 3, 3, 3]);
+
+        for _ in 0..40 {
+            let mut s = spoke(100, vec![3, 3, 3, 3]);
+            noise_floor.apply(&mut s, 16, 250);
+        }
+        let mut s = spoke(100, vec![3, 3, 3, 3]);
+        noise_floor.apply(&mut s, 16, 250);
+        assert_eq!(s.data, vec![0, 0, 0, 0]);
+
+        // A new spoke count maps the angles onto other bins, so the floor starts over
+        let mut s = spoke(100, vec![3, 3, 3, 3]);
+        noise_floor.apply(&mut s, 16, 2048);
+        assert_eq!(s.data, vec![3, 3, 3, 3]);
+    }
+}
@@ -0,0 +1,130 @@
+//! Smoothing of bursty spoke streams.
+//!
+//! Some radars send a rotation's worth of spokes in a few large bursts. When
+//! `--spoke-smoothing <ms>` is given, messages are queued and released onto
+//! the broadcast channel at the rate they arrived over the last window,
+//! instead of all at once. A message is never held longer than the window.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use tokio::sync::{broadcast, mpsc};
+
+/// Number of release ticks per smoothing window
+const TICKS_PER_WINDOW: u32 = 10;
+
+pub(crate) struct SpokePacer {
+    window: Duration,
+    tick: Duration,
+    queue: VecDeque<(Instant, Vec<u8>)>,
+    arrivals: VecDeque<Instant>, // Arrival times within the last window
+}
+
+impl SpokePacer {
+    pub(crate) fn new(window: Duration) -> Self {
+        let tick = (window / TICKS_PER_WINDOW).max(Duration::from_millis(1));
+        SpokePacer {
+            window,
+            tick,
+            queue: VecDeque::new(),
+            arrivals: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    pub(crate) fn push(&mut self, now: Instant, message: Vec<u8>) {
+        self.queue.push_back((now, message));
+        self.arrivals.push_back(now);
+    }
+
+    /// Messages to send on this tick: the average arrival rate over the last
+    /// window, plus anything that has waited a full window already.
+    pub(crate) fn release(&mut self, now: Instant) -> Vec<Vec<u8>> {
+        while matches!(self.arrivals.front(), Some(t) if now.duration_since(*t) > self.window) {
+            self.arrivals.pop_front();
+        }
+        let per_tick = (self.arrivals.len() as u128 * self.tick.as_nanos())
+            .div_ceil(self.window.as_nanos().max(1))
+            .max(1) as usize;
+
+        let mut released = Vec::new();
+        while let Some((arrived, _)) = self.queue.front() {
+            if released.len() >= per_tick && now.duration_since(*arrived) < self.window {
+                break;
+            }
+            released.push(self.queue.pop_front().unwrap().1);
+        }
+        released
+    }
+}
+
+/// Start a task that paces messages onto `message_tx`, returns the sender
+/// that takes the place of `message_tx` for the radar.
+pub(crate) fn spawn_pacer(
+    key: String,
+    window: Duration,
+    message_tx: broadcast::Sender<Vec<u8>>,
+) -> mpsc::UnboundedSender<Vec<u8>> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut pacer = SpokePacer::new(window);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(pacer.tick());
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                message = rx.recv() => match message {
+                    Some(message) => pacer.push(Instant::now(), message),
+                    None => break,
+                },
+                _ = interval.tick() => {
+                    for message in pacer.release(Instant::now()) {
+                        if message_tx.send(message).is_err() {
+                            log::trace!("{}: Dropping paced spoke, no receivers", key);
+                        }
+                    }
+                }
+            }
+        }
+        log::debug!("{}: spoke pacer stopped", key);
+    });
+    tx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn burst_is_released_at_capped_rate() {
+        let window = Duration::from_millis(100);
+        let mut pacer = SpokePacer::new(window);
+        let start = Instant::now();
+
+        for i in 0..100u8 {
+            pacer.push(start, vec![i]);
+        }
+
+        // 100 messages per window is 10 per tick
+        let mut now = start;
+        let first = pacer.release(now);
+        assert_eq!(first.len(), 10);
+        assert_eq!(first[0], vec![0]);
+
+        let mut total = first.len();
+        for _ in 1..9 {
+            now += pacer.tick();
+            let released = pacer.release(now);
+            assert_eq!(released.len(), 10);
+            total += released.len();
+        }
+
+        // After a full window nothing is held back any longer
+        let rest = pacer.release(start + window);
+        assert_eq!(total + rest.len(), 100);
+        assert_eq!(rest.last(), Some(&vec![99]));
+    }
+}
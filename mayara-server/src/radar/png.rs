@@ -0,0 +1,363 @@
+//! PNG rendering of rotation snapshots.
+//!
+//! `GET /v2/api/radars/{radar_id}/snapshot?format=png` paints the captured
+//! rotation head up, the same way as the cartesian raster stream, colors it
+//! with the radar's legend and draws range rings on top. The ring labels are
+//! in the range units the user chose for the radar (or the global choice),
+//! and when the heading is known a north arrow in the top right corner shows
+//! where north is. The labels and heading are also stored as PNG text chunks
+//! so tools can read them without looking at the pixels.
+
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::range::{format_range_in, RangeUnitPreference};
+use crate::radar::raster::Rasterizer;
+use crate::radar::Legend;
+
+pub const DEFAULT_PNG_SIZE: u16 = 512;
+pub const DEFAULT_RING_COUNT: usize = 4;
+
+/// Palette entries used for the overlays, above any legend value
+pub const RING_COLOR: u8 = 253;
+pub const LABEL_COLOR: u8 = 254;
+pub const ARROW_COLOR: u8 = 255;
+
+/// Distance of the north arrow center from the top right corner, and its
+/// length from the center to the tip
+const ARROW_OFFSET: usize = 20;
+const ARROW_LENGTH: usize = 14;
+
+/// What to draw on top of the radar image
+#[derive(Clone, Debug)]
+pub struct SnapshotStyle {
+    pub size: u16,
+    pub rings: usize,
+    pub units: RangeUnitPreference,
+    /// Heading in degrees true, draws the north arrow when known
+    pub heading: Option<f64>,
+}
+
+impl Default for SnapshotStyle {
+    fn default() -> Self {
+        SnapshotStyle {
+            size: DEFAULT_PNG_SIZE,
+            rings: DEFAULT_RING_COUNT,
+            units: RangeUnitPreference::default(),
+            heading: None,
+        }
+    }
+}
+
+/// A rendered snapshot as a palette image, before PNG encoding
+pub struct SnapshotImage {
+    pub size: usize,
+    /// Palette indices, row by row from the top
+    pub pixels: Vec<u8>,
+    palette: Vec<[u8; 4]>,
+    /// PNG text chunks (keyword, text)
+    pub text: Vec<(String, String)>,
+}
+
+impl SnapshotImage {
+    pub fn pixel(&self, row: usize, col: usize) -> u8 {
+        self.pixels[row * self.size + col]
+    }
+
+    fn set(&mut self, row: i32, col: i32, value: u8) {
+        if row >= 0 && col >= 0 && (row as usize) < self.size && (col as usize) < self.size {
+            self.pixels[row as usize * self.size + col as usize] = value;
+        }
+    }
+
+    /// Encode as an 8 bit palette PNG
+    pub fn encode(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(self.size as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(self.size as u32).to_be_bytes());
+        ihdr.extend_from_slice(&[8, 3, 0, 0, 0]); // 8 bit, palette, deflate, no filter, no interlace
+        write_chunk(&mut png, b"IHDR", &ihdr);
+
+        let plte: Vec<u8> = self.palette.iter().flat_map(|c| c[..3].to_vec()).collect();
+        write_chunk(&mut png, b"PLTE", &plte);
+        let trns: Vec<u8> = self.palette.iter().map(|c| c[3]).collect();
+        write_chunk(&mut png, b"tRNS", &trns);
+
+        for (keyword, text) in &self.text {
+            let mut chunk = keyword.as_bytes().to_vec();
+            chunk.push(0);
+            chunk.extend_from_slice(text.as_bytes());
+            write_chunk(&mut png, b"tEXt", &chunk);
+        }
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(self.size) {
+            // Writing to a Vec does not fail
+            let _ = encoder.write_all(&[0]); // No filter
+            let _ = encoder.write_all(row);
+        }
+        let idat = encoder.finish().unwrap_or_default();
+        write_chunk(&mut png, b"IDAT", &idat);
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+}
+
+/// Paint a captured rotation with range rings, their labels and the north arrow
+pub fn render_snapshot(
+    message: &RadarMessage,
+    spokes_per_revolution: u16,
+    legend: &Legend,
+    style: &SnapshotStyle,
+) -> SnapshotImage {
+    let mut rasterizer = Rasterizer::new(style.size, spokes_per_revolution);
+    for spoke in message.spokes.iter() {
+        rasterizer.add_spoke(spoke);
+    }
+    let size = rasterizer.size();
+    let range = message
+        .spokes
+        .iter()
+        .find(|spoke| !spoke.data.is_empty())
+        .map(|spoke| spoke.range)
+        .unwrap_or(0);
+
+    let mut image = SnapshotImage {
+        size,
+        pixels: rasterizer.image().to_vec(),
+        palette: palette(legend),
+        text: vec![("Software".to_string(), "mayara".to_string())],
+    };
+
+    let center = (size / 2) as i32;
+    if range > 0 && style.rings > 0 {
+        let mut labels = Vec::new();
+        for ring in 1..=style.rings {
+            let radius = (size / 2 * ring / style.rings) as i32 - 1;
+            draw_circle(&mut image, center, radius, RING_COLOR);
+
+            let label = format_range_in((range as usize * ring / style.rings) as i32, style.units);
+            draw_text(&mut image, center - radius + 2, center + 2, &label, LABEL_COLOR);
+            labels.push(label);
+        }
+        image.text.push(("Range".to_string(), format_range_in(range as i32, style.units)));
+        image.text.push(("Rings".to_string(), labels.join(",")));
+    }
+    image
+        .text
+        .push(("Units".to_string(), style.units.as_str().to_string()));
+
+    if let Some(heading) = style.heading {
+        draw_north_arrow(&mut image, heading);
+        image.text.push(("Heading".to_string(), format!("{:.1}", heading)));
+    }
+    image
+}
+
+fn palette(legend: &Legend) -> Vec<[u8; 4]> {
+    let mut palette: Vec<[u8; 4]> = legend
+        .pixels
+        .iter()
+        .take(RING_COLOR as usize)
+        .map(|lookup| [lookup.color.r, lookup.color.g, lookup.color.b, lookup.color.a])
+        .collect();
+    palette.resize(RING_COLOR as usize, [0, 0, 0, 255]);
+    palette.push([128, 128, 128, 255]); // RING_COLOR
+    palette.push([255, 255, 255, 255]); // LABEL_COLOR
+    palette.push([255, 64, 64, 255]); // ARROW_COLOR
+    palette
+}
+
+fn draw_circle(image: &mut SnapshotImage, center: i32, radius: i32, value: u8) {
+    let steps = (radius.max(1) * 8) as usize;
+    for step in 0..steps {
+        let (sin, cos) = (step as f64 / steps as f64 * std::f64::consts::TAU).sin_cos();
+        image.set(
+            center - (cos * radius as f64).round() as i32,
+            center + (sin * radius as f64).round() as i32,
+            value,
+        );
+    }
+}
+
+fn draw_line(image: &mut SnapshotImage, from: (f64, f64), to: (f64, f64), value: u8) {
+    let steps = ((to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil() as usize).max(1);
+    for step in 0..=steps {
+        let t = step as f64 / steps as f64;
+        image.set(
+            (from.0 + (to.0 - from.0) * t).round() as i32,
+            (from.1 + (to.1 - from.1) * t).round() as i32,
+            value,
+        );
+    }
+}
+
+/// The image is head up, so north is `heading` degrees counter clockwise from up
+fn draw_north_arrow(image: &mut SnapshotImage, heading: f64) {
+    let center = (ARROW_OFFSET as f64, (image.size - ARROW_OFFSET) as f64);
+    let point = |angle: f64, length: f64| {
+        let (sin, cos) = (angle - heading).to_radians().sin_cos();
+        (center.0 - cos * length, center.1 + sin * length)
+    };
+    let length = ARROW_LENGTH as f64;
+    let tip = point(0., length);
+    draw_line(image, point(180., length), tip, ARROW_COLOR);
+    draw_line(image, tip, point(150., length * 0.5), ARROW_COLOR);
+    draw_line(image, tip, point(210., length * 0.5), ARROW_COLOR);
+}
+
+/// 3x5 pixel glyphs for range labels, one bit per pixel, top row first
+fn glyph(c: char) -> Option<[u8; 5]> {
+    Some(match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        'n' => [0b000, 0b110, 0b101, 0b101, 0b101],
+        'm' => [0b000, 0b111, 0b111, 0b101, 0b101],
+        'k' => [0b100, 0b101, 0b110, 0b101, 0b101],
+        'i' => [0b010, 0b000, 0b010, 0b010, 0b010],
+        _ => return None,
+    })
+}
+
+fn draw_text(image: &mut SnapshotImage, row: i32, col: i32, text: &str, value: u8) {
+    for (n, c) in text.chars().enumerate() {
+        let Some(rows) = glyph(c) else {
+            continue; // Spaces and anything without a glyph
+        };
+        for (y, bits) in rows.iter().enumerate() {
+            for x in 0..3 {
+                if bits & (0b100 >> x) != 0 {
+                    image.set(row + y as i32, col + (n * 4 + x) as i32, value);
+                }
+            }
+        }
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// CRC-32 as used by PNG (ISO 3309, reflected, polynomial 0xEDB88320)
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protos::RadarMessage::radar_message::Spoke;
+    use crate::radar::{Color, Lookup, PixelType};
+
+    fn legend() -> Legend {
+        Legend {
+            pixels: (0..16)
+                .map(|v| Lookup {
+                    r#type: PixelType::Normal,
+                    color: Color { r: v * 16, g: v * 16, b: v * 16, a: 255 },
+                })
+                .collect(),
+            border: 255,
+            doppler_approaching: 255,
+            doppler_receding: 255,
+            history_start: 255,
+            strong_return: 10,
+        }
+    }
+
+    fn rotation() -> RadarMessage {
+        let mut message = RadarMessage::new();
+        for angle in 0..256 {
+            let mut spoke = Spoke::new();
+            spoke.angle = angle;
+            spoke.range = 1852;
+            spoke.data = vec![0; 128];
+            message.spokes.push(spoke);
+        }
+        message
+    }
+
+    fn text<'a>(image: &'a SnapshotImage, keyword: &str) -> Option<&'a str> {
+        image
+            .text
+            .iter()
+            .find(|(k, _)| k == keyword)
+            .map(|(_, t)| t.as_str())
+    }
+
+    #[test]
+    fn ring_labels_follow_units_and_north_arrow_needs_heading() {
+        let message = rotation();
+        let style = SnapshotStyle {
+            size: 128,
+            units: RangeUnitPreference::Metric,
+            ..Default::default()
+        };
+        let metric = render_snapshot(&message, 256, &legend(), &style);
+        assert_eq!(text(&metric, "Range"), Some("1.85 km"));
+        assert_eq!(text(&metric, "Rings"), Some("463 m,926 m,1.39 km,1.85 km"));
+        assert_eq!(text(&metric, "Heading"), None);
+        assert!(!metric.pixels.contains(&ARROW_COLOR));
+
+        // The label of the outer ring starts at its top, right of center: "1.85 km"
+        // has a 1 whose top pixel is in the middle column of the glyph
+        assert_eq!(metric.pixel(3, 64 + 2 + 1), LABEL_COLOR);
+        assert_eq!(metric.pixel(1, 64), RING_COLOR);
+
+        let nautical = render_snapshot(
+            &message,
+            256,
+            &legend(),
+            &SnapshotStyle {
+                units: RangeUnitPreference::Nautical,
+                heading: Some(90.),
+                ..style
+            },
+        );
+        assert_eq!(text(&nautical, "Range"), Some("1 nm"));
+        assert_eq!(text(&nautical, "Heading"), Some("90.0"));
+        assert_ne!(metric.pixels, nautical.pixels);
+
+        // Heading east, so north points left from the arrow center
+        let (row, col) = (ARROW_OFFSET, 128 - ARROW_OFFSET);
+        assert_eq!(nautical.pixel(row, col - ARROW_LENGTH), ARROW_COLOR);
+        assert_eq!(nautical.pixel(row, col + ARROW_LENGTH), ARROW_COLOR);
+        assert_ne!(nautical.pixel(row - ARROW_LENGTH, col), ARROW_COLOR);
+
+        let png = nautical.encode();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.windows(10).any(|w| w == b"Range\x001 nm"));
+    }
+
+    #[test]
+    fn png_crc_matches_reference() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+    }
+}
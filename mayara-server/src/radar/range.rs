@@ -7,11 +7,13 @@
 // Navico, Raymarine and Garmin radars use a numeric value,
 // while Furuno uses an index into a list of ranges.
 
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
 
 use mayara_core::{models, Brand};
 
-use crate::radar::NAUTICAL_MILE_F64;
+use crate::radar::{RadarError, NAUTICAL_MILE_F64};
 
 use super::NAUTICAL_MILE;
 
@@ -66,6 +68,50 @@ impl Range {
         !Self::metric(self.distance)
     }
 
+    /// Unit used when displaying this range
+    pub fn unit(&self) -> &'static str {
+        if !self.is_metric() {
+            "nm"
+        } else if self.distance >= 1000 {
+            "km"
+        } else {
+            "m"
+        }
+    }
+
+    /// Short label for tight spaces, such as "0.06NM", "1.5k" or "500m"
+    pub fn compact_label(&self) -> String {
+        let v = self.distance;
+        if !self.is_metric() {
+            format!("{}NM", compact_number(v as f64 / NAUTICAL_MILE_F64))
+        } else if v >= 1000 {
+            format!("{}k", compact_number(v as f64 / 1000.0))
+        } else {
+            format!("{}m", v)
+        }
+    }
+
+    /// Label and unit of this range in the preferred units
+    pub fn label_in(&self, units: RangeUnitPreference) -> (String, &'static str) {
+        let v = self.distance as f64;
+        match units {
+            RangeUnitPreference::Nautical if !self.is_nautical() => {
+                (format!("{} nm", compact_number(v / NAUTICAL_MILE_F64)), "nm")
+            }
+            RangeUnitPreference::Metric if !self.is_metric() => {
+                if v >= 1000.0 {
+                    (format!("{} km", compact_number(v / 1000.0)), "km")
+                } else {
+                    (format!("{} m", self.distance), "m")
+                }
+            }
+            RangeUnitPreference::Statute => {
+                (format!("{} mi", compact_number(v / STATUTE_MILE_F64)), "mi")
+            }
+            _ => (self.to_string(), self.unit()),
+        }
+    }
+
     fn mark(&mut self) {
         self.index = 1; // Mark this range as used
     }
@@ -75,6 +121,56 @@ impl Range {
     }
 }
 
+/// Meters in a statute mile
+const STATUTE_MILE_F64: f64 = 1609.344;
+
+/// Units in which ranges are labelled for clients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RangeUnitPreference {
+    /// Nautical or metric, whichever the range itself is
+    #[default]
+    Auto,
+    Nautical,
+    Metric,
+    /// Statute miles
+    Statute,
+}
+
+impl RangeUnitPreference {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RangeUnitPreference::Auto => "auto",
+            RangeUnitPreference::Nautical => "nautical",
+            RangeUnitPreference::Metric => "metric",
+            RangeUnitPreference::Statute => "statute",
+        }
+    }
+}
+
+impl FromStr for RangeUnitPreference {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(RangeUnitPreference::Auto),
+            "nautical" | "nm" => Ok(RangeUnitPreference::Nautical),
+            "metric" | "km" => Ok(RangeUnitPreference::Metric),
+            "statute" | "mi" => Ok(RangeUnitPreference::Statute),
+            _ => Err(format!(
+                "Unknown range unit '{}', use auto, nautical, metric or statute",
+                s
+            )),
+        }
+    }
+}
+
+/// At most two decimals, without trailing zeros
+fn compact_number(v: f64) -> String {
+    let s = format!("{:.2}", v);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
 impl PartialOrd for Range {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         Some(self.cmp(other))
@@ -183,6 +279,27 @@ impl Ranges {
         Self::new(r)
     }
 
+    /// Create ranges from a user supplied table, e.g. from the config file.
+    /// The table must be non-empty, strictly increasing and positive.
+    pub fn new_from_override(ranges: &[i32]) -> Result<Self, RadarError> {
+        if ranges.is_empty() {
+            return Err(RadarError::InvalidRangeTable("table is empty".to_string()));
+        }
+        if let Some(r) = ranges.iter().find(|&&r| r <= 0) {
+            return Err(RadarError::InvalidRangeTable(format!(
+                "range {} is not positive",
+                r
+            )));
+        }
+        if let Some(w) = ranges.windows(2).find(|w| w[0] >= w[1]) {
+            return Err(RadarError::InvalidRangeTable(format!(
+                "range {} does not follow {} in increasing order",
+                w[1], w[0]
+            )));
+        }
+        Ok(Self::new_by_distance(ranges))
+    }
+
     pub fn push(&mut self, range: Range) -> bool {
         if self.all.iter().any(|r| r.distance == range.distance) {
             // If the range already exists, do not add it again
@@ -265,6 +382,148 @@ impl Display for Ranges {
     }
 }
 
+/// A range formatted for display in a client
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormattedRange {
+    pub index: usize,
+    pub meters: i32,
+    pub label: String,
+    pub unit: &'static str,
+}
+
+/// How ranges are formatted for clients
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatOptions {
+    /// Short labels for small gauges, see `FormattedRange::compact`
+    #[serde(default)]
+    pub compact: bool,
+    /// Units of the labels; a unit other than `auto` overrides `compact`
+    #[serde(default)]
+    pub units: RangeUnitPreference,
+}
+
+impl FormattedRange {
+    pub fn with_options(range: &Range, options: &FormatOptions) -> Self {
+        if options.units != RangeUnitPreference::Auto {
+            let (label, unit) = range.label_in(options.units);
+            FormattedRange {
+                label,
+                unit,
+                ..Self::from(range)
+            }
+        } else if options.compact {
+            Self::compact(range)
+        } else {
+            Self::from(range)
+        }
+    }
+
+    /// Like `from`, but with a short label ("0.06NM" rather than "1/16 nm").
+    /// The unit stays the same.
+    pub fn compact(range: &Range) -> Self {
+        FormattedRange {
+            label: range.compact_label(),
+            ..Self::from(range)
+        }
+    }
+}
+
+impl From<&Range> for FormattedRange {
+    fn from(range: &Range) -> Self {
+        FormattedRange {
+            index: range.index,
+            meters: range.distance,
+            label: range.to_string(),
+            unit: range.unit(),
+        }
+    }
+}
+
+/// Label for a range in meters, as shown for the radar's own ranges
+pub fn format_range(meters: i32) -> String {
+    Range::initial(meters).to_string()
+}
+
+/// Label for a range in meters in the given units
+pub fn format_range_in(meters: i32, units: RangeUnitPreference) -> String {
+    Range::initial(meters).label_in(units).0
+}
+
+/// Format all ranges of a radar, in increasing order
+pub fn format_range_table(ranges: &Ranges) -> Vec<FormattedRange> {
+    format_range_table_with(ranges, &FormatOptions::default())
+}
+
+/// Format all ranges of a radar, in increasing order, with the given options
+pub fn format_range_table_with(ranges: &Ranges, options: &FormatOptions) -> Vec<FormattedRange> {
+    ranges
+        .all
+        .iter()
+        .map(|range| FormattedRange::with_options(range, options))
+        .collect()
+}
+
+/// Quick select ranges in nautical miles, for clients that offer buttons
+const NAUTICAL_PRESETS: [f64; 11] = [0.125, 0.25, 0.5, 1., 2., 3., 6., 12., 24., 48., 96.];
+/// Quick select ranges in meters, used with the metric preference
+const METRIC_PRESETS: [i32; 9] = [250, 500, 1000, 2000, 5000, 10000, 20000, 50000, 100000];
+/// How far a preset may be from the table entry it is snapped to, as a ratio
+const PRESET_SNAP_RATIO: f64 = 1.5;
+
+/// A short list of the common ranges up to `max_range` meters, each snapped
+/// to the nearest range of the table and labelled in `units`, in
+/// increasing order. Presets without a table entry close to them are left
+/// out, so a radar whose smallest range is 1/4 nm has no 1/8 nm button.
+pub fn range_presets(ranges: &Ranges, max_range: i32, units: RangeUnitPreference) -> Vec<FormattedRange> {
+    let options = FormatOptions {
+        units,
+        ..Default::default()
+    };
+    range_presets_with(ranges, max_range, &options)
+}
+
+/// Like `range_presets`, labelled with the given options
+pub fn range_presets_with(ranges: &Ranges, max_range: i32, options: &FormatOptions) -> Vec<FormattedRange> {
+    let metric = match options.units {
+        RangeUnitPreference::Metric => true,
+        RangeUnitPreference::Auto => ranges.nautical.is_empty(),
+        RangeUnitPreference::Nautical | RangeUnitPreference::Statute => false,
+    };
+    let presets: Vec<f64> = if metric {
+        METRIC_PRESETS.iter().map(|&m| m as f64).collect()
+    } else {
+        NAUTICAL_PRESETS.iter().map(|nm| nm * NAUTICAL_MILE_F64).collect()
+    };
+    // Snap to ranges of the same kind when the table has any
+    let same_kind = if metric { &ranges.metric } else { &ranges.nautical };
+    let table = if same_kind.is_empty() { &ranges.all } else { same_kind };
+    let candidates: Vec<&Range> = table.iter().filter(|r| r.distance <= max_range).collect();
+
+    let mut snapped: Vec<&Range> = Vec::new();
+    for preset in presets {
+        let nearest = candidates.iter().min_by(|a, b| {
+            let a = (a.distance as f64 / preset).ln().abs();
+            let b = (b.distance as f64 / preset).ln().abs();
+            a.total_cmp(&b)
+        });
+        let Some(&range) = nearest else {
+            break;
+        };
+        if (range.distance as f64 / preset).ln().abs() > PRESET_SNAP_RATIO.ln() {
+            continue;
+        }
+        if snapped.last().map_or(true, |last| last.distance < range.distance) {
+            snapped.push(range);
+        }
+    }
+    snapped
+        .into_iter()
+        .map(|range| FormattedRange::with_options(range, options))
+        .collect()
+}
+
 pub enum RangeDetectionResult {
     NoRange,
     Complete(Ranges, i32),
@@ -366,3 +625,73 @@ impl RangeDetection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact_labels_are_shorter() {
+        let cases = [
+            (116, "1/16 nm", "0.06NM"),
+            (463, "1/4 nm", "0.25NM"),
+            (1852, "1 nm", "1NM"),
+            (2778, "1.5 nm", "1.5NM"),
+            (500, "500 m", "500m"),
+            (1500, "1.5 km", "1.5k"),
+            (24000, "24 km", "24k"),
+        ];
+        for (meters, normal, compact) in cases {
+            let range = Range::new(meters, 3);
+            let formatted = FormattedRange::from(&range);
+            let options = FormatOptions {
+                compact: true,
+                ..Default::default()
+            };
+            let short = FormattedRange::with_options(&range, &options);
+            assert_eq!(formatted.label, normal);
+            assert_eq!(short.label, compact);
+            assert_eq!(short.unit, formatted.unit);
+            assert_eq!(short.meters, meters);
+            assert_eq!(short.index, 3);
+        }
+    }
+
+    #[test]
+    fn presets_of_a_six_mile_radar_are_table_entries() {
+        let ranges = Ranges::new_by_distance(&[
+            57, 116, 231, 463, 926, 1389, 1852, 2778, 3704, 5556, 7408, 11112,
+        ]);
+        let presets = range_presets(&ranges, 11112, RangeUnitPreference::Auto);
+        let labels: Vec<&str> = presets.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            ["1/8 nm", "1/4 nm", "1/2 nm", "1 nm", "2 nm", "3 nm", "6 nm"]
+        );
+        for preset in &presets {
+            assert_eq!(ranges.all[preset.index].distance(), preset.meters);
+            assert!(preset.meters <= 11112);
+        }
+        assert!(presets.windows(2).all(|w| w[0].meters < w[1].meters));
+
+        // A lower maximum cuts the list, without going past it
+        let presets = range_presets(&ranges, 3000, RangeUnitPreference::Nautical);
+        assert_eq!(presets.last().unwrap().label, "1.5 nm");
+
+        // Presets in other units are still snapped to the table
+        let presets = range_presets(&ranges, 11112, RangeUnitPreference::Metric);
+        assert!(presets.iter().all(|p| p.unit == "m" || p.unit == "km"));
+        assert!(presets.iter().all(|p| ranges.all[p.index].distance() == p.meters));
+
+        assert!(range_presets(&Ranges::empty(), 11112, RangeUnitPreference::Auto).is_empty());
+
+        // Compact labels for small gauges
+        let options = FormatOptions {
+            compact: true,
+            ..Default::default()
+        };
+        let presets = range_presets_with(&ranges, 3704, &options);
+        let labels: Vec<&str> = presets.iter().map(|p| p.label.as_str()).collect();
+        assert_eq!(labels, ["0.12NM", "0.25NM", "0.5NM", "1NM", "2NM"]);
+    }
+}
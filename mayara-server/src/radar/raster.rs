@@ -0,0 +1,369 @@
+//! Cartesian raster images of whole rotations, for thin clients.
+//!
+//! A client that opens the spokes WebSocket with `?raster=<pixels>` does not
+//! receive spokes but one square image per rotation, with the radar in the
+//! center, that it can draw as is. Each frame is
+//!
+//! ```text
+//! width: u16 LE, height: u16 LE, range: u32 LE (meters from center to edge),
+//! width * height bytes of pixel values (legend indices), row by row
+//! ```
+//!
+//! The image is painted with `PolarToCartesianLookup` and is not cleared
+//! between rotations, so pixels that no spoke reaches at large sizes keep
+//! their previous value rather than flickering.
+//!
+//! The image is oriented as the `orientation` preference of the radar says:
+//! bow up (the default, which needs no sensors), north up, turned by the
+//! heading, or course up, turned by the heading and the course over ground.
+//! Without the heading or course the image falls back to bow up.
+//!
+//! With `&headingLine=true` a line from the center towards the edge marks the
+//! bow, and with `&bearingMarkers=<degrees>` a tick at the edge marks every
+//! that many degrees, in true bearings when the heading is known and
+//! relative to the bow otherwise. Overlays are drawn into each frame with
+//! the value `OVERLAY_VALUE`, never into the image the spokes are painted on.
+
+use protobuf::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::trail::cartesian::PolarToCartesianLookup;
+
+pub const MIN_RASTER_SIZE: u16 = 64;
+pub const MAX_RASTER_SIZE: u16 = 2048;
+
+pub const RASTER_HEADER_LEN: usize = 8;
+
+/// Pixel value of the overlays, above any legend value
+pub const OVERLAY_VALUE: u8 = 255;
+
+/// Length of the bearing markers, as a fraction of the radius
+const MARKER_LENGTH: f64 = 0.08;
+
+/// Which way is up in the image
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Orientation {
+    /// The bow is up
+    #[default]
+    HeadUp,
+    /// True north is up, needs the heading
+    NorthUp,
+    /// The course over ground is up, needs the heading and the course
+    CourseUp,
+}
+
+impl Orientation {
+    /// Degrees clockwise the bow relative picture is turned, `None` when a
+    /// sensor that the orientation needs is missing
+    pub fn rotation(&self, heading: Option<f64>, course: Option<f64>) -> Option<f64> {
+        match self {
+            Orientation::HeadUp => Some(0.),
+            Orientation::NorthUp => heading,
+            Orientation::CourseUp => Some(heading? - course?),
+        }
+    }
+}
+
+/// Lines drawn on top of every frame
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RasterOverlay {
+    pub heading_line: bool,
+    /// Degrees between bearing markers, no markers when `None` or 0
+    pub bearing_markers: Option<u16>,
+}
+
+/// Per client rasterizer, collects the spokes of a rotation into an image
+pub struct Rasterizer {
+    size: usize,
+    spokes: usize,
+    lookup: PolarToCartesianLookup,
+    image: Vec<u8>,
+    range: u32,
+    painted: bool,
+    overlay: RasterOverlay,
+    orientation: Orientation,
+    heading: Option<f64>,
+    course: Option<f64>,
+}
+
+impl Rasterizer {
+    /// A rasterizer for square images of `size` pixels, clamped to
+    /// `MIN_RASTER_SIZE..=MAX_RASTER_SIZE` and rounded down to an even size
+    pub fn new(size: u16, spokes_per_revolution: u16) -> Self {
+        let size = (size.clamp(MIN_RASTER_SIZE, MAX_RASTER_SIZE) & !1) as usize;
+        let spokes = spokes_per_revolution.max(1) as usize;
+        Rasterizer {
+            size,
+            spokes,
+            lookup: PolarToCartesianLookup::new(spokes, size / 2)
+                .expect("raster is at least MIN_RASTER_SIZE"),
+            image: vec![0; size * size],
+            range: 0,
+            painted: false,
+            overlay: RasterOverlay::default(),
+            orientation: Orientation::default(),
+            heading: None,
+            course: None,
+        }
+    }
+
+    pub fn with_overlay(mut self, overlay: RasterOverlay) -> Self {
+        self.overlay = overlay;
+        self
+    }
+
+    pub fn overlay(&self) -> &RasterOverlay {
+        &self.overlay
+    }
+
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Whether the frames depend on the heading or course, that the caller
+    /// then keeps up to date
+    pub fn uses_heading(&self) -> bool {
+        self.orientation != Orientation::HeadUp || self.overlay.bearing_markers.is_some()
+    }
+
+    /// Heading in degrees true, turns the bearing markers to true bearings
+    /// and the image when north or course up
+    pub fn set_heading(&mut self, heading: Option<f64>) {
+        self.heading = heading;
+    }
+
+    /// Course over ground in degrees true, turns the image when course up
+    pub fn set_course(&mut self, course: Option<f64>) {
+        self.course = course;
+    }
+
+    /// Degrees clockwise the picture is turned from bow up
+    fn rotation(&self) -> f64 {
+        self.orientation
+            .rotation(self.heading, self.course)
+            .unwrap_or(0.)
+    }
+
+    /// Paint one spoke; returns the frame of the finished rotation when this
+    /// spoke starts the next one
+    pub fn add_spoke(&mut self, spoke: &Spoke) -> Option<Vec<u8>> {
+        let frame = if spoke.rotation_start == Some(true) && self.painted {
+            self.painted = false;
+            Some(self.frame())
+        } else {
+            None
+        };
+
+        // Delta spokes need the previous spoke to decode, use full spokes
+        if spoke.delta != Some(true) && !spoke.data.is_empty() {
+            let radius = self.size / 2;
+            let turn = (self.rotation() * self.spokes as f64 / 360.).round() as i64;
+            let angle = (spoke.angle as i64 + turn).rem_euclid(self.spokes as i64) as usize;
+            for r in 0..radius {
+                let Some(point) = self.lookup.try_get_point_int(angle, r) else {
+                    break;
+                };
+                let row = (radius as i32 - point.x as i32) as usize;
+                let col = (radius as i32 + point.y as i32) as usize;
+                if row < self.size && col < self.size {
+                    self.image[row * self.size + col] = spoke.data[r * spoke.data.len() / radius];
+                }
+            }
+            self.range = spoke.range;
+            self.painted = true;
+        }
+        frame
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The image as painted so far
+    pub fn image(&self) -> &[u8] {
+        &self.image
+    }
+
+    /// Rasterize a serialized RadarMessage as broadcast by the radar
+    pub fn encode_message(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let message = RadarMessage::parse_from_bytes(bytes).ok()?;
+        let mut frame = None;
+        for spoke in message.spokes.iter() {
+            if let Some(finished) = self.add_spoke(spoke) {
+                frame = Some(finished);
+            }
+        }
+        frame
+    }
+
+    fn frame(&self) -> Vec<u8> {
+        let mut frame = Vec::with_capacity(RASTER_HEADER_LEN + self.image.len());
+        frame.extend_from_slice(&(self.size as u16).to_le_bytes());
+        frame.extend_from_slice(&(self.size as u16).to_le_bytes());
+        frame.extend_from_slice(&self.range.to_le_bytes());
+        frame.extend_from_slice(&self.image);
+        self.draw_overlay(&mut frame[RASTER_HEADER_LEN..]);
+        frame
+    }
+
+    fn draw_overlay(&self, image: &mut [u8]) {
+        let size = self.size;
+        let radius = (size / 2) as f64;
+        // A line along `bearing` relative to the bow, from `from` pixels
+        // from the center to the edge
+        let mut line = |bearing: f64, from: f64| {
+            let (sin, cos) = bearing.to_radians().sin_cos();
+            let steps = size / 2;
+            for step in 0..=steps {
+                let r = from + (radius - from) * step as f64 / steps as f64;
+                let row = (radius - r * cos).round() as i64;
+                let col = (radius + r * sin).round() as i64;
+                if (0..size as i64).contains(&row) && (0..size as i64).contains(&col) {
+                    image[row as usize * size + col as usize] = OVERLAY_VALUE;
+                }
+            }
+        };
+
+        let rotation = self.rotation();
+        if self.overlay.heading_line {
+            line(rotation, 0.);
+        }
+        if let Some(interval) = self.overlay.bearing_markers.filter(|&i| i > 0) {
+            let heading = self.heading.unwrap_or(0.);
+            for bearing in (0..360).step_by(interval as usize) {
+                line(bearing as f64 - heading + rotation, radius * (1. - MARKER_LENGTH));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoke(angle: u32, data: Vec<u8>, rotation_start: bool) -> Spoke {
+        let mut spoke = Spoke::new();
+        spoke.angle = angle;
+        spoke.range = 1852;
+        spoke.data = data;
+        if rotation_start {
+            spoke.rotation_start = Some(true);
+        }
+        spoke
+    }
+
+    #[test]
+    fn echo_lands_on_its_cartesian_pixel() {
+        let mut rasterizer = Rasterizer::new(64, 2048);
+
+        // An echo at half range, due east (a quarter turn clockwise)
+        let mut data = vec![0u8; 256];
+        data[128] = 15;
+
+        assert!(rasterizer.add_spoke(&spoke(0, vec![0; 256], true)).is_none());
+        assert!(rasterizer.add_spoke(&spoke(512, data, false)).is_none());
+        let frame = rasterizer.add_spoke(&spoke(0, vec![0; 256], true)).unwrap();
+
+        assert_eq!(frame.len(), RASTER_HEADER_LEN + 64 * 64);
+        assert_eq!(u16::from_le_bytes([frame[0], frame[1]]), 64);
+        assert_eq!(u32::from_le_bytes([frame[4], frame[5], frame[6], frame[7]]), 1852);
+
+        let image = &frame[RASTER_HEADER_LEN..];
+        let (row, col) = (32, 48); // Center row, a quarter of the width right of center
+        assert_eq!(image[row * 64 + col], 15);
+        assert_eq!(image.iter().filter(|&&p| p != 0).count(), 1);
+    }
+
+    #[test]
+    fn overlay_draws_the_heading_line_and_bearing_markers() {
+        let overlay = RasterOverlay {
+            heading_line: true,
+            bearing_markers: Some(30),
+        };
+        let mut rasterizer = Rasterizer::new(64, 2048).with_overlay(overlay);
+        rasterizer.set_heading(Some(90.));
+        rasterizer.add_spoke(&spoke(0, vec![0; 256], true));
+        let frame = rasterizer.add_spoke(&spoke(0, vec![0; 256], true)).unwrap();
+        let image = &frame[RASTER_HEADER_LEN..];
+
+        // The heading line runs straight up from the center
+        for row in 0..=32 {
+            assert_eq!(image[row * 64 + 32], OVERLAY_VALUE, "row {}", row);
+        }
+        // Heading east puts the north marker at the left edge
+        assert_eq!(image[32 * 64], OVERLAY_VALUE);
+        assert_eq!(image[32 * 64 + 16], 0);
+        // 12 markers of a few pixels each, and the line
+        let set = image.iter().filter(|&&p| p == OVERLAY_VALUE).count();
+        assert!((33 + 12..33 + 12 * 4).contains(&set), "{} pixels set", set);
+
+        // The spokes are painted on an image without the overlay
+        assert!(rasterizer.image().iter().all(|&p| p == 0));
+    }
+
+    #[test]
+    fn north_up_turns_the_picture_by_the_heading() {
+        // A target dead ahead at half range
+        let mut data = vec![0u8; 256];
+        data[128] = 15;
+        let frame = |orientation: Orientation| {
+            let mut rasterizer = Rasterizer::new(64, 2048).with_orientation(orientation);
+            rasterizer.set_heading(Some(90.));
+            rasterizer.add_spoke(&spoke(0, data.clone(), true));
+            rasterizer.add_spoke(&spoke(0, vec![0; 256], true)).unwrap()
+        };
+
+        // Head up leaves it above the center, whatever the heading
+        let image = &frame(Orientation::HeadUp)[RASTER_HEADER_LEN..];
+        assert_eq!(image[16 * 64 + 32], 15);
+        assert_eq!(image.iter().filter(|&&p| p != 0).count(), 1);
+
+        // Heading east, north up: ahead is to the right of the center
+        let image = &frame(Orientation::NorthUp)[RASTER_HEADER_LEN..];
+        assert_eq!(image[32 * 64 + 48], 15);
+        assert_eq!(image.iter().filter(|&&p| p != 0).count(), 1);
+
+        // Course up without a course falls back to head up
+        let image = &frame(Orientation::CourseUp)[RASTER_HEADER_LEN..];
+        assert_eq!(image[16 * 64 + 32], 15);
+    }
+
+    #[test]
+    fn orientation_rotation_needs_its_sensors() {
+        assert_eq!(Orientation::HeadUp.rotation(None, None), Some(0.));
+        assert_eq!(Orientation::NorthUp.rotation(Some(90.), None), Some(90.));
+        assert_eq!(Orientation::NorthUp.rotation(None, Some(80.)), None);
+        assert_eq!(Orientation::CourseUp.rotation(Some(90.), Some(80.)), Some(10.));
+        assert_eq!(Orientation::CourseUp.rotation(Some(90.), None), None);
+    }
+
+    #[test]
+    fn center_blanking_clears_cells_near_the_antenna() {
+        use crate::radar::spoke::blank_center;
+
+        // 256 m over 256 cells, so each raster pixel (of 32 per spoke) is 8 m
+        let mut data = vec![15u8; 256];
+        blank_center(&mut data, 256, 0, 30.);
+        assert!(data[..30].iter().all(|&c| c == 0));
+        assert!(data[30..].iter().all(|&c| c == 15));
+
+        let mut rasterizer = Rasterizer::new(64, 2048);
+        let mut blanked = spoke(0, data, true);
+        blanked.range = 256;
+        rasterizer.add_spoke(&blanked);
+        let frame = rasterizer.add_spoke(&spoke(0, vec![0; 256], true)).unwrap();
+        let image = &frame[RASTER_HEADER_LEN..];
+
+        // Straight up from the center: pixels 0..3 are within 30 m
+        for r in 0..4 {
+            assert_eq!(image[(32 - r) * 64 + 32], 0, "pixel {} is inside 30 m", r);
+        }
+        for r in 4..32 {
+            assert_eq!(image[(32 - r) * 64 + 32], 15, "pixel {} is outside 30 m", r);
+        }
+    }
+}
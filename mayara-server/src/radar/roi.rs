@@ -0,0 +1,206 @@
+//! Region of interest clipping for the spokes WebSocket.
+//!
+//! A client showing a zoomed in part of the picture can open the spokes
+//! WebSocket with
+//!
+//! ```text
+//! ?bearingStart=300&bearingEnd=60&minRadius=100&maxRadius=1500
+//! ```
+//!
+//! and then only receives the spokes whose azimuth is inside the bearing
+//! window (which may wrap through the bow), cut off at `maxRadius` meters.
+//! Cells closer than `minRadius` are zeroed, the spoke keeps its first cell
+//! at the antenna so clients draw it unchanged. Bearings are relative to the
+//! bow, in the configured bearing unit, and apply to the normalized
+//! (clockwise, unmirrored) azimuth that clients see.
+
+use mayara_core::spoke_scale::SpokeScale;
+use protobuf::Message;
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+use crate::protos::RadarMessage::RadarMessage;
+
+/// The part of the picture a client wants
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegionOfInterest {
+    /// Start of the bearing window in degrees from the bow, clockwise
+    pub bearing_start: f64,
+    /// End of the bearing window, inclusive; below the start the window
+    /// wraps through the bow
+    pub bearing_end: f64,
+    /// Meters from the antenna below which cells are zeroed
+    pub min_radius: f64,
+    /// Meters from the antenna beyond which spokes are cut off
+    pub max_radius: Option<f64>,
+}
+
+impl Default for RegionOfInterest {
+    fn default() -> Self {
+        RegionOfInterest {
+            bearing_start: 0.,
+            bearing_end: 360.,
+            min_radius: 0.,
+            max_radius: None,
+        }
+    }
+}
+
+impl RegionOfInterest {
+    /// Whether the spoke at `angle` (of `spokes_per_revolution`) is in the window
+    pub fn contains_angle(&self, angle: u32, spokes_per_revolution: u16) -> bool {
+        let degrees = angle as f64 * 360. / spokes_per_revolution as f64;
+        let start = self.bearing_start.rem_euclid(360.);
+        let end = self.bearing_end.rem_euclid(360.);
+        if self.bearing_end - self.bearing_start >= 360. || start == end {
+            return true;
+        }
+        if start < end {
+            degrees >= start && degrees <= end
+        } else {
+            degrees >= start || degrees <= end
+        }
+    }
+
+    /// Cut the spoke off at `max_radius` and zero the cells within
+    /// `min_radius`, measuring with the radar's `range_offset` applied
+    pub fn clip_spoke(&self, spoke: &mut Spoke, range_offset: i32) {
+        let len = spoke.data.len();
+        if spoke.delta == Some(true) {
+            return;
+        }
+        let Some(scale) = SpokeScale::new(spoke.range as f64, len, range_offset as f64) else {
+            return;
+        };
+        if let Some(max_radius) = self.max_radius {
+            let keep = scale.cells_before(max_radius).max(1);
+            if keep < len {
+                spoke.data.truncate(keep);
+                spoke.range = (spoke.range as u64 * keep as u64 / len as u64) as u32;
+            }
+        }
+        let blank = scale.cells_within(self.min_radius).min(spoke.data.len());
+        spoke.data[..blank].fill(0);
+    }
+}
+
+/// Per client ROI filter over the broadcast RadarMessages
+pub struct RoiClipper {
+    roi: RegionOfInterest,
+    spokes_per_revolution: u16,
+    range_offset: i32,
+    /// A rotation started on a spoke outside the window; flag the next one sent
+    pending_rotation_start: bool,
+}
+
+impl RoiClipper {
+    pub fn new(roi: RegionOfInterest, spokes_per_revolution: u16, range_offset: i32) -> Self {
+        RoiClipper {
+            roi,
+            spokes_per_revolution,
+            range_offset,
+            pending_rotation_start: false,
+        }
+    }
+
+    /// Keep and clip the spokes in the region; `None` when nothing is left
+    pub fn clip_message(&mut self, bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut message = RadarMessage::parse_from_bytes(bytes).ok()?;
+        let spokes = std::mem::take(&mut message.spokes);
+        for mut spoke in spokes {
+            if !self.roi.contains_angle(spoke.angle, self.spokes_per_revolution) {
+                self.pending_rotation_start |= spoke.rotation_start == Some(true);
+                continue;
+            }
+            if std::mem::take(&mut self.pending_rotation_start) {
+                spoke.rotation_start = Some(true);
+            }
+            self.roi.clip_spoke(&mut spoke, self.range_offset);
+            message.spokes.push(spoke);
+        }
+        if message.spokes.is_empty() {
+            return None;
+        }
+        message.write_to_bytes().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(angles: impl Iterator<Item = u32>) -> Vec<u8> {
+        let mut message = RadarMessage::new();
+        for angle in angles {
+            let mut spoke = Spoke::new();
+            spoke.angle = angle;
+            spoke.range = 1024;
+            spoke.data = vec![7; 512]; // 2 m per cell
+            if angle == 0 {
+                spoke.rotation_start = Some(true);
+            }
+            message.spokes.push(spoke);
+        }
+        message.write_to_bytes().unwrap()
+    }
+
+    #[test]
+    fn spokes_outside_the_window_are_dropped_and_the_rest_clipped() {
+        // 330..30 degrees through the bow, 100..500 m
+        let roi = RegionOfInterest {
+            bearing_start: 330.,
+            bearing_end: 30.,
+            min_radius: 100.,
+            max_radius: Some(500.),
+        };
+        let mut clipper = RoiClipper::new(roi, 360, 0);
+
+        let bytes = clipper.clip_message(&message(0..360)).unwrap();
+        let clipped = RadarMessage::parse_from_bytes(&bytes).unwrap();
+        let angles: Vec<u32> = clipped.spokes.iter().map(|s| s.angle).collect();
+        let expected: Vec<u32> = (0..=30).chain(330..360).collect();
+        assert_eq!(angles, expected);
+
+        let spoke = &clipped.spokes[0];
+        assert_eq!(spoke.rotation_start, Some(true));
+        assert_eq!(spoke.data.len(), 250); // 500 m at 2 m per cell
+        assert_eq!(spoke.range, 500);
+        assert!(spoke.data[..50].iter().all(|&c| c == 0)); // within 100 m
+        assert!(spoke.data[50..].iter().all(|&c| c == 7));
+
+        // Nothing in the window, nothing sent
+        assert!(clipper.clip_message(&message(90..180)).is_none());
+    }
+
+    #[test]
+    fn rotation_start_outside_the_window_moves_to_the_first_spoke_sent() {
+        let roi = RegionOfInterest {
+            bearing_start: 90.,
+            bearing_end: 180.,
+            ..Default::default()
+        };
+        let mut clipper = RoiClipper::new(roi, 360, 0);
+        let bytes = clipper.clip_message(&message(0..360)).unwrap();
+        let clipped = RadarMessage::parse_from_bytes(&bytes).unwrap();
+        assert_eq!(clipped.spokes[0].angle, 90);
+        assert_eq!(clipped.spokes[0].rotation_start, Some(true));
+        assert_eq!(clipped.spokes[0].data.len(), 512);
+    }
+
+    #[test]
+    fn radii_are_measured_with_the_range_offset() {
+        let roi = RegionOfInterest {
+            min_radius: 100.,
+            max_radius: Some(500.),
+            ..Default::default()
+        };
+        // 1024 m reported over 512 cells, really covering 1280 m
+        let mut spoke = Spoke::new();
+        spoke.range = 1024;
+        spoke.data = vec![7; 512];
+        roi.clip_spoke(&mut spoke, 256);
+        assert_eq!(spoke.data.len(), 200); // 500 m at 2.5 m per cell
+        assert_eq!(spoke.range, 400);
+        assert!(spoke.data[..40].iter().all(|&c| c == 0));
+        assert!(spoke.data[40..].iter().all(|&c| c == 7));
+    }
+}
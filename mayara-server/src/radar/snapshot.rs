@@ -0,0 +1,144 @@
+//! Rotation locked snapshots.
+//!
+//! A snapshot waits for the next rotation boundary of a radar and then
+//! collects exactly one full rotation of spokes, ordered by azimuth. When two
+//! radars are captured at the same time the frames line up on rotation
+//! boundaries, which makes them easy to compare.
+
+use std::time::Duration;
+
+use protobuf::Message;
+
+use crate::protos::RadarMessage::radar_message::Spoke;
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::{RadarError, RadarInfo};
+
+/// Collects the spokes of one rotation, delimited by `rotation_start` spokes
+pub struct RotationCapture {
+    spokes_per_revolution: usize,
+    frame: Option<Vec<Option<Spoke>>>,
+}
+
+impl RotationCapture {
+    /// A capture of `spokes_per_revolution` azimuths; a radar that has not
+    /// reported its spoke count yet is captured as a single azimuth
+    pub fn new(spokes_per_revolution: usize) -> Self {
+        RotationCapture {
+            spokes_per_revolution: spokes_per_revolution.max(1),
+            frame: None,
+        }
+    }
+
+    /// Feed the next spoke from the radar. Returns the frame once a full
+    /// rotation has been seen: one spoke per azimuth, in azimuth order.
+    /// Azimuths the radar did not send have a spoke without data.
+    pub fn push(&mut self, spoke: Spoke) -> Option<Vec<Spoke>> {
+        if spoke.rotation_start == Some(true) {
+            if let Some(frame) = self.frame.take() {
+                return Some(
+                    frame
+                        .into_iter()
+                        .enumerate()
+                        .map(|(angle, spoke)| {
+                            spoke.unwrap_or_else(|| {
+                                let mut empty = Spoke::new();
+                                empty.angle = angle as u32;
+                                empty
+                            })
+                        })
+                        .collect(),
+                );
+            }
+            self.frame = Some(vec![None; self.spokes_per_revolution]);
+        }
+        if let Some(frame) = self.frame.as_mut() {
+            let index = spoke.angle as usize % self.spokes_per_revolution;
+            frame[index] = Some(spoke);
+        }
+        None
+    }
+}
+
+/// Wait for the next rotation of the radar and return it as a single message
+pub async fn capture_rotation(
+    info: &RadarInfo,
+    timeout: Duration,
+) -> Result<RadarMessage, RadarError> {
+    let mut rx = info.message_tx.subscribe();
+    let mut capture = RotationCapture::new(info.spokes_per_revolution as usize);
+
+    let frame = tokio::time::timeout(timeout, async {
+        loop {
+            let bytes = match rx.recv().await {
+                Ok(bytes) => bytes,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                    return Err(RadarError::Shutdown)
+                }
+            };
+            let message = match RadarMessage::parse_from_bytes(&bytes) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+            for spoke in message.spokes {
+                if let Some(frame) = capture.push(spoke) {
+                    return Ok(frame);
+                }
+            }
+        }
+    })
+    .await
+    .map_err(|_| RadarError::Timeout)??;
+
+    let mut message = RadarMessage::new();
+    message.radar = info.id as u32;
+    message.spokes = frame;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spoke(angle: u32, rotation_start: bool) -> Spoke {
+        let mut spoke = Spoke::new();
+        spoke.angle = angle;
+        spoke.data = vec![angle as u8; 4];
+        if rotation_start {
+            spoke.rotation_start = Some(true);
+        }
+        spoke
+    }
+
+    #[test]
+    fn capture_returns_one_spoke_per_azimuth() {
+        let spokes = 2048;
+        let mut capture = RotationCapture::new(spokes);
+
+        // Start mid rotation, skip one azimuth in the captured rotation
+        let mut frame = None;
+        for i in 1500..(1500 + 3 * spokes) {
+            let angle = (i % spokes) as u32;
+            if angle == 7 {
+                continue;
+            }
+            if let Some(f) = capture.push(spoke(angle, angle == 0)) {
+                frame = Some(f);
+                break;
+            }
+        }
+
+        let frame = frame.unwrap();
+        assert_eq!(frame.len(), spokes);
+        for (angle, spoke) in frame.iter().enumerate() {
+            assert_eq!(spoke.angle as usize, angle);
+        }
+        assert!(frame[7].data.is_empty());
+        assert_eq!(frame[8].data, vec![8; 4]);
+
+        // Before the radar reports its geometry spokes do not panic
+        let mut capture = RotationCapture::new(0);
+        assert!(capture.push(spoke(0, true)).is_none());
+        assert_eq!(capture.push(spoke(5, true)).unwrap().len(), 1);
+    }
+}
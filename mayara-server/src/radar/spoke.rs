@@ -1,5 +1,7 @@
 use std::f64::consts::PI;
 
+use mayara_core::spoke_scale::SpokeScale;
+
 use crate::{
     protos::RadarMessage::radar_message::Spoke,
     radar::{RadarInfo, SpokeBearing},
@@ -7,6 +9,58 @@ use crate::{
 
 pub(crate) type GenericSpoke = Vec<u8>;
 
+/// Detects the start of each rotation in a stream of spoke angles
+///
+/// A new rotation starts when the angle wraps past zero, i.e. when it is
+/// lower than the angle of the previous spoke.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RotationTracker {
+    prev_angle: Option<SpokeBearing>,
+}
+
+impl RotationTracker {
+    /// Returns true when `angle` is the first spoke of a new rotation
+    pub(crate) fn is_rotation_start(&mut self, angle: SpokeBearing) -> bool {
+        let wrapped = matches!(self.prev_angle, Some(prev) if angle < prev);
+        self.prev_angle = Some(angle);
+        wrapped
+    }
+}
+
+/// Smoothed estimate of the time one rotation takes
+///
+/// Each measured rotation moves the estimate a quarter of the way; a
+/// measurement that is more than 20% off is taken as a change of scan speed
+/// and replaces the estimate at once. Periods outside 300..10000 ms are
+/// measurement glitches (missed or doubled rotation starts) and are ignored.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct RotationPeriodEstimator {
+    period_ms: Option<f64>,
+}
+
+impl RotationPeriodEstimator {
+    pub(crate) fn add(&mut self, period_ms: f64) {
+        if !(300. ..10000.).contains(&period_ms) {
+            return;
+        }
+        self.period_ms = Some(match self.period_ms {
+            Some(estimate) if (period_ms - estimate).abs() <= estimate * 0.2 => {
+                estimate + (period_ms - estimate) / 4.
+            }
+            _ => period_ms,
+        });
+    }
+
+    pub(crate) fn period_ms(&self) -> Option<f64> {
+        self.period_ms
+    }
+
+    /// Rotations per minute
+    pub(crate) fn rpm(&self) -> Option<f64> {
+        self.period_ms.map(|ms| 60_000. / ms)
+    }
+}
+
 pub(crate) fn to_protobuf_spoke(
     info: &RadarInfo,
     range: u32,
@@ -23,6 +77,7 @@ pub(crate) fn to_protobuf_spoke(
         generic_spoke.len()
     );
 
+    let angle = info.spoke_angle(angle);
     let heading = if heading.is_some() {
         heading.map(|h| (((h / 2) + angle) % (info.spokes_per_revolution as u16)) as u32)
     } else {
@@ -33,6 +88,12 @@ pub(crate) fn to_protobuf_spoke(
         })
     };
 
+    let mut generic_spoke = generic_spoke;
+    let range = match info.spoke_len_limit {
+        Some(len) => clamp_spoke(&mut generic_spoke, range, len as usize),
+        None => range,
+    };
+
     let mut spoke = Spoke::new();
     spoke.range = range;
     spoke.angle = angle as u32;
@@ -44,3 +105,113 @@ pub(crate) fn to_protobuf_spoke(
 
     spoke
 }
+
+/// Reflect an azimuth about the bow-stern axis, for antennas that are
+/// mounted upside down and report bearings mirrored. Until the radar has
+/// reported its spoke count the angle is left alone.
+pub(crate) fn mirror_angle(angle: SpokeBearing, spokes_per_revolution: u16) -> SpokeBearing {
+    if spokes_per_revolution == 0 {
+        return angle;
+    }
+    (spokes_per_revolution - angle % spokes_per_revolution) % spokes_per_revolution
+}
+
+/// Truncate or zero-pad a spoke to exactly `len` cells.
+///
+/// The distance per cell is kept, so the returned range of the new last cell
+/// is scaled by the change in length.
+pub(crate) fn clamp_spoke(data: &mut GenericSpoke, range: u32, len: usize) -> u32 {
+    let actual = data.len();
+    if actual == len || actual == 0 {
+        return range;
+    }
+    data.resize(len, 0);
+    (range as u64 * len as u64 / actual as u64) as u32
+}
+
+/// Zero the cells within `radius` meters of the antenna, hiding the main
+/// bang. `range` is the distance covered by the whole spoke, before the
+/// radar's `range_offset`.
+pub(crate) fn blank_center(data: &mut [u8], range: u32, range_offset: i32, radius: f64) {
+    if radius <= 0. {
+        return;
+    }
+    if let Some(scale) = SpokeScale::new(range as f64, data.len(), range_offset as f64) {
+        let cells = scale.cells_before(radius);
+        data[..cells].fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longer_spoke_is_truncated() {
+        let mut data: GenericSpoke = (0..1024).map(|i| (i % 16) as u8).collect();
+        let range = clamp_spoke(&mut data, 1852, 512);
+        assert_eq!(data.len(), 512);
+        assert_eq!(data[511], (511 % 16) as u8);
+        assert_eq!(range, 926);
+
+        let mut data: GenericSpoke = vec![7; 256];
+        let range = clamp_spoke(&mut data, 1852, 512);
+        assert_eq!(data.len(), 512);
+        assert_eq!(data[255], 7);
+        assert_eq!(data[256], 0);
+        assert_eq!(range, 3704);
+    }
+
+    #[test]
+    fn mirrored_target_appears_on_other_side() {
+        let to_degrees = |angle: SpokeBearing, spokes: u16| {
+            (angle as f64 * 360. / spokes as f64).round() as u32
+        };
+
+        for spokes in [360u16, 2048] {
+            let target = (30 * spokes as u32 / 360) as SpokeBearing;
+            let mirrored = mirror_angle(target, spokes);
+            assert_eq!(to_degrees(mirrored, spokes), 330);
+            assert_eq!(mirror_angle(mirrored, spokes), target);
+        }
+        assert_eq!(mirror_angle(0, 2048), 0);
+        assert_eq!(mirror_angle(100, 0), 100);
+    }
+
+    #[test]
+    fn rpm_follows_rotation_period() {
+        let mut estimator = RotationPeriodEstimator::default();
+        assert_eq!(estimator.rpm(), None);
+
+        for period in [2500., 2510., 2490., 2500., 20000.] {
+            estimator.add(period);
+        }
+        assert!((estimator.rpm().unwrap() - 24.).abs() < 0.1);
+
+        // HALO switching to fast scan
+        estimator.add(1250.);
+        assert!((estimator.rpm().unwrap() - 48.).abs() < 0.1);
+        assert_eq!(estimator.period_ms(), Some(1250.));
+    }
+
+    #[test]
+    fn one_boundary_per_revolution() {
+        let mut tracker = RotationTracker::default();
+        let spokes = 2048u16;
+
+        // Start mid-rotation and feed three full wraps
+        let boundaries = (0..3 * spokes as usize)
+            .map(|i| ((i + 1000) % spokes as usize) as SpokeBearing)
+            .filter(|angle| tracker.is_rotation_start(*angle))
+            .count();
+        assert_eq!(boundaries, 3);
+
+        // Missing spokes do not create extra boundaries
+        let mut tracker = RotationTracker::default();
+        let boundaries = [10u16, 500, 1500, 2040, 3, 700, 2000, 1]
+            .iter()
+            .filter(|angle| tracker.is_rotation_start(**angle))
+            .count();
+        assert_eq!(boundaries, 2);
+    }
+}
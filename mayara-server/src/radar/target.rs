@@ -696,7 +696,7 @@ impl TargetBuffer {
     pub fn new(session: Session, info: &RadarInfo) -> Self {
         let stationary = session.read().unwrap().args.stationary;
         let spokes_per_revolution = info.spokes_per_revolution as i32;
-        let spoke_len = info.max_spoke_len as i32;
+        let spoke_len = info.output_spoke_len() as i32;
 
         TargetBuffer {
             session: session.clone(),
@@ -732,6 +732,19 @@ impl TargetBuffer {
         self.setup.rotation_speed_ms = ms;
     }
 
+    /// The spoke length became known after the buffer was created
+    pub fn set_spoke_len(&mut self, spoke_len: i32) {
+        if spoke_len == self.setup.spoke_len {
+            return;
+        }
+        self.setup.spoke_len = spoke_len;
+        self.history = HistorySpokes::new(
+            self.session.clone(),
+            self.setup.spokes_per_revolution,
+            spoke_len,
+        );
+    }
+
     pub fn set_arpa_via_doppler(&mut self, arpa: bool) -> Result<(), ControlError> {
         if arpa && !self.setup.have_doppler {
             return Err(ControlError::NotSupported("doppler_auto_track".to_string()));
@@ -0,0 +1,261 @@
+//! Scripted fake targets for deterministic alarm testing.
+//!
+//! A script is a timeline of target events: a target appears at a bearing and
+//! distance, moves in a straight line (in polar coordinates) to where the next
+//! `move` event puts it, and disappears again. The targets are painted as
+//! small blobs on otherwise empty spokes, one revolution per
+//! `SCRIPT_REVOLUTION_MS`, so guard zones and alarms see exactly the scripted
+//! crossings.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use mayara_core::spoke_scale::SpokeScale;
+use serde::{Deserialize, Serialize};
+
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::spoke::{to_protobuf_spoke, GenericSpoke};
+use crate::radar::{RadarInfo, SpokeBearing};
+
+/// Time between the revolutions of a scripted run
+pub(crate) const SCRIPT_REVOLUTION_MS: u64 = 1000;
+
+/// Half the width of a target blob, in degrees
+const TARGET_HALF_WIDTH_DEGREES: f64 = 1.0;
+
+/// Half the length of a target blob, in meters
+const TARGET_HALF_LENGTH_METERS: f64 = 10.0;
+
+/// Number of spokes sent per RadarMessage, as for the test pattern
+const SPOKES_PER_MESSAGE: usize = 32;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum TargetAction {
+    Appear { bearing: f64, distance: f64 },
+    Move { bearing: f64, distance: f64 },
+    Disappear,
+}
+
+/// One event of the timeline; `at_ms` counts from the start of the run
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetEvent {
+    pub at_ms: u64,
+    pub target: u32,
+    #[serde(flatten)]
+    pub action: TargetAction,
+}
+
+/// Where a scripted target is at a moment in time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ScriptedPosition {
+    pub target: u32,
+    pub bearing: f64,  // Degrees
+    pub distance: f64, // Meters
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct TargetScript {
+    events: Vec<TargetEvent>,
+}
+
+impl TargetScript {
+    pub fn new(mut events: Vec<TargetEvent>) -> Self {
+        events.sort_by_key(|e| e.at_ms);
+        TargetScript { events }
+    }
+
+    /// Time of the last event
+    pub fn duration_ms(&self) -> u64 {
+        self.events.iter().map(|e| e.at_ms).max().unwrap_or(0)
+    }
+
+    /// Positions of all visible targets `at_ms` into the run
+    pub(crate) fn positions_at(&self, at_ms: u64) -> Vec<ScriptedPosition> {
+        let mut targets: Vec<u32> = self.events.iter().map(|e| e.target).collect();
+        targets.sort_unstable();
+        targets.dedup();
+
+        targets
+            .into_iter()
+            .filter_map(|target| self.position_of(target, at_ms))
+            .collect()
+    }
+
+    fn position_of(&self, target: u32, at_ms: u64) -> Option<ScriptedPosition> {
+        // (time, bearing, distance) of the last keyframe at or before `at_ms`
+        let mut last: Option<(u64, f64, f64)> = None;
+        for event in self.events.iter().filter(|e| e.target == target) {
+            if event.at_ms > at_ms {
+                // Moving towards this event's position?
+                if let (Some((t0, b0, d0)), TargetAction::Move { bearing, distance }) =
+                    (last, &event.action)
+                {
+                    let f = (at_ms - t0) as f64 / (event.at_ms - t0) as f64;
+                    let turn = (bearing - b0 + 540.0) % 360.0 - 180.0; // Short way round
+                    return Some(ScriptedPosition {
+                        target,
+                        bearing: (b0 + turn * f).rem_euclid(360.0),
+                        distance: d0 + (distance - d0) * f,
+                    });
+                }
+                break;
+            }
+            last = match &event.action {
+                TargetAction::Appear { bearing, distance } => {
+                    Some((event.at_ms, *bearing, *distance))
+                }
+                TargetAction::Move { bearing, distance } => {
+                    last.map(|_| (event.at_ms, *bearing, *distance))
+                }
+                TargetAction::Disappear => None,
+            };
+        }
+        last.map(|(_, bearing, distance)| ScriptedPosition {
+            target,
+            bearing,
+            distance,
+        })
+    }
+
+    /// Spoke data for `angle`, with the given targets painted in
+    pub(crate) fn spoke(
+        positions: &[ScriptedPosition],
+        angle: SpokeBearing,
+        spokes_per_revolution: usize,
+        spoke_len: usize,
+        range: u32,
+        max_pixel: u8,
+    ) -> GenericSpoke {
+        let mut data = vec![0u8; spoke_len];
+        let Some(scale) = SpokeScale::new(range as f64, spoke_len, 0.0) else {
+            return data;
+        };
+        if spokes_per_revolution == 0 {
+            return data;
+        }
+        let bearing = angle as f64 * 360.0 / spokes_per_revolution as f64;
+
+        for position in positions {
+            let off = (bearing - position.bearing + 540.0) % 360.0 - 180.0;
+            if off.abs() > TARGET_HALF_WIDTH_DEGREES {
+                continue;
+            }
+            for (i, cell) in data.iter_mut().enumerate() {
+                let distance = scale.radius_to_meters(i as f64 + 0.5);
+                if (distance - position.distance).abs() <= TARGET_HALF_LENGTH_METERS {
+                    *cell = max_pixel;
+                }
+            }
+        }
+        data
+    }
+}
+
+/// Broadcast one revolution with the targets of `script` as they are `at_ms`
+/// into the run
+pub(crate) fn emit_revolution(info: &mut RadarInfo, range: u32, script: &TargetScript, at_ms: u64) {
+    let spokes = info.spokes_per_revolution as usize;
+    let spoke_len = info.output_spoke_len() as usize;
+    let max_pixel = info.legend.pixels.len().saturating_sub(1) as u8;
+    let positions = script.positions_at(at_ms);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .ok();
+
+    for first in (0..spokes).step_by(SPOKES_PER_MESSAGE) {
+        let mut message = RadarMessage::new();
+        message.radar = info.id as u32;
+
+        for angle in first..std::cmp::min(first + SPOKES_PER_MESSAGE, spokes) {
+            let angle = angle as SpokeBearing;
+            let data = TargetScript::spoke(&positions, angle, spokes, spoke_len, range, max_pixel);
+            message
+                .spokes
+                .push(to_protobuf_spoke(info, range, angle, None, now, data));
+        }
+
+        info.broadcast_spokes(message);
+    }
+}
+
+/// Play the script from start to end, one revolution per `SCRIPT_REVOLUTION_MS`
+pub(crate) fn spawn_script(mut info: RadarInfo, range: u32, script: TargetScript) {
+    tokio::spawn(async move {
+        log::info!(
+            "{}: running target script of {} ms",
+            info.key(),
+            script.duration_ms()
+        );
+        let mut interval = tokio::time::interval(Duration::from_millis(SCRIPT_REVOLUTION_MS));
+        let mut at_ms = 0;
+        while at_ms <= script.duration_ms() {
+            interval.tick().await;
+            emit_revolution(&mut info, range, &script, at_ms);
+            at_ms += SCRIPT_REVOLUTION_MS;
+        }
+        log::info!("{}: target script done", info.key());
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mayara_core::guard_zones::{GuardZone, GuardZoneProcessor};
+
+    fn event(at_ms: u64, action: TargetAction) -> TargetEvent {
+        TargetEvent {
+            at_ms,
+            target: 1,
+            action,
+        }
+    }
+
+    #[test]
+    fn scripted_crossing_triggers_enter_and_exit() {
+        // Approaches from 1500 m to 700 m and goes back out, at 80 m/s
+        let script = TargetScript::new(vec![
+            event(25000, TargetAction::Disappear),
+            event(0, TargetAction::Appear { bearing: 45.0, distance: 1500.0 }),
+            event(10000, TargetAction::Move { bearing: 45.0, distance: 700.0 }),
+            event(20000, TargetAction::Move { bearing: 45.0, distance: 1500.0 }),
+        ]);
+        assert_eq!(script.positions_at(5000)[0].distance, 1100.0);
+        assert!(script.positions_at(25000).is_empty());
+
+        let (spokes, spoke_len, range, max_pixel) = (720, 500, 2000, 15);
+        let mut zone = GuardZone::new_arc(1, 0.0, 90.0, 500.0, 1000.0);
+        zone.sensitivity = 10;
+        let mut processor = GuardZoneProcessor::new();
+        processor.set_range_scale(range as f64);
+        processor.add_zone(zone);
+
+        let mut events = Vec::new();
+        let mut inside = false;
+        for at_ms in (0..=script.duration_ms()).step_by(SCRIPT_REVOLUTION_MS as usize) {
+            let positions = script.positions_at(at_ms);
+            let mut intrusion = false;
+            for angle in 0..spokes {
+                let data = TargetScript::spoke(
+                    &positions,
+                    angle as SpokeBearing,
+                    spokes,
+                    spoke_len,
+                    range,
+                    max_pixel,
+                );
+                let bearing = angle as f64 * 360.0 / spokes as f64;
+                intrusion |= !processor.check_spoke(&data, bearing, at_ms).is_empty();
+            }
+            if intrusion != inside {
+                events.push((if intrusion { "enter" } else { "exit" }, at_ms));
+                inside = intrusion;
+            }
+        }
+
+        // The target is inside the 1000 m boundary from 6250 ms to 13750 ms
+        assert_eq!(events, vec![("enter", 7000), ("exit", 14000)]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Deterministic test pattern for validating client rendering.
+//!
+//! The pattern consists of four equally spaced range rings, a bearing line
+//! every 30 degrees and an intensity ramp in the sector between the 0 and
+//! 30 degree bearing lines. Clients can use it to check their geometry
+//! (rings must be round, lines must be straight) and their color map.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::protos::RadarMessage::RadarMessage;
+use crate::radar::spoke::{to_protobuf_spoke, GenericSpoke};
+use crate::radar::{RadarInfo, SpokeBearing};
+
+/// Number of range rings in the pattern
+pub(crate) const TEST_PATTERN_RINGS: usize = 4;
+
+/// Angle between consecutive bearing lines, in degrees
+pub(crate) const TEST_PATTERN_BEARING_STEP: usize = 30;
+
+/// Number of spokes sent per RadarMessage, similar to a Navico frame
+const SPOKES_PER_MESSAGE: usize = 32;
+
+/// Radius (in cells) of each range ring
+pub(crate) fn ring_radii(spoke_len: usize) -> Vec<usize> {
+    (1..=TEST_PATTERN_RINGS)
+        .map(|ring| ring * spoke_len / TEST_PATTERN_RINGS - 1)
+        .collect()
+}
+
+/// Generate the test pattern data for a single spoke
+///
+/// `max_pixel` is the strongest pixel value in the legend of the radar.
+pub(crate) fn test_pattern_spoke(
+    angle: SpokeBearing,
+    spokes_per_revolution: usize,
+    spoke_len: usize,
+    max_pixel: u8,
+) -> GenericSpoke {
+    let mut data = vec![0u8; spoke_len];
+    if spoke_len < TEST_PATTERN_RINGS || spokes_per_revolution == 0 {
+        return data;
+    }
+
+    let angle = angle as usize % spokes_per_revolution;
+    let lines = 360 / TEST_PATTERN_BEARING_STEP;
+    let is_bearing_line = (0..lines).any(|line| line * spokes_per_revolution / lines == angle);
+
+    if is_bearing_line {
+        data.fill(max_pixel);
+        return data;
+    }
+
+    // Intensity ramp from center to edge in the first sector
+    if angle * lines < spokes_per_revolution {
+        for (r, cell) in data.iter_mut().enumerate() {
+            *cell = ((r * max_pixel as usize) / spoke_len) as u8;
+        }
+    }
+
+    for r in ring_radii(spoke_len) {
+        data[r] = max_pixel;
+    }
+
+    data
+}
+
+/// Broadcast one full revolution of the test pattern to all spoke clients
+pub(crate) fn emit_test_pattern(info: &mut RadarInfo, range: u32) {
+    let spokes = info.spokes_per_revolution as usize;
+    let spoke_len = info.output_spoke_len() as usize;
+    let max_pixel = info.legend.pixels.len().saturating_sub(1) as u8;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .ok();
+
+    log::info!(
+        "{}: sending test pattern, {} spokes of {} cells at range {}",
+        info.key(),
+        spokes,
+        spoke_len,
+        range
+    );
+
+    for first in (0..spokes).step_by(SPOKES_PER_MESSAGE) {
+        let mut message = RadarMessage::new();
+        message.radar = info.id as u32;
+
+        for angle in first..std::cmp::min(first + SPOKES_PER_MESSAGE, spokes) {
+            let angle = angle as SpokeBearing;
+            let data = test_pattern_spoke(angle, spokes, spoke_len, max_pixel);
+            message
+                .spokes
+                .push(to_protobuf_spoke(info, range, angle, None, now, data));
+        }
+
+        info.broadcast_spokes(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pattern_has_rings_at_expected_radii() {
+        let spoke_len = 1024;
+        // An angle between bearing lines and outside the ramp sector
+        let data = test_pattern_spoke(300, 2048, spoke_len, 15);
+
+        assert_eq!(ring_radii(spoke_len), vec![255, 511, 767, 1023]);
+        for r in ring_radii(spoke_len) {
+            assert_eq!(data[r], 15, "ring at radius {}", r);
+        }
+        assert_eq!(data.iter().filter(|&&v| v != 0).count(), TEST_PATTERN_RINGS);
+    }
+
+    #[test]
+    fn pattern_has_bearing_lines_and_ramp() {
+        // Every 30 degrees is a full line: 2048 / 12 = 170.67 spokes apart
+        assert!(test_pattern_spoke(0, 2048, 512, 15).iter().all(|&v| v == 15));
+        assert!(test_pattern_spoke(170, 2048, 512, 15).iter().all(|&v| v == 15));
+        assert!(test_pattern_spoke(1024, 2048, 512, 15).iter().all(|&v| v == 15));
+        assert!(test_pattern_spoke(360, 4320, 512, 15).iter().all(|&v| v == 15));
+
+        // The ramp increases from center outward
+        let ramp = test_pattern_spoke(10, 2048, 512, 15);
+        assert_eq!(ramp[0], 0);
+        assert!(ramp[400] > ramp[100]);
+    }
+}
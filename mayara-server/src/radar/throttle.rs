@@ -0,0 +1,152 @@
+//! Server-wide spoke rate limit.
+//!
+//! Several radars at full rate can keep a small computer busy with spokes
+//! alone, before any client has been served. With `--max-spoke-rate <N>` the
+//! server measures the spokes arriving from all radars together, and when
+//! they exceed N per second every radar drops the same fraction of its
+//! spokes, evenly spread over the rotation. Whole spokes are dropped, so the
+//! picture gets coarser in azimuth but stays correct. The receivers drop
+//! spokes as they arrive, before converting them or adding them to the
+//! trails, so the noise floor, intensity curve, blanking and guard zones
+//! spend no work on them either, and `--interpolate-gaps` does not fill the
+//! gaps while spokes are being dropped.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often the input rate is measured and the fraction kept adjusted
+const MEASURE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The budget shared by all radars
+#[derive(Debug)]
+pub struct SpokeBudget {
+    max_rate: f64,
+    window_start: Option<Instant>,
+    count: u64,
+    /// Fraction of the spokes kept, 1.0 when not throttling
+    keep: f64,
+}
+
+pub type SharedSpokeBudget = Arc<Mutex<SpokeBudget>>;
+
+impl SpokeBudget {
+    pub fn new_shared(max_rate: u32) -> SharedSpokeBudget {
+        Arc::new(Mutex::new(SpokeBudget {
+            max_rate: max_rate as f64,
+            window_start: None,
+            count: 0,
+            keep: 1.,
+        }))
+    }
+
+    /// Count `spokes` arriving at `now`; returns the fraction of spokes to keep
+    pub(crate) fn admit(&mut self, spokes: usize, now: Instant) -> f64 {
+        let start = *self.window_start.get_or_insert(now);
+        self.count += spokes as u64;
+        let elapsed = now.duration_since(start);
+        if elapsed >= MEASURE_INTERVAL {
+            let rate = self.count as f64 / elapsed.as_secs_f64();
+            let keep = (self.max_rate / rate).min(1.);
+            if keep < 1. && self.keep == 1. {
+                log::info!(
+                    "Spoke rate {:.0}/s exceeds the limit of {:.0}/s, dropping {:.0}% of the spokes",
+                    rate,
+                    self.max_rate,
+                    (1. - keep) * 100.
+                );
+            } else if keep == 1. && self.keep < 1. {
+                log::info!("Spoke rate {:.0}/s is within the limit again", rate);
+            }
+            self.keep = keep;
+            self.window_start = Some(now);
+            self.count = 0;
+        }
+        self.keep
+    }
+}
+
+/// Per radar share of the budget, spreading the dropped spokes evenly
+#[derive(Clone, Debug)]
+pub(crate) struct SpokeThrottle {
+    budget: SharedSpokeBudget,
+    credit: f64,
+    /// Fraction of the spokes kept, as the budget set it at the last `measure`
+    keep: f64,
+}
+
+impl SpokeThrottle {
+    pub(crate) fn new(budget: SharedSpokeBudget) -> Self {
+        SpokeThrottle {
+            budget,
+            credit: 1.,
+            keep: 1.,
+        }
+    }
+
+    /// Whether spokes are being dropped, so azimuth gaps are expected
+    pub(crate) fn is_dropping(&self) -> bool {
+        self.keep < 1.
+    }
+
+    /// Count the `spokes` of a frame arriving at `now` against the budget
+    pub(crate) fn measure(&mut self, spokes: usize, now: Instant) {
+        self.keep = self.budget.lock().unwrap().admit(spokes, now);
+    }
+
+    /// Whether the next spoke of the frame fits in the budget
+    pub(crate) fn keep_next(&mut self) -> bool {
+        self.credit = (self.credit + self.keep).min(1. + self.keep);
+        if self.credit < 1. {
+            return false;
+        }
+        self.credit -= 1.;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The number of spokes of a frame of 10 that the receiver keeps
+    fn frame(throttle: &mut SpokeThrottle, now: Instant) -> usize {
+        throttle.measure(10, now);
+        (0..10).filter(|_| throttle.keep_next()).count()
+    }
+
+    #[test]
+    fn output_rate_is_capped_across_radars() {
+        let budget = SpokeBudget::new_shared(1000);
+        let mut radars = [
+            SpokeThrottle::new(budget.clone()),
+            SpokeThrottle::new(budget.clone()),
+        ];
+        // Two radars of 1000 spokes per second each, 10 spokes a message
+        let start = Instant::now();
+        let mut kept = [0; 2];
+        for ms in (0..5000).step_by(10) {
+            let now = start + Duration::from_millis(ms);
+            for (radar, throttle) in radars.iter_mut().enumerate() {
+                let sent = frame(throttle, now);
+                if ms >= 1000 {
+                    kept[radar] += sent;
+                }
+            }
+        }
+        // Four seconds after the first measurement, 1000 per second together
+        let total = kept[0] + kept[1];
+        assert!((3900..=4100).contains(&total), "{:?}", kept);
+        assert!(kept[0].abs_diff(kept[1]) <= 10, "{:?}", kept);
+        assert!(radars[0].is_dropping());
+
+        // Below the limit nothing is dropped
+        let budget = SpokeBudget::new_shared(5000);
+        let mut throttle = SpokeThrottle::new(budget);
+        let sent: usize = (0..3000)
+            .step_by(10)
+            .map(|ms| frame(&mut throttle, start + Duration::from_millis(ms as u64)))
+            .sum();
+        assert_eq!(sent, 3000);
+        assert!(!throttle.is_dropping());
+    }
+}
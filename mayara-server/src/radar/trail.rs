@@ -1,7 +1,7 @@
 use cartesian::PolarToCartesianLookup;
 use ndarray::{s, Array2};
 
-mod cartesian;
+pub(crate) mod cartesian;
 use crate::protos::RadarMessage::radar_message::Spoke;
 use crate::radar::target::{meters_per_degree_longitude, METERS_PER_DEGREE_LATITUDE};
 use crate::radar::trail::cartesian::PointInt;
@@ -30,7 +30,7 @@ pub struct TrailBuffer {
     position: GeoPosition,
     position_difference: GeoPosition, // Fraction of a pixel expressed in lat/lon for True Motion Target Trails
     position_offset: GeoPositionPixels, // Offset of the trails image in pixels
-    cartesian_lookup: PolarToCartesianLookup,
+    cartesian_lookup: Option<PolarToCartesianLookup>, // None until the spoke length is known
     true_trails: Box<Array2<u8>>,
     true_trails_offset: PointInt,
     relative_trails: Box<Vec<u16>>,
@@ -41,18 +41,14 @@ pub struct TrailBuffer {
     previous_range: u32,
     pixels_per_meter: f64,
     have_heading: bool,
+    range_locked: bool, // Rescale true trails on a range change instead of clearing
 }
 
 impl TrailBuffer {
     pub fn new(session: Session, info: &RadarInfo) -> Self {
         let legend = info.legend.clone();
         let spokes_per_revolution = info.spokes_per_revolution as usize;
-        let max_spoke_len = info.max_spoke_len as usize;
-        let trail_size: i16 = (info.max_spoke_len as i16 * 2 + MARGIN_I16 * 2) as i16;
-        let cartesian_lookup = PolarToCartesianLookup::new(
-            info.spokes_per_revolution as usize,
-            info.max_spoke_len as usize,
-        );
+        let max_spoke_len = info.output_spoke_len() as usize;
 
         let targets = match session.read().unwrap().args.targets {
             TargetMode::Arpa => Some(TargetBuffer::new(session.clone(), info)),
@@ -67,23 +63,20 @@ impl TrailBuffer {
             }
         }
 
-        TrailBuffer {
+        let mut trails = TrailBuffer {
             session: session.clone(),
             legend,
             spokes_per_revolution,
-            max_spoke_len,
-            trail_size,
+            max_spoke_len: 0,
+            trail_size: 0,
             motion_true: false,
             position: GeoPosition::new(0., 0.),
             position_difference: GeoPosition { lat: 0., lon: 0. },
             position_offset: GeoPositionPixels { lat: 0, lon: 0 },
-            cartesian_lookup,
-            true_trails: Box::new(Array2::<u8>::zeros((
-                trail_size as usize,
-                trail_size as usize,
-            ))),
+            cartesian_lookup: None,
+            true_trails: Box::new(Array2::<u8>::zeros((0, 0))),
             true_trails_offset: PointInt { x: 0, y: 0 },
-            relative_trails: Box::new(vec![0; spokes_per_revolution * max_spoke_len]),
+            relative_trails: Box::new(Vec::new()),
             targets,
             trail_length_ms: 0,
             rotation_speed_ms: 0,
@@ -91,16 +84,47 @@ impl TrailBuffer {
             previous_range: 0,
             pixels_per_meter: 0.0,
             have_heading: false,
+            range_locked: false,
+        };
+        trails.set_spoke_len(max_spoke_len);
+        trails
+    }
+
+    /// Size the buffers for spokes of `max_spoke_len` cells. Until a radar
+    /// reports its spoke length there is nothing to size them for, and the
+    /// trails wait for the first spoke instead.
+    fn set_spoke_len(&mut self, max_spoke_len: usize) {
+        self.cartesian_lookup =
+            PolarToCartesianLookup::new(self.spokes_per_revolution, max_spoke_len);
+        if self.cartesian_lookup.is_none() {
+            log::debug!("Trails wait for the spoke length to be known");
+            return;
+        }
+        self.max_spoke_len = max_spoke_len;
+        self.trail_size = (max_spoke_len as i16 * 2 + MARGIN_I16 * 2) as i16;
+        self.true_trails = Box::new(Array2::<u8>::zeros((
+            self.trail_size as usize,
+            self.trail_size as usize,
+        )));
+        self.true_trails_offset = PointInt { x: 0, y: 0 };
+        self.relative_trails = Box::new(vec![0; self.spokes_per_revolution * max_spoke_len]);
+        if let Some(targets) = self.targets.as_mut() {
+            targets.set_spoke_len(max_spoke_len as i32);
         }
     }
 
+    /// True until the spoke length is known and the buffers are sized
+    pub fn is_initializing(&self) -> bool {
+        self.cartesian_lookup.is_none()
+    }
+
     pub fn set_control_value(
         &mut self,
         controls: &SharedControls,
         cv: &ControlValue,
     ) -> Result<(), RadarError> {
         let mut reply = match cv.id.as_str() {
-            "clear_trails" => {
+            "clearTrails" => {
                 self.clear();
                 Ok(())
             }
@@ -126,6 +150,10 @@ impl TrailBuffer {
                 self.set_trails_mode(true_motion)
                     .map_err(|e| RadarError::ControlError(e))
             }
+            "trailsRangeLocked" => {
+                self.range_locked = cv.value.parse::<u16>().unwrap_or(0) > 0;
+                Ok(())
+            }
             "clear_targets" => {
                 self.targets.as_mut().map(|t| t.delete_all_targets());
                 Ok(())
@@ -204,6 +232,15 @@ impl TrailBuffer {
         if self.session.read().unwrap().args.targets == TargetMode::None {
             return;
         }
+        if self.is_initializing() {
+            if spoke.data.is_empty() {
+                return;
+            }
+            self.set_spoke_len(spoke.data.len());
+            if self.is_initializing() {
+                return;
+            }
+        }
         if spoke.range != self.previous_range && spoke.range != 0 {
             if self.previous_range != 0 {
                 let zoom_factor = self.previous_range as f64 / spoke.range as f64;
@@ -237,12 +274,16 @@ impl TrailBuffer {
 
         let mut radius = 0;
 
-        while radius < data.len() - 1 {
+        while radius + 1 < data.len() {
             //  len - 1 : no trails on range circle
-            let mut point = self
+            let mut point = match self
                 .cartesian_lookup
-                .get_point_int(bearing as usize, radius)
-                .clone();
+                .as_ref()
+                .and_then(|lookup| lookup.try_get_point_int(bearing as usize, radius))
+            {
+                Some(point) => point.clone(),
+                None => break, // Spoke is longer than the lookup table
+            };
 
             point.x += self.trail_size / 2 + self.true_trails_offset.x;
             point.y += self.trail_size / 2 + self.true_trails_offset.y;
@@ -324,7 +365,8 @@ impl TrailBuffer {
             // zoom trails
             let zoom_factor = pixels_per_meter / self.pixels_per_meter;
 
-            if zoom_factor < 0.25 || zoom_factor > 4.00 {
+            // Range locked trails are kept whatever the zoom
+            if !self.range_locked && (zoom_factor < 0.25 || zoom_factor > 4.00) {
                 self.clear();
                 return;
             }
@@ -495,7 +537,8 @@ impl TrailBuffer {
 
         // zoom true trails
         for i in MARGIN_USIZE..trail_size - MARGIN_USIZE {
-            let index_i = ((i - trail_size / 2) as f64 * zoom_factor) as i16 + self.trail_size / 2;
+            let index_i =
+                ((i as f64 - (trail_size / 2) as f64) * zoom_factor) as i16 + self.trail_size / 2;
             if index_i >= self.trail_size - 1 {
                 // allow adding an additional pixel later
                 break;
@@ -504,8 +547,8 @@ impl TrailBuffer {
                 continue;
             }
             for j in MARGIN_USIZE..trail_size - MARGIN_USIZE {
-                let index_j: i16 =
-                    ((j - trail_size / 2) as f64 * zoom_factor) as i16 + self.trail_size / 2;
+                let index_j: i16 = ((j as f64 - (trail_size / 2) as f64) * zoom_factor) as i16
+                    + self.trail_size / 2;
                 if index_j >= self.trail_size - 1 {
                     break;
                 }
@@ -542,7 +585,7 @@ impl TrailBuffer {
                 self.rotation_speed_ms
             );
         }
-        if self.trail_length_ms == 0 || self.rotation_speed_ms == 0 {
+        if self.trail_length_ms == 0 || self.rotation_speed_ms == 0 || self.max_spoke_len == 0 {
             return;
         }
         let max_trail_value = (self.trail_length_ms / self.rotation_speed_ms) as u16;
@@ -639,3 +682,136 @@ impl TrailBuffer {
         self.minimal_legend_value = Self::compute_minimal_legend_value(&self.legend, v);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::locator::LocatorId;
+    use crate::Brand;
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    #[test]
+    fn clear_trails_control_empties_the_trail_buffer() {
+        let session = Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            2048,
+            1024,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls.clone(),
+            false,
+        );
+        let mut trails = TrailBuffer::new(session, &info);
+        trails.set_relative_trails_length(1);
+        trails.set_rotation_speed(2500);
+
+        for angle in 0..100 {
+            let mut data = vec![info.legend.strong_return; 512];
+            trails.update_relative_trails(angle, &mut data);
+        }
+        trails.true_trails[[10, 10]] = 3;
+        assert!(trails.relative_trails.iter().any(|&age| age > 0));
+
+        trails
+            .set_control_value(&controls, &ControlValue::new("clearTrails", "1".to_string()))
+            .unwrap();
+        assert!(trails.relative_trails.iter().all(|&age| age == 0));
+        assert!(trails.true_trails.iter().all(|&age| age == 0));
+    }
+
+    #[test]
+    fn unknown_spoke_length_waits_for_the_first_spoke() {
+        let session = Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            2048,
+            0,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls.clone(),
+            false,
+        );
+        assert!(info.is_initializing());
+
+        let mut trails = TrailBuffer::new(session, &info);
+        trails.set_relative_trails_length(1);
+        trails.set_rotation_speed(2500);
+        assert!(trails.is_initializing());
+        let mut data = vec![info.legend.strong_return; 8];
+        trails.update_relative_trails(3, &mut data);
+
+        let mut spoke = Spoke::new();
+        spoke.angle = 3;
+        spoke.range = 1852;
+        trails.update_trails(&mut spoke, &info.legend);
+        assert!(trails.is_initializing());
+
+        spoke.data = vec![info.legend.strong_return; 512];
+        trails.update_trails(&mut spoke, &info.legend);
+        assert!(!trails.is_initializing());
+        assert_eq!(trails.relative_trails.len(), 2048 * 512);
+        assert!(trails.relative_trails.iter().any(|&age| age > 0));
+    }
+
+    #[test]
+    fn range_locked_trails_keep_their_position_across_range_change() {
+        let session = Session::new_fake();
+        let controls = SharedControls::new(session.clone(), HashMap::new());
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let info = RadarInfo::new(
+            session.clone(),
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            None,
+            16,
+            2048,
+            512,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls.clone(),
+            false,
+        );
+        let mut trails = TrailBuffer::new(session, &info);
+        let center = trails.trail_size as usize / 2;
+        trails.update_trail_position(1852, 512);
+
+        // An echo 44 cells north and 20 cells east of the radar
+        trails.true_trails[[center + 44, center + 20]] = 3;
+
+        // The range grows eight times, more than unlocked trails survive
+        trails.range_locked = true;
+        trails.update_trail_position(8 * 1852, 512);
+        assert_eq!(trails.true_trails[[center + 5, center + 2]], 3);
+        assert_eq!(trails.true_trails.iter().filter(|&&age| age > 0).count(), 1);
+
+        trails.range_locked = false;
+        trails.update_trail_position(1852, 512);
+        assert!(trails.true_trails.iter().all(|&age| age == 0));
+    }
+}
@@ -12,7 +12,12 @@ pub struct PolarToCartesianLookup {
 }
 
 impl PolarToCartesianLookup {
-    pub fn new(spokes_per_revolution: usize, spoke_len: usize) -> Self {
+    /// The lookup for spokes of `spoke_len` cells, `None` until the radar has
+    /// reported how many spokes and cells it sends
+    pub fn new(spokes_per_revolution: usize, spoke_len: usize) -> Option<Self> {
+        if spokes_per_revolution == 0 || spoke_len == 0 {
+            return None;
+        }
         let mut xyi = Vec::with_capacity(spokes_per_revolution * spoke_len);
         for arc in 0..spokes_per_revolution {
             let sine =
@@ -29,14 +34,44 @@ impl PolarToCartesianLookup {
             }
         }
         let xyi = Array2::from_shape_vec((spokes_per_revolution, spoke_len), xyi).unwrap();
-        PolarToCartesianLookup {
+        Some(PolarToCartesianLookup {
             spokes_per_revolution,
             xyi,
-        }
+        })
     }
 
+    /// Panics if `radius` is not less than the spoke length, use
+    /// `try_get_point_int` when the radius is not known to be in bounds.
     pub fn get_point_int(&self, angle: usize, radius: usize) -> &PointInt {
         let angle = (angle + self.spokes_per_revolution) % self.spokes_per_revolution;
         &self.xyi[[angle, radius]]
     }
+
+    /// Checked variant of `get_point_int`, returns `None` when `radius` is
+    /// beyond the end of the spoke.
+    pub fn try_get_point_int(&self, angle: usize, radius: usize) -> Option<&PointInt> {
+        let angle = (angle + self.spokes_per_revolution) % self.spokes_per_revolution;
+        self.xyi.get([angle, radius])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_range_radius_returns_none() {
+        let lookup = PolarToCartesianLookup::new(16, 8).unwrap();
+
+        let point = lookup.try_get_point_int(0, 7).unwrap();
+        assert_eq!((point.x, point.y), (7, 0));
+        assert!(lookup.try_get_point_int(4, 8).is_none());
+        assert!(lookup.try_get_point_int(4, usize::MAX).is_none());
+    }
+
+    #[test]
+    fn unknown_spoke_length_gives_no_lookup() {
+        assert!(PolarToCartesianLookup::new(2048, 0).is_none());
+        assert!(PolarToCartesianLookup::new(0, 512).is_none());
+    }
 }
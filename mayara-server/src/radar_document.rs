@@ -0,0 +1,244 @@
+//! Everything the server knows about a radar, as one document
+//!
+//! The state, capabilities and status endpoints each answer part of the
+//! question "what does the radar think it is doing". When a radar behaves
+//! oddly it helps to see all of it at once, as the decoders last understood
+//! it: `GET /v2/api/radars/{radar_id}/document` returns the power state, every
+//! decoded setting with its auto and enabled flags, the current range as the
+//! radar sent it, in meters and as labelled for the clients, the rotation
+//! period and RPM, the model, antenna and serial number, and for a dual range
+//! radar the power and range of each channel. Nothing is asked of the radar;
+//! the document is built from the controls the latest reports have set.
+
+use std::collections::BTreeMap;
+
+use mayara_core::PowerState;
+use serde::Serialize;
+
+use crate::radar::range::RangeUnitPreference;
+use crate::radar::{power_state, RadarInfo};
+use crate::settings::SharedControls;
+
+/// Controls that are part of the identity, or internal, and not settings
+const NOT_SETTINGS: [&str; 2] = ["userName", "modelName"];
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RadarDocument {
+    pub id: String,
+    pub power: PowerState,
+    pub settings: BTreeMap<String, Setting>,
+    pub range: Option<RangeState>,
+    pub rotation: RotationState,
+    pub radar: Identity,
+    pub channels: Vec<ChannelState>,
+}
+
+/// One decoded setting; flags the radar does not report are left out
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Setting {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeState {
+    /// The range in the units of the wire format, e.g. decimeters for Navico
+    pub raw: Option<f32>,
+    pub meters: i32,
+    pub formatted: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationState {
+    pub period_ms: Option<f64>,
+    pub rpm: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+    pub brand: String,
+    pub model: Option<String>,
+    pub antenna: Option<String>,
+    pub serial: Option<String>,
+    pub firmware: Option<String>,
+}
+
+/// The state of one channel of a dual range radar
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelState {
+    pub which: String,
+    pub power: PowerState,
+    pub range: Option<RangeState>,
+}
+
+impl RadarDocument {
+    /// The document of the radar `info`, known to clients as `id`. `channels`
+    /// are the channels of a dual range radar, including `info` itself, see
+    /// `SharedRadars::channels`; ranges are labelled in the radar's own
+    /// units or else in `units`.
+    pub fn new(id: &str, info: &RadarInfo, channels: &[RadarInfo], units: RangeUnitPreference) -> Self {
+        let controls = &info.controls;
+        let settings = controls
+            .get_all()
+            .into_iter()
+            .filter(|(id, _)| !NOT_SETTINGS.contains(&id.as_str()))
+            .map(|(id, control)| {
+                let setting = Setting {
+                    value: control.value,
+                    auto: control.auto,
+                    enabled: control.enabled,
+                    description: control.value_description(),
+                };
+                (id, setting)
+            })
+            .collect();
+
+        RadarDocument {
+            id: id.to_string(),
+            power: power(controls),
+            settings,
+            range: range(info, units),
+            rotation: RotationState {
+                period_ms: info.rotation_period_ms(),
+                rpm: info.rpm(),
+            },
+            radar: Identity {
+                brand: info.brand.to_string(),
+                model: controls.model_name(),
+                antenna: description(controls, "antennaKind"),
+                serial: description(controls, "serialNumber")
+                    .or_else(|| info.serial_no().map(String::from)),
+                firmware: description(controls, "firmwareVersion"),
+            },
+            channels: channels
+                .iter()
+                .filter_map(|channel| {
+                    Some(ChannelState {
+                        which: channel.which()?.to_string(),
+                        power: power(&channel.controls),
+                        range: range(channel, units),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The power control holds the status the radar reports, as the state endpoint
+fn power(controls: &SharedControls) -> PowerState {
+    power_state(controls.get("power").and_then(|c| c.value))
+}
+
+fn range(info: &RadarInfo, units: RangeUnitPreference) -> Option<RangeState> {
+    let control = info.controls.get("range")?;
+    let meters = control.value?.round() as i32;
+    Some(RangeState {
+        raw: control.wire_value(),
+        meters,
+        formatted: info.format_range(meters, units),
+    })
+}
+
+fn description(controls: &SharedControls, id: &str) -> Option<String> {
+    controls.get(id).and_then(|c| c.description)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::control_factory;
+    use crate::locator::LocatorId;
+    use crate::settings::Control;
+    use crate::{Brand, Session};
+    use std::collections::HashMap;
+    use std::net::{Ipv4Addr, SocketAddrV4};
+
+    fn channel(session: &Session, which: &str, range_decimeters: f32) -> RadarInfo {
+        let max = 96. * 1852.;
+        let mut controls = HashMap::new();
+        controls.insert(
+            "range".to_string(),
+            Control::new_numeric("range", 50., max)
+                .unit("m")
+                .wire_scale_factor(10. * max, false),
+        );
+        controls.insert(
+            "gain".to_string(),
+            control_factory::gain_control_for_brand(mayara_core::Brand::Navico),
+        );
+        controls.insert("serialNumber".to_string(), control_factory::serial_number_control());
+        controls.insert("antennaKind".to_string(), control_factory::antenna_kind_control());
+        let controls = SharedControls::new(session.clone(), controls);
+        controls.set("power", 2., None).unwrap();
+        controls.set("range", range_decimeters, None).unwrap();
+        controls.set_value_auto("gain", true, 102.).unwrap(); // 40 of 100
+        controls.set_string("serialNumber", "1234567".to_string()).unwrap();
+        controls.set_string("antennaKind", "4ft open array".to_string()).unwrap();
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        RadarInfo::new(
+            session.clone(),
+            LocatorId::Gen3Plus,
+            Brand::Navico,
+            Some("1234"),
+            Some(which),
+            16,
+            2048,
+            64,
+            addr,
+            Ipv4Addr::LOCALHOST,
+            addr,
+            addr,
+            addr,
+            controls,
+            false,
+        )
+    }
+
+    #[test]
+    fn document_holds_every_part_of_the_radar_state() {
+        let session = Session::new_fake();
+        let mut a = channel(&session, "A", 18520.);
+        let b = channel(&session, "B", 111120.);
+        a.set_rotation_length(2500);
+
+        let doc = RadarDocument::new("radar-1", &a, &[a.clone(), b], RangeUnitPreference::default());
+        let json = serde_json::to_value(&doc).unwrap();
+
+        assert_eq!(json["id"], "radar-1");
+        assert_eq!(json["power"], "transmit");
+        assert_eq!(json["settings"]["gain"]["auto"], true);
+        assert_eq!(json["settings"]["gain"]["value"], 40.);
+        assert!(json["settings"]["userName"].is_null());
+
+        let range = doc.range.as_ref().unwrap();
+        assert_eq!(range.meters, 1852);
+        assert_eq!(range.raw.map(f32::round), Some(18520.));
+        assert!(!range.formatted.is_empty());
+
+        assert_eq!(json["rotation"]["periodMs"], 2500.);
+        assert_eq!(json["rotation"]["rpm"], 24.);
+        assert_eq!(json["radar"]["brand"], Brand::Navico.to_string());
+        assert_eq!(json["radar"]["serial"], "1234567");
+        assert_eq!(json["radar"]["antenna"], "4ft open array");
+        assert!(json["radar"].get("model").is_some());
+
+        let channels = json["channels"].as_array().unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0]["which"], "A");
+        assert_eq!(channels[1]["which"], "B");
+        assert_eq!(channels[1]["power"], "transmit");
+        assert_eq!(channels[1]["range"]["meters"], 11112);
+    }
+}
@@ -175,7 +175,7 @@ pub async fn start_recording(
         writer,
         brand_id,
         radar_info.spokes_per_revolution as u32,
-        radar_info.max_spoke_len as u32,
+        radar_info.output_spoke_len() as u32,
         radar_info.pixel_values as u32,
         capabilities_json,
         initial_state_json,
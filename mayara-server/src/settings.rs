@@ -3,14 +3,18 @@ use std::{
     collections::HashMap,
     str::FromStr,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 use thiserror::Error;
 
 use mayara_core::capabilities::ControlDefinition as CoreControlDefinition;
+use mayara_core::SettingsDiff;
 
 use crate::{
     control_factory,
-    radar::{range::Ranges, DopplerMode, Legend, RadarError, Status},
+    decode_ring::{DecodedMessage, SharedDecodeRing},
+    radar::{command_queue, liveness::LinkState, range::Ranges, DopplerMode, Legend, RadarError, Status},
+    settings_history::{SettingChange, SettingsHistory, DEFAULT_SETTINGS_HISTORY_SIZE},
     Session, TargetMode,
 };
 
@@ -48,6 +52,17 @@ pub struct Controls {
     control_update_tx: tokio::sync::broadcast::Sender<ControlUpdate>,
     #[serde(skip)]
     data_update_tx: tokio::sync::broadcast::Sender<DataUpdate>,
+    #[serde(skip)]
+    settings_diff_tx: tokio::sync::broadcast::Sender<SettingsDiff>,
+    #[serde(skip)]
+    command_queue_tx: Option<tokio::sync::mpsc::UnboundedSender<ControlUpdate>>, // Started on first command
+
+    #[serde(skip)]
+    radar_key: Option<String>, // Identifies the radar in the decode ring
+    #[serde(skip)]
+    decode_ring: SharedDecodeRing,
+    #[serde(skip)]
+    settings_history: SettingsHistory,
 }
 
 impl Controls {
@@ -116,12 +131,29 @@ impl Controls {
                 .set_destination(ControlDestination::Data),
             );
 
+            string_controls.insert(
+                "trailsRangeLocked".to_string(),
+                Control::new_map(
+                    "trailsRangeLocked",
+                    HashMap::from([(0, "Off".to_string()), (1, "On".to_string())]),
+                )
+                .set_destination(ControlDestination::Data),
+            );
+
             string_controls.insert(
                 "clearTrails".to_string(),
                 Control::new_button("clearTrails")
                     .set_destination(ControlDestination::Data),
             );
 
+            if session.read().unwrap().args.noise_floor > 0 {
+                string_controls.insert(
+                    "clearNoiseFloor".to_string(),
+                    Control::new_button("clearNoiseFloor")
+                        .set_destination(ControlDestination::Data),
+                );
+            }
+
             if session.read().unwrap().args.targets == TargetMode::Arpa {
                 string_controls.insert(
                     "clearTargets".to_string(),
@@ -134,6 +166,8 @@ impl Controls {
         let (all_clients_tx, _) = tokio::sync::broadcast::channel(32);
         let (control_update_tx, _) = tokio::sync::broadcast::channel(32);
         let (data_update_tx, _) = tokio::sync::broadcast::channel(10);
+        let (settings_diff_tx, _) = tokio::sync::broadcast::channel(10);
+        let decode_ring = session.read().unwrap().decode_ring.clone();
 
         Controls {
             session: session.clone(),
@@ -141,8 +175,19 @@ impl Controls {
             all_clients_tx,
             control_update_tx,
             data_update_tx,
+            settings_diff_tx,
+            command_queue_tx: None,
+            radar_key: None,
+            decode_ring,
+            settings_history: SettingsHistory::new(DEFAULT_SETTINGS_HISTORY_SIZE),
         }
     }
+
+    /// Keep a decoded message in the session's decode ring
+    fn record_decoded(&self, kind: &'static str, message: serde_json::Value) {
+        let message = DecodedMessage::new(self.radar_key.as_deref(), kind, message);
+        self.decode_ring.lock().unwrap().push(message);
+    }
 }
 
 #[derive(Clone, Debug, Serialize)]
@@ -180,6 +225,12 @@ impl SharedControls {
         }
     }
 
+    /// Set the key under which this radar's messages are recorded
+    pub(crate) fn set_radar_key(&self, key: &str) {
+        let mut locked = self.controls.write().unwrap();
+        locked.radar_key = Some(key.to_string());
+    }
+
     pub(crate) fn get_data_update_tx(&self) -> tokio::sync::broadcast::Sender<DataUpdate> {
         let locked = self.controls.read().unwrap();
 
@@ -192,7 +243,7 @@ impl SharedControls {
         locked.control_update_tx.clone()
     }
 
-    pub(crate) fn all_clients_rx(&self) -> tokio::sync::broadcast::Receiver<ControlValue> {
+    pub fn all_clients_rx(&self) -> tokio::sync::broadcast::Receiver<ControlValue> {
         let locked = self.controls.read().unwrap();
 
         locked.all_clients_tx.subscribe()
@@ -256,6 +307,33 @@ impl SharedControls {
         locked.data_update_tx.subscribe()
     }
 
+    /// Subscribe to settings report diffs; only changed fields are sent
+    pub fn settings_diff_subscribe(&self) -> tokio::sync::broadcast::Receiver<SettingsDiff> {
+        let locked = self.controls.read().unwrap();
+
+        locked.settings_diff_tx.subscribe()
+    }
+
+    /// Emit the changed fields of a decoded settings report, if any
+    pub fn send_settings_diff(&self, diff: Option<SettingsDiff>) {
+        if let Some(diff) = diff {
+            log::debug!("Settings changed: {:?}", diff);
+            let mut locked = self.controls.write().unwrap();
+            locked.record_decoded("settings", serde_json::json!(diff));
+            locked.settings_history.record(&diff);
+            let _ = locked.settings_diff_tx.send(diff);
+        }
+    }
+
+    /// The last `limit` settings changes reported by the radar, oldest first
+    pub fn settings_history(&self, setting: Option<&str>, limit: usize) -> Vec<SettingChange> {
+        self.controls
+            .read()
+            .unwrap()
+            .settings_history
+            .recent(setting, limit)
+    }
+
     pub async fn send_all_controls(
         &self,
         reply_tx: tokio::sync::mpsc::Sender<ControlValue>,
@@ -289,14 +367,100 @@ impl SharedControls {
         control_value: ControlValue,
         reply_tx: tokio::sync::mpsc::Sender<ControlValue>,
     ) -> Result<(), RadarError> {
+        let previous = self.set_intended(&control_value)?;
+        let id = control_value.id.clone();
         let control_update = ControlUpdate {
             control_value,
             reply_tx,
         };
-        self.get_command_tx()
-            .send(control_update)
-            .map(|_| ())
-            .map_err(|_| RadarError::Shutdown)
+        let sent = match self.get_command_queue_tx() {
+            Some(queue_tx) => queue_tx
+                .send(control_update)
+                .map_err(|_| RadarError::Shutdown),
+            None => self
+                .get_command_tx()
+                .send(control_update)
+                .map(|_| ())
+                .map_err(|_| RadarError::Shutdown),
+        };
+        if sent.is_err() {
+            if let Some((value, auto)) = previous {
+                self.restore_intended(&id, value, auto);
+            }
+        }
+        sent
+    }
+
+    /// The sender of this radar's command queue, which is started on first
+    /// use; `None` when throttling is disabled with `--command-interval 0`
+    fn get_command_queue_tx(&self) -> Option<tokio::sync::mpsc::UnboundedSender<ControlUpdate>> {
+        let mut locked = self.controls.write().unwrap();
+        if locked.command_queue_tx.is_none() {
+            let ms = locked.session.read().unwrap().args.command_interval;
+            if ms == 0 {
+                return None;
+            }
+            locked.command_queue_tx = Some(command_queue::spawn_command_queue(
+                locked.radar_key.clone().unwrap_or_default(),
+                Duration::from_millis(ms),
+                locked.control_update_tx.clone(),
+            ));
+        }
+        locked.command_queue_tx.clone()
+    }
+
+    // Show a value the user asked for right away, before the command reaches
+    // the radar; the next report from the radar corrects it if need be.
+    // A value outside the control's limits is refused. Returns the value and
+    // auto state that were replaced, if any.
+    fn set_intended(
+        &self,
+        control_value: &ControlValue,
+    ) -> Result<Option<(Option<f32>, Option<bool>)>, ControlError> {
+        let Ok(value) = control_value.value.parse::<f32>() else {
+            return Ok(None);
+        };
+        let (control, previous) = {
+            let mut locked = self.controls.write().unwrap();
+            let Some(control) = locked.controls.get_mut(&control_value.id) else {
+                return Ok(None);
+            };
+            if let Some(min_value) = control.item().min_value {
+                if value < min_value {
+                    return Err(ControlError::TooLow(control_value.id.clone(), value, min_value));
+                }
+            }
+            if let Some(max_value) = control.item().max_value {
+                if value > max_value {
+                    return Err(ControlError::TooHigh(control_value.id.clone(), value, max_value));
+                }
+            }
+            let auto = control_value.auto.or(control.auto);
+            if control.value == Some(value) && control.auto == auto {
+                return Ok(None);
+            }
+            let previous = (control.value, control.auto);
+            control.value = Some(value);
+            control.auto = auto;
+            (control.clone(), previous)
+        };
+        self.send_to_all_clients(&control);
+        Ok(Some(previous))
+    }
+
+    // Put back the value an intended value replaced, when its command could
+    // not be handed to the radar
+    fn restore_intended(&self, id: &str, value: Option<f32>, auto: Option<bool>) {
+        let control = {
+            let mut locked = self.controls.write().unwrap();
+            let Some(control) = locked.controls.get_mut(id) else {
+                return;
+            };
+            control.value = value;
+            control.auto = auto;
+            control.clone()
+        };
+        self.send_to_all_clients(&control);
     }
 
     fn send_to_all_clients(&self, control: &Control) {
@@ -305,10 +469,12 @@ impl SharedControls {
             value: control.value(),
             auto: control.auto,
             enabled: control.enabled,
+            description: control.value_description(),
             error: None,
         };
 
         let locked = self.controls.read().unwrap();
+        locked.record_decoded("control", serde_json::json!(control_value));
         match locked.all_clients_tx.send(control_value) {
             Err(_e) => {}
             Ok(cnt) => {
@@ -321,6 +487,14 @@ impl SharedControls {
         }
     }
 
+    /// Tell the clients whether the radar is online, see `radar::liveness`
+    pub(crate) fn send_link_state(&self, state: LinkState) {
+        let control_value = ControlValue::new("link", state.as_str().to_string());
+        let locked = self.controls.read().unwrap();
+        locked.record_decoded("control", serde_json::json!(control_value));
+        let _ = locked.all_clients_tx.send(control_value);
+    }
+
     pub async fn send_reply_to_client(
         &self,
         reply_tx: tokio::sync::mpsc::Sender<ControlValue>,
@@ -480,6 +654,36 @@ impl SharedControls {
         }
     }
 
+    //
+    // Set a numeric control from a wire value together with a description
+    // of the value that clients can show, e.g. "Warming up (34s)"
+    //
+    pub fn set_with_description(
+        &self,
+        id: &str,
+        value: f32,
+        description: Option<String>,
+    ) -> Result<Option<()>, ControlError> {
+        let control = {
+            let mut locked = self.controls.write().unwrap();
+            if let Some(control) = locked.controls.get_mut(id) {
+                let changed = control.set(value, None, None, None)?.is_some();
+                let described = control.description != description;
+                control.description = description;
+                Ok((changed || described).then(|| control.clone()))
+            } else {
+                Err(ControlError::NotSupported(id.to_string()))
+            }
+        }?;
+
+        if let Some(control) = control {
+            self.send_to_all_clients(&control);
+            Ok(Some(()))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub fn set_auto_state(
         &self,
         id: &str,
@@ -651,6 +855,9 @@ pub struct ControlValue {
     pub auto: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub enabled: Option<bool>,
+    /// What a numeric value means, e.g. "Warming up (34s)"
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
@@ -662,6 +869,7 @@ impl ControlValue {
             value,
             auto: None,
             enabled: None,
+            description: None,
             error: None,
         }
     }
@@ -672,6 +880,7 @@ impl ControlValue {
             value: control.value(),
             auto: control.auto,
             enabled: control.enabled,
+            description: control.value_description(),
             error,
         }
     }
@@ -1016,6 +1225,16 @@ impl Control {
     //     self.auto
     // }
 
+    /// Description of a numeric value; for string controls the description
+    /// is the value itself
+    pub fn value_description(&self) -> Option<String> {
+        if self.item.data_type == ControlDataType::String {
+            None
+        } else {
+            self.description.clone()
+        }
+    }
+
     pub fn value(&self) -> String {
         if self.item.data_type == ControlDataType::String {
             return self.description.clone().unwrap_or_else(|| "".to_string());
@@ -1030,6 +1249,24 @@ impl Control {
             .to_string()
     }
 
+    /// The value as the radar sends it, undoing the scaling of `set`
+    pub fn wire_value(&self) -> Option<f32> {
+        let mut value = self.value?;
+        if let (Some(wire_scale_factor), Some(max_value)) =
+            (self.item.wire_scale_factor, self.item.max_value)
+        {
+            if wire_scale_factor != max_value {
+                value = value * wire_scale_factor / max_value;
+            }
+        }
+        if let Some(wire_offset) = self.item.wire_offset {
+            if wire_offset > 0.0 {
+                value += wire_offset;
+            }
+        }
+        Some(value)
+    }
+
     pub fn set_auto(&mut self, auto: bool) {
         self.needs_refresh = self.auto != Some(auto);
         log::trace!(
@@ -1339,4 +1576,61 @@ mod test {
         assert!(controls.set("targetTrails", -1., None).is_err());
         assert!(controls.set("targetTrails", 0.3, None).is_ok());
     }
+
+    #[test]
+    fn settings_diff_suppresses_no_op() {
+        let session = crate::Session::new_fake();
+        let controls = SharedControls::new(session, HashMap::new());
+        let mut rx = controls.settings_diff_subscribe();
+
+        controls.send_settings_diff(None);
+        assert!(rx.try_recv().is_err());
+
+        let diff = SettingsDiff::from([("gain".to_string(), serde_json::json!(60))]);
+        controls.send_settings_diff(Some(diff.clone()));
+        assert_eq!(rx.try_recv().unwrap(), diff);
+    }
+
+    #[test]
+    fn intended_value_is_validated_and_restored_when_not_sent() {
+        use clap::Parser;
+
+        let session = crate::Session::new_base(crate::Cli::parse_from([
+            "my_program",
+            "--command-interval",
+            "0",
+        ]));
+        let mut controls = HashMap::new();
+        controls.insert(
+            "gain".to_string(),
+            control_factory::gain_control_for_brand(mayara_core::Brand::Navico),
+        );
+        let controls = SharedControls::new(session, controls);
+        controls.set_value_auto("gain", false, 102.).unwrap(); // 40 of 100
+        let (reply_tx, _reply_rx) = tokio::sync::mpsc::channel(1);
+        let gain = |controls: &SharedControls| controls.get("gain").unwrap().value;
+
+        // Out of limits: refused, nothing is shown or sent
+        let result = controls
+            .send_to_command_handler(ControlValue::new("gain", "150".to_string()), reply_tx.clone());
+        assert!(matches!(
+            result,
+            Err(RadarError::ControlError(ControlError::TooHigh(..)))
+        ));
+        assert_eq!(gain(&controls), Some(40.));
+
+        // No command handler to take it: the previous value comes back
+        let result = controls
+            .send_to_command_handler(ControlValue::new("gain", "60".to_string()), reply_tx.clone());
+        assert!(matches!(result, Err(RadarError::Shutdown)));
+        assert_eq!(gain(&controls), Some(40.));
+
+        // Handed to the radar: shown right away
+        let mut command_rx = controls.control_update_subscribe();
+        controls
+            .send_to_command_handler(ControlValue::new("gain", "60".to_string()), reply_tx)
+            .unwrap();
+        assert_eq!(gain(&controls), Some(60.));
+        assert_eq!(command_rx.try_recv().unwrap().control_value.value, "60");
+    }
 }
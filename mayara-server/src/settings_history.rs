@@ -0,0 +1,138 @@
+//! History of the settings a radar reported
+//!
+//! During a tuning session it helps to see what changed and when, also when
+//! the change was made on another display. Every settings diff of a radar is
+//! compared with the values seen before, and each setting that got a new
+//! value is kept with its old value and the time. The first report of a
+//! setting only establishes its value and is not a change. Only the last
+//! `capacity` changes are kept, in memory.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mayara_core::SettingsDiff;
+use serde::Serialize;
+
+/// Number of changes kept per radar
+pub const DEFAULT_SETTINGS_HISTORY_SIZE: usize = 200;
+
+/// One setting that changed value
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingChange {
+    pub timestamp: u64, // Milliseconds since the epoch
+    pub setting: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+#[derive(Clone, Debug)]
+pub struct SettingsHistory {
+    capacity: usize,
+    last: HashMap<String, serde_json::Value>,
+    changes: VecDeque<SettingChange>,
+}
+
+impl SettingsHistory {
+    pub fn new(capacity: usize) -> Self {
+        SettingsHistory {
+            capacity,
+            last: HashMap::new(),
+            changes: VecDeque::new(),
+        }
+    }
+
+    /// Remember the changes in a settings diff, made now
+    pub fn record(&mut self, diff: &SettingsDiff) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.record_at(diff, timestamp);
+    }
+
+    fn record_at(&mut self, diff: &SettingsDiff, timestamp: u64) {
+        // Diffs are unordered, keep the entries of one report stable
+        let mut settings: Vec<_> = diff.iter().collect();
+        settings.sort_by(|a, b| a.0.cmp(b.0));
+        for (setting, value) in settings {
+            let Some(old) = self.last.insert(setting.clone(), value.clone()) else {
+                continue;
+            };
+            if old == *value || self.capacity == 0 {
+                continue;
+            }
+            self.changes.push_back(SettingChange {
+                timestamp,
+                setting: setting.clone(),
+                old,
+                new: value.clone(),
+            });
+            if self.changes.len() > self.capacity {
+                self.changes.pop_front();
+            }
+        }
+    }
+
+    /// The last `limit` changes, oldest first, optionally of one setting only
+    pub fn recent(&self, setting: Option<&str>, limit: usize) -> Vec<SettingChange> {
+        let mut changes: Vec<SettingChange> = self
+            .changes
+            .iter()
+            .rev()
+            .filter(|c| setting.map_or(true, |s| c.setting == s))
+            .take(limit)
+            .cloned()
+            .collect();
+        changes.reverse();
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn diff(fields: &[(&str, serde_json::Value)]) -> SettingsDiff {
+        fields
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn two_gain_changes_give_two_ordered_entries() {
+        let mut history = SettingsHistory::new(10);
+        // The first report only sets the starting values
+        history.record_at(&diff(&[("gain", json!(40)), ("sea", json!(30))]), 1000);
+        assert!(history.recent(None, 10).is_empty());
+
+        history.record_at(&diff(&[("gain", json!(55))]), 2000);
+        history.record_at(&diff(&[("gain", json!(70)), ("rain", json!(5))]), 3000);
+
+        let changes = history.recent(None, 10);
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            (changes[0].timestamp, &changes[0].old, &changes[0].new),
+            (2000, &json!(40), &json!(55))
+        );
+        assert_eq!(
+            (changes[1].timestamp, &changes[1].old, &changes[1].new),
+            (3000, &json!(55), &json!(70))
+        );
+        assert!(changes.iter().all(|c| c.setting == "gain"));
+        assert_eq!(history.recent(Some("gain"), 1), changes[1..]);
+        assert!(history.recent(Some("sea"), 10).is_empty());
+    }
+
+    #[test]
+    fn only_the_last_changes_are_kept() {
+        let mut history = SettingsHistory::new(3);
+        for gain in 0..10 {
+            history.record_at(&diff(&[("gain", json!(gain))]), gain as u64);
+        }
+        let new: Vec<_> = history.recent(None, 10).into_iter().map(|c| c.new).collect();
+        assert_eq!(new, vec![json!(7), json!(8), json!(9)]);
+    }
+}
@@ -0,0 +1,178 @@
+//! Capture file of packets the decoders could not make sense of
+//!
+//! To grow the decoder coverage we need the frames that stumped us. With
+//! `--log-unknown <FILE>` every packet sent to the decoders that no decoder
+//! handles, or that decodes to an error, and every report of a live radar
+//! that its receiver does not know, is appended to the file as one JSON
+//! object per line: the time, the brand of the decoder that was tried, the
+//! port, the direction and the raw bytes in hex. Once the file has reached
+//! `--log-unknown-size` megabytes nothing more is written, so a chatty radar
+//! cannot fill the disk; the first frames are usually the interesting ones.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+use serde::Serialize;
+
+use crate::decode_ring::DecodedMessage;
+use crate::decoders::Direction;
+use crate::Brand;
+
+/// Default size limit of the capture file, in megabytes
+pub const DEFAULT_UNKNOWN_LOG_SIZE_MB: u64 = 16;
+
+/// One packet as written to the capture file
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+struct UnknownPacket<'a> {
+    timestamp: u64, // Milliseconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    brand: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    port: Option<u16>,
+    direction: Direction,
+    length: usize,
+    data: &'a str,
+}
+
+pub struct UnknownLog {
+    path: PathBuf,
+    max_bytes: u64,
+    written: u64,
+    full: bool,
+}
+
+pub type SharedUnknownLog = Arc<Mutex<UnknownLog>>;
+
+impl UnknownLog {
+    /// Append to the capture file at `path`, which may already exist
+    pub fn open(path: &Path, max_bytes: u64) -> io::Result<Self> {
+        let written = match fs::metadata(path) {
+            Ok(metadata) => metadata.len(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        Ok(UnknownLog {
+            path: path.to_owned(),
+            max_bytes,
+            written,
+            full: false,
+        })
+    }
+
+    pub fn open_shared(path: &Path, size_mb: u64) -> io::Result<SharedUnknownLog> {
+        Ok(Arc::new(Mutex::new(Self::open(path, size_mb * 1024 * 1024)?)))
+    }
+
+    /// Write the packet when the decoders did not understand it: nothing was
+    /// decoded, or one of its messages is an error. Returns whether the
+    /// packet was written.
+    pub fn record(
+        &mut self,
+        brand: Option<Brand>,
+        port: Option<u16>,
+        direction: Direction,
+        data: &[u8],
+        decoded: &[DecodedMessage],
+    ) -> io::Result<bool> {
+        let unknown = decoded.is_empty() || decoded.iter().any(|d| d.message.get("error").is_some());
+        if !unknown {
+            return Ok(false);
+        }
+        self.record_unknown(brand, port, direction, data)
+    }
+
+    /// Write a packet that is known not to be understood, such as a report
+    /// the receiver of a live radar has no case for. Returns whether the
+    /// packet was written.
+    pub fn record_unknown(
+        &mut self,
+        brand: Option<Brand>,
+        port: Option<u16>,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<bool> {
+        if self.full {
+            return Ok(false);
+        }
+
+        let hex: String = data.iter().map(|b| format!("{:02x}", b)).collect();
+        let packet = UnknownPacket {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            brand: brand.map(|b| b.to_string()),
+            port,
+            direction,
+            length: data.len(),
+            data: &hex,
+        };
+        let mut line = serde_json::to_string(&packet)?;
+        line.push('\n');
+        if self.written + line.len() as u64 > self.max_bytes {
+            warn!(
+                "Unknown packet log '{}' reached {} bytes, no longer writing to it",
+                self.path.display(),
+                self.max_bytes
+            );
+            self.full = true;
+            return Ok(false);
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        self.written += line.len() as u64;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoders::DecoderRegistry;
+    use tempfile::TempDir;
+
+    #[test]
+    #[cfg(feature = "navico")]
+    fn unknown_packets_are_written_and_known_ones_are_not() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("unknown.ndjson");
+        let registry = DecoderRegistry::new();
+        let port = Some(mayara_core::protocol::navico::INFO_PORT);
+        let mut log = UnknownLog::open(&path, 1024).unwrap();
+
+        let mut status = vec![0u8; 18];
+        status[..3].copy_from_slice(&[0x01, 0xC4, 0x01]); // standby
+        let decoded = registry.decode_batch(port, &status, Direction::FromRadar);
+        assert!(!log
+            .record(Some(Brand::Navico), port, Direction::FromRadar, &status, &decoded)
+            .unwrap());
+
+        let garbage = [0xde, 0xad, 0xbe, 0xef];
+        let decoded = registry.decode_batch(port, &garbage, Direction::FromRadar);
+        assert!(log
+            .record(Some(Brand::Navico), port, Direction::FromRadar, &garbage, &decoded)
+            .unwrap());
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> =
+            text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["data"], "deadbeef");
+        assert_eq!(lines[0]["brand"], Brand::Navico.to_string());
+        assert_eq!(lines[0]["port"], port.unwrap());
+        assert_eq!(lines[0]["direction"], "fromRadar");
+
+        // The file stops growing at its limit
+        for _ in 0..100 {
+            log.record(None, Some(1234), Direction::ToRadar, &garbage, &[])
+                .unwrap();
+        }
+        assert!(fs::metadata(&path).unwrap().len() <= 1024);
+    }
+}
@@ -3,11 +3,15 @@ use axum::{
     extract::{ConnectInfo, Path, State},
     http::{header, StatusCode, Uri},
     middleware::{self, Next},
-    response::{IntoResponse, Response},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
     routing::{delete, get, post, put},
     Json, Router,
 };
 use axum_embed::ServeEmbed;
+use base64::prelude::*;
 use hyper;
 use log::{debug, trace};
 use miette::Result;
@@ -17,12 +21,15 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "dev")]
 use tower_http::services::ServeDir;
 use flate2::{write::GzEncoder, Compression};
+use futures_util::StreamExt;
 use std::{
     collections::{BTreeMap, HashMap},
+    convert::Infallible,
     io::{self, Write},
     net::{IpAddr, Ipv4Addr, SocketAddr},
     str::FromStr,
     sync::{Arc, RwLock},
+    time::{Duration, Instant},
 };
 use thiserror::Error;
 use tokio::{net::TcpListener, sync::broadcast};
@@ -33,11 +40,31 @@ mod axum_fix;
 use axum_fix::{Message, WebSocket, WebSocketUpgrade};
 
 use mayara_server::{
-    radar::{Legend, RadarError, RadarInfo},
+    capability_export::{self, CapabilityExport},
+    pcap,
+    radar::{
+        checksum,
+        composite,
+        delta,
+        guard_zone::GuardZones,
+        handshake,
+        range::{self, RangeUnitPreference},
+        raster,
+        png::{self, SnapshotStyle},
+        roi,
+        snapshot,
+        target_script::{TargetEvent, TargetScript},
+        power_state, Legend, RadarError, RadarInfo,
+    },
+    radar_document::RadarDocument,
     recording::{
         RecordingManager, RecordingInfo, RecordingStatus, ActiveRecording, start_recording, build_initial_state,
         ActivePlayback, PlaybackSettings, PlaybackStatus, load_recording, unregister_playback_radar,
     },
+    alarm_log::{AlarmEvent, DEFAULT_ALARM_LOG_SIZE},
+    settings_history::DEFAULT_SETTINGS_HISTORY_SIZE,
+    decode_ring::{DEFAULT_BOOKMARK_SECONDS, MAX_BOOKMARK_SECONDS},
+    navdata,
     storage::{AppDataKey, SharedStorage, create_shared_storage},
     ProtoAssets, Session,
 };
@@ -45,8 +72,12 @@ use mayara_server::{
 // ARPA types from mayara-core for v6 API
 use mayara_core::arpa::{ArpaSettings, ArpaTarget};
 
+// Target positions from mayara-core
+use mayara_core::geo::TargetPosition;
+
 // Guard zone types from mayara-core
 use mayara_core::guard_zones::{GuardZone, GuardZoneStatus};
+use mayara_core::units::BearingUnit;
 
 // Trail types from mayara-core
 use mayara_core::trails::{TrailData, TrailSettings};
@@ -58,22 +89,36 @@ use mayara_core::dual_range::{DualRangeConfig, DualRangeState as CoreDualRangeSt
 use mayara_core::engine::RadarEngine;
 
 // Capability types from mayara-core for v5 API
-use mayara_core::capabilities::{builder::build_capabilities_from_model_with_key, RadarStateV5, SupportedFeature};
+use mayara_core::capabilities::{
+    builder::build_capabilities_from_model_with_key, CapabilityManifest, OverlayAlignment, RadarStateV5,
+};
 use mayara_core::models;
 
+// Sea clutter auto mode from mayara-core
+use mayara_core::protocol::navico::SeaAutoMode;
+
 // Standalone Radar API v2 paths (matches SignalK Radar API v2 structure)
 const RADARS_URI: &str = "/v2/api/radars";
 const RADAR_CAPABILITIES_URI: &str = "/v2/api/radars/{radar_id}/capabilities";
+const CAPABILITIES_EXPORT_URI: &str = "/v2/api/radars/{radar_id}/capabilities/export";
 const RADAR_STATE_URI: &str = "/v2/api/radars/{radar_id}/state";
+const RADAR_DOCUMENT_URI: &str = "/v2/api/radars/{radar_id}/document";
 const SPOKES_URI: &str = "/v2/api/radars/{radar_id}/spokes";
+const SPOKE_EVENTS_URI: &str = "/v2/api/radars/{radar_id}/spokes/events";
+const STATUS_URI: &str = "/v2/api/radars/{radar_id}/status";
 const CONTROL_URI: &str = "/v2/api/radars/{radar_id}/control";
 const CONTROL_VALUE_URI: &str = "/v2/api/radars/{radar_id}/controls/{control_id}";
+const TEST_PATTERN_URI: &str = "/v2/api/radars/{radar_id}/testPattern";
+const TARGET_SCRIPT_URI: &str = "/v2/api/radars/{radar_id}/targetScript";
+const SNAPSHOT_URI: &str = "/v2/api/radars/{radar_id}/snapshot";
 const TARGETS_URI: &str = "/v2/api/radars/{radar_id}/targets";
 const TARGET_URI: &str = "/v2/api/radars/{radar_id}/targets/{target_id}";
 const ARPA_SETTINGS_URI: &str = "/v2/api/radars/{radar_id}/arpa/settings";
 // Guard zones
 const GUARD_ZONES_URI: &str = "/v2/api/radars/{radar_id}/guardZones";
 const GUARD_ZONE_URI: &str = "/v2/api/radars/{radar_id}/guardZones/{zone_id}";
+const ALARMS_URI: &str = "/v2/api/radars/{radar_id}/alarms";
+const SETTINGS_HISTORY_URI: &str = "/v2/api/radars/{radar_id}/settings/history";
 // Trails
 const TRAILS_URI: &str = "/v2/api/radars/{radar_id}/trails";
 const TRAIL_URI: &str = "/v2/api/radars/{radar_id}/trails/{target_id}";
@@ -81,9 +126,20 @@ const TRAIL_SETTINGS_URI: &str = "/v2/api/radars/{radar_id}/trails/settings";
 // Dual-range
 const DUAL_RANGE_URI: &str = "/v2/api/radars/{radar_id}/dualRange";
 const DUAL_RANGE_SPOKES_URI: &str = "/v2/api/radars/{radar_id}/dualRange/spokes";
+const DUAL_RANGE_COMPOSITE_URI: &str = "/v2/api/radars/{radar_id}/dualRange/composite";
+const RADAR_RANGE_UNITS_URI: &str = "/v2/api/radars/{radar_id}/rangeUnits";
+const AZIMUTH_OFFSET_URI: &str = "/v2/api/radars/{radar_id}/azimuthOffset";
+const RANGE_PRESETS_URI: &str = "/v2/api/radars/{radar_id}/rangePresets";
 
 // Non-radar endpoints
 const INTERFACES_URI: &str = "/v2/api/interfaces";
+const OPENAPI_URI: &str = "/v2/api/openapi.json";
+const DECODED_URI: &str = "/v2/api/debug/decoded";
+const DECODED_BOOKMARK_URI: &str = "/v2/api/debug/decoded/bookmark";
+const REPLAY_URI: &str = "/v2/api/debug/replay";
+const DECODERS_URI: &str = "/v2/api/decoders";
+const RANGE_UNITS_URI: &str = "/v2/api/rangeUnits";
+const DECODERS_DECODE_URI: &str = "/v2/api/decoders/decode";
 
 // SignalK applicationData API (for settings persistence)
 const APP_DATA_URI: &str = "/signalk/v1/applicationData/global/{appid}/{version}/{*key}";
@@ -176,6 +232,11 @@ impl Web {
         }
     }
 
+    /// Unit in which bearings are reported to and accepted from clients
+    fn bearing_unit(&self) -> BearingUnit {
+        self.session.read().unwrap().args.bearing_unit
+    }
+
     /// Ensure a radar exists in the engine (lazy initialization)
     /// The engine uses "virtual" radars since actual controller management
     /// is done by the Session. We just need the feature processors.
@@ -199,6 +260,49 @@ impl Web {
         engine.set_model_info(radar_id, model_name);
     }
 
+    /// Endpoints that change radar settings or the state of the server,
+    /// gated by --control-token
+    fn control_routes(&self) -> Router<Web> {
+        Router::new()
+            .route(CONTROL_URI, get(control_handler))
+            .route(CONTROL_VALUE_URI, put(set_control_value))
+            .route(TEST_PATTERN_URI, post(send_test_pattern))
+            .route(TARGET_SCRIPT_URI, post(run_target_script))
+            .route(DECODERS_URI, put(set_forced_decoder))
+            .route(TARGETS_URI, post(acquire_target))
+            .route(TARGET_URI, delete(cancel_target))
+            .route(ARPA_SETTINGS_URI, put(set_arpa_settings))
+            .route(GUARD_ZONES_URI, post(create_guard_zone))
+            .route(GUARD_ZONE_URI, put(update_guard_zone).delete(delete_guard_zone))
+            .route(TRAILS_URI, delete(clear_all_trails))
+            .route(TRAIL_URI, delete(clear_trail))
+            .route(TRAIL_SETTINGS_URI, put(set_trail_settings))
+            .route(DUAL_RANGE_URI, put(set_dual_range))
+            .route(RADAR_RANGE_UNITS_URI, put(set_radar_range_units))
+            .route(AZIMUTH_OFFSET_URI, put(set_azimuth_offset))
+            .route(RANGE_UNITS_URI, put(set_range_units))
+            .route(DECODED_BOOKMARK_URI, post(bookmark_decoded))
+            .route(REPLAY_URI, post(replay_capture))
+            // SignalK applicationData API
+            .route(APP_DATA_URI, put(put_app_data).delete(delete_app_data))
+            // Recordings API - File management
+            .route(RECORDING_URI, delete(delete_recording).put(update_recording))
+            .route(RECORDING_UPLOAD_URI, post(upload_recording))
+            .route(RECORDINGS_DIRS_URI, post(create_directory))
+            .route(RECORDING_DIR_URI, delete(delete_directory))
+            // Recordings API - Recording control
+            .route(RECORD_START_URI, post(start_recording_handler))
+            .route(RECORD_STOP_URI, post(stop_recording_handler))
+            // Recordings API - Playback control
+            .route(PLAYBACK_LOAD_URI, post(playback_load_handler))
+            .route(PLAYBACK_PLAY_URI, post(playback_play_handler))
+            .route(PLAYBACK_PAUSE_URI, post(playback_pause_handler))
+            .route(PLAYBACK_STOP_URI, post(playback_stop_handler))
+            .route(PLAYBACK_SEEK_URI, post(playback_seek_handler))
+            .route(PLAYBACK_SETTINGS_URI, put(playback_settings_handler))
+            .route_layer(middleware::from_fn_with_state(self.clone(), require_control_token))
+    }
+
     pub async fn run(self, subsys: SubsystemHandle) -> Result<(), WebError> {
         let port = self.session.read().unwrap().args.port.clone();
         let listener =
@@ -230,46 +334,50 @@ impl Web {
             // Standalone Radar API v1 (matches SignalK structure for GUI compatibility)
             .route(RADARS_URI, get(get_radars))
             .route(RADAR_CAPABILITIES_URI, get(get_radar_capabilities))
+            .route(CAPABILITIES_EXPORT_URI, get(export_radar_capabilities))
             .route(RADAR_STATE_URI, get(get_radar_state))
+            .route(RADAR_DOCUMENT_URI, get(get_radar_document))
             .route(SPOKES_URI, get(spokes_handler))
-            .route(CONTROL_URI, get(control_handler))
-            .route(CONTROL_VALUE_URI, put(set_control_value))
-            .route(TARGETS_URI, get(get_targets).post(acquire_target))
-            .route(TARGET_URI, delete(cancel_target))
-            .route(ARPA_SETTINGS_URI, get(get_arpa_settings).put(set_arpa_settings))
+            .route(SPOKE_EVENTS_URI, get(spoke_events_handler))
+            .route(STATUS_URI, get(status_handler))
+            .route(SNAPSHOT_URI, get(get_snapshot))
+            .merge(self.control_routes())
+            .route(TARGETS_URI, get(get_targets))
+            .route(ARPA_SETTINGS_URI, get(get_arpa_settings))
             // Guard zones
-            .route(GUARD_ZONES_URI, get(get_guard_zones).post(create_guard_zone))
-            .route(GUARD_ZONE_URI, get(get_guard_zone).put(update_guard_zone).delete(delete_guard_zone))
+            .route(GUARD_ZONES_URI, get(get_guard_zones))
+            .route(GUARD_ZONE_URI, get(get_guard_zone))
+            .route(ALARMS_URI, get(get_alarms))
+            .route(SETTINGS_HISTORY_URI, get(get_settings_history))
             // Trails
-            .route(TRAILS_URI, get(get_all_trails).delete(clear_all_trails))
-            .route(TRAIL_URI, get(get_trail).delete(clear_trail))
-            .route(TRAIL_SETTINGS_URI, get(get_trail_settings).put(set_trail_settings))
+            .route(TRAILS_URI, get(get_all_trails))
+            .route(TRAIL_URI, get(get_trail))
+            .route(TRAIL_SETTINGS_URI, get(get_trail_settings))
             // Dual-range
-            .route(DUAL_RANGE_URI, get(get_dual_range).put(set_dual_range))
+            .route(DUAL_RANGE_URI, get(get_dual_range))
             .route(DUAL_RANGE_SPOKES_URI, get(dual_range_spokes_handler))
+            .route(DUAL_RANGE_COMPOSITE_URI, get(dual_range_composite_handler))
+            .route(RADAR_RANGE_UNITS_URI, get(get_radar_range_units))
+            .route(AZIMUTH_OFFSET_URI, get(get_azimuth_offset))
+            .route(RANGE_PRESETS_URI, get(get_range_presets))
             // Other endpoints
             .route(INTERFACES_URI, get(get_interfaces))
+            .route(OPENAPI_URI, get(get_openapi))
+            .route(DECODED_URI, get(get_decoded))
+            .route(DECODERS_URI, get(get_decoders))
+            .route(RANGE_UNITS_URI, get(get_range_units))
+            .route(DECODERS_DECODE_URI, post(decode_capture))
             // SignalK applicationData API
-            .route(APP_DATA_URI, get(get_app_data).put(put_app_data).delete(delete_app_data))
+            .route(APP_DATA_URI, get(get_app_data))
             // Recordings API - File management
             .route(RECORDINGS_URI, get(list_recordings))
-            .route(RECORDING_URI, get(get_recording).delete(delete_recording).put(update_recording))
+            .route(RECORDING_URI, get(get_recording))
             .route(RECORDING_DOWNLOAD_URI, get(download_recording))
-            .route(RECORDING_UPLOAD_URI, post(upload_recording))
-            .route(RECORDINGS_DIRS_URI, get(list_directories).post(create_directory))
-            .route(RECORDING_DIR_URI, delete(delete_directory))
+            .route(RECORDINGS_DIRS_URI, get(list_directories))
             // Recordings API - Recording control
             .route(RECORD_RADARS_URI, get(get_recordable_radars))
-            .route(RECORD_START_URI, post(start_recording_handler))
-            .route(RECORD_STOP_URI, post(stop_recording_handler))
             .route(RECORD_STATUS_URI, get(get_recording_status))
             // Recordings API - Playback control
-            .route(PLAYBACK_LOAD_URI, post(playback_load_handler))
-            .route(PLAYBACK_PLAY_URI, post(playback_play_handler))
-            .route(PLAYBACK_PAUSE_URI, post(playback_pause_handler))
-            .route(PLAYBACK_STOP_URI, post(playback_stop_handler))
-            .route(PLAYBACK_SEEK_URI, post(playback_seek_handler))
-            .route(PLAYBACK_SETTINGS_URI, put(playback_settings_handler))
             .route(PLAYBACK_STATUS_URI, get(playback_status_handler))
             // Apply no-cache middleware to all API routes
             .layer(middleware::from_fn(no_cache_middleware))
@@ -317,6 +425,27 @@ async fn no_cache_middleware(request: axum::http::Request<axum::body::Body>, nex
     response
 }
 
+/// Middleware rejecting control requests without the configured bearer token
+async fn require_control_token(
+    State(state): State<Web>,
+    request: axum::http::Request<axum::body::Body>,
+    next: Next,
+) -> Response {
+    let expected = state.session.read().unwrap().args.control_token.clone();
+    let token = mayara_server::auth::token_from_request(request.headers(), request.uri().query());
+
+    if !mayara_server::auth::is_authorized(expected.as_deref(), token.as_deref()) {
+        debug!("Rejected unauthorized control request for {}", request.uri().path());
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "Missing or invalid control token",
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RadarApi {
@@ -327,6 +456,8 @@ struct RadarApi {
     model: Option<String>,
     spokes_per_revolution: u16,
     max_spoke_len: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    radar_spoke_len: Option<u16>, // Set when spokes are clamped to max_spoke_len
     stream_url: String,
     control_url: String,
     legend: Legend,
@@ -340,6 +471,7 @@ impl RadarApi {
         model: Option<String>,
         spokes_per_revolution: u16,
         max_spoke_len: u16,
+        radar_spoke_len: Option<u16>,
         stream_url: String,
         control_url: String,
         legend: Legend,
@@ -351,6 +483,7 @@ impl RadarApi {
             model,
             spokes_per_revolution,
             max_spoke_len,
+            radar_spoke_len,
             stream_url,
             control_url,
             legend,
@@ -407,7 +540,8 @@ async fn get_radars(
             info.brand.to_string(),
             info.controls.model_name(),
             info.spokes_per_revolution,
-            info.max_spoke_len,
+            info.output_spoke_len(),
+            Some(info.max_spoke_len).filter(|&len| len != info.output_spoke_len()),
             stream_url,
             control_url,
             legend.clone(),
@@ -424,6 +558,19 @@ struct RadarIdParam {
     radar_id: String,
 }
 
+/// The model database entry of a radar, by its reported model and antenna kind
+fn model_info(info: &RadarInfo) -> &'static models::ModelInfo {
+    let antenna_kind = info
+        .controls
+        .get("antennaKind")
+        .and_then(|c| c.description)
+        .and_then(|kind| models::AntennaKind::from_name(&kind));
+    info.controls
+        .model_name()
+        .and_then(|m| models::get_model_with_antenna(to_core_brand(info.brand), &m, antenna_kind))
+        .unwrap_or(&models::UNKNOWN_MODEL)
+}
+
 /// Convert server Brand to mayara_core Brand for model lookup
 fn to_core_brand(brand: mayara_server::Brand) -> mayara_core::Brand {
     match brand {
@@ -436,6 +583,43 @@ fn to_core_brand(brand: mayara_server::Brand) -> mayara_core::Brand {
     }
 }
 
+/// The capability manifest of a radar and the radar itself, `None` when
+/// there is no such radar
+async fn radar_capabilities(state: &Web, radar_id: &str) -> Option<(CapabilityManifest, RadarInfo)> {
+    // Extract data from session inside a block to drop the lock before await
+    let (model_info, info) = {
+        let session = state.session.read().unwrap();
+        let radars = session.radars.as_ref().unwrap();
+        let info = radars.get_by_id(radar_id)?;
+        // Look up model in mayara-core database
+        (model_info(&info).clone(), info)
+    }; // session lock released here
+
+    let radar_id = radar_id.to_string();
+    let radar_key = info.key(); // Persistent key for installation settings
+    let spokes_per_revolution = info.spokes_per_revolution;
+    let max_spoke_len = info.output_spoke_len();
+    let intensity_legend = info.intensity_legend();
+    // Use spawn_blocking to run capability building on a thread with larger stack
+    // This avoids stack overflow in debug builds where ControlDefinition structs
+    // (328 bytes each) can overflow the default 2MB async task stack
+    let capabilities = tokio::task::spawn_blocking(move || {
+        let mut capabilities = build_capabilities_from_model_with_key(
+            &model_info,
+            &radar_id,
+            Some(&radar_key), // Persistent key for installation settings storage
+            capability_export::supported_features(&model_info),
+            spokes_per_revolution,
+            max_spoke_len,
+        );
+        capabilities.intensity_legend = Some(intensity_legend);
+        capabilities
+    })
+    .await
+    .expect("spawn_blocking task failed");
+    Some((capabilities, info))
+}
+
 /// GET /v2/api/radars/{radar_id}/capabilities
 /// Returns the capability manifest for a specific radar (v5 API format)
 #[debug_handler]
@@ -445,66 +629,54 @@ async fn get_radar_capabilities(
 ) -> Response {
     debug!("Capabilities request for radar {}", params.radar_id);
 
-    // Extract data from session inside a block to drop the lock before await
-    let build_args = {
-        let session = state.session.read().unwrap();
-        let radars = session.radars.as_ref().unwrap();
-
-        match radars.get_by_id(&params.radar_id) {
-            Some(info) => {
-                let core_brand = to_core_brand(info.brand);
-                let model_name = info.controls.model_name();
-
-                // Look up model in mayara-core database
-                let model_info = model_name
-                    .as_deref()
-                    .and_then(|m| models::get_model(core_brand, m))
-                    .unwrap_or(&models::UNKNOWN_MODEL);
-
-                // Declare supported features for standalone server
-                let mut supported_features = vec![
-                    SupportedFeature::Arpa,
-                    SupportedFeature::GuardZones,
-                    SupportedFeature::Trails,
-                ];
-
-                // Add DualRange if the radar supports it
-                if model_info.has_dual_range {
-                    supported_features.push(SupportedFeature::DualRange);
-                }
+    match radar_capabilities(&state, &params.radar_id).await {
+        Some((capabilities, _)) => Json(capabilities).into_response(),
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
 
-                Some((
-                    model_info.clone(),
-                    params.radar_id.clone(),
-                    info.key(), // Persistent key for installation settings
-                    supported_features,
-                    info.spokes_per_revolution,
-                    info.max_spoke_len,
-                ))
-            }
-            None => None,
+/// GET /v2/api/radars/{radar_id}/capabilities/export
+/// The capability manifest with the labelled range table, as a file to
+/// bundle with clients that run offline
+#[debug_handler]
+async fn export_radar_capabilities(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+) -> Response {
+    match radar_capabilities(&state, &params.radar_id).await {
+        Some((capabilities, info)) => {
+            let export = CapabilityExport::new(capabilities, &info.ranges);
+            let json = match serde_json::to_string_pretty(&export) {
+                Ok(json) => json,
+                Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            };
+            let disposition = format!("attachment; filename=\"{}-capabilities.json\"", info.key());
+            (
+                [
+                    (header::CONTENT_TYPE, "application/json".to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                json,
+            )
+                .into_response()
         }
-    }; // session lock released here
-
-    match build_args {
-        Some((model_info, radar_id, radar_key, supported_features, spokes_per_revolution, max_spoke_len)) => {
-            // Use spawn_blocking to run capability building on a thread with larger stack
-            // This avoids stack overflow in debug builds where ControlDefinition structs
-            // (328 bytes each) can overflow the default 2MB async task stack
-            let capabilities = tokio::task::spawn_blocking(move || {
-                build_capabilities_from_model_with_key(
-                    &model_info,
-                    &radar_id,
-                    Some(&radar_key), // Persistent key for installation settings storage
-                    supported_features,
-                    spokes_per_revolution,
-                    max_spoke_len,
-                )
-            })
-            .await
-            .expect("spawn_blocking task failed");
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
 
-            Json(capabilities).into_response()
+/// GET /v2/api/radars/{radar_id}/document
+/// Everything the decoders know about a radar, see `radar_document`
+#[debug_handler]
+async fn get_radar_document(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+) -> Response {
+    let session = state.session.read().unwrap();
+    let radars = session.radars.as_ref().unwrap();
+    match radars.get_by_id(&params.radar_id) {
+        Some(info) => {
+            let channels = radars.channels(&info);
+            Json(RadarDocument::new(&params.radar_id, &info, &channels, session.range_units)).into_response()
         }
         None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
     }
@@ -532,15 +704,7 @@ async fn get_radar_state(
             fn format_control_value(control_id: &str, control: &mayara_server::settings::Control) -> serde_json::Value {
                 // Special handling for power/status - return string enum
                 if control_id == "power" {
-                    let status_val = control.value.unwrap_or(0.0) as i32;
-                    let status_str = match status_val {
-                        0 => "off",
-                        1 => "standby",
-                        2 => "transmit",
-                        3 => "warming",
-                        _ => "standby",
-                    };
-                    return serde_json::json!(status_str);
+                    return serde_json::json!(power_state(control.value).as_str());
                 }
 
                 // Controls with auto mode (compound controls)
@@ -585,19 +749,60 @@ async fn get_radar_state(
                 controls.insert(control_id.clone(), format_control_value(&control_id, &control));
             }
 
+            // Sea keeps reporting its raw value while auto is active, so tell
+            // clients which mode is in effect and whether the slider is live
+            if let Some(auto) = info.controls.get("sea").and_then(|c| c.auto) {
+                let effective_mode = match info.controls.get("seaState").and_then(|c| c.value) {
+                    Some(sea_state) => SeaAutoMode::from_halo(auto as u8, sea_state as u8).as_str(),
+                    None if auto => "auto",
+                    None => "manual",
+                };
+                if let Some(serde_json::Value::Object(sea)) = controls.get_mut("sea") {
+                    sea.insert("effectiveMode".into(), serde_json::json!(effective_mode));
+                    sea.insert("manualActive".into(), serde_json::json!(!auto));
+                }
+            }
+
             // Determine status string for top-level field
-            let status = controls
-                .get("power")
-                .and_then(|v| v.as_str())
-                .unwrap_or("standby")
-                .to_string();
+            let status = if info.is_initializing() {
+                "initializing"
+            } else {
+                controls
+                    .get("power")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("standby")
+            }
+            .to_string();
 
+            // Bearings outside the controls are reported in the configured unit
+            let unit = session.args.bearing_unit;
             let state_v5 = RadarStateV5 {
                 id: params.radar_id.clone(),
                 timestamp: chrono::Utc::now().to_rfc3339(),
                 status,
                 controls,
                 disabled_controls: vec![],
+                range_label: info
+                    .controls
+                    .get("range")
+                    .and_then(|c| c.value)
+                    .map(|meters| info.format_range(meters as i32, session.range_units)),
+                rpm: info.rpm().map(|rpm| (rpm * 10.).round() / 10.),
+                blind_sectors: info
+                    .blind_sectors()
+                    .into_iter()
+                    .map(|sector| unit.sector_from_degrees(sector))
+                    .collect(),
+                link: Some(info.link_state().as_str().to_string()),
+                overlay_alignment: Some(OverlayAlignment {
+                    bearing_offset: unit.from_degrees(
+                        info.controls
+                            .get("bearingAlignment")
+                            .and_then(|c| c.value)
+                            .unwrap_or(0.) as f64,
+                    ),
+                    range_offset: info.range_offset() as f64,
+                }),
             };
 
             Json(state_v5).into_response()
@@ -606,6 +811,549 @@ async fn get_radar_state(
     }
 }
 
+/// POST /v2/api/radars/{radar_id}/testPattern
+/// Sends one revolution of a deterministic test pattern to all spoke clients
+#[debug_handler]
+async fn send_test_pattern(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+) -> Response {
+    debug!("Test pattern request for radar {}", params.radar_id);
+
+    let session = state.session.read().unwrap();
+    let radars = session.radars.as_ref().unwrap();
+
+    match radars.get_by_id(&params.radar_id) {
+        Some(mut info) => {
+            info.send_test_pattern();
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// Request body for POST /v2/api/radars/{radar_id}/targetScript
+#[derive(Deserialize)]
+struct TargetScriptRequest {
+    events: Vec<TargetEvent>,
+}
+
+/// POST /v2/api/radars/{radar_id}/targetScript
+/// Plays a timeline of fake targets on the radar, for testing guard zone alarms
+#[debug_handler]
+async fn run_target_script(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    Json(request): Json<TargetScriptRequest>,
+) -> Response {
+    debug!(
+        "Target script request for radar {} with {} events",
+        params.radar_id,
+        request.events.len()
+    );
+
+    let session = state.session.read().unwrap();
+    let radars = session.radars.as_ref().unwrap();
+
+    match radars.get_by_id(&params.radar_id) {
+        Some(info) => {
+            info.run_target_script(TargetScript::new(request.events));
+            StatusCode::ACCEPTED.into_response()
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// How long to wait for a rotation boundary and the rotation after it
+const SNAPSHOT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Query parameters of GET /v2/api/radars/{radar_id}/snapshot
+#[derive(Deserialize)]
+struct SnapshotQuery {
+    /// `png` for an image, anything else for the RadarMessage
+    format: Option<String>,
+    /// Size of the PNG in pixels
+    size: Option<u16>,
+}
+
+/// GET /v2/api/radars/{radar_id}/snapshot
+/// Waits for the next full rotation and returns it as a single RadarMessage
+/// (protobuf) with one spoke per azimuth, in azimuth order, or with
+/// `?format=png` as a head up image with labelled range rings
+#[debug_handler]
+async fn get_snapshot(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    axum::extract::Query(query): axum::extract::Query<SnapshotQuery>,
+) -> Response {
+    debug!("Snapshot request for radar {}", params.radar_id);
+
+    let (info, default_units) = {
+        let session = state.session.read().unwrap();
+        let radars = session.radars.as_ref().unwrap();
+        (radars.get_by_id(&params.radar_id), session.range_units)
+    };
+    let Some(info) = info else {
+        return RadarError::NoSuchRadar(params.radar_id.to_string()).into_response();
+    };
+
+    match snapshot::capture_rotation(&info, SNAPSHOT_TIMEOUT).await {
+        Ok(message) if query.format.as_deref() == Some("png") => {
+            let style = SnapshotStyle {
+                size: query.size.unwrap_or(png::DEFAULT_PNG_SIZE),
+                units: info.range_units().unwrap_or(default_units),
+                heading: navdata::get_heading_true().map(f64::to_degrees),
+                ..Default::default()
+            };
+            let image = png::render_snapshot(&message, info.spokes_per_revolution, &info.legend, &style);
+            ([(header::CONTENT_TYPE, "image/png")], image.encode()).into_response()
+        }
+        Ok(message) => match protobuf::Message::write_to_bytes(&message) {
+            Ok(bytes) => ([(header::CONTENT_TYPE, "application/x-protobuf")], bytes).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        Err(RadarError::Timeout) => (
+            StatusCode::GATEWAY_TIMEOUT,
+            "No complete rotation received from the radar",
+        )
+            .into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Query parameters for downloading the decode ring
+#[derive(Deserialize)]
+struct DecodedQuery {
+    /// Only return messages from the last this many seconds
+    seconds: Option<u64>,
+}
+
+/// GET /v2/api/debug/decoded
+/// Downloads the recently decoded messages as NDJSON, oldest first
+#[debug_handler]
+async fn get_decoded(
+    State(state): State<Web>,
+    axum::extract::Query(query): axum::extract::Query<DecodedQuery>,
+) -> Response {
+    let since = query.seconds.map(|seconds| {
+        let now = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        now.saturating_sub(seconds * 1000)
+    });
+    let ring = state.session.read().unwrap().decode_ring.clone();
+    let ndjson = ring.lock().unwrap().to_ndjson(since);
+
+    (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"mayara-decoded.ndjson\"",
+            ),
+        ],
+        ndjson,
+    )
+        .into_response()
+}
+
+/// Request body for POST /v2/api/debug/decoded/bookmark
+#[derive(Deserialize)]
+struct BookmarkRequest {
+    label: String,
+    /// Seconds exported on either side of the moment of the request, at most
+    /// `MAX_BOOKMARK_SECONDS`
+    seconds: Option<u64>,
+}
+
+/// POST /v2/api/debug/decoded/bookmark
+/// Marks this moment and, once `seconds` have passed, returns the decoded
+/// messages from `seconds` before to `seconds` after it as NDJSON
+#[debug_handler]
+async fn bookmark_decoded(
+    State(state): State<Web>,
+    Json(request): Json<BookmarkRequest>,
+) -> Response {
+    let at = chrono::Utc::now().timestamp_millis().max(0) as u64;
+    let seconds = request
+        .seconds
+        .unwrap_or(DEFAULT_BOOKMARK_SECONDS)
+        .min(MAX_BOOKMARK_SECONDS);
+    log::info!("Bookmark '{}', exporting {} seconds either side", request.label, seconds);
+
+    tokio::time::sleep(std::time::Duration::from_secs(seconds)).await;
+
+    let ring = state.session.read().unwrap().decode_ring.clone();
+    let ndjson = ring
+        .lock()
+        .unwrap()
+        .bookmark_ndjson(&request.label, at, seconds.saturating_mul(1000));
+
+    let name: String = request
+        .label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    (
+        [
+            (header::CONTENT_TYPE, "application/x-ndjson".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"mayara-bookmark-{}.ndjson\"", name),
+            ),
+        ],
+        ndjson,
+    )
+        .into_response()
+}
+
+/// Range unit preference; `null` for a radar means it follows the global one
+#[derive(Serialize, Deserialize)]
+struct RangeUnitsBody {
+    units: Option<String>,
+}
+
+fn parse_range_units(body: &RangeUnitsBody) -> Result<Option<RangeUnitPreference>, Response> {
+    body.units
+        .as_deref()
+        .map(str::parse::<RangeUnitPreference>)
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())
+}
+
+/// GET /v2/api/rangeUnits
+#[debug_handler]
+async fn get_range_units(State(state): State<Web>) -> Response {
+    let units = state.session.read().unwrap().range_units;
+    Json(RangeUnitsBody {
+        units: Some(units.as_str().to_string()),
+    })
+    .into_response()
+}
+
+/// PUT /v2/api/rangeUnits
+/// Sets the units of range labels for all radars without their own preference
+#[debug_handler]
+async fn set_range_units(State(state): State<Web>, Json(body): Json<RangeUnitsBody>) -> Response {
+    match parse_range_units(&body) {
+        Ok(Some(units)) => {
+            let mut session = state.session.write().unwrap();
+            session.range_units = units;
+            session.decoders.write().unwrap().set_range_units(units);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Ok(None) => (StatusCode::BAD_REQUEST, "units is required").into_response(),
+        Err(response) => response,
+    }
+}
+
+/// GET /v2/api/radars/{radar_id}/rangeUnits
+#[debug_handler]
+async fn get_radar_range_units(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+) -> Response {
+    let session = state.session.read().unwrap();
+    match session.radars.as_ref().unwrap().get_by_id(&params.radar_id) {
+        Some(info) => Json(RangeUnitsBody {
+            units: info.range_units().map(|u| u.as_str().to_string()),
+        })
+        .into_response(),
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// PUT /v2/api/radars/{radar_id}/rangeUnits
+/// Sets the units of range labels for one radar, `null` to follow the global
+/// preference again. The preference is persisted with the radar.
+#[debug_handler]
+async fn set_radar_range_units(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    Json(body): Json<RangeUnitsBody>,
+) -> Response {
+    let units = match parse_range_units(&body) {
+        Ok(units) => units,
+        Err(response) => return response,
+    };
+    let session = state.session.read().unwrap();
+    let radars = session.radars.as_ref().unwrap();
+    match radars.get_by_id(&params.radar_id) {
+        Some(info) => {
+            info.set_range_units(units);
+            radars.update(&info);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// Software azimuth offset of a radar, in degrees clockwise
+#[derive(Serialize, Deserialize)]
+struct AzimuthOffsetBody {
+    degrees: f64,
+}
+
+/// GET /v2/api/radars/{radar_id}/azimuthOffset
+#[debug_handler]
+async fn get_azimuth_offset(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+) -> Response {
+    let session = state.session.read().unwrap();
+    match session.radars.as_ref().unwrap().get_by_id(&params.radar_id) {
+        Some(info) => Json(AzimuthOffsetBody {
+            degrees: info.azimuth_offset(),
+        })
+        .into_response(),
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// PUT /v2/api/radars/{radar_id}/azimuthOffset
+/// Turns the spokes of the radar in software, nothing is sent to the radar.
+/// The offset lasts until restart; `azimuth_offset` in radars.toml sets it
+/// at start.
+#[debug_handler]
+async fn set_azimuth_offset(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    Json(body): Json<AzimuthOffsetBody>,
+) -> Response {
+    if !body.degrees.is_finite() {
+        return (StatusCode::BAD_REQUEST, "degrees must be a number").into_response();
+    }
+    let session = state.session.read().unwrap();
+    match session.radars.as_ref().unwrap().get_by_id(&params.radar_id) {
+        Some(info) => {
+            info.set_azimuth_offset(body.degrees);
+            StatusCode::NO_CONTENT.into_response()
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// GET /v2/api/radars/{radar_id}/rangePresets?compact=true&units=metric
+/// Common ranges up to the radar's largest range, for quick select buttons,
+/// labelled in the units preferred for the radar unless `units` is given.
+/// `compact` asks for short labels, in the units of the ranges themselves.
+#[debug_handler]
+async fn get_range_presets(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    axum::extract::Query(mut options): axum::extract::Query<range::FormatOptions>,
+) -> Response {
+    let session = state.session.read().unwrap();
+    match session.radars.as_ref().unwrap().get_by_id(&params.radar_id) {
+        Some(info) => {
+            let max_range = info.ranges.all.iter().map(|r| r.distance()).max().unwrap_or(0);
+            if options.units == RangeUnitPreference::Auto && !options.compact {
+                options.units = info.range_units().unwrap_or(session.range_units);
+            }
+            Json(range::range_presets_with(&info.ranges, max_range, &options)).into_response()
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DecodersResponse {
+    decoders: Vec<mayara_server::decoders::DecoderInfo>,
+    forced: Option<&'static str>,
+}
+
+/// GET /v2/api/decoders
+/// Lists the protocol decoders and the one that is forced, if any
+#[debug_handler]
+async fn get_decoders(State(state): State<Web>) -> Response {
+    let decoders = state.session.read().unwrap().decoders.clone();
+    let decoders = decoders.read().unwrap();
+    Json(DecodersResponse {
+        decoders: decoders.list(),
+        forced: decoders.forced(),
+    })
+    .into_response()
+}
+
+#[derive(Deserialize)]
+struct ForceDecoderRequest {
+    forced: Option<String>,
+}
+
+/// PUT /v2/api/decoders
+/// Forces all captured traffic through one decoder; `null` restores
+/// detection by port
+#[debug_handler]
+async fn set_forced_decoder(
+    State(state): State<Web>,
+    Json(request): Json<ForceDecoderRequest>,
+) -> Response {
+    let decoders = state.session.read().unwrap().decoders.clone();
+    let result = decoders.write().unwrap().force(request.forced.as_deref());
+    match result {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct DecodeQuery {
+    /// Port the packet was captured on, used when no decoder is forced
+    port: Option<u16>,
+    /// Which way the packet went, from the radar unless given
+    #[serde(default)]
+    direction: mayara_server::decoders::Direction,
+}
+
+/// POST /v2/api/decoders/decode
+/// Decodes one captured packet, sent as the raw request body, to an array
+/// with one entry per message in the packet. The result is also added to the
+/// decode ring, and with `--log-unknown` a packet that could not be decoded
+/// is written to the unknown packet log.
+#[debug_handler]
+async fn decode_capture(
+    State(state): State<Web>,
+    axum::extract::Query(query): axum::extract::Query<DecodeQuery>,
+    body: axum::body::Bytes,
+) -> Response {
+    let (decoders, ring, unknown_log) = {
+        let session = state.session.read().unwrap();
+        (
+            session.decoders.clone(),
+            session.decode_ring.clone(),
+            session.unknown_log.clone(),
+        )
+    };
+    let (decoded, brand) = {
+        let decoders = decoders.read().unwrap();
+        (
+            decoders.decode_batch(query.port, &body, query.direction),
+            decoders.select(query.port).map(|d| d.brand()),
+        )
+    };
+    if let Some(unknown_log) = unknown_log {
+        let mut unknown_log = unknown_log.lock().unwrap();
+        if let Err(e) = unknown_log.record(brand, query.port, query.direction, &body, &decoded) {
+            log::warn!("Cannot write unknown packet log: {}", e);
+        }
+    }
+    if decoded.is_empty() {
+        return (
+            StatusCode::NOT_FOUND,
+            "No decoder for this port, force one with PUT /v2/api/decoders",
+        )
+            .into_response();
+    }
+    let messages: Vec<serde_json::Value> = decoded.iter().map(|d| d.message.clone()).collect();
+    {
+        let mut ring = ring.lock().unwrap();
+        for decoded in decoded {
+            ring.push(decoded);
+        }
+    }
+    Json(messages).into_response()
+}
+
+/// Query parameters for replaying a capture
+#[derive(Deserialize)]
+struct ReplayQuery {
+    /// Playback speed multiplier, 1.0 is real time
+    speed: Option<f64>,
+}
+
+/// POST /v2/api/debug/replay
+/// Sends the UDP packets of an uploaded pcap to this server's radar receivers
+/// as if the radar was live. Only available with --replay.
+#[debug_handler]
+async fn replay_capture(
+    State(state): State<Web>,
+    axum::extract::Query(query): axum::extract::Query<ReplayQuery>,
+    headers: hyper::header::HeaderMap,
+    body: axum::body::Body,
+) -> Response {
+    let (replay, max_size_mb) = {
+        let session = state.session.read().unwrap();
+        (session.args.replay, session.args.max_pcap_size)
+    };
+    if !replay {
+        return (StatusCode::FORBIDDEN, "Start the server with --replay to replay captures").into_response();
+    }
+    let speed = query.speed.unwrap_or(1.0);
+    if !(speed > 0.0 && speed.is_finite()) {
+        return (StatusCode::BAD_REQUEST, "speed must be above 0").into_response();
+    }
+    let limit = max_size_mb.saturating_mul(1024 * 1024);
+    let too_large = |e: pcap::PcapError| (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response();
+    let announced = headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if announced.is_some_and(|length| length > limit) {
+        return too_large(pcap::PcapError::TooLarge(limit));
+    }
+
+    // Spool the upload to disk, the capture may not fit in memory
+    let mut capture = match pcap::SpooledCapture::create(limit).await {
+        Ok(capture) => capture,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let result = match chunk {
+            Ok(chunk) => capture.write(&chunk).await,
+            Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        };
+        match result {
+            Ok(()) => {}
+            Err(e @ pcap::PcapError::TooLarge(_)) => return too_large(e),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+    let (count, last) = match capture.reader().await {
+        Ok(reader) => match tokio::task::spawn_blocking(move || pcap::summarize(reader)).await {
+            Ok(Ok(summary)) => summary,
+            Ok(Err(e)) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        Err(e) => return (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    };
+    let packets = match capture.reader().await {
+        Ok(reader) => pcap::stream(reader),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let socket = match pcap::replay_socket().await {
+        Ok(socket) => socket,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let duration = last.div_f64(speed);
+    log::info!("Replaying {} packets at {}x, {:?}", count, speed, duration);
+
+    tokio::spawn(async move {
+        match pcap::replay(packets, speed, &socket).await {
+            Ok(sent) => log::info!("Replay finished, {} of {} packets sent", sent, count),
+            Err(e) => log::warn!("Replay failed: {}", e),
+        }
+        // Removes the spool file
+        drop(capture);
+    });
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "packets": count,
+            "durationMs": duration.as_millis() as u64,
+            "speed": speed,
+        })),
+    )
+        .into_response()
+}
+
+/// GET /v2/api/openapi.json
+/// Returns the OpenAPI description of this REST API
+#[debug_handler]
+async fn get_openapi() -> Response {
+    Json(mayara_server::openapi::openapi_document()).into_response()
+}
+
 #[debug_handler]
 async fn get_interfaces(
     State(state): State<Web>,
@@ -624,17 +1372,80 @@ async fn get_interfaces(
     Json(status).into_response()
 }
 
+/// Query parameters of the spokes WebSocket
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpokesQuery {
+    /// Send one cartesian image of this many pixels square per rotation
+    /// instead of spokes
+    raster: Option<u16>,
+    /// Draw the heading line into the raster images
+    #[serde(default)]
+    heading_line: bool,
+    /// Draw bearing markers this many degrees apart into the raster images
+    bearing_markers: Option<u16>,
+    /// Region of interest: bearing window in the configured bearing unit,
+    /// relative to the bow, and radius window in meters
+    bearing_start: Option<f64>,
+    bearing_end: Option<f64>,
+    min_radius: Option<f64>,
+    max_radius: Option<f64>,
+}
+
+impl SpokesQuery {
+    /// The region of interest, when any part of it is given
+    fn region_of_interest(&self, unit: BearingUnit) -> Option<roi::RegionOfInterest> {
+        if self.bearing_start.is_none()
+            && self.bearing_end.is_none()
+            && self.min_radius.is_none()
+            && self.max_radius.is_none()
+        {
+            return None;
+        }
+        let full = roi::RegionOfInterest::default();
+        Some(roi::RegionOfInterest {
+            bearing_start: self.bearing_start.map_or(full.bearing_start, |b| unit.to_degrees(b)),
+            bearing_end: self.bearing_end.map_or(full.bearing_end, |b| unit.to_degrees(b)),
+            min_radius: self.min_radius.unwrap_or(full.min_radius),
+            max_radius: self.max_radius,
+        })
+    }
+}
+
+/// 503 while the radar has not told how many spokes or cells it sends, so
+/// that clients retry instead of receiving images built for no geometry
+fn initializing_response(radar: &RadarInfo) -> Option<Response> {
+    radar.is_initializing().then(|| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("Radar {} is initializing", radar.key()),
+        )
+            .into_response()
+    })
+}
+
 #[debug_handler]
 async fn spokes_handler(
     State(state): State<Web>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(params): Path<RadarIdParam>,
+    axum::extract::Query(query): axum::extract::Query<SpokesQuery>,
+    headers: hyper::header::HeaderMap,
     ws: WebSocketUpgrade,
 ) -> Response {
     debug!("spokes request from {} for {}", addr, params.radar_id);
 
+    let spoke_format = handshake::negotiate(
+        headers
+            .get(hyper::header::SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|v| v.to_str().ok()),
+    );
+    debug!("spokes format for {}: {:?}", addr, spoke_format);
+
     // Disable compression temporarily to debug browser WebSocket issues
-    let ws = ws.accept_compression(false);
+    let ws = ws
+        .accept_compression(false)
+        .protocols(spoke_format.subprotocol());
 
     match state
         .session
@@ -647,13 +1458,382 @@ async fn spokes_handler(
         .clone()
     {
         Some(radar) => {
+            if let Some(response) = initializing_response(&radar) {
+                return response;
+            }
             let shutdown_rx = state.shutdown_tx.subscribe();
             let radar_message_rx = radar.message_tx.subscribe();
+            let overlay = raster::RasterOverlay {
+                heading_line: query.heading_line,
+                bearing_markers: query.bearing_markers,
+            };
+            let rasterizer = query.raster.map(|size| {
+                raster::Rasterizer::new(size, radar.spokes_per_revolution)
+                    .with_overlay(overlay)
+                    .with_orientation(radar.orientation())
+            });
+            let lag = SimulatedLag::from_arg(state.session.read().unwrap().args.debug_spoke_lag);
+            let roi = query.region_of_interest(state.bearing_unit()).map(|roi| {
+                roi::RoiClipper::new(roi, radar.spokes_per_revolution, radar.range_offset())
+            });
             // finalize the upgrade process by returning upgrade callback.
             // we can customize the callback by sending additional info such as address.
-            ws.on_upgrade(move |socket| spokes_stream(socket, radar_message_rx, shutdown_rx))
+            ws.on_upgrade(move |mut socket| async move {
+                let delta_encoder = spoke_format
+                    .delta()
+                    .then(|| delta::DeltaEncoder::new(delta::DEFAULT_KEYFRAME_INTERVAL));
+                if let Some(config) = spoke_format.config_frame() {
+                    if let Err(e) = socket.send(Message::Text(config.into())).await {
+                        debug!("Error on send to websocket: {}", e);
+                        return;
+                    }
+                }
+                spokes_stream(
+                    socket,
+                    radar_message_rx,
+                    shutdown_rx,
+                    roi,
+                    delta_encoder,
+                    rasterizer,
+                    spoke_format.crc(),
+                    lag,
+                )
+                .await
+            })
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// Spokes as server-sent events, for clients on networks that block
+/// WebSockets: every RadarMessage becomes a `spoke` event with the protobuf
+/// in base64, and changes of the radar status a `status` event with the
+/// `power` control value as JSON
+#[debug_handler]
+async fn spoke_events_handler(State(state): State<Web>, Path(params): Path<RadarIdParam>) -> Response {
+    let radar = state
+        .session
+        .read()
+        .unwrap()
+        .radars
+        .as_ref()
+        .unwrap()
+        .get_by_id(&params.radar_id);
+    match radar {
+        Some(radar) => {
+            let events = spoke_events(
+                radar.message_tx.subscribe(),
+                radar.all_clients_rx(),
+                state.shutdown_tx.subscribe(),
+            );
+            Sse::new(events).keep_alive(KeepAlive::default()).into_response()
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+fn spoke_events(
+    radar_message_rx: broadcast::Receiver<Vec<u8>>,
+    control_rx: broadcast::Receiver<mayara_server::settings::ControlValue>,
+    shutdown_rx: broadcast::Receiver<()>,
+) -> impl futures_util::Stream<Item = Result<Event, Infallible>> {
+    futures_util::stream::unfold(
+        (radar_message_rx, control_rx, shutdown_rx),
+        |(mut radar_message_rx, mut control_rx, mut shutdown_rx)| async move {
+            let event = loop {
+                tokio::select! {
+                    _ = shutdown_rx.recv() => return None,
+                    r = recv_radar_message(&mut radar_message_rx) => match r {
+                        Some(message) => {
+                            break Event::default().event("spoke").data(BASE64_STANDARD.encode(message));
+                        }
+                        None => return None,
+                    },
+                    r = control_rx.recv() => match r {
+                        Ok(control) if control.id == "power" || control.id == "link" => {
+                            match Event::default().event("status").json_data(&control) {
+                                Ok(event) => break event,
+                                Err(e) => debug!("Cannot send status event: {}", e),
+                            }
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    },
+                }
+            };
+            Some((Ok(event), (radar_message_rx, control_rx, shutdown_rx)))
+        },
+    )
+}
+
+/// GET /v2/api/radars/{radar_id}/status
+/// WebSocket that pushes the settings and status of the radar as JSON, the
+/// first message with all of them and then only the values that changed
+#[debug_handler]
+async fn status_handler(
+    State(state): State<Web>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(params): Path<RadarIdParam>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    debug!("status request from {} for {}", addr, params.radar_id);
+
+    let radar = state
+        .session
+        .read()
+        .unwrap()
+        .radars
+        .as_ref()
+        .unwrap()
+        .get_by_id(&params.radar_id);
+    match radar {
+        Some(radar) => {
+            let current = radar
+                .controls
+                .get_all()
+                .iter()
+                .map(|(id, control)| {
+                    let value = mayara_server::settings::ControlValue::from(control, None);
+                    (id.clone(), status_value(&value))
+                })
+                .collect();
+            let events = status_events(current, radar.all_clients_rx(), state.shutdown_tx.subscribe());
+            ws.on_upgrade(move |socket| status_stream(socket, events))
+        }
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
+/// A control value as sent on the status WebSocket: numbers as numbers,
+/// with the auto mode and description when the control has them
+fn status_value(control: &mayara_server::settings::ControlValue) -> serde_json::Value {
+    let value = match control.value.parse::<f64>() {
+        Ok(v) if v.fract() == 0. => serde_json::json!(v as i64),
+        Ok(v) => serde_json::json!(v),
+        Err(_) => serde_json::json!(control.value),
+    };
+    if control.auto.is_none() && control.description.is_none() {
+        return value;
+    }
+    let mut object = serde_json::json!({ "value": value });
+    if let Some(auto) = control.auto {
+        object["auto"] = serde_json::json!(auto);
+    }
+    if let Some(description) = &control.description {
+        object["description"] = serde_json::json!(description);
+    }
+    object
+}
+
+/// The settings and status changes of a radar, as JSON objects of the
+/// changed values. Only the control values are sent, in the units the
+/// clients use, never the raw settings reports of the radar. They go
+/// through a `SettingsReportTracker`, so a value is only sent when it
+/// differs from the one sent before; the first message is `current` in full.
+fn status_events(
+    current: mayara_core::SettingsDiff,
+    control_rx: broadcast::Receiver<mayara_server::settings::ControlValue>,
+    shutdown_rx: broadcast::Receiver<()>,
+) -> impl futures_util::Stream<Item = String> {
+    let mut tracker = mayara_core::SettingsReportTracker::new();
+    let first = tracker.diff(current);
+    futures_util::stream::unfold(
+        (first, tracker, control_rx, shutdown_rx),
+        |(mut pending, mut tracker, mut control_rx, mut shutdown_rx)| async move {
+            loop {
+                if let Some(diff) = pending.take() {
+                    // Sorted, so that messages are easy to compare
+                    let diff: BTreeMap<_, _> = diff.into_iter().collect();
+                    let message = serde_json::to_string(&diff).unwrap();
+                    return Some((message, (None, tracker, control_rx, shutdown_rx)));
+                }
+                let control = tokio::select! {
+                    _ = shutdown_rx.recv() => return None,
+                    r = control_rx.recv() => match r {
+                        Ok(control) => control,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    },
+                };
+                pending = tracker.diff(HashMap::from([(control.id.clone(), status_value(&control))]));
+            }
+        },
+    )
+}
+
+async fn status_stream(mut socket: WebSocket, events: impl futures_util::Stream<Item = String>) {
+    let mut events = std::pin::pin!(events);
+
+    loop {
+        tokio::select! {
+            event = events.next() => match event {
+                Some(message) => {
+                    if let Err(e) = socket.send(Message::Text(message.into())).await {
+                        debug!("Error on send to websocket: {}", e);
+                        break;
+                    }
+                }
+                None => break,
+            },
+            // Read-only: answer pings, notice when the client goes away
+            r = socket.recv() => match ClientFrame::from_received(r) {
+                ClientFrame::Ignore => {}
+                ClientFrame::Reply(reply) => {
+                    if socket.send(reply).await.is_err() {
+                        break;
+                    }
+                }
+                ClientFrame::Close => break,
+            },
+        }
+    }
+    debug!("Status websocket closed");
+}
+
+/// What to do with a frame a client sent on the send-only spokes WebSocket
+#[derive(Debug, PartialEq)]
+enum ClientFrame {
+    /// Drop it and keep streaming
+    Ignore,
+    /// Send this back and keep streaming
+    Reply(Message),
+    /// The client closed the connection or it broke
+    Close,
+}
+
+impl ClientFrame {
+    fn from_received<E: std::fmt::Debug>(received: Option<Result<Message, E>>) -> Self {
+        match received {
+            Some(Ok(Message::Ping(payload))) => ClientFrame::Reply(Message::Pong(payload)),
+            Some(Ok(Message::Close(_))) | None => ClientFrame::Close,
+            Some(Ok(message)) => {
+                trace!("Ignoring unexpected message on spokes websocket {:?}", message);
+                ClientFrame::Ignore
+            }
+            Some(Err(e)) => {
+                debug!("Error reading spokes websocket: {:?}", e);
+                ClientFrame::Close
+            }
+        }
+    }
+}
+
+/// Ping a spokes client that has been quiet for this long
+const SPOKES_PING_INTERVAL: Duration = Duration::from_secs(10);
+/// Close the connection when the Pong has not arrived after this long
+const SPOKES_PONG_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, PartialEq)]
+enum KeepaliveAction {
+    Wait,
+    Ping,
+    Timeout,
+}
+
+/// Detects half-open spokes connections: when nothing was heard from the
+/// client for a while it is pinged, and declared dead when no frame arrives
+/// within the timeout
+struct Keepalive {
+    interval: Duration,
+    timeout: Duration,
+    last_seen: Instant,
+    ping_sent: Option<Instant>,
+}
+
+impl Keepalive {
+    fn new(interval: Duration, timeout: Duration, now: Instant) -> Self {
+        Keepalive {
+            interval,
+            timeout,
+            last_seen: now,
+            ping_sent: None,
+        }
+    }
+
+    /// Any frame from the client, normally the Pong, proves it is still there
+    fn alive(&mut self, now: Instant) {
+        self.last_seen = now;
+        self.ping_sent = None;
+    }
+
+    fn tick(&mut self, now: Instant) -> KeepaliveAction {
+        match self.ping_sent {
+            Some(sent) if now.duration_since(sent) >= self.timeout => KeepaliveAction::Timeout,
+            Some(_) => KeepaliveAction::Wait,
+            None if now.duration_since(self.last_seen) >= self.interval => {
+                self.ping_sent = Some(now);
+                KeepaliveAction::Ping
+            }
+            None => KeepaliveAction::Wait,
+        }
+    }
+}
+
+/// Closes control sessions of clients that sent nothing for a while, such as
+/// forgotten browser tabs, after `--control-idle-timeout` seconds
+struct IdleTimeout {
+    timeout: Option<Duration>,
+    last_activity: Instant,
+}
+
+impl IdleTimeout {
+    /// Never expires when `timeout` is zero
+    fn new(timeout: Duration, now: Instant) -> Self {
+        IdleTimeout {
+            timeout: (!timeout.is_zero()).then_some(timeout),
+            last_activity: now,
+        }
+    }
+
+    /// A message from the client
+    fn activity(&mut self, now: Instant) {
+        self.last_activity = now;
+    }
+
+    fn expired(&self, now: Instant) -> bool {
+        self.timeout
+            .is_some_and(|timeout| now.duration_since(self.last_activity) >= timeout)
+    }
+}
+
+/// Debug aid set with `--debug-spoke-lag`: a spokes WebSocket that sleeps
+/// after every message falls behind the radar, so the skipping of lagged
+/// messages can be tried out with real clients
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SimulatedLag(Duration);
+
+impl SimulatedLag {
+    /// Only debug builds honour the knob
+    fn from_arg(millis: Option<u64>) -> Option<Self> {
+        let millis = millis.filter(|&ms| ms > 0)?;
+        if !cfg!(debug_assertions) {
+            log::warn!("--debug-spoke-lag is ignored in release builds");
+            return None;
+        }
+        Some(SimulatedLag(Duration::from_millis(millis)))
+    }
+
+    async fn fall_behind(&self) {
+        tokio::time::sleep(self.0).await;
+    }
+}
+
+/// The next radar message for a spokes WebSocket, skipping the messages it
+/// was too slow for; `None` once the radar is gone
+async fn recv_radar_message(rx: &mut tokio::sync::broadcast::Receiver<Vec<u8>>) -> Option<Vec<u8>> {
+    loop {
+        match rx.recv().await {
+            Ok(message) => return Some(message),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                // Receiver fell behind, skip missed messages
+                debug!("Websocket receiver lagged, skipped {} messages", n);
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => {
+                // Sender was dropped (radar disconnected or playback stopped)
+                debug!("RadarMessage channel closed");
+                return None;
+            }
         }
-        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
     }
 }
 
@@ -663,16 +1843,71 @@ async fn spokes_stream(
     mut socket: WebSocket,
     mut radar_message_rx: tokio::sync::broadcast::Receiver<Vec<u8>>,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    mut roi: Option<roi::RoiClipper>,
+    mut delta_encoder: Option<delta::DeltaEncoder>,
+    mut rasterizer: Option<raster::Rasterizer>,
+    crc: bool,
+    lag: Option<SimulatedLag>,
 ) {
+    let mut keepalive = Keepalive::new(SPOKES_PING_INTERVAL, SPOKES_PONG_TIMEOUT, Instant::now());
+    let mut keepalive_timer = tokio::time::interval(Duration::from_secs(1));
+    keepalive_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
     loop {
         tokio::select! {
             _ = shutdown_rx.recv() => {
                 debug!("Shutdown of websocket");
                 break;
             },
-            r = radar_message_rx.recv() => {
+            _ = keepalive_timer.tick() => {
+                match keepalive.tick(Instant::now()) {
+                    KeepaliveAction::Wait => {}
+                    KeepaliveAction::Ping => {
+                        if let Err(e) = socket.send(Message::Ping(Vec::<u8>::new().into())).await {
+                            debug!("Error on send to websocket: {}", e);
+                            break;
+                        }
+                    }
+                    KeepaliveAction::Timeout => {
+                        debug!("Spokes websocket client did not answer ping, closing");
+                        break;
+                    }
+                }
+            },
+            r = recv_radar_message(&mut radar_message_rx) => {
                 match r {
-                    Ok(message) => {
+                    Some(message) => {
+                        let message = match roi.as_mut() {
+                            Some(roi) => match roi.clip_message(&message) {
+                                Some(message) => message,
+                                None => continue,
+                            },
+                            None => message,
+                        };
+                        let azimuth = match (crc, rasterizer.is_some()) {
+                            (false, _) => None,
+                            (true, true) => Some(checksum::NO_AZIMUTH),
+                            (true, false) => Some(checksum::first_azimuth(&message)),
+                        };
+                        if let Some(rasterizer) = rasterizer.as_mut().filter(|r| r.uses_heading()) {
+                            rasterizer.set_heading(navdata::get_heading_true().map(f64::to_degrees));
+                            rasterizer.set_course(navdata::get_cog().map(f64::to_degrees));
+                        }
+                        let message = match (rasterizer.as_mut(), delta_encoder.as_mut()) {
+                            (Some(rasterizer), _) => match rasterizer.encode_message(&message) {
+                                Some(frame) => frame,
+                                None => continue,
+                            },
+                            (None, Some(encoder)) => match encoder.encode_message(&message) {
+                                Some(message) => message,
+                                None => continue,
+                            },
+                            (None, None) => message,
+                        };
+                        let message = match azimuth {
+                            Some(azimuth) => checksum::frame(azimuth, &message),
+                            None => message,
+                        };
                         let len = message.len();
                         let ws_message = Message::Binary(message.into());
                         if let Err(e) = socket.send(ws_message).await {
@@ -680,15 +1915,29 @@ async fn spokes_stream(
                             break;
                         }
                         trace!("Sent radar message {} bytes", len);
+                        if let Some(lag) = lag {
+                            lag.fall_behind().await;
+                        }
                     },
-                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                        // Channel lagged - receiver fell behind, skip missed messages
-                        debug!("Websocket receiver lagged, skipped {} messages", n);
-                        // Continue to receive next message
-                    },
-                    Err(tokio::sync::broadcast::error::RecvError::Closed) => {
-                        // Channel closed - sender was dropped (radar disconnected or playback stopped)
-                        debug!("RadarMessage channel closed");
+                    None => break,
+                }
+            },
+            // nothing is expected from the client, but keep reading so pings
+            // are answered and stray frames don't pile up
+            r = socket.recv() => {
+                if matches!(r, Some(Ok(_))) {
+                    keepalive.alive(Instant::now());
+                }
+                match ClientFrame::from_received(r) {
+                    ClientFrame::Ignore => {}
+                    ClientFrame::Reply(reply) => {
+                        if let Err(e) = socket.send(reply).await {
+                            debug!("Error on send to websocket: {}", e);
+                            break;
+                        }
+                    }
+                    ClientFrame::Close => {
+                        debug!("Spokes websocket closed by client");
                         break;
                     }
                 }
@@ -720,10 +1969,12 @@ async fn control_handler(
     {
         Some(radar) => {
             let shutdown_rx = state.shutdown_tx.subscribe();
+            let idle_timeout =
+                Duration::from_secs(state.session.read().unwrap().args.control_idle_timeout);
 
             // finalize the upgrade process by returning upgrade callback.
             // we can customize the callback by sending additional info such as address.
-            ws.on_upgrade(move |socket| control_stream(socket, radar, shutdown_rx))
+            ws.on_upgrade(move |socket| control_stream(socket, radar, shutdown_rx, idle_timeout))
         }
         None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
     }
@@ -731,12 +1982,24 @@ async fn control_handler(
 
 /// Actual websocket statemachine (one will be spawned per connection)
 
+/// The `/control` websocket message for a settings diff; sorted, so that
+/// messages are easy to compare
+fn settings_diff_message(diff: mayara_core::SettingsDiff) -> String {
+    let diff: BTreeMap<_, _> = diff.into_iter().collect();
+    serde_json::json!({ "settingsDiff": diff }).to_string()
+}
+
 async fn control_stream(
     mut socket: WebSocket,
     radar: RadarInfo,
     mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    idle_timeout: Duration,
 ) {
     let mut broadcast_control_rx = radar.all_clients_rx();
+    let mut settings_diff_rx = radar.controls.settings_diff_subscribe();
+    let mut idle = IdleTimeout::new(idle_timeout, Instant::now());
+    let mut idle_timer = tokio::time::interval(Duration::from_secs(1));
+    idle_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
     let (reply_tx, mut reply_rx) = tokio::sync::mpsc::channel(60);
 
     if radar
@@ -756,6 +2019,17 @@ async fn control_stream(
                 debug!("Shutdown of /control websocket");
                 break;
             },
+            _ = idle_timer.tick() => {
+                if idle.expired(Instant::now()) {
+                    debug!("Closing idle /control websocket after {:?}", idle_timeout);
+                    let close = axum_fix::CloseFrame {
+                        code: axum_fix::close_code::NORMAL,
+                        reason: "idle timeout".into(),
+                    };
+                    socket.send(Message::Close(Some(close))).await.ok();
+                    break;
+                }
+            },
             // this is where we receive directed control messages meant just for us, they
             // are either error replies for an invalid control value or the full list of
             // controls.
@@ -798,10 +2072,30 @@ async fn control_stream(
                     }
                 }
             },
+            // the fields of the settings reports that changed, as decoded from the radar
+            r = settings_diff_rx.recv() => {
+                match r {
+                    Ok(diff) => {
+                        let message = settings_diff_message(diff);
+                        log::debug!("Sending {:?}", message);
+
+                        if let Err(e) = socket.send(Message::Text(message.into())).await {
+                            log::error!("send to websocket client: {e}");
+                            break;
+                        }
+                    },
+                    Err(broadcast::error::RecvError::Lagged(_)) => {},
+                    Err(broadcast::error::RecvError::Closed) => {
+                        log::error!("Error on settings diff channel");
+                        break;
+                    }
+                }
+            },
             // receive control values from the client
             r = socket.recv() => {
                 match r {
                     Some(Ok(message)) => {
+                        idle.activity(Instant::now());
                         match message {
                             Message::Text(message) => {
                                 if let Ok(control_value) = serde_json::from_str(&message) {
@@ -996,6 +2290,7 @@ struct RadarTargetIdParam {
 struct TargetListResponse {
     radar_id: String,
     timestamp: String,
+    bearing_unit: BearingUnit,
     targets: Vec<ArpaTarget>,
 }
 
@@ -1014,6 +2309,9 @@ struct AcquireTargetResponse {
     success: bool,
     target_id: Option<u32>,
     error: Option<String>,
+    /// Where the picked target is; only relative without own-ship position
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<TargetPosition>,
 }
 
 /// GET /radars/{radar_id}/targets - List all tracked ARPA targets
@@ -1024,12 +2322,18 @@ async fn get_targets(
 ) -> Response {
     debug!("GET targets for radar {}", params.radar_id);
 
+    let unit = state.bearing_unit();
     let engine = state.engine.read().unwrap();
-    let targets = engine.get_targets(&params.radar_id);
+    let targets = engine
+        .get_targets(&params.radar_id)
+        .iter()
+        .map(|target| unit.target_from_degrees(target))
+        .collect();
 
     let response = TargetListResponse {
         radar_id: params.radar_id,
         timestamp: chrono::Utc::now().to_rfc3339(),
+        bearing_unit: unit,
         targets,
     };
 
@@ -1048,18 +2352,21 @@ async fn acquire_target(
         params.radar_id, request.bearing, request.distance
     );
 
-    // Validate bearing
-    if request.bearing < 0.0 || request.bearing >= 360.0 {
+    // Validate bearing, given in the configured bearing unit
+    let unit = state.bearing_unit();
+    if request.bearing < 0.0 || request.bearing >= unit.revolution() {
         return (
             StatusCode::BAD_REQUEST,
             Json(AcquireTargetResponse {
                 success: false,
                 target_id: None,
-                error: Some("bearing must be 0-360".to_string()),
+                error: Some(format!("bearing must be 0-{} {}", unit.revolution(), unit.as_str())),
+                position: None,
             }),
         )
             .into_response();
     }
+    let bearing = unit.to_degrees(request.bearing);
 
     // Validate distance
     if request.distance <= 0.0 {
@@ -1069,6 +2376,7 @@ async fn acquire_target(
                 success: false,
                 target_id: None,
                 error: Some("distance must be positive".to_string()),
+                position: None,
             }),
         )
             .into_response();
@@ -1084,13 +2392,14 @@ async fn acquire_target(
         .as_millis() as u64;
 
     let mut engine = state.engine.write().unwrap();
-    match engine.acquire_target(&params.radar_id, request.bearing, request.distance, timestamp) {
+    match engine.acquire_target(&params.radar_id, bearing, request.distance, timestamp) {
         Some(target_id) => {
             debug!("Acquired target {} on radar {}", target_id, params.radar_id);
             Json(AcquireTargetResponse {
                 success: true,
                 target_id: Some(target_id),
                 error: None,
+                position: Some(unit.position_from_degrees(navdata::locate_target(bearing, request.distance))),
             })
             .into_response()
         }
@@ -1100,6 +2409,7 @@ async fn acquire_target(
                 success: false,
                 target_id: None,
                 error: Some("max targets reached".to_string()),
+                position: None,
             }),
         )
             .into_response(),
@@ -1253,6 +2563,25 @@ struct GuardZoneListResponse {
     zones: Vec<GuardZoneStatus>,
 }
 
+/// A guard zone status with its bearings in the reported unit
+fn zone_status_in_unit(status: GuardZoneStatus, unit: BearingUnit) -> GuardZoneStatus {
+    GuardZoneStatus {
+        zone: unit.zone_from_degrees(&status.zone),
+        ..status
+    }
+}
+
+/// The guard zones the spokes of a radar are checked against
+fn guard_zones(state: &Web, radar_id: &str) -> Result<GuardZones, Response> {
+    let session = state.session.read().unwrap();
+    session
+        .radars
+        .as_ref()
+        .and_then(|radars| radars.get_by_id(radar_id))
+        .map(|info| info.guard_zones)
+        .ok_or_else(|| RadarError::NoSuchRadar(radar_id.to_string()).into_response())
+}
+
 /// GET /radars/{radar_id}/guardZones - List all guard zones
 #[debug_handler]
 async fn get_guard_zones(
@@ -1261,8 +2590,16 @@ async fn get_guard_zones(
 ) -> Response {
     debug!("GET guard zones for radar {}", params.radar_id);
 
-    let engine = state.engine.read().unwrap();
-    let zones = engine.get_guard_zones(&params.radar_id);
+    let unit = state.bearing_unit();
+    let guard_zones = match guard_zones(&state, &params.radar_id) {
+        Ok(guard_zones) => guard_zones,
+        Err(response) => return response,
+    };
+    let zones = guard_zones
+        .status()
+        .into_iter()
+        .map(|status| zone_status_in_unit(status, unit))
+        .collect();
 
     let response = GuardZoneListResponse {
         radar_id: params.radar_id,
@@ -1280,12 +2617,13 @@ async fn create_guard_zone(
     Json(zone): Json<GuardZone>,
 ) -> Response {
     debug!("POST create guard zone {} for radar {}", zone.id, params.radar_id);
+    let zone = state.bearing_unit().zone_to_degrees(&zone);
+    let guard_zones = match guard_zones(&state, &params.radar_id) {
+        Ok(guard_zones) => guard_zones,
+        Err(response) => return response,
+    };
 
-    // Ensure radar exists in engine
-    state.ensure_radar_in_engine(&params.radar_id);
-
-    let mut engine = state.engine.write().unwrap();
-    engine.set_guard_zone(&params.radar_id, zone.clone());
+    guard_zones.set(zone.clone());
     debug!("Created guard zone {} on radar {}", zone.id, params.radar_id);
 
     (StatusCode::CREATED, Json(zone)).into_response()
@@ -1299,9 +2637,13 @@ async fn get_guard_zone(
 ) -> Response {
     debug!("GET guard zone {} for radar {}", params.zone_id, params.radar_id);
 
-    let engine = state.engine.read().unwrap();
-    if let Some(status) = engine.get_guard_zone(&params.radar_id, params.zone_id) {
-        return Json(status).into_response();
+    let unit = state.bearing_unit();
+    let guard_zones = match guard_zones(&state, &params.radar_id) {
+        Ok(guard_zones) => guard_zones,
+        Err(response) => return response,
+    };
+    if let Some(status) = guard_zones.get(params.zone_id) {
+        return Json(zone_status_in_unit(status, unit)).into_response();
     }
 
     (StatusCode::NOT_FOUND, "Zone not found").into_response()
@@ -1316,15 +2658,17 @@ async fn update_guard_zone(
 ) -> Response {
     debug!("PUT update guard zone {} for radar {}", params.zone_id, params.radar_id);
 
-    // Ensure radar exists in engine
-    state.ensure_radar_in_engine(&params.radar_id);
+    let guard_zones = match guard_zones(&state, &params.radar_id) {
+        Ok(guard_zones) => guard_zones,
+        Err(response) => return response,
+    };
 
-    // Ensure zone ID matches path
-    let mut zone = zone;
+    // Ensure zone ID matches path, bearings are in the configured unit
+    let unit = state.bearing_unit();
+    let mut zone = unit.zone_to_degrees(&zone);
     zone.id = params.zone_id;
 
-    let mut engine = state.engine.write().unwrap();
-    engine.set_guard_zone(&params.radar_id, zone);
+    guard_zones.set(zone);
     debug!("Updated guard zone {} on radar {}", params.zone_id, params.radar_id);
 
     StatusCode::OK.into_response()
@@ -1338,8 +2682,11 @@ async fn delete_guard_zone(
 ) -> Response {
     debug!("DELETE guard zone {} for radar {}", params.zone_id, params.radar_id);
 
-    let mut engine = state.engine.write().unwrap();
-    if engine.remove_guard_zone(&params.radar_id, params.zone_id) {
+    let guard_zones = match guard_zones(&state, &params.radar_id) {
+        Ok(guard_zones) => guard_zones,
+        Err(response) => return response,
+    };
+    if guard_zones.remove(params.zone_id) {
         debug!("Deleted guard zone {} on radar {}", params.zone_id, params.radar_id);
         return StatusCode::NO_CONTENT.into_response();
     }
@@ -1347,6 +2694,68 @@ async fn delete_guard_zone(
     (StatusCode::NOT_FOUND, "Zone not found").into_response()
 }
 
+/// Query parameters for GET /radars/{radar_id}/alarms
+#[derive(Deserialize)]
+struct AlarmsQuery {
+    /// Return at most this many of the most recent events
+    limit: Option<usize>,
+}
+
+/// GET /radars/{radar_id}/alarms - Recent guard zone enter and exit events
+#[debug_handler]
+async fn get_alarms(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    axum::extract::Query(query): axum::extract::Query<AlarmsQuery>,
+) -> Response {
+    debug!("GET alarm history for radar {}", params.radar_id);
+
+    let unit = state.bearing_unit();
+    let log = state.session.read().unwrap().alarm_log.clone();
+    let events: Vec<_> = log
+        .lock()
+        .unwrap()
+        .recent(&params.radar_id, query.limit.unwrap_or(DEFAULT_ALARM_LOG_SIZE))
+        .into_iter()
+        .map(|event| AlarmEvent {
+            bearing: unit.from_degrees(event.bearing),
+            ..event
+        })
+        .collect();
+
+    Json(events).into_response()
+}
+
+/// Query parameters for GET /radars/{radar_id}/settings/history
+#[derive(Deserialize)]
+struct SettingsHistoryQuery {
+    /// Only changes of this setting
+    setting: Option<String>,
+    /// Return at most this many of the most recent changes
+    limit: Option<usize>,
+}
+
+/// GET /radars/{radar_id}/settings/history - Recent setting changes reported by the radar
+#[debug_handler]
+async fn get_settings_history(
+    State(state): State<Web>,
+    Path(params): Path<RadarIdParam>,
+    axum::extract::Query(query): axum::extract::Query<SettingsHistoryQuery>,
+) -> Response {
+    debug!("GET settings history for radar {}", params.radar_id);
+
+    let session = state.session.read().unwrap();
+    let radars = session.radars.as_ref().unwrap();
+    match radars.get_by_id(&params.radar_id) {
+        Some(info) => Json(info.controls.settings_history(
+            query.setting.as_deref(),
+            query.limit.unwrap_or(DEFAULT_SETTINGS_HISTORY_SIZE),
+        ))
+        .into_response(),
+        None => RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+    }
+}
+
 // =============================================================================
 // Trail API Handlers
 // =============================================================================
@@ -1403,16 +2812,54 @@ async fn get_trail(
     (StatusCode::NOT_FOUND, "Trail not found").into_response()
 }
 
+/// Query parameters for clearing trails
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClearTrailsQuery {
+    /// Also forget the learned noise floor (--noise-floor)
+    #[serde(default)]
+    noise_floor: bool,
+}
+
 /// DELETE /radars/{radar_id}/trails - Clear all trails
+/// Clears the target trails and the trails drawn into the spokes, and with
+/// `?noiseFloor=true` restarts the noise floor estimation
 #[debug_handler]
 async fn clear_all_trails(
     State(state): State<Web>,
     Path(params): Path<RadarIdParam>,
+    axum::extract::Query(query): axum::extract::Query<ClearTrailsQuery>,
 ) -> Response {
+    use mayara_server::settings::ControlValue;
+
     debug!("DELETE all trails for radar {}", params.radar_id);
 
-    let mut engine = state.engine.write().unwrap();
-    engine.clear_all_trails(&params.radar_id);
+    state.engine.write().unwrap().clear_all_trails(&params.radar_id);
+
+    let controls = {
+        let session = state.session.read().unwrap();
+        session
+            .radars
+            .as_ref()
+            .and_then(|radars| radars.get_by_id(&params.radar_id))
+            .map(|radar| radar.controls.clone())
+    };
+    if let Some(controls) = controls {
+        let mut buttons = vec!["clearTrails"];
+        if query.noise_floor {
+            buttons.push("clearNoiseFloor");
+        }
+        for id in buttons.into_iter().filter(|id| controls.get(id).is_some()) {
+            let (reply_tx, _reply_rx) = tokio::sync::mpsc::channel(1);
+            if let Err(e) = controls
+                .process_client_request(ControlValue::new(id, "1".to_string()), reply_tx)
+                .await
+            {
+                return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to send {}: {:?}", id, e))
+                    .into_response();
+            }
+        }
+    }
     debug!("Cleared all trails on radar {}", params.radar_id);
 
     StatusCode::NO_CONTENT.into_response()
@@ -1479,6 +2926,18 @@ struct DualRangeResponse {
     radar_id: String,
     state: CoreDualRangeState,
     available_ranges: Vec<u32>,
+    /// Which channels of a Navico dual range unit transmit
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    channels: Vec<mayara_core::protocol::navico::ChannelTransmit>,
+}
+
+/// The range the radar currently reports, in meters
+fn current_range(info: &RadarInfo) -> Option<u32> {
+    info.controls
+        .get("range")
+        .and_then(|c| c.value)
+        .filter(|&v| v > 0.)
+        .map(|v| v as u32)
 }
 
 /// GET /radars/{radar_id}/dualRange - Get dual-range state
@@ -1490,18 +2949,13 @@ async fn get_dual_range(
     debug!("GET dual-range for radar {}", params.radar_id);
 
     // Check if radar exists and supports dual-range (get model info from session)
-    let model_info = {
+    let (model_info, primary_range, channels) = {
         let session = state.session.read().unwrap();
         let radars = session.radars.as_ref().unwrap();
 
         match radars.get_by_id(&params.radar_id) {
             Some(info) => {
-                let core_brand = to_core_brand(info.brand);
-                let model_name = info.controls.model_name();
-                let model_info = model_name
-                    .as_deref()
-                    .and_then(|m| models::get_model(core_brand, m))
-                    .unwrap_or(&models::UNKNOWN_MODEL);
+                let model_info = model_info(&info);
 
                 if !model_info.has_dual_range {
                     return (
@@ -1511,7 +2965,7 @@ async fn get_dual_range(
                         .into_response();
                 }
 
-                model_info.clone()
+                (model_info.clone(), current_range(&info), radars.channel_transmit(&info))
             }
             None => return RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
         }
@@ -1519,13 +2973,17 @@ async fn get_dual_range(
 
     // Get dual-range state from engine
     let engine = state.engine.read().unwrap();
-    let dual_state = engine
+    let mut dual_state = engine
         .get_dual_range(&params.radar_id)
         .cloned()
         .unwrap_or_else(|| CoreDualRangeState {
             max_secondary_range: model_info.max_dual_range,
             ..Default::default()
         });
+    // The primary range is whatever the radar reports for its main range
+    if let Some(range) = primary_range {
+        dual_state.primary_range = range;
+    }
 
     // Filter ranges for secondary display
     let available_ranges: Vec<u32> = model_info
@@ -1539,6 +2997,7 @@ async fn get_dual_range(
         radar_id: params.radar_id,
         state: dual_state,
         available_ranges,
+        channels,
     };
 
     Json(response).into_response()
@@ -1557,18 +3016,13 @@ async fn set_dual_range(
     );
 
     // Check if radar exists and supports dual-range (get model info from session)
-    let (model_name, model_info) = {
+    let (model_name, primary_range) = {
         let session = state.session.read().unwrap();
         let radars = session.radars.as_ref().unwrap();
 
         match radars.get_by_id(&params.radar_id) {
             Some(info) => {
-                let core_brand = to_core_brand(info.brand);
-                let model_name_opt = info.controls.model_name();
-                let model = model_name_opt
-                    .as_deref()
-                    .and_then(|m| models::get_model(core_brand, m))
-                    .unwrap_or(&models::UNKNOWN_MODEL);
+                let model = model_info(&info);
 
                 if !model.has_dual_range {
                     return (
@@ -1578,7 +3032,7 @@ async fn set_dual_range(
                         .into_response();
                 }
 
-                (model_name_opt, model.clone())
+                (model_name_opt, current_range(&info))
             }
             None => return RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
         }
@@ -1591,16 +3045,11 @@ async fn set_dual_range(
 
     // Apply config to engine
     let mut engine = state.engine.write().unwrap();
-    if !engine.set_dual_range(&params.radar_id, &config) {
-        return (
-            StatusCode::BAD_REQUEST,
-            format!(
-                "Secondary range {} exceeds maximum {}",
-                config.secondary_range,
-                model_info.max_dual_range
-            ),
-        )
-            .into_response();
+    if let Err(e) = engine.try_set_dual_range(&params.radar_id, primary_range, &config) {
+        debug!("Rejected dual-range for radar {}: {}", params.radar_id, e);
+        let mut body = serde_json::to_value(&e).unwrap_or_default();
+        body["message"] = e.to_string().into();
+        return (StatusCode::BAD_REQUEST, Json(body)).into_response();
     }
 
     debug!(
@@ -1633,12 +3082,7 @@ async fn dual_range_spokes_handler(
 
         match radars.get_by_id(&params.radar_id) {
             Some(info) => {
-                let core_brand = to_core_brand(info.brand);
-                let model_name = info.controls.model_name();
-                let model = model_name
-                    .as_deref()
-                    .and_then(|m| models::get_model(core_brand, m))
-                    .unwrap_or(&models::UNKNOWN_MODEL);
+                let model = model_info(&info);
 
                 if !model.has_dual_range {
                     return (
@@ -1701,6 +3145,110 @@ async fn dual_range_spokes_stream(
     }
 }
 
+/// Query parameters of the combined dual range WebSocket
+#[derive(Deserialize)]
+struct CompositeQuery {
+    /// Pixels square of the combined image
+    #[serde(default = "default_composite_size")]
+    size: u16,
+}
+
+fn default_composite_size() -> u16 {
+    512
+}
+
+/// WebSocket with one image per rotation that combines both channels of a
+/// dual range radar, see [`composite`]
+async fn dual_range_composite_handler(
+    State(state): State<Web>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(params): Path<RadarIdParam>,
+    axum::extract::Query(query): axum::extract::Query<CompositeQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    debug!(
+        "dual-range composite request from {} for {}",
+        addr, params.radar_id
+    );
+
+    let channels = {
+        let session = state.session.read().unwrap();
+        let radars = session.radars.as_ref().unwrap();
+        match radars.get_by_id(&params.radar_id) {
+            Some(info) => radars.channels(&info),
+            None => return RadarError::NoSuchRadar(params.radar_id.to_string()).into_response(),
+        }
+    };
+    if channels.len() != composite::CHANNEL_COUNT {
+        return (
+            StatusCode::NOT_FOUND,
+            "Radar does not have two range channels",
+        )
+            .into_response();
+    }
+    if let Some(response) = channels.iter().find_map(initializing_response) {
+        return response;
+    }
+
+    let compositor = composite::Compositor::new(query.size, channels[0].spokes_per_revolution);
+    let shutdown_rx = state.shutdown_tx.subscribe();
+    let range_a_rx = channels[0].message_tx.subscribe();
+    let range_b_rx = channels[1].message_tx.subscribe();
+
+    ws.accept_compression(false).on_upgrade(move |socket| {
+        dual_range_composite_stream(socket, range_a_rx, range_b_rx, shutdown_rx, compositor)
+    })
+}
+
+async fn dual_range_composite_stream(
+    mut socket: WebSocket,
+    mut range_a_rx: tokio::sync::broadcast::Receiver<Vec<u8>>,
+    mut range_b_rx: tokio::sync::broadcast::Receiver<Vec<u8>>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    mut compositor: composite::Compositor,
+) {
+    loop {
+        let frame = tokio::select! {
+            _ = shutdown_rx.recv() => {
+                debug!("Shutdown of dual-range composite websocket");
+                break;
+            },
+            r = recv_radar_message(&mut range_a_rx) => match r {
+                Some(message) => compositor.encode_message(0, &message),
+                None => break,
+            },
+            r = recv_radar_message(&mut range_b_rx) => match r {
+                Some(message) => compositor.encode_message(1, &message),
+                None => break,
+            },
+            r = socket.recv() => {
+                match ClientFrame::from_received(r) {
+                    ClientFrame::Ignore => {}
+                    ClientFrame::Reply(reply) => {
+                        if let Err(e) = socket.send(reply).await {
+                            debug!("Error on send to dual-range composite websocket: {}", e);
+                            break;
+                        }
+                    }
+                    ClientFrame::Close => {
+                        debug!("Dual-range composite websocket closed by client");
+                        break;
+                    }
+                }
+                None
+            }
+        };
+        if let Some(frame) = frame {
+            let len = frame.len();
+            if let Err(e) = socket.send(Message::Binary(frame.into())).await {
+                debug!("Error on send to dual-range composite websocket: {}", e);
+                break;
+            }
+            trace!("Sent dual-range composite frame {} bytes", len);
+        }
+    }
+}
+
 // ============================================================================
 // Recordings API handlers
 // ============================================================================
@@ -2043,31 +3591,17 @@ async fn start_recording_handler(
         };
 
         // Build capabilities JSON
-        let core_brand = to_core_brand(radar.brand);
-        let model_name = radar.controls.model_name();
-        let model_info = model_name
-            .as_deref()
-            .and_then(|name| models::get_model(core_brand, name))
-            .unwrap_or(&models::UNKNOWN_MODEL);
-
-        // Declare supported features for recording
-        let mut supported_features = vec![
-            SupportedFeature::Arpa,
-            SupportedFeature::GuardZones,
-            SupportedFeature::Trails,
-        ];
-        if model_info.has_dual_range {
-            supported_features.push(SupportedFeature::DualRange);
-        }
+        let model_info = model_info(&radar);
 
-        let capabilities = build_capabilities_from_model_with_key(
+        let mut capabilities = build_capabilities_from_model_with_key(
             model_info,
             &request.radar_id,
             Some(&radar.key()),
-            supported_features,
+            capability_export::supported_features(model_info),
             radar.spokes_per_revolution,
-            radar.max_spoke_len,
+            radar.output_spoke_len(),
         );
+        capabilities.intensity_legend = Some(radar.intensity_legend());
 
         let capabilities_json = serde_json::to_vec(&capabilities).unwrap_or_else(|_| b"{}".to_vec());
 
@@ -2346,3 +3880,405 @@ async fn playback_status_handler(State(state): State<Web>) -> Response {
 
     Json(status).into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn spoke_events_carry_the_spokes_of_a_synthetic_radar() {
+        use http_body_util::BodyExt;
+        use mayara_server::protos::RadarMessage::{radar_message::Spoke, RadarMessage};
+        use mayara_server::settings::ControlValue;
+        use protobuf::Message as _;
+
+        let (message_tx, _) = broadcast::channel(8);
+        let (control_tx, _) = broadcast::channel(8);
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let events = spoke_events(message_tx.subscribe(), control_tx.subscribe(), shutdown_tx.subscribe());
+        let mut body = Sse::new(events).into_response().into_body();
+        async fn next_event(body: &mut axum::body::Body) -> String {
+            let frame = body.frame().await.unwrap().unwrap();
+            String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap()
+        }
+
+        let mut message = RadarMessage::new();
+        let mut spoke = Spoke::new();
+        spoke.angle = 100;
+        spoke.range = 1852;
+        spoke.data = vec![0, 7, 15, 7, 0];
+        message.spokes.push(spoke);
+        let bytes = message.write_to_bytes().unwrap();
+        message_tx.send(bytes.clone()).unwrap();
+
+        let event = next_event(&mut body).await;
+        assert!(event.starts_with("event: spoke\n"), "{}", event);
+        assert!(event.contains(&format!("data: {}\n", BASE64_STANDARD.encode(&bytes))));
+
+        // Other controls are left out, status changes are sent
+        control_tx.send(ControlValue::new("gain", "50".to_string())).unwrap();
+        control_tx.send(ControlValue::new("power", "2".to_string())).unwrap();
+        let event = next_event(&mut body).await;
+        assert!(event.starts_with("event: status\n"), "{}", event);
+        assert!(event.contains(r#""id":"power""#), "{}", event);
+
+        drop(message_tx);
+        assert!(body.frame().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn settings_changes_are_pushed_once_to_status_subscribers() {
+        use mayara_server::settings::ControlValue;
+
+        let (control_tx, _) = broadcast::channel(8);
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let current = mayara_core::SettingsDiff::from([
+            ("gain".to_string(), serde_json::json!(40)),
+            ("power".to_string(), serde_json::json!(1)),
+        ]);
+        let events = status_events(current, control_tx.subscribe(), shutdown_tx.subscribe());
+        let mut events = std::pin::pin!(events);
+        assert_eq!(events.next().await.unwrap(), r#"{"gain":40,"power":1}"#);
+
+        // The same value again is no change, a new one is
+        control_tx.send(ControlValue::new("gain", "40".to_string())).unwrap();
+        control_tx.send(ControlValue::new("gain", "55".to_string())).unwrap();
+        assert_eq!(events.next().await.unwrap(), r#"{"gain":55}"#);
+
+        let mut auto = ControlValue::new("sea", "30".to_string());
+        auto.auto = Some(true);
+        control_tx.send(auto).unwrap();
+        let event: serde_json::Value = serde_json::from_str(&events.next().await.unwrap()).unwrap();
+        assert_eq!(event, serde_json::json!({ "sea": { "value": 30, "auto": true } }));
+
+        shutdown_tx.send(()).unwrap();
+        assert!(events.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn navico_report_02_is_pushed_in_client_units() {
+        use clap::Parser;
+        use mayara_core::protocol::navico::{parse_report_02, REPORT_02_SIZE};
+        use mayara_server::control_factory;
+        use mayara_server::settings::{Control, ControlValue, SharedControls};
+
+        let session = Session::new_base(mayara_server::Cli::parse_from(["mayara"]));
+        let max = 96. * 1852.;
+        let controls = SharedControls::new(
+            session,
+            HashMap::from([
+                (
+                    "range".to_string(),
+                    Control::new_numeric("range", 50., max)
+                        .unit("m")
+                        .wire_scale_factor(10. * max, false),
+                ),
+                (
+                    "gain".to_string(),
+                    control_factory::gain_control_for_brand(mayara_core::Brand::Navico),
+                ),
+            ]),
+        );
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let current = controls
+            .get_all()
+            .iter()
+            .map(|(id, control)| (id.clone(), status_value(&ControlValue::from(control, None))))
+            .collect();
+        let events = status_events(current, controls.all_clients_rx(), shutdown_tx.subscribe());
+        let mut events = std::pin::pin!(events);
+        assert!(events.next().await.is_some());
+
+        // 1852 m in decimeters, gain 102 of 255; set as the report receiver does
+        let mut data = vec![0u8; REPORT_02_SIZE];
+        data[0] = 0x02;
+        data[1] = 0xC4;
+        data[2..6].copy_from_slice(&18520i32.to_le_bytes());
+        data[12] = 102;
+        let report = parse_report_02(&data).unwrap();
+        controls.set("range", report.range as f32, None).unwrap();
+        controls
+            .set_value_auto("gain", report.gain_auto, report.gain as f32)
+            .unwrap();
+
+        let event: serde_json::Value = serde_json::from_str(&events.next().await.unwrap()).unwrap();
+        assert_eq!(event["range"].as_f64().map(f64::round), Some(1852.));
+        let event: serde_json::Value = serde_json::from_str(&events.next().await.unwrap()).unwrap();
+        assert_eq!(event["gain"]["auto"], false);
+        assert_eq!(event["gain"]["value"].as_f64().map(f64::round), Some(40.));
+
+        // The same report again changes nothing
+        controls.set("range", report.range as f32, None).unwrap();
+        shutdown_tx.send(()).unwrap();
+        assert!(events.next().await.is_none());
+    }
+
+    #[test]
+    fn settings_diffs_are_wrapped_for_the_control_websocket() {
+        let diff = HashMap::from([
+            ("sea".to_string(), serde_json::json!({"auto": true, "value": 30})),
+            ("gain".to_string(), serde_json::json!({"auto": false, "value": 55})),
+        ]);
+
+        assert_eq!(
+            settings_diff_message(diff),
+            r#"{"settingsDiff":{"gain":{"auto":false,"value":55},"sea":{"auto":true,"value":30}}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn simulated_lag_skips_messages_and_the_stream_goes_on() {
+        let lag = SimulatedLag::from_arg(Some(50));
+        assert_eq!(lag.is_some(), cfg!(debug_assertions));
+        let lag = SimulatedLag(Duration::from_millis(50));
+
+        let (tx, mut rx) = tokio::sync::broadcast::channel(4);
+        tx.send(vec![0u8]).unwrap();
+        assert_eq!(recv_radar_message(&mut rx).await, Some(vec![0]));
+
+        // The radar keeps sending while the subscriber sleeps
+        let sender = tokio::spawn(async move {
+            for i in 1..=20u8 {
+                tx.send(vec![i]).unwrap();
+            }
+            tx
+        });
+        lag.fall_behind().await;
+        let tx = sender.await.unwrap();
+
+        // The oldest messages are gone, the newest still arrive
+        assert_eq!(recv_radar_message(&mut rx).await, Some(vec![17]));
+        for i in 18..=20u8 {
+            assert_eq!(recv_radar_message(&mut rx).await, Some(vec![i]));
+        }
+        tx.send(vec![21]).unwrap();
+        assert_eq!(recv_radar_message(&mut rx).await, Some(vec![21]));
+
+        drop(tx);
+        assert_eq!(recv_radar_message(&mut rx).await, None);
+        assert_eq!(SimulatedLag::from_arg(None), None);
+        assert_eq!(SimulatedLag::from_arg(Some(0)), None);
+    }
+
+    #[test]
+    fn stray_client_frames_do_not_end_the_spokes_stream() {
+        let frame = |message: Message| ClientFrame::from_received::<()>(Some(Ok(message)));
+
+        assert_eq!(frame(Message::Text("hello".into())), ClientFrame::Ignore);
+        assert_eq!(frame(Message::Binary(vec![1, 2, 3].into())), ClientFrame::Ignore);
+        assert_eq!(frame(Message::Pong(Vec::<u8>::new().into())), ClientFrame::Ignore);
+        assert_eq!(
+            frame(Message::Ping(vec![7].into())),
+            ClientFrame::Reply(Message::Pong(vec![7].into()))
+        );
+        assert_eq!(frame(Message::Close(None)), ClientFrame::Close);
+        assert_eq!(ClientFrame::from_received::<()>(None), ClientFrame::Close);
+        assert_eq!(ClientFrame::from_received(Some(Err("reset"))), ClientFrame::Close);
+    }
+
+    #[test]
+    fn client_that_never_pongs_is_disconnected() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut keepalive = Keepalive::new(Duration::from_secs(10), Duration::from_secs(5), start);
+
+        assert_eq!(keepalive.tick(at(9)), KeepaliveAction::Wait);
+        assert_eq!(keepalive.tick(at(10)), KeepaliveAction::Ping);
+        assert_eq!(keepalive.tick(at(14)), KeepaliveAction::Wait);
+        assert_eq!(keepalive.tick(at(15)), KeepaliveAction::Timeout);
+
+        // A client that answers is pinged again only after the next quiet interval
+        let mut keepalive = Keepalive::new(Duration::from_secs(10), Duration::from_secs(5), start);
+        assert_eq!(keepalive.tick(at(10)), KeepaliveAction::Ping);
+        keepalive.alive(at(11));
+        assert_eq!(keepalive.tick(at(16)), KeepaliveAction::Wait);
+        assert_eq!(keepalive.tick(at(21)), KeepaliveAction::Ping);
+    }
+
+    #[test]
+    fn idle_control_session_is_closed_after_the_timeout() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut idle = IdleTimeout::new(Duration::from_secs(60), start);
+
+        assert!(!idle.expired(at(59)));
+        assert!(idle.expired(at(60)));
+
+        // Every message from the client starts the period again
+        let mut idle = IdleTimeout::new(Duration::from_secs(60), start);
+        idle.activity(at(50));
+        assert!(!idle.expired(at(100)));
+        assert!(idle.expired(at(110)));
+
+        // A zero timeout keeps the session open
+        let idle = IdleTimeout::new(Duration::ZERO, start);
+        assert!(!idle.expired(at(1_000_000)));
+    }
+
+    #[test]
+    fn spoke_streams_wait_for_the_spoke_geometry() {
+        use clap::Parser;
+        use mayara_server::locator::LocatorId;
+        use mayara_server::settings::SharedControls;
+        use std::net::{Ipv4Addr, SocketAddrV4};
+
+        let session = Session::new_base(mayara_server::Cli::parse_from(["mayara"]));
+        let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, 0);
+        let radar = |spokes_per_revolution| {
+            let controls = SharedControls::new(session.clone(), HashMap::new());
+            RadarInfo::new(
+                session.clone(),
+                LocatorId::Gen3Plus,
+                mayara_core::Brand::Navico,
+                Some("1234"),
+                None,
+                16,
+                spokes_per_revolution,
+                1024,
+                addr,
+                Ipv4Addr::LOCALHOST,
+                addr,
+                addr,
+                addr,
+                controls,
+                false,
+            )
+        };
+
+        let response = initializing_response(&radar(0)).unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(initializing_response(&radar(2048)).is_none());
+    }
+
+    #[tokio::test]
+    async fn changing_endpoints_need_the_control_token() {
+        use clap::Parser;
+        use tower::ServiceExt;
+
+        let args = mayara_server::Cli::parse_from(["mayara", "--control-token", "secret"]);
+        let session = Session::new_base(args);
+        let radars = mayara_server::radar::SharedRadars::new(session.clone());
+        session.write().unwrap().radars = Some(radars);
+        let web = Web::new(session);
+        let app = web.control_routes().with_state(web.clone());
+        let radar = "/v2/api/radars/radar-1";
+        let routes = [
+            ("GET", format!("{}/control", radar)),
+            ("PUT", format!("{}/controls/gain", radar)),
+            ("POST", format!("{}/testPattern", radar)),
+            ("POST", format!("{}/targetScript", radar)),
+            ("POST", format!("{}/targets", radar)),
+            ("DELETE", format!("{}/targets/1", radar)),
+            ("PUT", format!("{}/arpa/settings", radar)),
+            ("POST", format!("{}/guardZones", radar)),
+            ("PUT", format!("{}/guardZones/1", radar)),
+            ("DELETE", format!("{}/guardZones/1", radar)),
+            ("DELETE", format!("{}/trails", radar)),
+            ("DELETE", format!("{}/trails/1", radar)),
+            ("PUT", format!("{}/trails/settings", radar)),
+            ("PUT", format!("{}/dualRange", radar)),
+            ("PUT", format!("{}/rangeUnits", radar)),
+            ("PUT", format!("{}/azimuthOffset", radar)),
+            ("PUT", RANGE_UNITS_URI.to_string()),
+            ("PUT", DECODERS_URI.to_string()),
+            ("POST", DECODED_BOOKMARK_URI.to_string()),
+            ("POST", REPLAY_URI.to_string()),
+            ("PUT", "/signalk/v1/applicationData/global/mayara/1.0/settings".to_string()),
+            ("DELETE", "/signalk/v1/applicationData/global/mayara/1.0/settings".to_string()),
+            ("PUT", "/v2/api/recordings/files/a.mrr".to_string()),
+            ("DELETE", "/v2/api/recordings/files/a.mrr".to_string()),
+            ("POST", RECORDING_UPLOAD_URI.to_string()),
+            ("POST", RECORDINGS_DIRS_URI.to_string()),
+            ("DELETE", "/v2/api/recordings/directories/a".to_string()),
+            ("POST", RECORD_START_URI.to_string()),
+            ("POST", RECORD_STOP_URI.to_string()),
+            ("POST", PLAYBACK_LOAD_URI.to_string()),
+            ("POST", PLAYBACK_PLAY_URI.to_string()),
+            ("POST", PLAYBACK_PAUSE_URI.to_string()),
+            ("POST", PLAYBACK_STOP_URI.to_string()),
+            ("POST", PLAYBACK_SEEK_URI.to_string()),
+            ("PUT", PLAYBACK_SETTINGS_URI.to_string()),
+        ];
+        for (method, uri) in routes {
+            let request = axum::http::Request::builder()
+                .method(method)
+                .uri(&uri)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{} {}", method, uri);
+        }
+
+        // With the token the request reaches the handler, which knows no such radar
+        let request = axum::http::Request::builder()
+            .method("PUT")
+            .uri(format!("{}/azimuthOffset", radar))
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(r#"{"degrees": 10}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "navico")]
+    async fn new_range_units_label_the_next_decoded_range() {
+        use clap::Parser;
+        use http_body_util::BodyExt;
+        use tower::ServiceExt;
+
+        let args = mayara_server::Cli::parse_from(["mayara"]);
+        let web = Web::new(Session::new_base(args));
+        let app = web
+            .control_routes()
+            .route(RANGE_UNITS_URI, get(get_range_units))
+            .route(DECODERS_DECODE_URI, post(decode_capture))
+            .with_state(web.clone());
+        async fn call(app: &Router, method: &str, uri: &str, body: Vec<u8>) -> (StatusCode, String) {
+            let request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(axum::body::Body::from(body))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            (status, String::from_utf8(body.to_vec()).unwrap())
+        }
+
+        // A Navico settings report with a range of 1852 m
+        let mut report = vec![0u8; 99];
+        report[..2].copy_from_slice(&[0x02, 0xC4]);
+        report[2..6].copy_from_slice(&18520i32.to_le_bytes());
+        let decode_uri = format!(
+            "{}?port={}",
+            DECODERS_DECODE_URI,
+            mayara_core::protocol::navico::INFO_PORT
+        );
+        let formatted = |body: &str| {
+            let decoded: serde_json::Value = serde_json::from_str(body).unwrap();
+            decoded[0]["formatted"].as_str().unwrap().to_string()
+        };
+
+        let body = br#"{"units": "statute"}"#.to_vec();
+        assert_eq!(call(&app, "PUT", RANGE_UNITS_URI, body).await.0, StatusCode::NO_CONTENT);
+        let (status, units) = call(&app, "GET", RANGE_UNITS_URI, vec![]).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(units, r#"{"units":"statute"}"#);
+        let (status, decoded) = call(&app, "POST", &decode_uri, report.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(formatted(&decoded), "1.15 mi");
+
+        let body = br#"{"units": "metric"}"#.to_vec();
+        assert_eq!(call(&app, "PUT", RANGE_UNITS_URI, body).await.0, StatusCode::NO_CONTENT);
+        let (_, decoded) = call(&app, "POST", &decode_uri, report).await;
+        assert_eq!(formatted(&decoded), "1.85 km");
+
+        let body = br#"{"units": "furlongs"}"#.to_vec();
+        assert_eq!(call(&app, "PUT", RANGE_UNITS_URI, body).await.0, StatusCode::BAD_REQUEST);
+        let (_, units) = call(&app, "GET", RANGE_UNITS_URI, vec![]).await;
+        assert_eq!(units, r#"{"units":"metric"}"#);
+    }
+}